@@ -0,0 +1,192 @@
+"""
+Generates the OpenStreetMap extract `test.osm.pbf` by running
+`python generate_osm_pbf.py`.
+
+The PBF blocks are encoded by hand, following
+https://wiki.openstreetmap.org/wiki/PBF_Format, to avoid depending on
+osmium or protobuf. The extract contains:
+
+- nodes 1-4, the corners of the square (0 0, 1 1), nodes 5-8, the corners of
+  the square (0.25 0.25, 0.75 0.75), and the tagged nodes 9 and 10
+- way 100, a footway from node 9 to node 10
+- way 101, a closed building way around the outer square
+- ways 102-104, the inner ring and the two halves of the outer ring of the
+  multipolygon relation 200
+- way 105, referencing the node 999 missing from the extract
+- relation 200, a forest multipolygon with a hole
+- relation 201, a route with the footway as member
+"""
+import pathlib
+import struct
+import zlib
+
+
+HERE = pathlib.Path(__file__).parent
+
+GRANULARITY = 100
+
+
+## Protobuf wire format
+
+def varint(value):
+    out = bytearray()
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        if value:
+            out.append(byte | 0x80)
+        else:
+            out.append(byte)
+            return bytes(out)
+
+
+def zigzag(value):
+    return (value << 1) ^ (value >> 63)
+
+
+def key(number, wire_type):
+    return varint((number << 3) | wire_type)
+
+
+def uint(number, value):
+    return key(number, 0) + varint(value)
+
+
+def sint(number, value):
+    return key(number, 0) + varint(zigzag(value))
+
+
+def data(number, value):
+    if isinstance(value, str):
+        value = value.encode()
+    return key(number, 2) + varint(len(value)) + value
+
+
+def packed(number, values, encode=varint):
+    return data(number, b"".join(encode(v) for v in values))
+
+
+def packed_sint(number, values):
+    return packed(number, values, lambda v: varint(zigzag(v)))
+
+
+def delta(values):
+    previous = 0
+    for value in values:
+        yield value - previous
+        previous = value
+
+
+## OSM objects
+
+class StringTable:
+    def __init__(self):
+        # the first string is always empty
+        self.strings = [""]
+
+    def index(self, string):
+        if string not in self.strings:
+            self.strings.append(string)
+        return self.strings.index(string)
+
+    def encode(self):
+        return b"".join(data(1, s) for s in self.strings)
+
+
+def tags(strings, values):
+    keys = [strings.index(k) for k in values]
+    vals = [strings.index(v) for v in values.values()]
+    return packed(2, keys) + packed(3, vals) if keys else b""
+
+
+def node(strings, id, lon, lat, **values):
+    return (
+        sint(1, id)
+        + tags(strings, values)
+        + sint(8, round(lat * 1e9 / GRANULARITY))
+        + sint(9, round(lon * 1e9 / GRANULARITY))
+    )
+
+
+def way(strings, id, refs, **values):
+    return uint(1, id) + tags(strings, values) + packed_sint(8, delta(refs))
+
+
+def relation(strings, id, members, **values):
+    types = {"node": 0, "way": 1, "relation": 2}
+    return (
+        uint(1, id)
+        + tags(strings, values)
+        + packed(8, [strings.index(role) for _, _, role in members])
+        + packed_sint(9, delta([ref for _, ref, _ in members]))
+        + packed(10, [types[t] for t, _, _ in members])
+    )
+
+
+## File blocks
+
+def blob(block_type, block):
+    compressed = zlib.compress(block)
+    blob = uint(2, len(block)) + data(3, compressed)
+    header = data(1, block_type) + uint(3, len(blob))
+    return struct.pack(">I", len(header)) + header + blob
+
+
+def header_block():
+    return (
+        data(4, "OsmSchema-V0.6")
+        + data(16, "datafusion-spatial generate_osm_pbf.py")
+    )
+
+
+def primitive_block():
+    strings = StringTable()
+
+    nodes = [
+        node(strings, 1, 0, 0),
+        node(strings, 2, 1, 0),
+        node(strings, 3, 1, 1),
+        node(strings, 4, 0, 1),
+        node(strings, 5, 0.25, 0.25),
+        node(strings, 6, 0.75, 0.25),
+        node(strings, 7, 0.75, 0.75),
+        node(strings, 8, 0.25, 0.75),
+        node(strings, 9, 2, 2, amenity="bench"),
+        node(strings, 10, 3, 3, name="end"),
+    ]
+    ways = [
+        way(strings, 100, [9, 10], highway="footway"),
+        way(strings, 101, [1, 2, 3, 4, 1], building="yes"),
+        way(strings, 102, [5, 6, 7, 8, 5]),
+        way(strings, 103, [1, 2, 3]),
+        way(strings, 104, [3, 4, 1]),
+        way(strings, 105, [9, 999]),
+    ]
+    relations = [
+        relation(
+            strings,
+            200,
+            [("way", 103, "outer"), ("way", 104, "outer"), ("way", 102, "inner")],
+            type="multipolygon",
+            landuse="forest",
+        ),
+        relation(strings, 201, [("way", 100, "")], type="route", route="foot"),
+    ]
+
+    # one primitive group per object type
+    groups = [
+        b"".join(data(1, n) for n in nodes),
+        b"".join(data(3, w) for w in ways),
+        b"".join(data(4, r) for r in relations),
+    ]
+    return (
+        data(1, strings.encode())
+        + b"".join(data(2, group) for group in groups)
+        + uint(17, GRANULARITY)
+    )
+
+
+if __name__ == "__main__":
+    with open(HERE / "test.osm.pbf", "wb") as f:
+        f.write(blob("OSMHeader", header_block()))
+        f.write(blob("OSMData", primitive_block()))
@@ -2,14 +2,24 @@ use std::{path::Path, sync::Arc};
 
 use datafusion::{
     error::Result,
-    logical_expr::{AggregateUDF, ScalarUDF},
+    logical_expr::{AggregateUDF, ScalarUDF, WindowUDF},
     prelude::{ParquetReadOptions, SessionConfig, SessionContext},
 };
 
 use datafusion_spatial::{
+    io::{register_file_formats, SpatialTableFactory},
     rules::SpatialAnalyzerRule,
-    udafs::Extent,
-    udfs::{AsText, Envelope, GeometryType},
+    udafs::{
+        CentroidAgg, ClusterIntersecting, ClusterWithin, CollectAgg, ConvexHullAgg, EnvelopeAgg,
+        Extent, Extent3D, MakeLineAgg, UnionAgg,
+    },
+    udfs::{
+        Area, AsText, Collect, Distance, Dump, DumpPoints, DumpRings, Envelope, Envelope3D,
+        GeometryType, Intersects, Length, MakeLine, SetSrid, Srid, ToGeography, ToGeometry,
+        Transform, M,
+    },
+    udtfs::{HexagonGrid, SquareGrid},
+    udwfs::{self, ClusterDbscan, ClusterKMeans},
 };
 
 #[tokio::main]
@@ -21,11 +31,49 @@ async fn main() -> Result<()> {
     ctx.register_udf(ScalarUDF::from(AsText::new()));
     ctx.register_udf(ScalarUDF::from(GeometryType::new()));
     ctx.register_udf(ScalarUDF::from(Envelope::new()));
+    ctx.register_udf(ScalarUDF::from(Envelope3D::new()));
+    ctx.register_udf(ScalarUDF::from(Srid::new()));
+    ctx.register_udf(ScalarUDF::from(SetSrid::new()));
+    ctx.register_udf(ScalarUDF::from(Transform::new()));
+    ctx.register_udf(ScalarUDF::from(ToGeography::new()));
+    ctx.register_udf(ScalarUDF::from(ToGeometry::new()));
+    ctx.register_udf(ScalarUDF::from(Intersects::new()));
+    ctx.register_udf(ScalarUDF::from(Distance::new()));
+    ctx.register_udf(ScalarUDF::from(Length::new()));
+    ctx.register_udf(ScalarUDF::from(Area::new()));
+    ctx.register_udf(ScalarUDF::from(M::new()));
+    ctx.register_udf(ScalarUDF::from(Collect::new()));
+    ctx.register_udf(ScalarUDF::from(MakeLine::new()));
+    ctx.register_udf(ScalarUDF::from(Dump::new()));
+    ctx.register_udf(ScalarUDF::from(DumpPoints::new()));
+    ctx.register_udf(ScalarUDF::from(DumpRings::new()));
 
     ctx.register_udaf(AggregateUDF::from(Extent::new()));
+    ctx.register_udaf(AggregateUDF::from(Extent3D::new()));
+    ctx.register_udaf(AggregateUDF::from(UnionAgg::new()));
+    ctx.register_udaf(AggregateUDF::from(CollectAgg::new()));
+    ctx.register_udaf(AggregateUDF::from(MakeLineAgg::new()));
+    ctx.register_udaf(AggregateUDF::from(EnvelopeAgg::new()));
+    ctx.register_udaf(AggregateUDF::from(ConvexHullAgg::new()));
+    ctx.register_udaf(AggregateUDF::from(CentroidAgg::new()));
+    ctx.register_udaf(AggregateUDF::from(ClusterIntersecting::new()));
+    ctx.register_udaf(AggregateUDF::from(ClusterWithin::new()));
+
+    ctx.register_udwf(WindowUDF::from(ClusterDbscan::new()));
+    ctx.register_udwf(WindowUDF::from(ClusterKMeans::new()));
+    // windowed calls of the cluster aggregates resolve to the aggregates,
+    // the analyzer rule replaces them with these
+    ctx.register_udwf(WindowUDF::from(udwfs::ClusterIntersecting::new()));
+    ctx.register_udwf(WindowUDF::from(udwfs::ClusterWithin::new()));
+
+    ctx.register_udtf("st_squaregrid", Arc::new(SquareGrid));
+    ctx.register_udtf("st_hexagongrid", Arc::new(HexagonGrid));
 
     ctx.add_analyzer_rule(Arc::new(SpatialAnalyzerRule {}));
 
+    register_file_formats(&mut ctx.state_ref().write())?;
+    SpatialTableFactory::register(&mut ctx.state_ref().write());
+
     for path in std::fs::read_dir(Path::new("data/")).unwrap() {
         let path = path.unwrap().path();
 
@@ -47,7 +95,10 @@ async fn main() -> Result<()> {
         )
         .await?;
 
-        let query = format!("SELECT ST_Envelope(geometry), ST_AsText(geometry) FROM '{}'", table_name);
+        let query = format!(
+            "SELECT ST_Envelope(geometry), ST_AsText(geometry) FROM '{}'",
+            table_name
+        );
         let df = ctx.sql(&query).await?;
 
         df.show_limit(5).await?;
@@ -0,0 +1,347 @@
+//! Spatial clustering of the geometries of a window partition or group.
+
+use geo::{BoundingRect, Coord, Distance as _, Euclidean, Geometry, Intersects};
+use rstar::{
+    primitives::{GeomWithData, Rectangle},
+    RTree, AABB,
+};
+
+/// R-tree over the bounding boxes of geometries, NULL and empty geometries
+/// are left out.
+pub struct GeometryIndex<'a> {
+    geometries: &'a [Option<Geometry>],
+    tree: RTree<GeomWithData<Rectangle<[f64; 2]>, usize>>,
+}
+
+impl<'a> GeometryIndex<'a> {
+    pub fn new(geometries: &'a [Option<Geometry>]) -> Self {
+        let boxes = geometries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, geometry)| {
+                let rect = geometry.as_ref()?.bounding_rect()?;
+                let rectangle = Rectangle::from_corners(rect.min().into(), rect.max().into());
+                Some(GeomWithData::new(rectangle, i))
+            })
+            .collect();
+
+        Self {
+            geometries,
+            tree: RTree::bulk_load(boxes),
+        }
+    }
+
+    /// Indices of the geometries within `distance` of geometry `i`, `i`
+    /// included. With a distance of 0 these are the geometries intersecting
+    /// geometry `i`.
+    pub fn within(&self, i: usize, distance: f64) -> Vec<usize> {
+        let Some(geometry) = &self.geometries[i] else {
+            return vec![];
+        };
+        let Some(rect) = geometry.bounding_rect() else {
+            return vec![];
+        };
+
+        let envelope = AABB::from_corners(
+            [rect.min().x - distance, rect.min().y - distance],
+            [rect.max().x + distance, rect.max().y + distance],
+        );
+        self.tree
+            .locate_in_envelope_intersecting(&envelope)
+            .map(|candidate| candidate.data)
+            .filter(|&j| {
+                let other = self.geometries[j].as_ref().unwrap();
+                match distance > 0. {
+                    _ if j == i => true,
+                    true => Euclidean::distance(geometry, other) <= distance,
+                    false => geometry.intersects(other),
+                }
+            })
+            .collect()
+    }
+}
+
+/// DBSCAN cluster number of each geometry, `None` for noise. Geometries
+/// with at least `min_points` geometries (themselves included) within `eps`
+/// are core geometries, clusters are the core geometries reachable from one
+/// another and the border geometries within `eps` of them, the latter
+/// joining the first cluster reaching them.
+pub fn dbscan(geometries: &[Option<Geometry>], eps: f64, min_points: usize) -> Vec<Option<u32>> {
+    let index = GeometryIndex::new(geometries);
+    let mut clusters = vec![None; geometries.len()];
+    let mut visited = vec![false; geometries.len()];
+    let mut next = 0;
+
+    for i in 0..geometries.len() {
+        if visited[i] {
+            continue;
+        }
+        visited[i] = true;
+
+        let neighbors = index.within(i, eps);
+        if neighbors.is_empty() || neighbors.len() < min_points {
+            continue;
+        }
+
+        clusters[i] = Some(next);
+        let mut queue = neighbors;
+        while let Some(j) = queue.pop() {
+            if clusters[j].is_none() {
+                clusters[j] = Some(next);
+            }
+            if !visited[j] {
+                visited[j] = true;
+                let neighbors = index.within(j, eps);
+                if neighbors.len() >= min_points {
+                    queue.extend(neighbors);
+                }
+            }
+        }
+        next += 1;
+    }
+
+    clusters
+}
+
+/// Connected component number of each geometry, `None` for NULL and empty
+/// geometries. Geometries within `distance` of each other (intersecting for
+/// a distance of 0) are connected, components are numbered in the order of
+/// their first geometry.
+pub fn components(geometries: &[Option<Geometry>], distance: f64) -> Vec<Option<u32>> {
+    let index = GeometryIndex::new(geometries);
+    let mut parents = (0..geometries.len()).collect::<Vec<_>>();
+
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            // path halving
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    for i in 0..geometries.len() {
+        for j in index.within(i, distance) {
+            let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+            // the lower index is the root, so roots are first geometries
+            parents[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut numbers = vec![None; geometries.len()];
+    let mut next = 0;
+    (0..geometries.len())
+        .map(|i| {
+            geometries[i].as_ref()?.bounding_rect()?;
+            let root = root(&mut parents, i);
+            Some(*numbers[root].get_or_insert_with(|| {
+                next += 1;
+                next - 1
+            }))
+        })
+        .collect()
+}
+
+/// Maximum number of k-means iterations.
+const KMEANS_ITERATIONS: usize = 100;
+
+/// K-means cluster number of each coordinate (geometry centroid), `None`
+/// for missing coordinates. Clusters are seeded farthest-first starting at
+/// the coordinate nearest to the lower left corner, all ties being broken by
+/// coordinate, so the result does not depend on the order of the input.
+///
+/// With `max_radius`, clusters are added until no coordinate is farther
+/// from its cluster's center than that.
+pub fn kmeans(coords: &[Option<Coord>], mut k: usize, max_radius: Option<f64>) -> Vec<Option<u32>> {
+    // distinct coordinates in lexicographic order with their number of
+    // occurrences, mapped back at the end
+    let mut sorted = coords.iter().flatten().copied().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    let (mut points, mut counts) = (Vec::<Coord>::new(), Vec::<usize>::new());
+    for p in sorted {
+        match points.last() == Some(&p) {
+            true => *counts.last_mut().unwrap() += 1,
+            false => {
+                points.push(p);
+                counts.push(1);
+            }
+        }
+    }
+    if points.is_empty() || k == 0 {
+        return vec![None; coords.len()];
+    }
+
+    let squared = |a: Coord, b: Coord| (a.x - b.x).powi(2) + (a.y - b.y).powi(2);
+    let nearest = |centers: &[Coord], point: Coord| {
+        (0..centers.len())
+            .min_by(|&a, &b| squared(centers[a], point).total_cmp(&squared(centers[b], point)))
+            .unwrap()
+    };
+
+    // farthest-first seeding, points are sorted so `max_by` ties resolve by
+    // coordinate
+    let corner = points.iter().fold(points[0], |corner, p| Coord {
+        x: corner.x.min(p.x),
+        y: corner.y.min(p.y),
+    });
+    let mut centers = vec![points[nearest(&points, corner)]];
+    let mut distances = points
+        .iter()
+        .map(|&p| squared(p, centers[0]))
+        .collect::<Vec<_>>();
+    let mut assignments;
+    loop {
+        while centers.len() < k.min(points.len()) {
+            let (farthest, _) = distances
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .unwrap();
+            centers.push(points[farthest]);
+            for (distance, &p) in distances.iter_mut().zip(points.iter()) {
+                *distance = distance.min(squared(p, points[farthest]));
+            }
+        }
+
+        // Lloyd iterations, each distinct point counted as often as it occurs
+        assignments = points
+            .iter()
+            .map(|&p| nearest(&centers, p))
+            .collect::<Vec<_>>();
+        for _ in 0..KMEANS_ITERATIONS {
+            let mut sums = vec![(0., 0., 0); centers.len()];
+            for ((p, &count), &cluster) in points.iter().zip(&counts).zip(assignments.iter()) {
+                let sum = &mut sums[cluster];
+                let weight = count as f64;
+                *sum = (sum.0 + p.x * weight, sum.1 + p.y * weight, sum.2 + count);
+            }
+            for (center, (x, y, n)) in centers.iter_mut().zip(sums) {
+                if n > 0 {
+                    *center = Coord {
+                        x: x / n as f64,
+                        y: y / n as f64,
+                    };
+                }
+            }
+
+            let next = points
+                .iter()
+                .map(|&p| nearest(&centers, p))
+                .collect::<Vec<_>>();
+            if next == assignments {
+                break;
+            }
+            assignments = next;
+        }
+
+        // the point farthest from its center seeds another cluster if it is
+        // beyond the maximum radius
+        let Some(max_radius) = max_radius else { break };
+        distances = points
+            .iter()
+            .zip(assignments.iter())
+            .map(|(&p, &cluster)| squared(p, centers[cluster]))
+            .collect();
+        let farthest = distances.iter().copied().fold(0., f64::max);
+        if farthest <= max_radius.powi(2) || centers.len() == points.len() {
+            break;
+        }
+        k = centers.len() + 1;
+    }
+
+    coords
+        .iter()
+        .map(|coord| {
+            let coord = coord.as_ref()?;
+            let index = points
+                .binary_search_by(|p| p.x.total_cmp(&coord.x).then(p.y.total_cmp(&coord.y)))
+                .ok()?;
+            Some(assignments[index] as u32)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{coord, line_string, point};
+
+    use super::*;
+
+    #[test]
+    fn dbscan_clusters() {
+        let geometries = vec![
+            Some(point!(x: 0., y: 0.).into()),
+            Some(point!(x: 0.5, y: 0.).into()),
+            None,
+            // joins the first cluster through its distance to the line
+            Some(line_string![(x: 1., y: 2.), (x: 1., y: 0.6)].into()),
+            Some(point!(x: 10., y: 10.).into()),
+            Some(point!(x: 10., y: 10.5).into()),
+            Some(point!(x: 50., y: 50.).into()),
+        ];
+
+        assert_eq!(
+            dbscan(&geometries, 1., 2),
+            vec![Some(0), Some(0), None, Some(0), Some(1), Some(1), None]
+        );
+        assert_eq!(dbscan(&geometries, 1., 1)[6], Some(2));
+    }
+
+    #[test]
+    fn connected_components() {
+        let geometries = vec![
+            Some(line_string![(x: 0., y: 0.), (x: 2., y: 0.)].into()),
+            Some(point!(x: 5., y: 0.).into()),
+            None,
+            Some(line_string![(x: 2., y: 0.), (x: 2., y: 2.)].into()),
+            Some(point!(x: 2., y: 3.).into()),
+        ];
+
+        assert_eq!(
+            components(&geometries, 0.),
+            vec![Some(0), Some(1), None, Some(0), Some(2)]
+        );
+        assert_eq!(
+            components(&geometries, 1.),
+            vec![Some(0), Some(1), None, Some(0), Some(0)]
+        );
+    }
+
+    #[test]
+    fn kmeans_clusters() {
+        let coords = [(0., 0.), (10., 10.), (0., 1.), (10., 11.), (1., 0.)]
+            .map(|(x, y)| Some(coord! { x: x, y: y }));
+        let clusters = kmeans(&coords, 2, None);
+        assert_eq!(clusters[0], clusters[2]);
+        assert_eq!(clusters[0], clusters[4]);
+        assert_eq!(clusters[1], clusters[3]);
+        assert_ne!(clusters[0], clusters[1]);
+
+        // independent of the input order
+        let mut reversed = coords;
+        reversed.reverse();
+        let mut reversed = kmeans(&reversed, 2, None);
+        reversed.reverse();
+        assert_eq!(reversed, clusters);
+
+        // more clusters to keep within the maximum radius
+        let clusters = kmeans(&coords, 1, Some(2.));
+        assert_ne!(clusters[0], clusters[1]);
+        assert_eq!(kmeans(&[None], 3, None), vec![None]);
+    }
+
+    #[test]
+    fn kmeans_duplicates() {
+        // the repeated point pulls its center away from 5.5, which without
+        // counting duplicates would stay in its cluster
+        let mut coords = [0., 2., 4., 5.5]
+            .map(|x| Some(coord! { x: x, y: 0. }))
+            .to_vec();
+        coords.extend([Some(coord! { x: 10., y: 0. }); 10]);
+        let clusters = kmeans(&coords, 2, None);
+        assert_eq!(clusters[3], clusters[0]);
+        assert_ne!(clusters[3], clusters[4]);
+        assert!(clusters[4..].iter().all(|cluster| *cluster == clusters[4]));
+    }
+}
@@ -1,76 +1,353 @@
 use core::f64;
 
-use datafusion::arrow::{
-    array::{AsArray, BooleanArray, Float64Array},
-    compute::{filter, max, min},
-    datatypes::Float64Type,
+use std::sync::Arc;
+
+use datafusion::{
+    arrow::{
+        array::{
+            Array, ArrayRef, AsArray, BooleanArray, FixedSizeListArray, Float64Array,
+            GenericListArray, OffsetSizeTrait, StructArray, UnionArray,
+        },
+        compute::{filter, max, min},
+        datatypes::{DataType, Float64Type},
+    },
+    error::{DataFusionError, Result},
 };
 
+use geo::{BooleanOps, BoundingRect, MultiPolygon};
+use geo_traits::*;
 use geoarrow::array::CoordBuffer;
 
+/// Minimum and maximum of each ordinate of a coordinate buffer, `f64::MAX`
+/// and `f64::MIN` for empty buffers. With `empty_point_check` coordinates
+/// with NaN x (empty points) are skipped.
+pub fn min_max<const D: usize>(
+    coords: &CoordBuffer<D>,
+    empty_point_check: bool,
+) -> ([f64; D], [f64; D]) {
+    let mut mins = [f64::MAX; D];
+    let mut maxs = [f64::MIN; D];
+    if coords.is_empty() {
+        return (mins, maxs);
+    }
+
+    match coords {
+        CoordBuffer::Interleaved(coords) => {
+            for coord in coords.coords().chunks(D) {
+                if empty_point_check && coord[0].is_nan() {
+                    continue;
+                }
+                for ((min, max), ordinate) in mins.iter_mut().zip(maxs.iter_mut()).zip(coord) {
+                    *min = min.min(*ordinate);
+                    *max = max.max(*ordinate);
+                }
+            }
+        }
+        CoordBuffer::Separated(coords) => {
+            let ordinates = coords
+                .coords()
+                .iter()
+                .map(|ordinates| Float64Array::try_new(ordinates.clone(), None).unwrap())
+                .collect::<Vec<_>>();
+
+            // hack to work around empty points
+            let ordinates = if empty_point_check {
+                let filter_mask = BooleanArray::from_unary(&ordinates[0], |x| !x.is_nan());
+                ordinates
+                    .iter()
+                    .map(|ordinates| {
+                        filter(ordinates, &filter_mask)
+                            .unwrap()
+                            .as_primitive::<Float64Type>()
+                            .to_owned()
+                    })
+                    .collect()
+            } else {
+                ordinates
+            };
+
+            for (i, ordinates) in ordinates.iter().enumerate() {
+                mins[i] = min(ordinates).unwrap_or(f64::MAX);
+                maxs[i] = max(ordinates).unwrap_or(f64::MIN);
+            }
+        }
+    }
+
+    (mins, maxs)
+}
+
+/// 2D bounds of a coordinate buffer, see [`min_max`].
 pub fn min_max_2d<const D: usize>(
     coords: &CoordBuffer<D>,
     empty_point_check: bool,
 ) -> ((f64, f64), (f64, f64)) {
-    if coords.is_empty() {
-        ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN))
-    } else {
-        match coords {
-            CoordBuffer::Interleaved(coords) => coords.coords().chunks(D).fold(
-                (
-                    (f64::INFINITY, f64::INFINITY),
-                    (f64::NEG_INFINITY, f64::NEG_INFINITY),
-                ),
-                |((mut xmin, mut ymin), (mut xmax, mut ymax)), coord| {
-                    let x = coord[0];
-                    let y = coord[1];
-
-                    if x < xmin {
-                        xmin = x;
-                    }
-                    if x > xmax && !x.is_nan() {
-                        xmax = x;
-                    }
-
-                    if y < ymin {
-                        ymin = y;
-                    }
-                    if y > ymax && !y.is_nan() {
-                        ymax = y;
-                    }
-
-                    ((xmin, ymin), (xmax, ymax))
-                },
-            ),
-            CoordBuffer::Separated(coords) => {
-                let xcoords = coords.coords()[0].clone();
-                let ycoords = coords.coords()[1].clone();
-
-                let xcoords = Float64Array::try_new(xcoords, None).unwrap();
-                let ycoords = Float64Array::try_new(ycoords, None).unwrap();
-
-                // hack to work around empty points
-                let (xcoords, ycoords) = if empty_point_check {
-                    let xfilter = BooleanArray::from_unary(&xcoords, |x| !x.is_nan());
-                    let xcoords = filter(&xcoords, &xfilter).unwrap();
-                    let xcoords = xcoords.as_primitive::<Float64Type>().to_owned();
-
-                    let yfilter = BooleanArray::from_unary(&ycoords, |y| !y.is_nan());
-                    let ycoords = filter(&ycoords, &yfilter).unwrap();
-                    let ycoords = ycoords.as_primitive::<Float64Type>().to_owned();
-
-                    (xcoords, ycoords)
-                } else {
-                    (xcoords, ycoords)
-                };
-
-                let xmin = min(&xcoords).unwrap_or(f64::MAX);
-                let ymin = min(&ycoords).unwrap_or(f64::MAX);
-                let xmax = max(&xcoords).unwrap_or(f64::MIN);
-                let ymax = max(&ycoords).unwrap_or(f64::MIN);
-
-                ((xmin, ymin), (xmax, ymax))
+    let (mins, maxs) = min_max(coords, empty_point_check);
+    ((mins[0], mins[1]), (maxs[0], maxs[1]))
+}
+
+/// Whether the child values of all (nested) variable size lists of `array`
+/// are referenced by its offsets, i.e. its coordinate buffer holds the
+/// coordinates of its rows only. Sliced list arrays keep the values of all
+/// rows.
+pub fn is_compact(array: &dyn Array) -> bool {
+    fn compact_list<O: OffsetSizeTrait>(list: &GenericListArray<O>) -> bool {
+        let offsets = list.value_offsets();
+        offsets.first().map_or(true, |o| o.as_usize() == 0)
+            && offsets
+                .last()
+                .map_or(true, |o| o.as_usize() == list.values().len())
+            && is_compact(list.values().as_ref())
+    }
+
+    match array.data_type() {
+        DataType::List(_) => compact_list(array.as_list::<i32>()),
+        DataType::LargeList(_) => compact_list(array.as_list::<i64>()),
+        _ => true,
+    }
+}
+
+/// Extend `bounds` by all coordinates of `geometry`, skipping NaN (empty point) coordinates.
+pub fn update_min_max_2d(
+    geometry: &impl GeometryTrait<T = f64>,
+    bounds: &mut ((f64, f64), (f64, f64)),
+) {
+    fn add(coord: &impl CoordTrait<T = f64>, bounds: &mut ((f64, f64), (f64, f64))) {
+        let ((xmin, ymin), (xmax, ymax)) = bounds;
+        let x = coord.x();
+        let y = coord.y();
+
+        if !x.is_nan() {
+            *xmin = xmin.min(x);
+            *xmax = xmax.max(x);
+        }
+        if !y.is_nan() {
+            *ymin = ymin.min(y);
+            *ymax = ymax.max(y);
+        }
+    }
+
+    use geo_traits::GeometryType::*;
+
+    match geometry.as_type() {
+        Point(point) => {
+            if let Some(coord) = point.coord() {
+                add(&coord, bounds);
+            }
+        }
+        LineString(linestring) => linestring.coords().for_each(|c| add(&c, bounds)),
+        Polygon(polygon) => {
+            if let Some(exterior) = polygon.exterior() {
+                exterior.coords().for_each(|c| add(&c, bounds));
+            }
+            for interior in polygon.interiors() {
+                interior.coords().for_each(|c| add(&c, bounds));
+            }
+        }
+        MultiPoint(multi_point) => {
+            for point in multi_point.points() {
+                if let Some(coord) = point.coord() {
+                    add(&coord, bounds);
+                }
+            }
+        }
+        MultiLineString(mls) => {
+            for linestring in mls.line_strings() {
+                linestring.coords().for_each(|c| add(&c, bounds));
             }
         }
+        MultiPolygon(multi_polygon) => {
+            for polygon in multi_polygon.polygons() {
+                if let Some(exterior) = polygon.exterior() {
+                    exterior.coords().for_each(|c| add(&c, bounds));
+                }
+                for interior in polygon.interiors() {
+                    interior.coords().for_each(|c| add(&c, bounds));
+                }
+            }
+        }
+        GeometryCollection(gc) => {
+            for geometry in gc.geometries() {
+                update_min_max_2d(&geometry, bounds);
+            }
+        }
+        Rect(rect) => {
+            add(&rect.min(), bounds);
+            add(&rect.max(), bounds);
+        }
+        Triangle(triangle) => triangle.coords().iter().for_each(|c| add(c, bounds)),
+        Line(line) => line.coords().iter().for_each(|c| add(c, bounds)),
+    }
+}
+
+/// Apply `f` to the ordinates of every coordinate of a native GeoArrow array,
+/// keeping its offsets and validity. NaN (empty point) coordinates are kept as is.
+pub fn map_coords(array: &ArrayRef, f: &dyn Fn(&mut [f64]) -> Result<()>) -> Result<ArrayRef> {
+    match array.data_type() {
+        DataType::List(_) => map_list_coords(array.as_list::<i32>(), f),
+        DataType::LargeList(_) => map_list_coords(array.as_list::<i64>(), f),
+        DataType::FixedSizeList(_, size) => {
+            // interleaved coordinates
+            let list = array.as_fixed_size_list();
+            let mut values = list
+                .values()
+                .as_primitive::<Float64Type>()
+                .values()
+                .to_vec();
+            for coord in values.chunks_mut(*size as usize) {
+                if !coord[0].is_nan() {
+                    f(coord)?;
+                }
+            }
+
+            let field = match list.data_type() {
+                DataType::FixedSizeList(field, _) => field.clone(),
+                _ => unreachable!(),
+            };
+            Ok(Arc::new(FixedSizeListArray::try_new(
+                field,
+                *size,
+                Arc::new(Float64Array::from(values)),
+                list.nulls().cloned(),
+            )?))
+        }
+        DataType::Struct(fields) if fields.first().is_some_and(|f| f.name() == "x") => {
+            // separated coordinates
+            let coords = array.as_struct();
+            let mut dimensions = coords
+                .columns()
+                .iter()
+                .map(|c| c.as_primitive::<Float64Type>().values().to_vec())
+                .collect::<Vec<_>>();
+
+            let mut coord = vec![0.; dimensions.len()];
+            for index in 0..coords.len() {
+                if dimensions[0][index].is_nan() {
+                    continue;
+                }
+                for (ordinate, values) in coord.iter_mut().zip(dimensions.iter()) {
+                    *ordinate = values[index];
+                }
+                f(&mut coord)?;
+                for (ordinate, values) in coord.iter().zip(dimensions.iter_mut()) {
+                    values[index] = *ordinate;
+                }
+            }
+
+            Ok(Arc::new(StructArray::try_new(
+                fields.clone(),
+                dimensions
+                    .into_iter()
+                    .map(|values| Arc::new(Float64Array::from(values)) as ArrayRef)
+                    .collect(),
+                coords.nulls().cloned(),
+            )?))
+        }
+        DataType::Union(fields, _) => {
+            let union = array.as_any().downcast_ref::<UnionArray>().unwrap();
+            let children = fields
+                .iter()
+                .map(|(type_id, _)| map_coords(union.child(type_id), f))
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(Arc::new(UnionArray::try_new(
+                fields.clone(),
+                union.type_ids().clone(),
+                union.offsets().cloned(),
+                children,
+            )?))
+        }
+        dt => Err(DataFusionError::NotImplemented(format!(
+            "Coordinate mapping for `{dt}`"
+        ))),
+    }
+}
+
+fn map_list_coords<O: OffsetSizeTrait>(
+    list: &GenericListArray<O>,
+    f: &dyn Fn(&mut [f64]) -> Result<()>,
+) -> Result<ArrayRef> {
+    let field = match list.data_type() {
+        DataType::List(field) | DataType::LargeList(field) => field.clone(),
+        _ => unreachable!(),
+    };
+
+    Ok(Arc::new(GenericListArray::<O>::try_new(
+        field,
+        list.offsets().clone(),
+        map_coords(list.values(), f)?,
+        list.nulls().cloned(),
+    )?))
+}
+
+/// Union of polygons, merged pairwise in a balanced tree (cascaded union) so
+/// that each union only involves geometries of similar size. Sorting by the
+/// lower left corner first lets neighbouring polygons dissolve early.
+pub fn cascaded_union(mut polygons: Vec<MultiPolygon>) -> MultiPolygon {
+    let key = |mp: &MultiPolygon| mp.bounding_rect().map_or(f64::MAX, |r| r.min().x);
+    polygons.sort_by(|a, b| key(a).total_cmp(&key(b)));
+
+    while polygons.len() > 1 {
+        let mut merged = Vec::with_capacity(polygons.len() / 2 + 1);
+        let mut polygons_iter = polygons.into_iter();
+        while let Some(a) = polygons_iter.next() {
+            merged.push(match polygons_iter.next() {
+                Some(b) => a.union(&b),
+                None => a,
+            });
+        }
+        polygons = merged;
+    }
+
+    polygons.pop().unwrap_or_else(|| MultiPolygon::new(vec![]))
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::{array::ListArray, datatypes::Int32Type};
+    use geoarrow::array::SeparatedCoordBufferBuilder;
+
+    use geo::{polygon, Area};
+
+    use super::*;
+
+    #[test]
+    fn union() {
+        let square = |x: f64, y: f64| {
+            MultiPolygon::new(vec![polygon![
+                (x: x, y: y),
+                (x: x + 2., y: y),
+                (x: x + 2., y: y + 2.),
+                (x: x, y: y + 2.),
+                (x: x, y: y),
+            ]])
+        };
+        let union = cascaded_union(vec![square(1., 1.), square(10., 10.), square(0., 0.)]);
+        assert_eq!(union.0.len(), 2);
+        assert_eq!(union.unsigned_area(), 11.);
+        assert!(cascaded_union(vec![]).0.is_empty());
+    }
+
+    #[test]
+    fn min_max_3d() {
+        let coords = CoordBuffer::Separated(
+            SeparatedCoordBufferBuilder::from_vecs([
+                vec![1., f64::NAN, -2.],
+                vec![5., f64::NAN, 3.],
+                vec![0., f64::NAN, 10.],
+            ])
+            .into(),
+        );
+        assert_eq!(min_max(&coords, true), ([-2., 3., 0.], [1., 5., 10.]));
+        assert_eq!(min_max_2d(&coords, true), ((-2., 3.), (1., 5.)));
+    }
+
+    #[test]
+    fn sliced_lists() {
+        let list = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![Some(3)]),
+        ]);
+        assert!(is_compact(&list));
+        assert!(!is_compact(&list.slice(1, 1)));
     }
 }
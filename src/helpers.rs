@@ -1,15 +1,21 @@
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 use datafusion::{
-    arrow::datatypes::DataType,
+    arrow::{
+        array::{ArrayRef, AsArray},
+        compute::cast,
+        datatypes::{DataType, Field, Fields, UnionFields},
+    },
     error::{DataFusionError, Result},
     logical_expr::ColumnarValue,
     scalar::ScalarValue,
 };
 use geoarrow::{
-    array::CoordType,
+    array::{CoordType, NativeArrayDyn},
     datatypes::{Dimension, NativeType},
-    io::parquet::metadata::GeoParquetGeometryType,
+    io::{parquet::metadata::GeoParquetGeometryType, wkb::to_wkb},
+    trait_::ArrayAccessor,
+    ArrayBase,
 };
 
 pub fn scalar_arg_as_str(arg: &ColumnarValue) -> Result<&str> {
@@ -25,6 +31,234 @@ pub fn scalar_arg_as_str(arg: &ColumnarValue) -> Result<&str> {
     }
 }
 
+/// CRS argument appended by the [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule),
+/// `None` if the CRS is unknown.
+pub fn crs_arg(arg: &ColumnarValue) -> Result<Option<&str>> {
+    match arg {
+        ColumnarValue::Scalar(ScalarValue::Utf8(s) | ScalarValue::Utf8View(s)) => Ok(s.as_deref()),
+        ColumnarValue::Scalar(ScalarValue::Null) => Ok(None),
+        arg => Err(DataFusionError::Internal(format!(
+            "Expected a constant CRS argument, got `{arg:?}`"
+        ))),
+    }
+}
+
+/// Whether the edges argument appended by the
+/// [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule) marks a
+/// geography, i.e. geometries with spherical edges.
+pub fn is_spherical(arg: &ColumnarValue) -> bool {
+    matches!(
+        arg,
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(edges)) | ScalarValue::Utf8View(Some(edges)))
+            if edges == "spherical"
+    )
+}
+
+/// Geometries of a WKB or native array as [`geo`] geometries, `None` for nulls.
+pub fn geo_geometries(
+    geoms: &ArrayRef,
+    geometry_type: &ColumnarValue,
+) -> Result<Vec<Option<geo::Geometry>>> {
+    match geoms.data_type() {
+        DataType::Binary => geoms
+            .as_binary::<i32>()
+            .iter()
+            .map(|wkb| {
+                wkb.map(|wkb| Ok(crate::wkb::parse(wkb)?.to_geo()))
+                    .transpose()
+            })
+            .collect(),
+        DataType::LargeBinary => geoms
+            .as_binary::<i64>()
+            .iter()
+            .map(|wkb| {
+                wkb.map(|wkb| Ok(crate::wkb::parse(wkb)?.to_geo()))
+                    .transpose()
+            })
+            .collect(),
+        _ => {
+            let geoms = native_array(geoms, geometry_type)?;
+            Ok(to_wkb::<i32>(geoms.as_ref()).iter_geo().collect())
+        }
+    }
+}
+
+/// Fields of the Box3D struct returned by `ST_3DEnvelope` and `ST_3DExtent`,
+/// laid out like a GeoArrow XYZ rect. They are nullable like the struct, which
+/// is NULL for NULL or empty geometries.
+pub fn box3d_fields() -> Fields {
+    ["xmin", "ymin", "zmin", "xmax", "ymax", "zmax"]
+        .into_iter()
+        .map(|name| Field::new(name, DataType::Float64, true))
+        .collect()
+}
+
+/// Whether a data type is a native GeoArrow point array, interleaved or
+/// separated (but not a rect).
+pub fn is_point_type(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::FixedSizeList(_, _) => true,
+        DataType::Struct(fields) => fields.first().is_some_and(|field| field.name() == "x"),
+        _ => false,
+    }
+}
+
+/// Native GeoArrow multi geometry type holding geometries of a native point,
+/// line string or polygon type as its parts, `None` for other types.
+pub fn multi_type(part_type: &DataType) -> Option<DataType> {
+    let name = match part_type {
+        dt if is_point_type(dt) => "points",
+        DataType::List(field) if field.name() == "vertices" => "linestrings",
+        DataType::List(field) if field.name() == "rings" => "polygons",
+        _ => return None,
+    };
+    Some(DataType::List(Arc::new(Field::new(
+        name,
+        part_type.clone(),
+        true,
+    ))))
+}
+
+/// Native GeoArrow line string type with vertices of the given point type.
+pub fn line_string_type(point_type: &DataType) -> DataType {
+    DataType::List(Arc::new(Field::new("vertices", point_type.clone(), true)))
+}
+
+/// Geometries of a WKB or native array decoded by the crate's WKB reader,
+/// keeping Z and M ordinates, `None` for nulls.
+pub fn wkb_geometries(
+    geoms: &ArrayRef,
+    geometry_type: &ColumnarValue,
+) -> Result<Vec<Option<crate::wkb::Geometry>>> {
+    let parse = |wkb: Option<&[u8]>| wkb.map(crate::wkb::parse).transpose();
+
+    match geoms.data_type() {
+        DataType::LargeBinary => geoms.as_binary::<i64>().iter().map(parse).collect(),
+        _ => wkb_array(geoms, geometry_type)?
+            .as_binary::<i32>()
+            .iter()
+            .map(parse)
+            .collect(),
+    }
+}
+
+/// Geometries of a WKB or native array as a (32 bit offset) WKB array.
+pub fn wkb_array(geoms: &ArrayRef, geometry_type: &ColumnarValue) -> Result<ArrayRef> {
+    match geoms.data_type() {
+        DataType::Binary => Ok(geoms.clone()),
+        DataType::LargeBinary => Ok(cast(geoms, &DataType::Binary)?),
+        _ => {
+            let geoms = native_array(geoms, geometry_type)?;
+            Ok(to_wkb::<i32>(geoms.as_ref()).to_array_ref())
+        }
+    }
+}
+
+/// Geometries of the first `n` arguments of a spatial function, broadcast to
+/// arrays of equal length and decoded with the geometry types appended by the
+/// [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule).
+///
+/// The analyzer appends four arguments per geometry argument it knows, in the
+/// order of the arguments but skipping those it doesn't know.
+pub fn geometry_args(args: &[ColumnarValue], n: usize) -> Result<Vec<Vec<Option<geo::Geometry>>>> {
+    let arrays = ColumnarValue::values_to_arrays(&args[..n])?;
+    geometry_types(&arrays, &args[n..])
+        .into_iter()
+        .zip(arrays.iter())
+        .map(|(geometry_type, array)| geo_geometries(array, &geometry_type))
+        .collect()
+}
+
+/// Like [`geometry_args`], decoded by the crate's WKB reader.
+pub fn wkb_geometry_args(
+    args: &[ColumnarValue],
+    n: usize,
+) -> Result<Vec<Vec<Option<crate::wkb::Geometry>>>> {
+    let arrays = ColumnarValue::values_to_arrays(&args[..n])?;
+    geometry_types(&arrays, &args[n..])
+        .into_iter()
+        .zip(arrays.iter())
+        .map(|(geometry_type, array)| wkb_geometries(array, &geometry_type))
+        .collect()
+}
+
+/// Geometry type arguments of the geometry `arrays` among the arguments
+/// appended by the analyzer, matched to the arrays in order by their encoding.
+fn geometry_types(arrays: &[ArrayRef], infos: &[ColumnarValue]) -> Vec<ColumnarValue> {
+    let infos = infos.chunks(4).collect::<Vec<_>>();
+    let unknown = ColumnarValue::Scalar(ScalarValue::Null);
+
+    let mut next = 0;
+    arrays
+        .iter()
+        .map(|array| {
+            let Some(encoding) = array_encoding(array.data_type()) else {
+                return unknown.clone();
+            };
+            let found = infos[next..].iter().position(|info| {
+                matches!(
+                    &info[1],
+                    ColumnarValue::Scalar(ScalarValue::Utf8(Some(e)) | ScalarValue::Utf8View(Some(e)))
+                        if e.eq_ignore_ascii_case(encoding)
+                )
+            });
+            match found {
+                Some(offset) => {
+                    next += offset + 1;
+                    infos[next - 1][0].clone()
+                }
+                None => unknown.clone(),
+            }
+        })
+        .collect()
+}
+
+/// GeoParquet encoding of a WKB or native geometry array, told apart by the
+/// names of its nested fields. `None` for union encoded arrays, which have no
+/// such encoding.
+fn array_encoding(data_type: &DataType) -> Option<&'static str> {
+    match data_type {
+        DataType::Binary | DataType::LargeBinary => Some("WKB"),
+        dt if is_point_type(dt) => Some("point"),
+        DataType::Struct(fields) if fields.first().is_some_and(|f| f.name() == "xmin") => {
+            Some("box")
+        }
+        DataType::List(field) | DataType::LargeList(field) => match field.name().as_str() {
+            "vertices" => Some("linestring"),
+            "rings" => Some("polygon"),
+            "points" => Some("multipoint"),
+            "linestrings" => Some("multilinestring"),
+            "polygons" => Some("multipolygon"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// EPSG code of a CRS given as `EPSG:xxxx`, `OGC:CRS84` or PROJJSON with an
+/// EPSG identifier.
+pub fn srid(crs: &str) -> Option<i32> {
+    let (authority, code) = match serde_json::from_str::<serde_json::Value>(crs) {
+        Ok(projjson) if projjson.is_object() => {
+            let id = projjson.get("id")?;
+            let code = match id.get("code")? {
+                serde_json::Value::String(code) => code.to_owned(),
+                code => code.to_string(),
+            };
+            (id.get("authority")?.as_str()?.to_owned(), code)
+        }
+        _ => crs
+            .split_once(':')
+            .map(|(authority, code)| (authority.to_owned(), code.to_owned()))?,
+    };
+
+    match (authority.to_uppercase().as_str(), code.as_str()) {
+        ("OGC", "CRS84") => Some(4326),
+        ("EPSG", code) => code.parse().ok(),
+        _ => None,
+    }
+}
+
 pub fn geom_type(arg: &ColumnarValue) -> Result<GeoParquetGeometryType> {
     let s = scalar_arg_as_str(arg)?;
 
@@ -48,24 +282,13 @@ pub fn geom_type(arg: &ColumnarValue) -> Result<GeoParquetGeometryType> {
 
 pub fn coord_type(data_type: &DataType) -> Option<CoordType> {
     match data_type {
-        DataType::List(l1) => match l1.data_type() {
-            DataType::FixedSizeList(_, _) => Some(CoordType::Interleaved),
-            DataType::Struct(_) => Some(CoordType::Separated),
-            DataType::List(l2) => match l2.data_type() {
-                DataType::FixedSizeList(_, _) => Some(CoordType::Interleaved),
-                DataType::Struct(_) => Some(CoordType::Separated),
-                DataType::List(l1) => match l1.data_type() {
-                    DataType::FixedSizeList(_, _) => Some(CoordType::Interleaved),
-                    DataType::Struct(_) => Some(CoordType::Separated),
-                    _ => None,
-                },
-                _ => None,
-            },
-            _ => None,
-        },
+        DataType::List(field) | DataType::LargeList(field) => coord_type(field.data_type()),
         DataType::FixedSizeList(_, _) => Some(CoordType::Interleaved),
         DataType::Struct(_) => Some(CoordType::Separated),
-        // DataType::Union(union_fields, union_mode) => todo!(),
+        // mixed geometries, the coordinate type is shared by all children
+        DataType::Union(fields, _) => fields
+            .iter()
+            .find_map(|(_, field)| coord_type(field.data_type())),
         _ => None,
     }
 }
@@ -106,15 +329,51 @@ pub fn coord_type(data_type: &DataType) -> Option<CoordType> {
 //     }
 // }
 
-pub fn native_type(arg: &ColumnarValue, geometry_type: GeoParquetGeometryType) -> NativeType {
-    let dt = arg.data_type();
-
-    let ct = coord_type(&dt).unwrap_or(CoordType::Separated);
+/// Native GeoArrow type of a geometry array.
+///
+/// Union encoded (mixed and geometry collection) and rect arrays are
+/// recognized by their data type, the others need the geometry type appended
+/// by the [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule) to tell
+/// apart e.g. line strings from multi points.
+pub fn native_type(geoms: &ArrayRef, geometry_type: &ColumnarValue) -> Result<NativeType> {
+    let dt = geoms.data_type();
+    let ct = coord_type(dt).unwrap_or(CoordType::Separated);
 
     use Dimension::*;
-    use GeoParquetGeometryType::*;
+
+    match dt {
+        DataType::Union(fields, _) => return Ok(NativeType::Mixed(ct, union_dimension(fields))),
+        DataType::List(field) | DataType::LargeList(field) => {
+            if let DataType::Union(fields, _) = field.data_type() {
+                return Ok(NativeType::GeometryCollection(ct, union_dimension(fields)));
+            }
+        }
+        DataType::Struct(fields) if fields.first().is_some_and(|f| f.name() == "xmin") => {
+            return match fields.len() {
+                6 => Ok(NativeType::Rect(XYZ)),
+                _ => Ok(NativeType::Rect(XY)),
+            };
+        }
+        _ => {}
+    }
 
     match geometry_type {
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(t)) | ScalarValue::Utf8View(Some(t)))
+            if has_measures(t) =>
+        {
+            return Err(native_measures_error(t))
+        }
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(_)) | ScalarValue::Utf8View(Some(_))) => {}
+        _ => {
+            return Err(DataFusionError::Plan(format!(
+                "Unknown geometry type of native geometry argument `{dt}`"
+            )))
+        }
+    }
+
+    use GeoParquetGeometryType::*;
+
+    Ok(match geom_type(geometry_type)? {
         Point => NativeType::Point(ct, XY),
         LineString => NativeType::LineString(ct, XY),
         Polygon => NativeType::Polygon(ct, XY),
@@ -129,5 +388,132 @@ pub fn native_type(arg: &ColumnarValue, geometry_type: GeoParquetGeometryType) -
         MultiLineStringZ => NativeType::MultiLineString(ct, XYZ),
         MultiPolygonZ => NativeType::MultiPolygon(ct, XYZ),
         GeometryCollectionZ => NativeType::GeometryCollection(ct, XYZ),
+    })
+}
+
+/// Whether a geometry type name like `LineString ZM` has measures.
+pub fn has_measures(geometry_type: &str) -> bool {
+    geometry_type.ends_with(" M") || geometry_type.ends_with(" ZM")
+}
+
+/// Error for native geometries with measures. GeoArrow arrays of this crate
+/// only hold XY and XYZ coordinates, XYM and XYZM geometries are kept as WKB.
+pub fn native_measures_error(geometry_type: &str) -> DataFusionError {
+    DataFusionError::Plan(format!(
+        "Native `{geometry_type}` geometries are not supported, \
+         geometries with measures (M and ZM) are only supported as WKB"
+    ))
+}
+
+/// Dimension of mixed geometries, taken from the coordinates of their children.
+fn union_dimension(fields: &UnionFields) -> Dimension {
+    let dimensions = fields
+        .iter()
+        .find_map(|(_, field)| coord_dimensions(field.data_type()));
+
+    match dimensions {
+        Some(3) => Dimension::XYZ,
+        _ => Dimension::XY,
+    }
+}
+
+/// Number of ordinates of the coordinates of a native geometry data type.
+fn coord_dimensions(data_type: &DataType) -> Option<usize> {
+    match data_type {
+        DataType::List(field) | DataType::LargeList(field) => coord_dimensions(field.data_type()),
+        DataType::FixedSizeList(_, size) => Some(*size as usize),
+        DataType::Struct(fields) if fields.first().is_some_and(|f| f.name() == "x") => {
+            Some(fields.len())
+        }
+        DataType::Union(fields, _) => fields
+            .iter()
+            .find_map(|(_, field)| coord_dimensions(field.data_type())),
+        _ => None,
+    }
+}
+
+/// Decode a native geometry array with the type given by [`native_type`].
+pub fn native_array(geoms: &ArrayRef, geometry_type: &ColumnarValue) -> Result<NativeArrayDyn> {
+    let native_type = native_type(geoms, geometry_type)?;
+
+    NativeArrayDyn::from_arrow_array(geoms, &native_type.to_field("geometry", true))
+        .map_err(|e| DataFusionError::Internal(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::new_empty_array;
+
+    use super::*;
+
+    #[test]
+    fn srid_from_crs() {
+        assert_eq!(srid("EPSG:2056"), Some(2056));
+        assert_eq!(srid("OGC:CRS84"), Some(4326));
+        assert_eq!(
+            srid(r#"{"type":"GeographicCRS","id":{"authority":"EPSG","code":4326}}"#),
+            Some(4326)
+        );
+        assert_eq!(srid(r#"{"type":"GeographicCRS"}"#), None);
+        assert_eq!(srid("PROJCS[\"CH1903+ / LV95\"]"), None);
+    }
+
+    #[test]
+    fn union_native_types() {
+        let unknown = ColumnarValue::Scalar(ScalarValue::Null);
+
+        for native in [
+            NativeType::Mixed(CoordType::Separated, Dimension::XY),
+            NativeType::Mixed(CoordType::Interleaved, Dimension::XYZ),
+            NativeType::GeometryCollection(CoordType::Separated, Dimension::XYZ),
+            NativeType::Rect(Dimension::XY),
+        ] {
+            let geoms = new_empty_array(&native.to_data_type());
+            assert_eq!(native_type(&geoms, &unknown).unwrap(), native);
+        }
+    }
+
+    #[test]
+    fn geometry_types_by_encoding() {
+        let utf8 = |s: &str| ColumnarValue::Scalar(ScalarValue::Utf8(Some(s.to_string())));
+        let info = |geometry_type: &str, encoding: &str| {
+            let unknown = ColumnarValue::Scalar(ScalarValue::Null);
+            vec![
+                utf8(geometry_type),
+                utf8(encoding),
+                unknown.clone(),
+                unknown,
+            ]
+        };
+        let array = |native: NativeType| new_empty_array(&native.to_data_type());
+
+        let wkb = new_empty_array(&DataType::Binary);
+        let line = array(NativeType::LineString(CoordType::Separated, Dimension::XY));
+        let points = array(NativeType::MultiPoint(CoordType::Separated, Dimension::XY));
+
+        // the info of a WKB column is not taken for a native argument
+        let infos = [info("Polygon", "WKB"), info("MultiPoint", "multipoint")].concat();
+        let types = geometry_types(&[wkb.clone(), wkb.clone(), points.clone()], &infos);
+        assert!(
+            matches!(&types[0], ColumnarValue::Scalar(ScalarValue::Utf8(Some(t))) if t == "Polygon")
+        );
+        assert!(matches!(
+            &types[1],
+            ColumnarValue::Scalar(ScalarValue::Null)
+        ));
+        assert!(
+            matches!(&types[2], ColumnarValue::Scalar(ScalarValue::Utf8(Some(t))) if t == "MultiPoint")
+        );
+
+        // nor the info of another native argument laid out alike
+        let infos = info("MultiPoint", "multipoint");
+        let types = geometry_types(&[line, points], &infos);
+        assert!(matches!(
+            &types[0],
+            ColumnarValue::Scalar(ScalarValue::Null)
+        ));
+        assert!(
+            matches!(&types[1], ColumnarValue::Scalar(ScalarValue::Utf8(Some(t))) if t == "MultiPoint")
+        );
     }
 }
@@ -0,0 +1,147 @@
+use datafusion::{
+    arrow::array::ArrayRef,
+    error::{DataFusionError, Result},
+};
+use geo_traits::GeometryTrait;
+use geoarrow::{
+    array::{
+        CoordType, LineStringBuilder, MultiLineStringBuilder, MultiPointBuilder,
+        MultiPolygonBuilder, PointBuilder, PolygonBuilder,
+    },
+    datatypes::{Dimension, NativeType},
+    ArrayBase,
+};
+
+/// Builder for single typed native geometry arrays, used by the readers to
+/// assemble geometry columns batch by batch.
+pub(crate) enum GeometryBuilder {
+    Point2(PointBuilder<2>),
+    LineString2(LineStringBuilder<2>),
+    Polygon2(PolygonBuilder<2>),
+    MultiPoint2(MultiPointBuilder<2>),
+    MultiLineString2(MultiLineStringBuilder<2>),
+    MultiPolygon2(MultiPolygonBuilder<2>),
+    Point3(PointBuilder<3>),
+    LineString3(LineStringBuilder<3>),
+    Polygon3(PolygonBuilder<3>),
+    MultiPoint3(MultiPointBuilder<3>),
+    MultiLineString3(MultiLineStringBuilder<3>),
+    MultiPolygon3(MultiPolygonBuilder<3>),
+}
+
+macro_rules! dispatch {
+    ($self:expr, $builder:ident => $body:expr) => {
+        match $self {
+            GeometryBuilder::Point2($builder) => $body,
+            GeometryBuilder::LineString2($builder) => $body,
+            GeometryBuilder::Polygon2($builder) => $body,
+            GeometryBuilder::MultiPoint2($builder) => $body,
+            GeometryBuilder::MultiLineString2($builder) => $body,
+            GeometryBuilder::MultiPolygon2($builder) => $body,
+            GeometryBuilder::Point3($builder) => $body,
+            GeometryBuilder::LineString3($builder) => $body,
+            GeometryBuilder::Polygon3($builder) => $body,
+            GeometryBuilder::MultiPoint3($builder) => $body,
+            GeometryBuilder::MultiLineString3($builder) => $body,
+            GeometryBuilder::MultiPolygon3($builder) => $body,
+        }
+    };
+}
+
+impl GeometryBuilder {
+    pub fn try_new(native_type: &NativeType) -> Result<Self> {
+        use Dimension::*;
+        use NativeType::*;
+
+        let ct = CoordType::Separated;
+        let md = Default::default();
+
+        Ok(match native_type {
+            Point(_, XY) => Self::Point2(PointBuilder::new_with_options(ct, md)),
+            LineString(_, XY) => Self::LineString2(LineStringBuilder::new_with_options(ct, md)),
+            Polygon(_, XY) => Self::Polygon2(PolygonBuilder::new_with_options(ct, md)),
+            MultiPoint(_, XY) => Self::MultiPoint2(MultiPointBuilder::new_with_options(ct, md)),
+            MultiLineString(_, XY) => {
+                Self::MultiLineString2(MultiLineStringBuilder::new_with_options(ct, md))
+            }
+            MultiPolygon(_, XY) => {
+                Self::MultiPolygon2(MultiPolygonBuilder::new_with_options(ct, md))
+            }
+            Point(_, XYZ) => Self::Point3(PointBuilder::new_with_options(ct, md)),
+            LineString(_, XYZ) => Self::LineString3(LineStringBuilder::new_with_options(ct, md)),
+            Polygon(_, XYZ) => Self::Polygon3(PolygonBuilder::new_with_options(ct, md)),
+            MultiPoint(_, XYZ) => Self::MultiPoint3(MultiPointBuilder::new_with_options(ct, md)),
+            MultiLineString(_, XYZ) => {
+                Self::MultiLineString3(MultiLineStringBuilder::new_with_options(ct, md))
+            }
+            MultiPolygon(_, XYZ) => {
+                Self::MultiPolygon3(MultiPolygonBuilder::new_with_options(ct, md))
+            }
+            nt => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Geometry builder for `{nt:?}`"
+                )))
+            }
+        })
+    }
+
+    /// The native type of the arrays built.
+    pub fn native_type(&self) -> NativeType {
+        use Dimension::*;
+
+        let ct = CoordType::Separated;
+
+        match self {
+            Self::Point2(_) => NativeType::Point(ct, XY),
+            Self::LineString2(_) => NativeType::LineString(ct, XY),
+            Self::Polygon2(_) => NativeType::Polygon(ct, XY),
+            Self::MultiPoint2(_) => NativeType::MultiPoint(ct, XY),
+            Self::MultiLineString2(_) => NativeType::MultiLineString(ct, XY),
+            Self::MultiPolygon2(_) => NativeType::MultiPolygon(ct, XY),
+            Self::Point3(_) => NativeType::Point(ct, XYZ),
+            Self::LineString3(_) => NativeType::LineString(ct, XYZ),
+            Self::Polygon3(_) => NativeType::Polygon(ct, XYZ),
+            Self::MultiPoint3(_) => NativeType::MultiPoint(ct, XYZ),
+            Self::MultiLineString3(_) => NativeType::MultiLineString(ct, XYZ),
+            Self::MultiPolygon3(_) => NativeType::MultiPolygon(ct, XYZ),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        dispatch!(self, b => b.len())
+    }
+
+    pub fn push_geometry(&mut self, geometry: Option<&impl GeometryTrait<T = f64>>) -> Result<()> {
+        dispatch!(self, b => b.push_geometry(geometry))
+            .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+
+    /// Push an empty point: NaN coordinates in point arrays, an empty multi
+    /// point in multi point arrays.
+    pub fn push_empty_point(&mut self) -> Result<()> {
+        match self {
+            Self::Point2(b) => b.push_empty(),
+            Self::Point3(b) => b.push_empty(),
+            Self::MultiPoint2(_) | Self::MultiPoint3(_) => {
+                let empty = geo::MultiPoint::<f64>(vec![]);
+                return self.push_geometry(Some(&empty));
+            }
+            b => {
+                return Err(DataFusionError::Execution(format!(
+                    "Empty point in a `{:?}` geometry column",
+                    b.native_type()
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish the array built so far and reset the builder.
+    pub fn finish(&mut self) -> Result<ArrayRef> {
+        let empty = Self::try_new(&self.native_type())?;
+        let builder = std::mem::replace(self, empty);
+
+        Ok(dispatch!(builder, b => b.finish().to_array_ref()))
+    }
+}
@@ -0,0 +1,502 @@
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    str::FromStr,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, AsArray, BinaryArray, RecordBatch},
+        compute::cast,
+        datatypes::{DataType, Field, Float64Type, Schema, SchemaRef},
+    },
+    catalog::{Session, TableProviderFactory},
+    datasource::{provider::DefaultTableFactory, TableProvider},
+    error::{DataFusionError, Result},
+    execution::TaskContext,
+    logical_expr::{CreateExternalTable, Expr, TableType},
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        collect, stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionPlan,
+        PlanProperties, SendableRecordBatchStream,
+    },
+};
+use futures::StreamExt;
+use geoarrow::datatypes::NativeType;
+
+use super::{builder::GeometryBuilder, native_target_type, prepare_batches, wkb_field};
+use crate::wkb;
+
+/// Options of the CSV reader handled by this crate, all other options are
+/// passed on to DataFusion's CSV format.
+const GEOMETRY_OPTIONS: &[&str] = &[
+    "geometry_column",
+    "geometry_format",
+    "geometry_type",
+    "x_column",
+    "y_column",
+];
+
+/// Column names considered when no geometry column is given.
+const WKT_COLUMNS: &[&str] = &["geometry", "geom", "the_geom", "wkt"];
+const X_COLUMNS: &[&str] = &["x", "lon", "lng", "long", "longitude"];
+const Y_COLUMNS: &[&str] = &["y", "lat", "latitude"];
+
+/// Create a CSV table, parsing a WKT or x/y columns into a native geometry
+/// column when one of the geometry options is given, e.g.
+///
+/// ```sql
+/// CREATE EXTERNAL TABLE t STORED AS CSV LOCATION 'data/data-point-wkt.csv'
+/// OPTIONS ('format.has_header' 'true', 'geometry_format' 'wkt', 'geometry_column' 'geometry');
+/// ```
+pub(crate) async fn create(
+    state: &dyn Session,
+    cmd: &CreateExternalTable,
+) -> Result<Arc<dyn TableProvider>> {
+    let mut cmd = cmd.clone();
+    let mut options = HashMap::new();
+    cmd.options.retain(|key, value| {
+        let key = key.trim_start_matches("format.");
+        if GEOMETRY_OPTIONS.contains(&key) {
+            options.insert(key.to_string(), value.clone());
+            false
+        } else {
+            true
+        }
+    });
+
+    let inner = DefaultTableFactory::new().create(state, &cmd).await?;
+    if options.is_empty() {
+        return Ok(inner);
+    }
+
+    Ok(Arc::new(
+        CsvGeometryTable::try_new(inner, state, &options).await?,
+    ))
+}
+
+/// Where the geometry of a row is read from.
+#[derive(Debug, Clone, Copy)]
+enum GeometrySource {
+    Wkt(usize),
+    XY(usize, usize),
+}
+
+fn find_column(schema: &Schema, name: Option<&String>, candidates: &[&str]) -> Result<usize> {
+    match name {
+        Some(name) => Ok(schema.index_of(name)?),
+        None => schema
+            .fields()
+            .iter()
+            .position(|f| candidates.contains(&f.name().to_lowercase().as_str()))
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "No geometry column found, expected one of {candidates:?}"
+                ))
+            }),
+    }
+}
+
+/// Parse a WKT geometry, `None` for empty text.
+fn parse_wkt(text: &str) -> Result<Option<wkt::Wkt<f64>>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    wkt::Wkt::<f64>::from_str(text)
+        .map(Some)
+        .map_err(|e| DataFusionError::Execution(format!("Invalid WKT `{text}`: {e}")))
+}
+
+/// Parse WKT keeping Z and M coordinates.
+fn parse_wkb(text: &str) -> Result<Option<wkb::Geometry>> {
+    Ok(parse_wkt(text)?.map(|wkt| wkb::from_wkt(&wkt)))
+}
+
+/// Parse WKT into a native builder. Empty points, which `geo` can't hold, are
+/// pushed as such.
+fn push_wkt(builder: &mut GeometryBuilder, text: Option<&str>) -> Result<()> {
+    match text.map(parse_wkt).transpose()?.flatten() {
+        Some(wkt::Wkt::Point(wkt::types::Point(None))) => builder.push_empty_point(),
+        Some(wkt) => {
+            let geometry = geo::Geometry::try_from(wkt).map_err(|e| {
+                DataFusionError::Execution(format!(
+                    "Unsupported WKT `{}`: {e}",
+                    text.unwrap_or_default().trim()
+                ))
+            })?;
+            builder.push_geometry(Some(&geometry))
+        }
+        None => builder.push_geometry(None::<&geo::Geometry>),
+    }
+}
+
+/// Native type for WKT with the given geometry types, `None` for WKB if any
+/// of them has Z or M coordinates which `geo` geometries can't hold, or if
+/// they need a mixed or collection type without a native GeoParquet encoding.
+fn wkt_target_type(geometry_types: &BTreeSet<String>) -> Option<NativeType> {
+    match geometry_types.iter().any(|t| t.contains(' ')) {
+        true => None,
+        false => native_target_type(geometry_types).ok(),
+    }
+}
+
+/// Parses the geometry of CSV batches into a native or WKB geometry column.
+#[derive(Debug)]
+struct GeometryParser {
+    source: GeometrySource,
+    /// `None` for WKB
+    native_type: Option<NativeType>,
+    schema: SchemaRef,
+}
+
+impl GeometryParser {
+    fn parse(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let Some(native_type) = &self.native_type else {
+            let GeometrySource::Wkt(index) = self.source else {
+                unreachable!("x/y columns are always parsed into points")
+            };
+            let wkt = cast(batch.column(index), &DataType::Utf8)?;
+            let wkb = wkt
+                .as_string::<i32>()
+                .iter()
+                .map(|text| {
+                    Ok(text
+                        .map(parse_wkb)
+                        .transpose()?
+                        .flatten()
+                        .map(|geometry| geometry.to_wkb()))
+                })
+                .collect::<Result<BinaryArray>>()?;
+
+            let mut columns = batch.columns().to_vec();
+            columns[index] = Arc::new(wkb) as ArrayRef;
+            return Ok(RecordBatch::try_new(self.schema.clone(), columns)?);
+        };
+
+        let mut builder = GeometryBuilder::try_new(native_type)?;
+
+        match self.source {
+            GeometrySource::Wkt(index) => {
+                let wkt = cast(batch.column(index), &DataType::Utf8)?;
+                for text in wkt.as_string::<i32>().iter() {
+                    push_wkt(&mut builder, text)?;
+                }
+            }
+            GeometrySource::XY(x, y) => {
+                let x = cast(batch.column(x), &DataType::Float64)?;
+                let y = cast(batch.column(y), &DataType::Float64)?;
+                let x = x.as_primitive::<Float64Type>();
+                let y = y.as_primitive::<Float64Type>();
+                for (x, y) in x.iter().zip(y.iter()) {
+                    let point = x
+                        .zip(y)
+                        .map(|(x, y)| geo::Geometry::Point(geo::Point::new(x, y)));
+                    builder.push_geometry(point.as_ref())?;
+                }
+            }
+        }
+
+        let mut columns = batch.columns().to_vec();
+        match self.source {
+            GeometrySource::Wkt(index) => columns[index] = builder.finish()?,
+            GeometrySource::XY(_, _) => columns.push(builder.finish()?),
+        }
+
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+/// CSV table with a geometry column parsed during the scan.
+#[derive(Debug)]
+pub struct CsvGeometryTable {
+    inner: Arc<dyn TableProvider>,
+    parser: Arc<GeometryParser>,
+}
+
+impl CsvGeometryTable {
+    async fn try_new(
+        inner: Arc<dyn TableProvider>,
+        state: &dyn Session,
+        options: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let schema = inner.schema();
+        let format = options.get("geometry_format").map(|f| f.to_lowercase());
+
+        let source = match format.as_deref() {
+            Some("xy" | "lonlat" | "point") => GeometrySource::XY(
+                find_column(&schema, options.get("x_column"), X_COLUMNS)?,
+                find_column(&schema, options.get("y_column"), Y_COLUMNS)?,
+            ),
+            None if options.contains_key("x_column") || options.contains_key("y_column") => {
+                GeometrySource::XY(
+                    find_column(&schema, options.get("x_column"), X_COLUMNS)?,
+                    find_column(&schema, options.get("y_column"), Y_COLUMNS)?,
+                )
+            }
+            Some("wkt") | None => GeometrySource::Wkt(find_column(
+                &schema,
+                options.get("geometry_column"),
+                WKT_COLUMNS,
+            )?),
+            Some(format) => {
+                return Err(DataFusionError::Configuration(format!(
+                    "Unsupported geometry format `{format}`"
+                )))
+            }
+        };
+
+        let native_type = match (options.get("geometry_type"), source) {
+            (Some(geometry_type), GeometrySource::Wkt(_)) => {
+                wkt_target_type(&BTreeSet::from([geometry_type.to_owned()]))
+            }
+            (Some(geometry_type), GeometrySource::XY(_, _)) => {
+                Some(native_target_type(&BTreeSet::from([
+                    geometry_type.to_owned()
+                ]))?)
+            }
+            (None, GeometrySource::XY(_, _)) => {
+                Some(native_target_type(&BTreeSet::from(["Point".to_string()]))?)
+            }
+            (None, GeometrySource::Wkt(index)) => {
+                infer_native_type(inner.as_ref(), state, index).await?
+            }
+        };
+        let field = |name: &str| match &native_type {
+            Some(native_type) => native_type.to_field(name, true),
+            None => wkb_field(&Field::new(name, DataType::Binary, true)),
+        };
+
+        let mut fields = schema.fields().iter().cloned().collect::<Vec<_>>();
+        match source {
+            GeometrySource::Wkt(index) => fields[index] = field(fields[index].name()).into(),
+            GeometrySource::XY(_, _) => {
+                let name = options
+                    .get("geometry_column")
+                    .map(String::as_str)
+                    .unwrap_or("geometry");
+                fields.push(field(name).into())
+            }
+        }
+        let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+        let (schema, _) = prepare_batches(schema, vec![])?;
+
+        Ok(Self {
+            inner,
+            parser: Arc::new(GeometryParser {
+                source,
+                native_type,
+                schema,
+            }),
+        })
+    }
+}
+
+/// Infer the geometry type of a WKT column from the rows of all partitions,
+/// `None` for WKB.
+async fn infer_native_type(
+    table: &dyn TableProvider,
+    state: &dyn Session,
+    index: usize,
+) -> Result<Option<NativeType>> {
+    let plan = table.scan(state, Some(&vec![index]), &[], None).await?;
+    let batches = collect(plan, state.task_ctx()).await?;
+
+    let mut geometry_types = BTreeSet::new();
+    for batch in batches {
+        let wkt = cast(batch.column(0), &DataType::Utf8)?;
+        for text in wkt.as_string::<i32>().iter().flatten() {
+            if let Some(geometry) = parse_wkb(text)? {
+                geometry_types.insert(geometry.type_name());
+            }
+        }
+    }
+
+    Ok(wkt_target_type(&geometry_types))
+}
+
+#[async_trait]
+impl TableProvider for CsvGeometryTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.parser.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let input = self.inner.scan(state, None, &[], limit).await?;
+
+        Ok(Arc::new(ParseGeometryExec::try_new(
+            input,
+            self.parser.clone(),
+            projection.cloned(),
+        )?))
+    }
+}
+
+/// Execution plan parsing the geometry column of its input batches.
+#[derive(Debug)]
+struct ParseGeometryExec {
+    input: Arc<dyn ExecutionPlan>,
+    parser: Arc<GeometryParser>,
+    projection: Option<Vec<usize>>,
+    properties: PlanProperties,
+}
+
+impl ParseGeometryExec {
+    fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        parser: Arc<GeometryParser>,
+        projection: Option<Vec<usize>>,
+    ) -> Result<Self> {
+        let schema = match &projection {
+            Some(projection) => Arc::new(parser.schema.project(projection)?),
+            None => parser.schema.clone(),
+        };
+
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema),
+            input.output_partitioning().clone(),
+            input.properties().execution_mode,
+        );
+
+        Ok(Self {
+            input,
+            parser,
+            projection,
+            properties,
+        })
+    }
+}
+
+impl DisplayAs for ParseGeometryExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, "ParseGeometryExec: source={:?}", self.parser.source)
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for ParseGeometryExec {
+    fn name(&self) -> &str {
+        "ParseGeometryExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![&self.input]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::try_new(
+            children[0].clone(),
+            self.parser.clone(),
+            self.projection.clone(),
+        )?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let parser = self.parser.clone();
+        let projection = self.projection.clone();
+
+        let stream = self.input.execute(partition, context)?.map(move |batch| {
+            let batch = parser.parse(&batch?)?;
+            match &projection {
+                Some(projection) => Ok(batch.project(projection)?),
+                None => Ok(batch),
+            }
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.properties.eq_properties.schema().clone(),
+            stream,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geoarrow::{
+        array::{AsNativeArray, CoordType, NativeArrayDyn},
+        datatypes::Dimension,
+        trait_::ArrayAccessor,
+    };
+
+    use super::*;
+
+    #[test]
+    fn wkt() {
+        assert_eq!(parse_wkt("").unwrap(), None);
+        assert!(parse_wkt("POINT (30").is_err());
+
+        let native_type = NativeType::Point(CoordType::Separated, Dimension::XY);
+        let mut builder = GeometryBuilder::try_new(&native_type).unwrap();
+        for text in [Some("POINT (30 10)"), Some("POINT EMPTY"), None] {
+            push_wkt(&mut builder, text).unwrap();
+        }
+        let array = builder.finish().unwrap();
+        let field = native_type.to_field("geometry", true);
+        let points = NativeArrayDyn::from_arrow_array(&array, &field).unwrap();
+        let points = points
+            .as_ref()
+            .as_point::<2>()
+            .iter_geo()
+            .collect::<Vec<_>>();
+
+        assert_eq!(points[0], Some(geo::Point::new(30., 10.)));
+        // empty points are valid with NaN coordinates
+        assert!(points[1].is_some_and(|p| p.x().is_nan()));
+        assert_eq!(points[2], None);
+    }
+
+    #[test]
+    fn wkt_fallback() {
+        // mixed geometry types and collections are read as WKB
+        let types = BTreeSet::from(["Point".to_string(), "LineString".to_string()]);
+        assert!(wkt_target_type(&types).is_none());
+        let types = BTreeSet::from(["GeometryCollection".to_string()]);
+        assert!(wkt_target_type(&types).is_none());
+    }
+
+    #[test]
+    fn wkt_measures() {
+        let geometry = parse_wkb("LINESTRING ZM (1 2 3 4, 5 6 7 8)")
+            .unwrap()
+            .unwrap();
+        assert_eq!(geometry.type_name(), "LineString ZM");
+        assert!(wkt_target_type(&BTreeSet::from([geometry.type_name()])).is_none());
+        assert!(wkt_target_type(&BTreeSet::from(["Point".to_string()])).is_some());
+    }
+}
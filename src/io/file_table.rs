@@ -0,0 +1,363 @@
+//! Tables of spatial files without a [`FileFormat`](datafusion::datasource::file_format::FileFormat),
+//! read when scanned.
+
+use std::{
+    any::Any,
+    collections::BTreeSet,
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        array::RecordBatch,
+        datatypes::{Schema, SchemaRef},
+    },
+    catalog::Session,
+    datasource::TableProvider,
+    error::{DataFusionError, Result},
+    execution::TaskContext,
+    logical_expr::{Expr, TableType},
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionMode,
+        ExecutionPlan, Partitioning, PlanProperties, SendableRecordBatchStream,
+    },
+};
+use futures::{StreamExt, TryStreamExt};
+use serde_json::Value;
+
+use super::local_path;
+
+type ReadFn = dyn Fn(&Path) -> Result<(SchemaRef, Vec<RecordBatch>)> + Send + Sync;
+
+/// Table of a single file or all files with one of the given extensions in a
+/// directory, one partition per file.
+///
+/// Unless the schema is known in advance, the files are read once when the
+/// table is created to infer the schema and merge the `geo` metadata of all
+/// files, and again file by file whenever the table is scanned. Only the batches of the files being scanned are kept in
+/// memory.
+pub(crate) struct FileTable {
+    paths: Vec<PathBuf>,
+    schema: SchemaRef,
+    read: Arc<ReadFn>,
+}
+
+impl FileTable {
+    pub(crate) fn try_new(
+        location: &str,
+        extensions: &[&str],
+        read: impl Fn(&Path) -> Result<(SchemaRef, Vec<RecordBatch>)> + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let paths = file_paths(location, extensions)?;
+
+        let mut schema: Option<Schema> = None;
+        for path in paths.iter() {
+            let (file_schema, _) = read(path)?;
+            schema = Some(match schema {
+                Some(schema) => merge_schemas(schema, &file_schema, path)?,
+                None => file_schema.as_ref().clone(),
+            });
+        }
+
+        let schema = schema.ok_or_else(|| no_files(location, extensions))?;
+
+        Ok(Self {
+            paths,
+            schema: Arc::new(schema),
+            read: Arc::new(read),
+        })
+    }
+
+    /// Table of files read with a schema known in advance, the files are
+    /// only read when the table is scanned.
+    pub(crate) fn try_new_with_schema(
+        location: &str,
+        extensions: &[&str],
+        schema: SchemaRef,
+        read: impl Fn(&Path) -> Result<(SchemaRef, Vec<RecordBatch>)> + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let paths = file_paths(location, extensions)?;
+        if paths.is_empty() {
+            return Err(no_files(location, extensions));
+        }
+
+        Ok(Self {
+            paths,
+            schema,
+            read: Arc::new(read),
+        })
+    }
+}
+
+impl fmt::Debug for FileTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileTable")
+            .field("paths", &self.paths)
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TableProvider for FileTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(FileExec::try_new(
+            self.paths.clone(),
+            self.schema.clone(),
+            projection.cloned(),
+            self.read.clone(),
+        )?))
+    }
+}
+
+/// Execution plan reading one file per partition.
+struct FileExec {
+    paths: Vec<PathBuf>,
+    schema: SchemaRef,
+    projection: Option<Vec<usize>>,
+    read: Arc<ReadFn>,
+    properties: PlanProperties,
+}
+
+impl FileExec {
+    fn try_new(
+        paths: Vec<PathBuf>,
+        schema: SchemaRef,
+        projection: Option<Vec<usize>>,
+        read: Arc<ReadFn>,
+    ) -> Result<Self> {
+        let projected = match &projection {
+            Some(projection) => Arc::new(schema.project(projection)?),
+            None => schema.clone(),
+        };
+
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(projected),
+            Partitioning::UnknownPartitioning(paths.len()),
+            ExecutionMode::Bounded,
+        );
+
+        Ok(Self {
+            paths,
+            schema,
+            projection,
+            read,
+            properties,
+        })
+    }
+}
+
+impl fmt::Debug for FileExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileExec")
+            .field("paths", &self.paths)
+            .field("projection", &self.projection)
+            .finish()
+    }
+}
+
+impl DisplayAs for FileExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, "FileExec: files={}", self.paths.len())
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for FileExec {
+    fn name(&self) -> &str {
+        "FileExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let path = self.paths[partition].clone();
+        let schema = self.schema.clone();
+        let projection = self.projection.clone();
+        let read = self.read.clone();
+
+        // the file is only read once the partition is polled
+        let batches = futures::stream::once(async move {
+            let (file_schema, batches) = tokio::task::spawn_blocking(move || read(&path))
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))??;
+            if file_schema.fields() != schema.fields() {
+                return Err(DataFusionError::Execution(
+                    "Schema of a file changed since the table was created".to_string(),
+                ));
+            }
+
+            let batches = batches.into_iter().map(move |batch| {
+                let batch = RecordBatch::try_new(schema.clone(), batch.columns().to_vec())?;
+                match &projection {
+                    Some(projection) => Ok(batch.project(projection)?),
+                    None => Ok(batch),
+                }
+            });
+            Ok(futures::stream::iter(batches))
+        })
+        .try_flatten();
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.properties.eq_properties.schema().clone(),
+            batches.boxed(),
+        )))
+    }
+}
+
+/// A single file or all files with one of `extensions` in a directory.
+fn file_paths(location: &str, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    let path = local_path(location);
+
+    if !path.is_dir() {
+        return Ok(vec![path]);
+    }
+
+    let mut paths = std::fs::read_dir(&path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.retain(|p| {
+        p.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| extensions.contains(&e.to_lowercase().as_str()))
+    });
+    paths.sort();
+
+    Ok(paths)
+}
+
+fn no_files(location: &str, extensions: &[&str]) -> DataFusionError {
+    DataFusionError::Plan(format!(
+        "No files with extension {extensions:?} in `{location}`"
+    ))
+}
+
+/// Merge the schema of a further file, the fields must match and the `geo`
+/// metadata is combined.
+fn merge_schemas(schema: Schema, file_schema: &Schema, path: &Path) -> Result<Schema> {
+    if schema.fields() != file_schema.fields() {
+        return Err(DataFusionError::Plan(format!(
+            "Schema of `{}` differs from the other files",
+            path.display()
+        )));
+    }
+
+    let geo = |schema: &Schema| {
+        schema
+            .metadata()
+            .get("geo")
+            .and_then(|geo| serde_json::from_str::<Value>(geo).ok())
+    };
+    let (Some(mut geo), Some(file_geo)) = (geo(&schema), geo(file_schema)) else {
+        return Ok(schema);
+    };
+
+    let columns = geo["columns"].as_object_mut().into_iter().flatten();
+    for (name, column) in columns {
+        let file_column = &file_geo["columns"][name];
+
+        for key in ["crs", "edges"] {
+            if column.get(key) != file_column.get(key) {
+                return Err(DataFusionError::Plan(format!(
+                    "`{key}` of column `{name}` in `{}` differs from the other files",
+                    path.display()
+                )));
+            }
+        }
+
+        let geometry_types = [&column["geometry_types"], &file_column["geometry_types"]]
+            .into_iter()
+            .flat_map(|types| types.as_array().into_iter().flatten())
+            .filter_map(|t| t.as_str().map(str::to_string))
+            .collect::<BTreeSet<_>>();
+        column["geometry_types"] = geometry_types.into_iter().collect();
+    }
+
+    let mut metadata = schema.metadata().clone();
+    metadata.insert("geo".to_string(), geo.to_string());
+
+    Ok(schema.with_metadata(metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use datafusion::arrow::datatypes::{DataType, Field};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merged_geometry_types() {
+        let schema = |types: &[&str]| {
+            let geo = json!({
+                "version": "1.1.0",
+                "primary_column": "geometry",
+                "columns": {"geometry": {"encoding": "WKB", "geometry_types": types}},
+            });
+            Schema::new_with_metadata(
+                vec![Field::new("geometry", DataType::Binary, true)],
+                HashMap::from([("geo".to_string(), geo.to_string())]),
+            )
+        };
+
+        let merged = merge_schemas(
+            schema(&["Point"]),
+            &schema(&["MultiPoint", "Point"]),
+            Path::new("b.geojson"),
+        )
+        .unwrap();
+        let geo = serde_json::from_str::<Value>(&merged.metadata()["geo"]).unwrap();
+        assert_eq!(
+            geo["columns"]["geometry"]["geometry_types"],
+            json!(["MultiPoint", "Point"])
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use std::{any::Any, collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        array::{AsArray, RecordBatch},
+        datatypes::{Schema, SchemaRef},
+    },
+    common::{GetExt, Statistics},
+    datasource::{
+        file_format::{file_compression_type::FileCompressionType, FileFormat, FileFormatFactory},
+        physical_plan::{FileScanConfig, FileSinkConfig},
+    },
+    error::{DataFusionError, Result},
+    execution::context::SessionState,
+    physical_expr::{LexRequirement, PhysicalExpr},
+    physical_plan::ExecutionPlan,
+};
+use futures::StreamExt;
+use geo_traits::Dimensions;
+use geoarrow::{
+    array::{CoordType, WKBArray},
+    datatypes::{Dimension, NativeType},
+    error::GeoArrowError,
+    io::{
+        flatgeobuf::{read_flatgeobuf_async, write_flatgeobuf, FlatGeobufReaderOptions},
+        wkb::from_wkb,
+    },
+    table::Table,
+    ArrayBase,
+};
+use object_store::{ObjectMeta, ObjectStore};
+
+use crate::{helpers::native_measures_error, wkb};
+
+use super::{
+    bbox_from_filter, collecting_encoder, create_writer_plan, prepare_batches,
+    scan::{Batches, ObjectScanExec},
+};
+
+/// Factory registering the `FLATGEOBUF` file format with a session.
+#[derive(Debug, Default)]
+pub struct FlatGeobufFormatFactory {}
+
+impl FlatGeobufFormatFactory {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl GetExt for FlatGeobufFormatFactory {
+    fn get_ext(&self) -> String {
+        "flatgeobuf".to_string()
+    }
+}
+
+impl FileFormatFactory for FlatGeobufFormatFactory {
+    fn create(
+        &self,
+        _state: &SessionState,
+        format_options: &HashMap<String, String>,
+    ) -> Result<Arc<dyn FileFormat>> {
+        let mut format = FlatGeobufFormat::default();
+
+        for (key, value) in format_options {
+            match key.trim_start_matches("format.") {
+                "layer_name" => format.layer_name = Some(value.to_owned()),
+                "batch_size" => {
+                    format.batch_size = Some(value.parse().map_err(|_| {
+                        DataFusionError::Configuration(format!(
+                            "Expected positive integer for `batch_size`, got `{value}`"
+                        ))
+                    })?)
+                }
+                _ => {
+                    return Err(DataFusionError::Configuration(format!(
+                        "Unsupported FlatGeobuf option `{key}`"
+                    )))
+                }
+            }
+        }
+
+        Ok(Arc::new(format))
+    }
+
+    fn default(&self) -> Arc<dyn FileFormat> {
+        Arc::new(FlatGeobufFormat::default())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// FlatGeobuf file format.
+///
+/// Spatial predicates with a constant bounding box pushed down into the scan
+/// are answered with the packed Hilbert R-tree of the file, only features
+/// intersecting the bounding box are read.
+#[derive(Debug, Default)]
+pub struct FlatGeobufFormat {
+    layer_name: Option<String>,
+    batch_size: Option<usize>,
+}
+
+/// Bounding box selecting no features, reading only the header and the root
+/// of the spatial index.
+const EMPTY_BBOX: (f64, f64, f64, f64) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+
+/// Read the features of a FlatGeobuf object intersecting `bbox`.
+///
+/// The header, the spatial index and the selected features are fetched with
+/// range requests, only the index nodes and features intersecting `bbox` are
+/// read from the object.
+async fn read_object(
+    store: &Arc<dyn ObjectStore>,
+    object: &ObjectMeta,
+    bbox: Option<(f64, f64, f64, f64)>,
+    batch_size: Option<usize>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let options = FlatGeobufReaderOptions {
+        bbox,
+        batch_size,
+        coord_type: CoordType::Separated,
+    };
+    let table = read_flatgeobuf_async(store.clone(), object.location.clone(), options)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    let (batches, schema) = table.into_inner();
+
+    prepare_batches(schema, batches)
+}
+
+#[async_trait]
+impl FileFormat for FlatGeobufFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_ext(&self) -> String {
+        "fgb".to_string()
+    }
+
+    fn get_ext_with_compression(
+        &self,
+        _file_compression_type: &FileCompressionType,
+    ) -> Result<String> {
+        Ok(self.get_ext())
+    }
+
+    /// The schema is taken from the file header, files with a spatial index
+    /// are queried for an empty bounding box to skip decoding their features.
+    async fn infer_schema(
+        &self,
+        _state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        objects: &[ObjectMeta],
+    ) -> Result<SchemaRef> {
+        let mut schemas = vec![];
+        for object in objects {
+            let schema = match read_object(store, object, Some(EMPTY_BBOX), Some(1)).await {
+                Ok((schema, _)) => schema,
+                // without an index the features have to be read
+                Err(_) => read_object(store, object, None, self.batch_size).await?.0,
+            };
+            schemas.push(schema.as_ref().clone());
+        }
+
+        Ok(Arc::new(Schema::try_merge(schemas)?))
+    }
+
+    async fn infer_stats(
+        &self,
+        _state: &SessionState,
+        _store: &Arc<dyn ObjectStore>,
+        table_schema: SchemaRef,
+        _object: &ObjectMeta,
+    ) -> Result<Statistics> {
+        Ok(Statistics::new_unknown(&table_schema))
+    }
+
+    async fn create_physical_plan(
+        &self,
+        _state: &SessionState,
+        conf: FileScanConfig,
+        filters: Option<&Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let bbox = filters.and_then(bbox_from_filter);
+
+        let batch_size = self.batch_size;
+
+        Ok(Arc::new(ObjectScanExec::try_new(
+            "FlatGeobufExec",
+            conf,
+            format!("bbox={bbox:?}"),
+            move |store, object| {
+                Box::pin(async move {
+                    let (_, batches) = read_object(&store, &object, bbox, batch_size).await?;
+                    Ok(futures::stream::iter(batches.into_iter().map(Ok)).boxed() as Batches)
+                })
+            },
+        )?))
+    }
+
+    async fn create_writer_physical_plan(
+        &self,
+        input: Arc<dyn ExecutionPlan>,
+        _state: &SessionState,
+        conf: FileSinkConfig,
+        order_requirements: Option<LexRequirement>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let layer_name = self.layer_name.clone().unwrap_or_else(|| {
+            conf.table_paths[0]
+                .prefix()
+                .filename()
+                .and_then(|name| name.split('.').next())
+                .unwrap_or("layer")
+                .to_string()
+        });
+
+        create_writer_plan(
+            "FlatGeobuf",
+            input,
+            conf,
+            order_requirements,
+            collecting_encoder(move |schema, batches| write(schema, batches, &layer_name)),
+        )
+    }
+}
+
+/// Encode `batches` as FlatGeobuf with a spatial index, WKB geometry columns
+/// are decoded to native GeoArrow geometries first.
+fn write(schema: SchemaRef, batches: &[RecordBatch], layer_name: &str) -> Result<Vec<u8>> {
+    let mut fields = schema.fields().iter().cloned().collect::<Vec<_>>();
+    let wkb_columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| {
+            field
+                .metadata()
+                .get("ARROW:extension:name")
+                .is_some_and(|name| name == "geoarrow.wkb")
+        })
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+
+    let mut targets = HashMap::new();
+    for index in wkb_columns.iter() {
+        let dimension = wkb_dimension(batches, *index)?;
+        let target = NativeType::Mixed(CoordType::Interleaved, dimension);
+        fields[*index] = target.to_field(schema.field(*index).name(), true).into();
+        targets.insert(*index, target);
+    }
+    let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+    let batches = batches
+        .iter()
+        .map(|batch| {
+            let mut arrays = batch.columns().to_vec();
+            for index in wkb_columns.iter() {
+                let wkb: WKBArray<i32> = WKBArray::try_from(arrays[*index].as_ref())
+                    .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
+                arrays[*index] = from_wkb(&wkb, targets[index].clone(), true)
+                    .map_err(|e| DataFusionError::Internal(e.to_string()))?
+                    .to_array_ref();
+            }
+            Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let table =
+        Table::try_new(batches, schema).map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    let mut buffer = vec![];
+    write_flatgeobuf(table, &mut buffer, layer_name)
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    Ok(buffer)
+}
+
+/// Coordinate dimension of the WKB geometries of a column, XYZ if any of them
+/// has z coordinates.
+fn wkb_dimension(batches: &[RecordBatch], index: usize) -> Result<Dimension> {
+    let mut dimension = Dimension::XY;
+    for batch in batches {
+        for bytes in batch.column(index).as_binary::<i32>().iter().flatten() {
+            let span = wkb::scan(bytes)?;
+            match span.dim {
+                Dimensions::Xyz => dimension = Dimension::XYZ,
+                Dimensions::Xym | Dimensions::Xyzm => {
+                    return Err(native_measures_error(&span.type_name()))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(dimension)
+}
@@ -0,0 +1,64 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use datafusion::{
+    arrow::{array::RecordBatch, datatypes::SchemaRef},
+    error::{DataFusionError, Result},
+};
+use geoarrow::io::{geojson, geojson_lines};
+
+use super::prepare_batches;
+
+fn batch_size(options: &HashMap<String, String>) -> Result<Option<usize>> {
+    options
+        .get("batch_size")
+        .map(|size| {
+            size.parse().map_err(|_| {
+                DataFusionError::Configuration(format!(
+                    "Expected positive integer for `batch_size`, got `{size}`"
+                ))
+            })
+        })
+        .transpose()
+}
+
+/// Read a GeoJSON `FeatureCollection`, properties become columns and the
+/// geometry a native GeoArrow column.
+pub fn read_geojson(
+    path: &Path,
+    options: &HashMap<String, String>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let reader = BufReader::new(File::open(path)?);
+
+    read(reader, options, |reader, batch_size| {
+        geojson::read_geojson(reader, batch_size)
+    })
+}
+
+/// Read newline-delimited GeoJSON, one feature per line.
+pub fn read_geojson_lines(
+    path: &Path,
+    options: &HashMap<String, String>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let reader = BufReader::new(File::open(path)?);
+
+    read(reader, options, |reader, batch_size| {
+        geojson_lines::read_geojson_lines(reader, batch_size)
+    })
+}
+
+fn read<R: Read>(
+    reader: R,
+    options: &HashMap<String, String>,
+    read_table: impl FnOnce(R, Option<usize>) -> geoarrow::error::Result<geoarrow::table::Table>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let table = read_table(reader, batch_size(options)?)
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    let (batches, schema) = table.into_inner();
+
+    prepare_batches(schema, batches)
+}
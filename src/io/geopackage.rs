@@ -0,0 +1,1188 @@
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        array::{
+            Array, ArrayRef, AsArray, BinaryBuilder, BooleanBuilder, Float64Builder, Int64Builder,
+            RecordBatch, RecordBatchOptions, StringBuilder,
+        },
+        compute::cast,
+        datatypes::{DataType, Field, Float64Type, Int64Type, Schema, SchemaRef},
+    },
+    catalog::{SchemaProvider, Session},
+    common::DFSchema,
+    datasource::TableProvider,
+    error::{DataFusionError, Result},
+    execution::TaskContext,
+    logical_expr::{Expr, TableProviderFilterPushDown, TableType},
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionMode,
+        ExecutionPlan, Partitioning, PlanProperties, SendableRecordBatchStream,
+    },
+};
+use geoarrow::{
+    array::{CoordType, NativeArrayDyn, WKBArray},
+    datatypes::{Dimension, NativeType},
+    io::wkb::{from_wkb, to_wkb},
+    ArrayBase,
+};
+use rusqlite::{
+    params,
+    types::{Value as SqlValue, ValueRef},
+    Connection, OpenFlags, OptionalExtension,
+};
+use serde_json::{json, Value};
+
+use super::{bbox_from_filter, prepare_batches, wkb_field};
+use crate::wkb;
+
+const DEFAULT_BATCH_SIZE: usize = 8192;
+
+/// `application_id` of a GeoPackage file, `GPKG` in ASCII.
+const APPLICATION_ID: i32 = 0x4750_4B47;
+
+/// `user_version` of a GeoPackage 1.3 file.
+const USER_VERSION: i32 = 10300;
+
+const CREATE_TABLES: &str = r#"
+CREATE TABLE IF NOT EXISTS gpkg_spatial_ref_sys (
+    srs_name TEXT NOT NULL,
+    srs_id INTEGER PRIMARY KEY,
+    organization TEXT NOT NULL,
+    organization_coordsys_id INTEGER NOT NULL,
+    definition TEXT NOT NULL,
+    description TEXT
+);
+CREATE TABLE IF NOT EXISTS gpkg_contents (
+    table_name TEXT NOT NULL PRIMARY KEY,
+    data_type TEXT NOT NULL,
+    identifier TEXT UNIQUE,
+    description TEXT DEFAULT '',
+    last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+    min_x DOUBLE,
+    min_y DOUBLE,
+    max_x DOUBLE,
+    max_y DOUBLE,
+    srs_id INTEGER,
+    CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+);
+CREATE TABLE IF NOT EXISTS gpkg_geometry_columns (
+    table_name TEXT NOT NULL,
+    column_name TEXT NOT NULL,
+    geometry_type_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL,
+    z TINYINT NOT NULL,
+    m TINYINT NOT NULL,
+    CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+    CONSTRAINT uk_gc_table_name UNIQUE (table_name),
+    CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+    CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES
+    ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined cartesian coordinate reference system'),
+    ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system'),
+    ('WGS 84 geodetic', 4326, 'EPSG', 4326, 'GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AXIS["Latitude",NORTH],AXIS["Longitude",EAST],AUTHORITY["EPSG","4326"]]', 'longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid');
+"#;
+
+/// Schema provider listing the feature tables of a GeoPackage.
+///
+/// ```ignore
+/// let schema = GeoPackageSchemaProvider::try_new("data.gpkg")?;
+/// ctx.catalog("datafusion").unwrap().register_schema("gpkg", Arc::new(schema))?;
+/// ctx.sql("SELECT * FROM gpkg.buildings").await?;
+/// ```
+#[derive(Debug)]
+pub struct GeoPackageSchemaProvider {
+    path: PathBuf,
+    layers: Vec<String>,
+}
+
+impl GeoPackageSchemaProvider {
+    pub fn try_new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let layers = layers(&open(&path)?)?;
+
+        Ok(Self { path, layers })
+    }
+}
+
+#[async_trait]
+impl SchemaProvider for GeoPackageSchemaProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.layers.clone()
+    }
+
+    async fn table(&self, name: &str) -> Result<Option<Arc<dyn TableProvider>>> {
+        if !self.table_exist(name) {
+            return Ok(None);
+        }
+
+        let table = GeoPackageTable::try_new(&self.path, name, &HashMap::new())?;
+        Ok(Some(Arc::new(table)))
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.layers.iter().any(|layer| layer == name)
+    }
+}
+
+/// Geometry column of a feature table as registered in `gpkg_geometry_columns`.
+#[derive(Debug, Clone)]
+struct GeometryColumn {
+    name: String,
+    native_type: Option<NativeType>,
+    crs: Option<String>,
+}
+
+/// Feature table of a GeoPackage.
+///
+/// The table is read when its partitions are executed, each partition reading
+/// a range of `rowid`s with only the projected columns. Filters with a
+/// constant bounding box skip features whose envelope, stored in the
+/// GeoPackage geometry header, does not intersect it.
+#[derive(Debug, Clone)]
+pub struct GeoPackageTable {
+    path: PathBuf,
+    layer: String,
+    geometry: GeometryColumn,
+    /// Source schema, without `geo` metadata and before WKB decoding.
+    source_schema: SchemaRef,
+    schema: SchemaRef,
+    batch_size: usize,
+}
+
+impl GeoPackageTable {
+    /// Open the feature table `layer` of the GeoPackage at `path`.
+    pub fn try_new(
+        path: impl AsRef<Path>,
+        layer: &str,
+        options: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let connection = open(&path)?;
+
+        let batch_size = options
+            .get("batch_size")
+            .map(|size| {
+                size.parse::<usize>().map_err(|_| {
+                    DataFusionError::Configuration(format!(
+                        "Expected positive integer for `batch_size`, got `{size}`"
+                    ))
+                })
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_BATCH_SIZE);
+
+        let geometry = geometry_column(&connection, layer)?;
+
+        let mut statement = connection
+            .prepare(&format!("PRAGMA table_info({})", quote(layer)))
+            .map_err(external)?;
+        let columns = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+            })
+            .map_err(external)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(external)?;
+
+        let fields = columns
+            .iter()
+            .map(|(name, declared_type)| {
+                if *name != geometry.name {
+                    return Field::new(name, data_type(declared_type), true);
+                }
+
+                let field = match &geometry.native_type {
+                    Some(native_type) => native_type.to_field(name, true),
+                    None => wkb_field(&Field::new(name, DataType::Binary, true)),
+                };
+                let mut metadata = field.metadata().clone();
+                if let Some(crs) = &geometry.crs {
+                    metadata.insert(
+                        "ARROW:extension:metadata".to_string(),
+                        json!({ "crs": crs }).to_string(),
+                    );
+                }
+                field.with_metadata(metadata)
+            })
+            .collect::<Vec<_>>();
+        let source_schema = Arc::new(Schema::new(fields));
+        let (schema, _) = prepare_batches(source_schema.clone(), vec![])?;
+
+        Ok(Self {
+            path,
+            layer: layer.to_string(),
+            geometry,
+            source_schema,
+            schema,
+            batch_size,
+        })
+    }
+
+    /// Range of the `rowid`s of the feature table, `None` if it is empty.
+    fn rowids(&self) -> Result<Option<(i64, i64)>> {
+        let connection = open(&self.path)?;
+        let (min, max) = connection
+            .query_row(
+                &format!("SELECT MIN(rowid), MAX(rowid) FROM {}", quote(&self.layer)),
+                [],
+                |row| Ok((row.get::<_, Option<i64>>(0)?, row.get::<_, Option<i64>>(1)?)),
+            )
+            .map_err(external)?;
+
+        Ok(min.zip(max))
+    }
+
+    /// Read the rows with `rowid`s in `rowids`, passing batches of the
+    /// projected columns to `send` until it returns `false`.
+    fn read(
+        &self,
+        scan: &GeoPackageScan,
+        rowids: (i64, i64),
+        send: &mut dyn FnMut(RecordBatch) -> bool,
+    ) -> Result<()> {
+        let connection = open(&self.path)?;
+        let geometry_index = self.source_schema.index_of(&self.geometry.name)?;
+
+        // the geometry is read for the bounding box filter even if not projected
+        let mut columns = scan.projection.clone();
+        if scan.bbox.is_some() && !columns.contains(&geometry_index) {
+            columns.push(geometry_index);
+        }
+        let geometry = columns.iter().position(|index| *index == geometry_index);
+
+        let names = match columns.is_empty() {
+            true => "rowid".to_string(),
+            false => columns
+                .iter()
+                .map(|index| quote(self.source_schema.field(*index).name()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        let mut sql = format!(
+            "SELECT {names} FROM {} WHERE rowid BETWEEN ?1 AND ?2",
+            quote(&self.layer)
+        );
+        if let (Some(limit), None) = (scan.limit, scan.bbox) {
+            sql.push_str(&format!(" LIMIT {limit}"));
+        }
+        let mut statement = connection.prepare(&sql).map_err(external)?;
+
+        let mut builders = columns
+            .iter()
+            .map(|index| match *index == geometry_index {
+                true => ColumnBuilder::Binary(BinaryBuilder::new()),
+                false => ColumnBuilder::new(self.source_schema.field(*index).data_type()),
+            })
+            .collect::<Vec<_>>();
+
+        let mut rows = statement
+            .query(params![rowids.0, rowids.1])
+            .map_err(external)?;
+        let (mut len, mut total) = (0, 0);
+        while let Some(row) = rows.next().map_err(external)? {
+            if scan.limit == Some(total) {
+                break;
+            }
+
+            let blob = geometry
+                .map(|index| row.get_ref(index))
+                .transpose()
+                .map_err(external)?;
+            let geometry_value = match blob {
+                Some(ValueRef::Blob(blob)) => Some(decode_geometry(blob)?),
+                _ => None,
+            };
+            if let (Some(bbox), Some((Some(envelope), _))) = (scan.bbox, &geometry_value) {
+                if !intersects(bbox, *envelope) {
+                    continue;
+                }
+            }
+
+            for (index, builder) in builders.iter_mut().enumerate() {
+                if Some(index) == geometry {
+                    builder.append_blob(geometry_value.map(|(_, wkb)| wkb));
+                } else {
+                    builder.append(row.get_ref(index).map_err(external)?)?;
+                }
+            }
+            len += 1;
+            total += 1;
+
+            if len == self.batch_size {
+                if !send(self.finish_batch(scan, &mut builders, geometry, len)?) {
+                    return Ok(());
+                }
+                len = 0;
+            }
+        }
+        if len > 0 {
+            send(self.finish_batch(scan, &mut builders, geometry, len)?);
+        }
+
+        Ok(())
+    }
+
+    /// Batch of the projected columns, the geometry column decoded into its
+    /// native type.
+    fn finish_batch(
+        &self,
+        scan: &GeoPackageScan,
+        builders: &mut [ColumnBuilder],
+        geometry: Option<usize>,
+        len: usize,
+    ) -> Result<RecordBatch> {
+        let mut columns = builders
+            .iter_mut()
+            .map(ColumnBuilder::finish)
+            .collect::<Vec<_>>();
+
+        if let (Some(index), Some(native_type)) = (geometry, &self.geometry.native_type) {
+            let wkb: WKBArray<i32> = WKBArray::try_from(columns[index].as_ref())
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            columns[index] = from_wkb(&wkb, native_type.clone(), false)
+                .map_err(|e| DataFusionError::External(Box::new(e)))?
+                .to_array_ref();
+        }
+        // drop the geometry read for the bounding box filter only
+        columns.truncate(scan.projection.len());
+
+        let options = RecordBatchOptions::new().with_row_count(Some(len));
+        Ok(RecordBatch::try_new_with_options(
+            scan.schema.clone(),
+            columns,
+            &options,
+        )?)
+    }
+}
+
+#[async_trait]
+impl TableProvider for GeoPackageTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let df_schema = DFSchema::try_from(self.schema.as_ref().clone())?;
+        let mut bbox = None;
+        for filter in filters {
+            let expr = state.create_physical_expr(filter.clone(), &df_schema)?;
+            if let Some(filter_bbox) = bbox_from_filter(&expr) {
+                bbox = Some(filter_bbox);
+                break;
+            }
+        }
+
+        let projection = projection
+            .cloned()
+            .unwrap_or_else(|| (0..self.schema.fields().len()).collect());
+        let scan = GeoPackageScan {
+            schema: Arc::new(self.schema.project(&projection)?),
+            projection,
+            bbox,
+            limit,
+        };
+
+        // rowid ranges of about the same size, one per partition
+        let partitions = state.config().target_partitions().max(1) as i64;
+        let ranges = match self.rowids()? {
+            Some((min, max)) => {
+                let size = ((max - min) / partitions + 1).max(1);
+                (min..=max)
+                    .step_by(size as usize)
+                    .map(|start| (start, (start + size - 1).min(max)))
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        Ok(Arc::new(GeoPackageExec::new(
+            Arc::new(self.clone()),
+            scan,
+            ranges,
+        )))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        Ok(vec![TableProviderFilterPushDown::Inexact; filters.len()])
+    }
+}
+
+/// Columns, bounding box and limit of a GeoPackage scan.
+#[derive(Debug, Clone)]
+struct GeoPackageScan {
+    /// Schema of the projected columns
+    schema: SchemaRef,
+    projection: Vec<usize>,
+    bbox: Option<Envelope>,
+    limit: Option<usize>,
+}
+
+/// Execution plan reading a range of `rowid`s of a feature table per
+/// partition.
+#[derive(Debug)]
+struct GeoPackageExec {
+    table: Arc<GeoPackageTable>,
+    scan: GeoPackageScan,
+    ranges: Vec<(i64, i64)>,
+    properties: PlanProperties,
+}
+
+impl GeoPackageExec {
+    fn new(table: Arc<GeoPackageTable>, scan: GeoPackageScan, ranges: Vec<(i64, i64)>) -> Self {
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(scan.schema.clone()),
+            Partitioning::UnknownPartitioning(ranges.len().max(1)),
+            ExecutionMode::Bounded,
+        );
+
+        Self {
+            table,
+            scan,
+            ranges,
+            properties,
+        }
+    }
+}
+
+impl DisplayAs for GeoPackageExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(
+                    f,
+                    "GeoPackageExec: layer={}, bbox={:?}",
+                    self.table.layer, self.scan.bbox
+                )?;
+                if let Some(limit) = self.scan.limit {
+                    write!(f, ", limit={limit}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for GeoPackageExec {
+    fn name(&self) -> &str {
+        "GeoPackageExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let schema = self.scan.schema.clone();
+        let Some(rowids) = self.ranges.get(partition).copied() else {
+            // empty feature tables have a single empty partition
+            return Ok(Box::pin(RecordBatchStreamAdapter::new(
+                schema,
+                futures::stream::empty(),
+            )));
+        };
+
+        // the rows are read on a blocking thread as the stream is polled,
+        // dropping the stream stops the reader
+        let (sender, receiver) = tokio::sync::mpsc::channel::<Result<RecordBatch>>(2);
+        let table = self.table.clone();
+        let scan = self.scan.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut send = |batch: RecordBatch| sender.blocking_send(Ok(batch)).is_ok();
+            if let Err(e) = table.read(&scan, rowids, &mut send) {
+                let _ = sender.blocking_send(Err(e));
+            }
+        });
+        let batches = futures::stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|batch| (batch, receiver))
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, batches)))
+    }
+}
+
+/// Open the GeoPackage at `path` read only.
+fn open(path: &Path) -> Result<Connection> {
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(external)
+}
+
+fn external(e: rusqlite::Error) -> DataFusionError {
+    DataFusionError::External(Box::new(e))
+}
+
+/// Quote an SQLite identifier.
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Names of the feature tables in a GeoPackage.
+pub(crate) fn layers(connection: &Connection) -> Result<Vec<String>> {
+    let mut statement = connection
+        .prepare(
+            "SELECT table_name FROM gpkg_contents WHERE data_type = 'features' ORDER BY table_name",
+        )
+        .map_err(external)?;
+    let layers = statement
+        .query_map([], |row| row.get(0))
+        .map_err(external)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(external)?;
+
+    Ok(layers)
+}
+
+/// Geometry column of `layer` with its native type (if a single geometry type
+/// is declared) and CRS.
+fn geometry_column(connection: &Connection, layer: &str) -> Result<GeometryColumn> {
+    let (name, type_name, z, m, organization, code, definition) = connection
+        .query_row(
+            "SELECT g.column_name, g.geometry_type_name, g.z, g.m, s.organization, s.organization_coordsys_id, s.definition
+             FROM gpkg_geometry_columns g
+             LEFT JOIN gpkg_spatial_ref_sys s ON g.srs_id = s.srs_id
+             WHERE g.table_name = ?1",
+            [layer],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<i64>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(external)?
+        .ok_or_else(|| {
+            DataFusionError::Plan(format!("No feature table `{layer}` in GeoPackage"))
+        })?;
+
+    // geometries with measures or optional z coordinates are read as WKB
+    let ct = CoordType::Separated;
+    let dimension = match (z, m) {
+        (0, 0) => Some(Dimension::XY),
+        (1, 0) => Some(Dimension::XYZ),
+        _ => None,
+    };
+    let native_type = dimension.and_then(|dimension| match type_name.to_uppercase().as_str() {
+        "POINT" => Some(NativeType::Point(ct, dimension)),
+        "LINESTRING" => Some(NativeType::LineString(ct, dimension)),
+        "POLYGON" => Some(NativeType::Polygon(ct, dimension)),
+        "MULTIPOINT" => Some(NativeType::MultiPoint(ct, dimension)),
+        "MULTILINESTRING" => Some(NativeType::MultiLineString(ct, dimension)),
+        "MULTIPOLYGON" => Some(NativeType::MultiPolygon(ct, dimension)),
+        _ => None,
+    });
+
+    let crs = match (organization, code, definition) {
+        (Some(organization), Some(code), _)
+            if organization.eq_ignore_ascii_case("EPSG") && code > 0 =>
+        {
+            Some(format!("EPSG:{code}"))
+        }
+        (_, _, Some(definition)) if definition != "undefined" => Some(definition),
+        _ => None,
+    };
+
+    Ok(GeometryColumn {
+        name,
+        native_type,
+        crs,
+    })
+}
+
+/// Arrow data type for a declared SQLite column type.
+fn data_type(declared_type: &str) -> DataType {
+    let declared_type = declared_type.to_uppercase();
+
+    if declared_type == "BOOLEAN" {
+        DataType::Boolean
+    } else if declared_type.contains("INT") {
+        DataType::Int64
+    } else if ["REAL", "FLOA", "DOUB"]
+        .iter()
+        .any(|t| declared_type.contains(t))
+    {
+        DataType::Float64
+    } else if declared_type == "BLOB" {
+        DataType::Binary
+    } else {
+        DataType::Utf8
+    }
+}
+
+enum ColumnBuilder {
+    Boolean(BooleanBuilder),
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Utf8(StringBuilder),
+    Binary(BinaryBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Boolean => Self::Boolean(BooleanBuilder::new()),
+            DataType::Int64 => Self::Int64(Int64Builder::new()),
+            DataType::Float64 => Self::Float64(Float64Builder::new()),
+            DataType::Binary => Self::Binary(BinaryBuilder::new()),
+            _ => Self::Utf8(StringBuilder::new()),
+        }
+    }
+
+    /// Append an SQLite value, numeric values are coerced as SQLite would.
+    fn append(&mut self, value: ValueRef) -> Result<()> {
+        match (self, value) {
+            (Self::Boolean(b), ValueRef::Null) => b.append_null(),
+            (Self::Int64(b), ValueRef::Null) => b.append_null(),
+            (Self::Float64(b), ValueRef::Null) => b.append_null(),
+            (Self::Utf8(b), ValueRef::Null) => b.append_null(),
+            (Self::Binary(b), ValueRef::Null) => b.append_null(),
+            (Self::Boolean(b), ValueRef::Integer(v)) => b.append_value(v != 0),
+            (Self::Int64(b), ValueRef::Integer(v)) => b.append_value(v),
+            (Self::Int64(b), ValueRef::Real(v)) => b.append_value(v as i64),
+            (Self::Float64(b), ValueRef::Integer(v)) => b.append_value(v as f64),
+            (Self::Float64(b), ValueRef::Real(v)) => b.append_value(v),
+            (Self::Utf8(b), ValueRef::Integer(v)) => b.append_value(v.to_string()),
+            (Self::Utf8(b), ValueRef::Real(v)) => b.append_value(v.to_string()),
+            (Self::Utf8(b), ValueRef::Text(v)) => b.append_value(String::from_utf8_lossy(v)),
+            (Self::Binary(b), ValueRef::Blob(v)) => b.append_value(v),
+            (_, value) => {
+                return Err(DataFusionError::Execution(format!(
+                    "Unexpected SQLite value `{value:?}`"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn append_blob(&mut self, value: Option<&[u8]>) {
+        if let Self::Binary(b) = self {
+            b.append_option(value);
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Boolean(b) => Arc::new(b.finish()),
+            Self::Int64(b) => Arc::new(b.finish()),
+            Self::Float64(b) => Arc::new(b.finish()),
+            Self::Utf8(b) => Arc::new(b.finish()),
+            Self::Binary(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// Envelope `(xmin, ymin, xmax, ymax)` of a geometry.
+type Envelope = (f64, f64, f64, f64);
+
+fn intersects(a: Envelope, b: Envelope) -> bool {
+    a.0 <= b.2 && a.2 >= b.0 && a.1 <= b.3 && a.3 >= b.1
+}
+
+/// Split a GeoPackage geometry blob into the envelope of its header and the
+/// WKB geometry.
+fn decode_geometry(blob: &[u8]) -> Result<(Option<Envelope>, &[u8])> {
+    if blob.len() < 8 || &blob[..2] != b"GP" {
+        return Err(DataFusionError::Execution(
+            "Invalid GeoPackage geometry header".to_string(),
+        ));
+    }
+
+    let flags = blob[3];
+    let little_endian = flags & 0b1 == 1;
+    let envelope_len = match (flags >> 1) & 0b111 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        indicator => {
+            return Err(DataFusionError::Execution(format!(
+                "Invalid GeoPackage envelope indicator `{indicator}`"
+            )))
+        }
+    };
+    if blob.len() < 8 + envelope_len {
+        return Err(DataFusionError::Execution(
+            "Truncated GeoPackage geometry header".to_string(),
+        ));
+    }
+
+    let read = |offset: usize| {
+        let bytes: [u8; 8] = blob[offset..offset + 8].try_into().unwrap();
+        match little_endian {
+            true => f64::from_le_bytes(bytes),
+            false => f64::from_be_bytes(bytes),
+        }
+    };
+    // The envelope is stored as [minx, maxx, miny, maxy, ...].
+    let envelope = (envelope_len > 0).then(|| (read(8), read(24), read(16), read(32)));
+
+    Ok((envelope, &blob[8 + envelope_len..]))
+}
+
+/// Prefix `wkb` with a little endian GeoPackage geometry header, including
+/// the 2D envelope unless the geometry is empty.
+fn encode_geometry(wkb: &[u8], srs_id: i32, envelope: Option<Envelope>) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(40 + wkb.len());
+
+    let flags = match envelope {
+        Some(_) => 0b0000_0011,
+        None => 0b0001_0001,
+    };
+    blob.extend_from_slice(&[b'G', b'P', 0, flags]);
+    blob.extend_from_slice(&srs_id.to_le_bytes());
+    if let Some((xmin, ymin, xmax, ymax)) = envelope {
+        for value in [xmin, xmax, ymin, ymax] {
+            blob.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    blob.extend_from_slice(wkb);
+
+    blob
+}
+
+/// Spatial reference system entry for a GeoArrow CRS.
+fn spatial_ref_sys(crs: Option<&Value>) -> (i32, String, i64, String) {
+    let authority_code = match crs {
+        Some(Value::String(crs)) => crs
+            .split_once(':')
+            .map(|(authority, code)| (authority.to_string(), code.to_string())),
+        Some(Value::Object(crs)) => crs.get("id").and_then(|id| {
+            Some((
+                id.get("authority")?.as_str()?.to_string(),
+                match id.get("code")? {
+                    Value::String(code) => code.to_owned(),
+                    code => code.to_string(),
+                },
+            ))
+        }),
+        _ => None,
+    };
+
+    match authority_code {
+        Some((authority, code)) if authority.eq_ignore_ascii_case("OGC") && code == "CRS84" => {
+            (4326, "EPSG".to_string(), 4326, "undefined".to_string())
+        }
+        Some((authority, code)) if code.parse::<i32>().is_ok_and(|code| code > 0) => {
+            let code = code.parse::<i32>().unwrap();
+            (
+                code,
+                authority.to_uppercase(),
+                code as i64,
+                "undefined".to_string(),
+            )
+        }
+        _ => match crs {
+            Some(Value::String(definition)) => (-1, "NONE".to_string(), -1, definition.to_owned()),
+            _ => (0, "NONE".to_string(), 0, "undefined".to_string()),
+        },
+    }
+}
+
+/// Write `batches` as a new feature table `layer` to the GeoPackage at
+/// `path`, creating the file if it does not exist.
+///
+/// The first GeoArrow column is written as the geometry column, its CRS is
+/// registered in `gpkg_spatial_ref_sys`.
+pub fn write_geopackage(
+    path: &Path,
+    layer: &str,
+    schema: SchemaRef,
+    batches: &[RecordBatch],
+) -> Result<u64> {
+    let geometry_index = schema
+        .fields()
+        .iter()
+        .position(|field| {
+            field
+                .metadata()
+                .get("ARROW:extension:name")
+                .is_some_and(|name| name.starts_with("geoarrow."))
+        })
+        .ok_or_else(|| DataFusionError::Plan("No geometry column to write".to_string()))?;
+    let geometry_field = schema.field(geometry_index);
+
+    let crs = geometry_field
+        .metadata()
+        .get("ARROW:extension:metadata")
+        .and_then(|m| serde_json::from_str::<Value>(m).ok())
+        .and_then(|m| m.get("crs").cloned());
+    let (mut srs_id, organization, code, definition) = spatial_ref_sys(crs.as_ref());
+
+    let mut connection = Connection::open(path).map_err(external)?;
+    connection
+        .pragma_update(None, "application_id", APPLICATION_ID)
+        .map_err(external)?;
+    connection
+        .pragma_update(None, "user_version", USER_VERSION)
+        .map_err(external)?;
+    connection.execute_batch(CREATE_TABLES).map_err(external)?;
+
+    if layers(&connection)?.iter().any(|l| l == layer) {
+        return Err(DataFusionError::Plan(format!(
+            "Layer `{layer}` already exists in `{}`",
+            path.display()
+        )));
+    }
+
+    let transaction = connection.transaction().map_err(external)?;
+
+    if srs_id == -1 {
+        // Custom CRS definition, registered with the next free id.
+        srs_id = transaction
+            .query_row(
+                "SELECT srs_id FROM gpkg_spatial_ref_sys WHERE definition = ?1",
+                [&definition],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(external)?
+            .unwrap_or(
+                transaction
+                    .query_row(
+                        "SELECT MAX(100000, MAX(srs_id) + 1) FROM gpkg_spatial_ref_sys",
+                        [],
+                        |row| row.get(0),
+                    )
+                    .map_err(external)?,
+            );
+    }
+    transaction
+        .execute(
+            "INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES (?1, ?2, ?3, ?4, ?5, NULL)",
+            params![
+                format!("{organization}:{code}"),
+                srs_id,
+                organization,
+                if srs_id >= 100000 {
+                    srs_id as i64
+                } else {
+                    code
+                },
+                definition
+            ],
+        )
+        .map_err(external)?;
+
+    let has_fid = schema.fields().iter().any(|field| field.name() == "fid");
+    let mut columns = vec![];
+    if !has_fid {
+        columns.push("\"fid\" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL".to_string());
+    }
+    for (index, field) in schema.fields().iter().enumerate() {
+        let declared_type = match (index == geometry_index, field.data_type()) {
+            (true, _) => "GEOMETRY",
+            (false, _) if field.name() == "fid" => "INTEGER PRIMARY KEY AUTOINCREMENT",
+            (false, DataType::Boolean) => "BOOLEAN",
+            (false, dt) if dt.is_integer() => "INTEGER",
+            (false, dt) if dt.is_floating() || dt.is_numeric() => "DOUBLE",
+            (false, DataType::Date32 | DataType::Date64) => "DATE",
+            (false, DataType::Timestamp(_, _)) => "DATETIME",
+            (false, DataType::Binary | DataType::LargeBinary) => "BLOB",
+            _ => "TEXT",
+        };
+        columns.push(format!("{} {declared_type}", quote(field.name())));
+    }
+    transaction
+        .execute(
+            &format!("CREATE TABLE {} ({})", quote(layer), columns.join(", ")),
+            [],
+        )
+        .map_err(external)?;
+
+    let mut rows = 0;
+    let mut bounds = (
+        (f64::INFINITY, f64::INFINITY),
+        (-f64::INFINITY, -f64::INFINITY),
+    );
+    let mut geometry_types = BTreeSet::new();
+    {
+        let names = schema
+            .fields()
+            .iter()
+            .map(|field| quote(field.name()))
+            .collect::<Vec<_>>();
+        let placeholders = (1..=names.len())
+            .map(|i| format!("?{i}"))
+            .collect::<Vec<_>>();
+        let mut statement = transaction
+            .prepare(&format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                quote(layer),
+                names.join(", "),
+                placeholders.join(", ")
+            ))
+            .map_err(external)?;
+
+        for batch in batches {
+            let blobs = geometry_wkb(batch.column(geometry_index), geometry_field)?.to_array_ref();
+            let blobs = blobs.as_binary::<i32>();
+            let attributes = batch
+                .columns()
+                .iter()
+                .map(|array| AttributeColumn::try_new(array))
+                .collect::<Result<Vec<_>>>()?;
+
+            for row in 0..batch.num_rows() {
+                let mut values = attributes
+                    .iter()
+                    .map(|column| column.value(row))
+                    .collect::<Vec<_>>();
+
+                values[geometry_index] = match blobs.is_valid(row) {
+                    true => {
+                        // parsed by the crate's own reader to keep M dimensions
+                        let wkb = blobs.value(row);
+                        let geometry = wkb::parse(wkb)?;
+                        geometry_types.insert(geometry.type_name());
+
+                        let envelope = geometry
+                            .bounds()
+                            .map(|((xmin, ymin), (xmax, ymax))| (xmin, ymin, xmax, ymax));
+                        if let Some((xmin, ymin, xmax, ymax)) = envelope {
+                            bounds.0 .0 = bounds.0 .0.min(xmin);
+                            bounds.0 .1 = bounds.0 .1.min(ymin);
+                            bounds.1 .0 = bounds.1 .0.max(xmax);
+                            bounds.1 .1 = bounds.1 .1.max(ymax);
+                        }
+
+                        SqlValue::Blob(encode_geometry(wkb, srs_id, envelope))
+                    }
+                    false => SqlValue::Null,
+                };
+
+                statement
+                    .execute(rusqlite::params_from_iter(values))
+                    .map_err(external)?;
+                rows += 1;
+            }
+        }
+    }
+
+    let bounds = (bounds.0 .0 <= bounds.1 .0).then_some(bounds);
+    transaction
+        .execute(
+            "INSERT INTO gpkg_contents (table_name, data_type, identifier, min_x, min_y, max_x, max_y, srs_id)
+             VALUES (?1, 'features', ?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                layer,
+                bounds.map(|b| b.0 .0),
+                bounds.map(|b| b.0 .1),
+                bounds.map(|b| b.1 .0),
+                bounds.map(|b| b.1 .1),
+                srs_id
+            ],
+        )
+        .map_err(external)?;
+
+    let (geometry_type_name, z, m) = gpkg_geometry_type(&geometry_types);
+    transaction
+        .execute(
+            "INSERT INTO gpkg_geometry_columns VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                layer,
+                geometry_field.name(),
+                geometry_type_name,
+                srs_id,
+                z,
+                m
+            ],
+        )
+        .map_err(external)?;
+
+    transaction.commit().map_err(external)?;
+
+    Ok(rows)
+}
+
+/// WKB encoded geometries of a WKB or native GeoArrow array.
+fn geometry_wkb(array: &ArrayRef, field: &Field) -> Result<WKBArray<i32>> {
+    match field.metadata().get("ARROW:extension:name") {
+        Some(name) if name == "geoarrow.wkb" => {
+            let array = cast(array, &DataType::Binary)?;
+            WKBArray::try_from(array.as_ref()).map_err(|e| DataFusionError::External(Box::new(e)))
+        }
+        _ => Ok(to_wkb::<i32>(
+            NativeArrayDyn::from_arrow_array(array, field)
+                .map_err(|e| DataFusionError::External(Box::new(e)))?
+                .as_ref(),
+        )),
+    }
+}
+
+/// `gpkg_geometry_columns` geometry type name and z and m flags for the
+/// GeoParquet geometry type names written: 0 if no geometry has the
+/// dimension, 1 if all have it and 2 if only some do.
+fn gpkg_geometry_type(geometry_types: &BTreeSet<String>) -> (String, i32, i32) {
+    let split = |name: &str| match name.split_once(' ') {
+        Some((base, dimension)) => (base.to_uppercase(), dimension.to_string()),
+        None => (name.to_uppercase(), String::new()),
+    };
+    let (base_types, dimensions): (BTreeSet<_>, Vec<_>) =
+        geometry_types.iter().map(|name| split(name)).unzip();
+
+    let name = match base_types.len() {
+        1 => base_types.into_iter().next().unwrap(),
+        _ => "GEOMETRY".to_string(),
+    };
+    let flag = |ordinate: char| match dimensions.iter().filter(|d| d.contains(ordinate)).count() {
+        0 => 0,
+        n if n == dimensions.len() => 1,
+        _ => 2,
+    };
+
+    (name, flag('Z'), flag('M'))
+}
+
+/// Attribute column cast to a type with an SQLite storage class.
+enum AttributeColumn {
+    Boolean(ArrayRef),
+    Int64(ArrayRef),
+    Float64(ArrayRef),
+    Utf8(ArrayRef),
+    Binary(ArrayRef),
+}
+
+impl AttributeColumn {
+    fn try_new(array: &ArrayRef) -> Result<Self> {
+        Ok(match array.data_type() {
+            DataType::Boolean => Self::Boolean(array.clone()),
+            dt if dt.is_integer() => Self::Int64(cast(array, &DataType::Int64)?),
+            dt if dt.is_floating() || dt.is_numeric() => {
+                Self::Float64(cast(array, &DataType::Float64)?)
+            }
+            DataType::Binary | DataType::LargeBinary | DataType::BinaryView => {
+                Self::Binary(cast(array, &DataType::Binary)?)
+            }
+            _ => Self::Utf8(cast(array, &DataType::Utf8)?),
+        })
+    }
+
+    fn value(&self, row: usize) -> SqlValue {
+        let array = match self {
+            Self::Boolean(a)
+            | Self::Int64(a)
+            | Self::Float64(a)
+            | Self::Utf8(a)
+            | Self::Binary(a) => a,
+        };
+        if array.is_null(row) {
+            return SqlValue::Null;
+        }
+
+        match self {
+            Self::Boolean(a) => SqlValue::Integer(a.as_boolean().value(row) as i64),
+            Self::Int64(a) => SqlValue::Integer(a.as_primitive::<Int64Type>().value(row)),
+            Self::Float64(a) => SqlValue::Real(a.as_primitive::<Float64Type>().value(row)),
+            Self::Utf8(a) => SqlValue::Text(a.as_string::<i32>().value(row).to_string()),
+            Self::Binary(a) => SqlValue::Blob(a.as_binary::<i32>().value(row).to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometry_header() {
+        let wkb = [1, 1, 0, 0, 0];
+        let blob = encode_geometry(&wkb, 4326, Some((1., 2., 3., 4.)));
+        assert_eq!(blob.len(), 8 + 32 + wkb.len());
+        assert_eq!(
+            decode_geometry(&blob).unwrap(),
+            (Some((1., 2., 3., 4.)), wkb.as_slice())
+        );
+
+        let blob = encode_geometry(&wkb, 4326, None);
+        assert_eq!(decode_geometry(&blob).unwrap(), (None, wkb.as_slice()));
+
+        assert!(decode_geometry(b"XX\0\x01\0\0\0\0").is_err());
+    }
+
+    #[test]
+    fn srs() {
+        assert_eq!(spatial_ref_sys(Some(&json!("EPSG:2056"))).0, 2056);
+        assert_eq!(spatial_ref_sys(Some(&json!("OGC:CRS84"))).0, 4326);
+        assert_eq!(
+            spatial_ref_sys(Some(
+                &json!({ "id": { "authority": "EPSG", "code": 3857 } })
+            ))
+            .0,
+            3857
+        );
+        assert_eq!(spatial_ref_sys(Some(&json!("PROJCS[...]"))).0, -1);
+        assert_eq!(spatial_ref_sys(None).0, 0);
+    }
+
+    #[test]
+    fn geometry_types() {
+        let types = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+
+        assert_eq!(
+            gpkg_geometry_type(&types(&["Point"])),
+            ("POINT".to_string(), 0, 0)
+        );
+        assert_eq!(
+            gpkg_geometry_type(&types(&["Polygon", "MultiPolygon Z"])),
+            ("GEOMETRY".to_string(), 2, 0)
+        );
+        assert_eq!(
+            gpkg_geometry_type(&types(&["LineString M", "LineString ZM"])),
+            ("LINESTRING".to_string(), 2, 1)
+        );
+    }
+}
@@ -0,0 +1,854 @@
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap},
+    io::Write,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, AsArray, Float64Array, OffsetSizeTrait, RecordBatch, StructArray},
+        buffer::NullBuffer,
+        datatypes::{DataType, Field, Fields, Schema, SchemaRef},
+    },
+    common::{GetExt, Statistics},
+    datasource::{
+        file_format::{
+            file_compression_type::FileCompressionType, parquet::ParquetFormat, FileFormat,
+            FileFormatFactory,
+        },
+        physical_plan::{FileScanConfig, FileSinkConfig},
+    },
+    error::{DataFusionError, Result},
+    execution::context::SessionState,
+    parquet::{arrow::ArrowWriter, file::metadata::KeyValue},
+    physical_expr::{LexRequirement, PhysicalExpr},
+    physical_plan::ExecutionPlan,
+};
+use geoarrow::{
+    array::{NativeArrayDyn, WKBArray},
+    datatypes::NativeType,
+    error::GeoArrowError,
+    io::wkb::{from_wkb, to_wkb},
+    ArrayBase,
+};
+use object_store::{ObjectMeta, ObjectStore};
+use serde_json::{json, Map, Value};
+
+use super::{
+    create_writer_plan, native_encoding_name, native_geometry_type_name, native_target_type,
+    BatchEncoder, SharedBuffer,
+};
+use crate::wkb;
+
+const GEOPARQUET_VERSION: &str = "1.1.0";
+
+/// Encoding of the geometry columns written by the GeoParquet writer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GeoParquetWriterEncoding {
+    /// Well-known binary
+    #[default]
+    WKB,
+    /// Native GeoArrow (single geometry type per column)
+    Native,
+}
+
+/// Options of the GeoParquet writer.
+///
+/// When used through `COPY ... STORED AS GEOPARQUET` the options are read from
+/// the `OPTIONS (...)` clause, e.g. `OPTIONS ('encoding' 'native', 'bbox_covering' 'true')`.
+#[derive(Debug, Clone, Default)]
+pub struct GeoParquetWriterOptions {
+    pub encoding: GeoParquetWriterEncoding,
+    pub bbox_covering: bool,
+    pub primary_column: Option<String>,
+    pub geometry_columns: Vec<String>,
+    pub crs: Option<String>,
+}
+
+impl TryFrom<&HashMap<String, String>> for GeoParquetWriterOptions {
+    type Error = DataFusionError;
+
+    fn try_from(options: &HashMap<String, String>) -> Result<Self> {
+        let mut writer_options = Self::default();
+
+        for (key, value) in options {
+            match key.trim_start_matches("format.") {
+                "encoding" => {
+                    writer_options.encoding = match value.to_lowercase().as_str() {
+                        "wkb" => GeoParquetWriterEncoding::WKB,
+                        "native" | "geoarrow" => GeoParquetWriterEncoding::Native,
+                        enc => {
+                            return Err(DataFusionError::Configuration(format!(
+                                "Unsupported GeoParquet encoding `{enc}`"
+                            )))
+                        }
+                    }
+                }
+                "bbox_covering" => {
+                    writer_options.bbox_covering = value.parse().map_err(|_| {
+                        DataFusionError::Configuration(format!(
+                            "Expected boolean for `bbox_covering`, got `{value}`"
+                        ))
+                    })?
+                }
+                "primary_column" => writer_options.primary_column = Some(value.to_owned()),
+                "geometry_columns" => {
+                    writer_options.geometry_columns =
+                        value.split(',').map(|s| s.trim().to_string()).collect()
+                }
+                "crs" => writer_options.crs = Some(value.to_owned()),
+                _ => {
+                    return Err(DataFusionError::Configuration(format!(
+                        "Unsupported GeoParquet writer option `{key}`"
+                    )))
+                }
+            }
+        }
+
+        Ok(writer_options)
+    }
+}
+
+/// Factory registering the `GEOPARQUET` file format with a session.
+#[derive(Debug, Default)]
+pub struct GeoParquetFormatFactory {}
+
+impl GeoParquetFormatFactory {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl GetExt for GeoParquetFormatFactory {
+    fn get_ext(&self) -> String {
+        "geoparquet".to_string()
+    }
+}
+
+impl FileFormatFactory for GeoParquetFormatFactory {
+    fn create(
+        &self,
+        _state: &SessionState,
+        format_options: &HashMap<String, String>,
+    ) -> Result<Arc<dyn FileFormat>> {
+        Ok(Arc::new(GeoParquetFormat::new(
+            GeoParquetWriterOptions::try_from(format_options)?,
+        )))
+    }
+
+    fn default(&self) -> Arc<dyn FileFormat> {
+        Arc::new(GeoParquetFormat::default())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// GeoParquet file format, reading is delegated to [`ParquetFormat`] while
+/// preserving the `geo` metadata.
+#[derive(Debug)]
+pub struct GeoParquetFormat {
+    inner: ParquetFormat,
+    options: GeoParquetWriterOptions,
+}
+
+impl Default for GeoParquetFormat {
+    fn default() -> Self {
+        Self::new(GeoParquetWriterOptions::default())
+    }
+}
+
+impl GeoParquetFormat {
+    pub fn new(options: GeoParquetWriterOptions) -> Self {
+        Self {
+            inner: ParquetFormat::default().with_skip_metadata(false),
+            options,
+        }
+    }
+}
+
+#[async_trait]
+impl FileFormat for GeoParquetFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_ext(&self) -> String {
+        self.inner.get_ext()
+    }
+
+    fn get_ext_with_compression(
+        &self,
+        file_compression_type: &FileCompressionType,
+    ) -> Result<String> {
+        self.inner.get_ext_with_compression(file_compression_type)
+    }
+
+    async fn infer_schema(
+        &self,
+        state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        objects: &[ObjectMeta],
+    ) -> Result<SchemaRef> {
+        self.inner.infer_schema(state, store, objects).await
+    }
+
+    async fn infer_stats(
+        &self,
+        state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        table_schema: SchemaRef,
+        object: &ObjectMeta,
+    ) -> Result<Statistics> {
+        self.inner
+            .infer_stats(state, store, table_schema, object)
+            .await
+    }
+
+    async fn create_physical_plan(
+        &self,
+        state: &SessionState,
+        conf: FileScanConfig,
+        filters: Option<&Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.inner.create_physical_plan(state, conf, filters).await
+    }
+
+    async fn create_writer_physical_plan(
+        &self,
+        input: Arc<dyn ExecutionPlan>,
+        _state: &SessionState,
+        conf: FileSinkConfig,
+        order_requirements: Option<LexRequirement>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let options = self.options.clone();
+
+        create_writer_plan(
+            "GeoParquet",
+            input,
+            conf,
+            order_requirements,
+            move |schema, buffer| {
+                let writer = GeoParquetWriter::try_new(buffer, schema, options.clone())?;
+                Ok(Box::new(writer) as Box<dyn BatchEncoder>)
+            },
+        )
+    }
+}
+
+/// How a geometry column is encoded in the input batches.
+#[derive(Debug, Clone)]
+enum InputEncoding {
+    WKB,
+    LargeWKB,
+    Native(NativeType),
+}
+
+#[derive(Debug)]
+struct GeometryColumn {
+    index: usize,
+    name: String,
+    input: InputEncoding,
+    crs: Option<Value>,
+    edges: Option<Value>,
+    geometry_types: BTreeSet<String>,
+    bounds: ((f64, f64), (f64, f64)),
+    output: Option<NativeType>,
+}
+
+/// Detect geometry columns from GeoArrow extension metadata, explicitly named
+/// columns or a binary column named `geometry`.
+fn geometry_columns(
+    schema: &Schema,
+    options: &GeoParquetWriterOptions,
+) -> Result<Vec<GeometryColumn>> {
+    let mut columns = vec![];
+
+    // `geo` metadata of GeoParquet input, e.g. a table read from GeoParquet
+    let geo = schema
+        .metadata()
+        .get("geo")
+        .and_then(|geo| serde_json::from_str::<Value>(geo).ok());
+
+    for (index, field) in schema.fields().iter().enumerate() {
+        let extension = field.metadata().get("ARROW:extension:name");
+        let named = if options.geometry_columns.is_empty() {
+            field.name() == "geometry"
+        } else {
+            options.geometry_columns.contains(field.name())
+        };
+
+        let input = match (field.data_type(), extension.map(String::as_str)) {
+            (DataType::Binary, None | Some("geoarrow.wkb")) if named || extension.is_some() => {
+                InputEncoding::WKB
+            }
+            (DataType::LargeBinary, None | Some("geoarrow.wkb"))
+                if named || extension.is_some() =>
+            {
+                InputEncoding::LargeWKB
+            }
+            (_, Some(name)) if name.starts_with("geoarrow.") => InputEncoding::Native(
+                NativeType::try_from(field.as_ref())
+                    .map_err(|e| DataFusionError::External(Box::new(e)))?,
+            ),
+            (dt, _) if named => {
+                return Err(DataFusionError::Plan(format!(
+                    "Geometry column `{}` of type `{dt}` lacks GeoArrow extension metadata",
+                    field.name()
+                )))
+            }
+            _ => continue,
+        };
+
+        let extension = field
+            .metadata()
+            .get("ARROW:extension:metadata")
+            .and_then(|m| serde_json::from_str::<Value>(m).ok());
+        let input_metadata = geo
+            .as_ref()
+            .and_then(|geo| geo.get("columns")?.get(field.name()));
+        let crs = extension
+            .as_ref()
+            .and_then(|m| m.get("crs").cloned())
+            .or_else(|| input_metadata.and_then(|m| m.get("crs").cloned()))
+            .or_else(|| options.crs.clone().map(Value::from))
+            .and_then(|crs| crs_to_json(&crs));
+        let edges = extension
+            .and_then(|m| m.get("edges").cloned())
+            .or_else(|| input_metadata.and_then(|m| m.get("edges").cloned()));
+
+        columns.push(GeometryColumn {
+            index,
+            name: field.name().to_owned(),
+            input,
+            crs,
+            edges,
+            geometry_types: BTreeSet::new(),
+            bounds: ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN)),
+            output: None,
+        });
+    }
+
+    if columns.is_empty() {
+        return Err(DataFusionError::Plan(
+            "GeoParquet output requires at least one geometry column".to_string(),
+        ));
+    }
+
+    Ok(columns)
+}
+
+/// Convert a CRS into its GeoParquet representation, PROJJSON or `null`.
+///
+/// PROJJSON is passed through. `OGC:CRS84` is the default of GeoParquet and
+/// omitted (`None`). Other authority codes and CRS strings have no PROJJSON
+/// definition at hand, they are written as an unknown (`null`) CRS since an
+/// omitted CRS would claim `OGC:CRS84`.
+fn crs_to_json(crs: &Value) -> Option<Value> {
+    let crs = match crs {
+        Value::String(crs) => match serde_json::from_str::<Value>(crs) {
+            Ok(value @ Value::Object(_)) => return Some(value),
+            _ => crs,
+        },
+        Value::Object(_) | Value::Null => return Some(crs.clone()),
+        _ => return Some(Value::Null),
+    };
+
+    match crs.split_once(':') {
+        Some((authority, "CRS84")) if authority.eq_ignore_ascii_case("OGC") => None,
+        _ => Some(Value::Null),
+    }
+}
+
+/// Update the column statistics and return the per row bounding boxes, `None`
+/// for null and empty geometries.
+fn scan_wkb<O: OffsetSizeTrait>(
+    wkb: &WKBArray<O>,
+    column: &mut GeometryColumn,
+) -> Result<Vec<Option<((f64, f64), (f64, f64))>>> {
+    let array = wkb.to_array_ref();
+    array
+        .as_binary::<O>()
+        .iter()
+        .map(|geom| {
+            let Some(geom) = geom else {
+                return Ok(None);
+            };
+            let geom = wkb::parse(geom)?;
+            column.geometry_types.insert(geom.type_name());
+
+            // empty geometries have no bounding box
+            let bounds = geom.bounds();
+            if let Some(((x0, y0), (x1, y1))) = bounds {
+                let ((xmin, ymin), (xmax, ymax)) = &mut column.bounds;
+                *xmin = xmin.min(x0);
+                *ymin = ymin.min(y0);
+                *xmax = xmax.max(x1);
+                *ymax = ymax.max(y1);
+            }
+
+            Ok(bounds)
+        })
+        .collect()
+}
+
+fn as_wkb(array: &ArrayRef, field: &Field, input: &InputEncoding) -> Result<WKBArray<i32>> {
+    match input {
+        InputEncoding::WKB => WKBArray::try_from(array.as_ref())
+            .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string())),
+        InputEncoding::LargeWKB => {
+            let wkb: WKBArray<i64> = WKBArray::try_from(array.as_ref())
+                .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
+            wkb.try_into()
+                .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))
+        }
+        InputEncoding::Native(_) => {
+            let geoms = NativeArrayDyn::from_arrow_array(array, field)
+                .map_err(|e| DataFusionError::Internal(e.to_string()))?;
+            Ok(to_wkb::<i32>(geoms.as_ref()))
+        }
+    }
+}
+
+fn bbox_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("xmin", DataType::Float64, false),
+        Field::new("ymin", DataType::Float64, false),
+        Field::new("xmax", DataType::Float64, false),
+        Field::new("ymax", DataType::Float64, false),
+    ])
+}
+
+fn bbox_array(bboxes: &[Option<((f64, f64), (f64, f64))>]) -> Result<ArrayRef> {
+    let value = |f: fn(&((f64, f64), (f64, f64))) -> f64| {
+        Arc::new(Float64Array::from_iter_values(
+            bboxes.iter().map(|b| b.as_ref().map(f).unwrap_or_default()),
+        )) as ArrayRef
+    };
+
+    Ok(Arc::new(StructArray::try_new(
+        bbox_fields(),
+        vec![
+            value(|b| b.0 .0),
+            value(|b| b.0 .1),
+            value(|b| b.1 .0),
+            value(|b| b.1 .1),
+        ],
+        Some(NullBuffer::from_iter(bboxes.iter().map(Option::is_some))),
+    )?))
+}
+
+fn bbox_column_name(column: &GeometryColumn, primary_column: &str) -> String {
+    if column.name == primary_column {
+        "bbox".to_string()
+    } else {
+        format!("{}_bbox", column.name)
+    }
+}
+
+/// Geometry column as WKB with the bounding box of each row.
+type Encoded = (WKBArray<i32>, Vec<Option<((f64, f64), (f64, f64))>>);
+
+/// Incremental GeoParquet writer, accumulating the `geo` metadata while the
+/// batches are written and appending it on [`close`](Self::close).
+///
+/// With native encoding, the output type of WKB and mixed geometry columns is
+/// chosen from the geometry types of the first batches containing geometries.
+/// Later geometries not fitting that type fail the write, use WKB encoding
+/// for such columns.
+pub struct GeoParquetWriter<W: Write + Send> {
+    schema: SchemaRef,
+    options: GeoParquetWriterOptions,
+    columns: Vec<GeometryColumn>,
+    primary_column: String,
+    state: WriterState<W>,
+}
+
+enum WriterState<W: Write + Send> {
+    /// Waiting for geometries to decide on the native output types
+    Pending(W, Vec<(RecordBatch, Vec<Encoded>)>),
+    Writing(ArrowWriter<W>, SchemaRef),
+    Closed,
+}
+
+impl<W: Write + Send> GeoParquetWriter<W> {
+    pub fn try_new(writer: W, schema: SchemaRef, options: GeoParquetWriterOptions) -> Result<Self> {
+        let mut columns = geometry_columns(&schema, &options)?;
+
+        let primary_column = match &options.primary_column {
+            Some(name) => columns
+                .iter()
+                .find(|c| &c.name == name)
+                .map(|c| c.name.clone())
+                .ok_or_else(|| {
+                    DataFusionError::Plan(format!(
+                        "Primary column `{name}` is not a geometry column"
+                    ))
+                })?,
+            None => columns[0].name.clone(),
+        };
+
+        if options.encoding == GeoParquetWriterEncoding::Native {
+            for column in columns.iter_mut() {
+                if let InputEncoding::Native(native_type) = &column.input {
+                    if native_encoding_name(native_type).is_some() {
+                        column.output = Some(native_type.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            schema,
+            options,
+            columns,
+            primary_column,
+            state: WriterState::Pending(writer, vec![]),
+        })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        let encoded = self.scan(batch)?;
+
+        match &mut self.state {
+            WriterState::Pending(_, pending) => {
+                pending.push((batch.clone(), encoded));
+                if !self.undecided() {
+                    self.start()?;
+                }
+                Ok(())
+            }
+            WriterState::Writing(writer, output_schema) => {
+                let batch = encode(
+                    &self.columns,
+                    self.options.bbox_covering,
+                    batch,
+                    encoded,
+                    output_schema,
+                )?;
+                writer.write(&batch)?;
+                Ok(())
+            }
+            WriterState::Closed => Err(DataFusionError::Execution(
+                "GeoParquet writer is already closed".to_string(),
+            )),
+        }
+    }
+
+    /// Write the remaining batches and the `geo` metadata.
+    pub fn close(mut self) -> Result<()> {
+        if matches!(self.state, WriterState::Pending(..)) {
+            self.start()?;
+        }
+        let WriterState::Writing(mut writer, _) =
+            std::mem::replace(&mut self.state, WriterState::Closed)
+        else {
+            unreachable!("GeoParquet writer started")
+        };
+
+        writer.append_key_value_metadata(KeyValue::new(
+            "geo".to_string(),
+            self.geo_metadata().to_string(),
+        ));
+        writer.close()?;
+
+        Ok(())
+    }
+
+    /// Whether a native output type still depends on geometries to come.
+    fn undecided(&self) -> bool {
+        self.options.encoding == GeoParquetWriterEncoding::Native
+            && self
+                .columns
+                .iter()
+                .any(|c| c.output.is_none() && c.geometry_types.is_empty())
+    }
+
+    /// Update the column statistics with `batch`.
+    fn scan(&mut self, batch: &RecordBatch) -> Result<Vec<Encoded>> {
+        self.columns
+            .iter_mut()
+            .map(|column| {
+                let field = self.schema.field(column.index);
+                let wkb = as_wkb(batch.column(column.index), field, &column.input)?;
+                let bboxes = scan_wkb(&wkb, column)?;
+                Ok((wkb, bboxes))
+            })
+            .collect()
+    }
+
+    /// Decide on the output schema, create the Parquet writer and write the
+    /// pending batches.
+    fn start(&mut self) -> Result<()> {
+        let WriterState::Pending(sink, pending) =
+            std::mem::replace(&mut self.state, WriterState::Closed)
+        else {
+            return Ok(());
+        };
+
+        if self.options.encoding == GeoParquetWriterEncoding::Native {
+            for column in self.columns.iter_mut().filter(|c| c.output.is_none()) {
+                column.output = Some(native_target_type(&column.geometry_types)?);
+            }
+        }
+
+        let mut fields = self.schema.fields().iter().cloned().collect::<Vec<_>>();
+        for column in self.columns.iter() {
+            let name = &column.name;
+            fields[column.index] = match &column.output {
+                Some(native_type) => native_type.to_field(name, true).into(),
+                None => Field::new(name, DataType::Binary, true).into(),
+            };
+        }
+        if self.options.bbox_covering {
+            for column in self.columns.iter() {
+                let name = bbox_column_name(column, &self.primary_column);
+                fields.push(Field::new(name, DataType::Struct(bbox_fields()), true).into());
+            }
+        }
+        let output_schema = Arc::new(Schema::new(fields));
+
+        let mut writer = ArrowWriter::try_new(sink, output_schema.clone(), None)?;
+        for (batch, encoded) in pending {
+            let batch = encode(
+                &self.columns,
+                self.options.bbox_covering,
+                &batch,
+                encoded,
+                &output_schema,
+            )?;
+            writer.write(&batch)?;
+        }
+        self.state = WriterState::Writing(writer, output_schema);
+
+        Ok(())
+    }
+
+    fn geo_metadata(&self) -> Value {
+        let mut metadata_columns = Map::new();
+        for column in self.columns.iter() {
+            let encoding = match &column.output {
+                Some(native_type) => native_encoding_name(native_type).unwrap(),
+                None => "WKB",
+            };
+
+            let mut metadata = json!({
+                "encoding": encoding,
+                "geometry_types": column.geometry_types,
+            });
+
+            if column.bounds.0 .0 <= column.bounds.1 .0 {
+                let ((xmin, ymin), (xmax, ymax)) = column.bounds;
+                metadata["bbox"] = json!([xmin, ymin, xmax, ymax]);
+            }
+            if let Some(crs) = &column.crs {
+                metadata["crs"] = crs.clone();
+            }
+            if let Some(edges) = &column.edges {
+                metadata["edges"] = edges.clone();
+            }
+            if self.options.bbox_covering {
+                let bbox = bbox_column_name(column, &self.primary_column);
+                metadata["covering"] = json!({
+                    "bbox": {
+                        "xmin": [bbox, "xmin"],
+                        "ymin": [bbox, "ymin"],
+                        "xmax": [bbox, "xmax"],
+                        "ymax": [bbox, "ymax"],
+                    }
+                });
+            }
+
+            metadata_columns.insert(column.name.clone(), metadata);
+        }
+
+        json!({
+            "version": GEOPARQUET_VERSION,
+            "primary_column": self.primary_column,
+            "columns": metadata_columns,
+        })
+    }
+}
+
+/// Encode the geometry columns of `batch` and add the bounding boxes.
+fn encode(
+    columns: &[GeometryColumn],
+    bbox_covering: bool,
+    batch: &RecordBatch,
+    encoded: Vec<Encoded>,
+    output_schema: &SchemaRef,
+) -> Result<RecordBatch> {
+    let mut arrays = batch.columns().to_vec();
+    let mut bboxes = vec![];
+
+    for (column, (wkb, bbox)) in columns.iter().zip(encoded) {
+        arrays[column.index] = match (&column.output, &column.input) {
+            (None, _) => wkb.to_array_ref(),
+            (Some(output), InputEncoding::Native(input)) if output == input => {
+                arrays[column.index].clone()
+            }
+            (Some(output), _) => from_wkb(&wkb, output.clone(), true)
+                .map_err(|e| {
+                    DataFusionError::Execution(format!(
+                        "Geometries of column `{}` do not fit the native type `{}` \
+                         chosen from the first batches, use WKB encoding instead: {e}",
+                        column.name,
+                        native_geometry_type_name(output),
+                    ))
+                })?
+                .to_array_ref(),
+        };
+
+        if bbox_covering {
+            bboxes.push(bbox_array(&bbox)?);
+        }
+    }
+    arrays.extend(bboxes);
+
+    Ok(RecordBatch::try_new(output_schema.clone(), arrays)?)
+}
+
+impl BatchEncoder for GeoParquetWriter<SharedBuffer> {
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        GeoParquetWriter::write(self, batch)
+    }
+
+    fn close(self: Box<Self>) -> Result<()> {
+        GeoParquetWriter::close(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geoarrow::{array::CoordType, datatypes::Dimension};
+
+    use super::*;
+
+    #[test]
+    fn writer_options() {
+        let options = HashMap::from([
+            ("format.encoding".to_string(), "native".to_string()),
+            ("bbox_covering".to_string(), "true".to_string()),
+        ]);
+        let options = GeoParquetWriterOptions::try_from(&options).unwrap();
+
+        assert_eq!(options.encoding, GeoParquetWriterEncoding::Native);
+        assert!(options.bbox_covering);
+    }
+
+    #[test]
+    fn native_target() {
+        let types = BTreeSet::from(["Point".to_string(), "MultiPoint".to_string()]);
+        assert_eq!(
+            native_target_type(&types).unwrap(),
+            NativeType::MultiPoint(CoordType::Separated, Dimension::XY)
+        );
+
+        let types = BTreeSet::from(["Polygon Z".to_string()]);
+        assert_eq!(
+            native_target_type(&types).unwrap(),
+            NativeType::Polygon(CoordType::Separated, Dimension::XYZ)
+        );
+
+        let types = BTreeSet::from(["Point".to_string(), "Polygon".to_string()]);
+        assert!(native_target_type(&types).is_err());
+
+        // measures are only supported as WKB
+        let types = BTreeSet::from(["LineString ZM".to_string()]);
+        let error = native_target_type(&types).unwrap_err().to_string();
+        assert!(error.contains("`LineString ZM`"), "{error}");
+    }
+
+    #[test]
+    fn streaming() -> Result<()> {
+        use datafusion::{
+            arrow::array::BinaryArray,
+            parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
+        };
+
+        let crs = json!({
+            "type": "ProjectedCRS",
+            "name": "CH1903+ / LV95",
+            "id": {"authority": "EPSG", "code": 2056},
+        });
+        let geo = json!({
+            "version": GEOPARQUET_VERSION,
+            "primary_column": "geometry",
+            "columns": {"geometry": {"encoding": "WKB", "crs": crs}},
+        });
+        let schema = Arc::new(Schema::new_with_metadata(
+            vec![Field::new("geometry", DataType::Binary, true)],
+            HashMap::from([("geo".to_string(), geo.to_string())]),
+        ));
+        let batch = |wkt: &[Option<&str>]| {
+            let wkb = wkt
+                .iter()
+                .map(|wkt| wkt.map(|wkt| wkb::from_wkt(&wkt.parse().unwrap()).to_wkb()))
+                .collect::<BinaryArray>();
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(wkb)])
+        };
+
+        let path = std::env::temp_dir().join("datafusion-spatial-streaming.parquet");
+        let options = GeoParquetWriterOptions {
+            encoding: GeoParquetWriterEncoding::Native,
+            bbox_covering: true,
+            ..Default::default()
+        };
+        let mut writer =
+            GeoParquetWriter::try_new(std::fs::File::create(&path)?, schema.clone(), options)?;
+        // the native type is chosen once the first geometries arrive
+        writer.write(&batch(&[None])?)?;
+        writer.write(&batch(&[Some("MULTIPOINT (1 2, 3 4)")])?)?;
+        writer.write(&batch(&[Some("POINT (5 6)"), Some("MULTIPOINT EMPTY")])?)?;
+        writer.close()?;
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path)?)?;
+        let geo = reader
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|kv| kv.iter().find(|kv| kv.key == "geo"))
+            .and_then(|kv| kv.value.clone())
+            .unwrap();
+        let geo = serde_json::from_str::<Value>(&geo).unwrap();
+        assert_eq!(geo["columns"]["geometry"]["encoding"], "multipoint");
+        assert_eq!(geo["columns"]["geometry"]["bbox"], json!([1., 2., 5., 6.]));
+        assert_eq!(geo["columns"]["geometry"]["crs"], crs);
+
+        let batches = reader.build()?.collect::<Result<Vec<_>, _>>()?;
+        let rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert_eq!(rows, 4);
+        // null and empty geometries have no bounding box
+        let bbox = batches
+            .iter()
+            .flat_map(|b| {
+                let bbox = b.column(1).as_struct();
+                (0..bbox.len())
+                    .map(|i| bbox.is_valid(i))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bbox, vec![false, true, true, false]);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn crs() {
+        let projjson = json!({"type": "GeographicCRS", "name": "WGS 84"});
+        assert_eq!(crs_to_json(&projjson), Some(projjson.clone()));
+        assert_eq!(
+            crs_to_json(&json!(projjson.to_string())),
+            Some(projjson.clone())
+        );
+        assert_eq!(crs_to_json(&Value::Null), Some(Value::Null));
+
+        // authority codes are no PROJJSON, the default CRS is omitted
+        assert_eq!(crs_to_json(&json!("EPSG:2056")), Some(Value::Null));
+        assert_eq!(crs_to_json(&json!("OGC:CRS84")), None);
+    }
+}
@@ -0,0 +1,406 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    io::{Cursor, Write},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        array::RecordBatch,
+        buffer::Buffer,
+        datatypes::{Schema, SchemaRef},
+        ipc::{
+            convert::fb_to_schema,
+            reader::{FileDecoder, StreamDecoder, StreamReader},
+            root_as_footer,
+            writer::{FileWriter, StreamWriter},
+            Block,
+        },
+    },
+    common::{GetExt, Statistics},
+    datasource::{
+        file_format::{file_compression_type::FileCompressionType, FileFormat, FileFormatFactory},
+        physical_plan::{FileScanConfig, FileSinkConfig},
+    },
+    error::{DataFusionError, Result},
+    execution::context::SessionState,
+    physical_expr::{LexRequirement, PhysicalExpr},
+    physical_plan::ExecutionPlan,
+};
+use futures::{StreamExt, TryStreamExt};
+use object_store::{ObjectMeta, ObjectStore};
+
+use super::{
+    create_writer_plan,
+    scan::{Batches, ObjectScanExec},
+    BatchEncoder, SharedBuffer,
+};
+
+/// Magic bytes starting an Arrow IPC file, streams start with a message.
+const FILE_MAGIC: &[u8] = b"ARROW1";
+
+/// Arrow IPC serialization format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpcFormat {
+    /// Random access file format (Feather v2).
+    #[default]
+    File,
+    /// Streaming format.
+    Stream,
+}
+
+impl TryFrom<&str> for IpcFormat {
+    type Error = DataFusionError;
+
+    fn try_from(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "file" | "feather" => Ok(Self::File),
+            "stream" => Ok(Self::Stream),
+            format => Err(DataFusionError::Configuration(format!(
+                "Unsupported Arrow IPC format `{format}`, expected `file` or `stream`"
+            ))),
+        }
+    }
+}
+
+/// Factory registering the `GEOARROW` file format with a session.
+#[derive(Debug, Default)]
+pub struct GeoArrowFormatFactory {}
+
+impl GeoArrowFormatFactory {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl GetExt for GeoArrowFormatFactory {
+    fn get_ext(&self) -> String {
+        "geoarrow".to_string()
+    }
+}
+
+impl FileFormatFactory for GeoArrowFormatFactory {
+    fn create(
+        &self,
+        _state: &SessionState,
+        format_options: &HashMap<String, String>,
+    ) -> Result<Arc<dyn FileFormat>> {
+        let mut format = GeoArrowFormat::default();
+
+        for (key, value) in format_options {
+            match key.trim_start_matches("format.") {
+                "ipc_format" => format.ipc_format = IpcFormat::try_from(value.as_str())?,
+                _ => {
+                    return Err(DataFusionError::Configuration(format!(
+                        "Unsupported GeoArrow option `{key}`"
+                    )))
+                }
+            }
+        }
+
+        Ok(Arc::new(format))
+    }
+
+    fn default(&self) -> Arc<dyn FileFormat> {
+        Arc::new(GeoArrowFormat::default())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Arrow IPC file format for GeoArrow data.
+///
+/// Both IPC files and streams are read, the `geoarrow.*` extension metadata of
+/// geometry fields is kept. Written files use the `ipc_format` option.
+#[derive(Debug, Default)]
+pub struct GeoArrowFormat {
+    ipc_format: IpcFormat,
+}
+
+#[async_trait]
+impl FileFormat for GeoArrowFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_ext(&self) -> String {
+        match self.ipc_format {
+            IpcFormat::File => "arrow".to_string(),
+            IpcFormat::Stream => "arrows".to_string(),
+        }
+    }
+
+    fn get_ext_with_compression(
+        &self,
+        _file_compression_type: &FileCompressionType,
+    ) -> Result<String> {
+        Ok(self.get_ext())
+    }
+
+    async fn infer_schema(
+        &self,
+        _state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        objects: &[ObjectMeta],
+    ) -> Result<SchemaRef> {
+        let mut schemas = vec![];
+        for object in objects {
+            schemas.push(read_schema(store, object).await?);
+        }
+
+        Ok(Arc::new(Schema::try_merge(schemas)?))
+    }
+
+    async fn infer_stats(
+        &self,
+        _state: &SessionState,
+        _store: &Arc<dyn ObjectStore>,
+        table_schema: SchemaRef,
+        _object: &ObjectMeta,
+    ) -> Result<Statistics> {
+        Ok(Statistics::new_unknown(&table_schema))
+    }
+
+    async fn create_physical_plan(
+        &self,
+        _state: &SessionState,
+        conf: FileScanConfig,
+        _filters: Option<&Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(ObjectScanExec::try_new(
+            "GeoArrowExec",
+            conf,
+            String::new(),
+            |store, object| Box::pin(async move { open_object(&store, &object).await }),
+        )?))
+    }
+
+    async fn create_writer_physical_plan(
+        &self,
+        input: Arc<dyn ExecutionPlan>,
+        _state: &SessionState,
+        conf: FileSinkConfig,
+        order_requirements: Option<LexRequirement>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let ipc_format = self.ipc_format;
+
+        create_writer_plan(
+            "GeoArrow",
+            input,
+            conf,
+            order_requirements,
+            move |schema, buffer| {
+                let writer = IpcWriter::try_new(buffer, &schema, ipc_format)?;
+                Ok(Box::new(writer) as Box<dyn BatchEncoder>)
+            },
+        )
+    }
+}
+
+/// Error for an object which is not a valid Arrow IPC file or stream.
+fn invalid(object: &ObjectMeta, e: &dyn std::fmt::Display) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "Invalid Arrow IPC object `{}`: {e}",
+        object.location
+    ))
+}
+
+/// Fetch the first bytes of an object, enough to tell files from streams.
+async fn read_head(store: &Arc<dyn ObjectStore>, object: &ObjectMeta) -> Result<Buffer> {
+    if object.size < 8 {
+        return Err(invalid(object, &"too small"));
+    }
+
+    Ok(store.get_range(&object.location, 0..8).await?.into())
+}
+
+/// Fetch the footer of an Arrow IPC file, which is followed by its length and
+/// the magic bytes.
+async fn read_footer(store: &Arc<dyn ObjectStore>, object: &ObjectMeta) -> Result<Buffer> {
+    let size = object.size;
+    let tail = store
+        .get_range(&object.location, size.saturating_sub(10)..size)
+        .await?;
+    if tail.len() < 10 || !tail.ends_with(FILE_MAGIC) {
+        return Err(invalid(object, &"missing magic bytes at the end"));
+    }
+
+    let length = i32::from_le_bytes([tail[0], tail[1], tail[2], tail[3]]);
+    let length = usize::try_from(length)
+        .ok()
+        .filter(|length| size >= 10 + length)
+        .ok_or_else(|| invalid(object, &format!("footer length {length} out of bounds")))?;
+
+    let footer = store
+        .get_range(&object.location, size - 10 - length..size - 10)
+        .await?;
+    Ok(footer.into())
+}
+
+/// Read the schema of an Arrow IPC file from its footer, or of a stream from
+/// its first message, without fetching the record batches.
+async fn read_schema(store: &Arc<dyn ObjectStore>, object: &ObjectMeta) -> Result<Schema> {
+    let head = read_head(store, object).await?;
+    if head.starts_with(FILE_MAGIC) {
+        let footer = read_footer(store, object).await?;
+        let footer = root_as_footer(&footer).map_err(|e| invalid(object, &e))?;
+        let schema = footer
+            .schema()
+            .ok_or_else(|| invalid(object, &"missing schema"))?;
+
+        Ok(fb_to_schema(schema))
+    } else {
+        // messages start with a continuation marker and their length, streams
+        // written before Arrow 0.15 only with the length
+        let (prefix, length) = match head[..4] == [0xff; 4] {
+            true => (8, i32::from_le_bytes([head[4], head[5], head[6], head[7]])),
+            false => (4, i32::from_le_bytes([head[0], head[1], head[2], head[3]])),
+        };
+        let end = usize::try_from(length)
+            .ok()
+            .map(|length| prefix + length)
+            .filter(|end| *end <= object.size)
+            .ok_or_else(|| invalid(object, &format!("message length {length} out of bounds")))?;
+        let message = store.get_range(&object.location, 0..end).await?;
+        let reader = StreamReader::try_new(Cursor::new(message), None)?;
+
+        Ok(reader.schema().as_ref().clone())
+    }
+}
+
+/// Open an Arrow IPC file or stream, record batches are decoded as they are
+/// polled and passed on as they are.
+///
+/// The record batches of files are fetched one by one with range requests
+/// from the blocks listed in the footer, streams are decoded while their
+/// bytes are downloaded.
+async fn open_object(store: &Arc<dyn ObjectStore>, object: &ObjectMeta) -> Result<Batches> {
+    let head = read_head(store, object).await?;
+    if !head.starts_with(FILE_MAGIC) {
+        return decode_stream(store, object).await;
+    }
+
+    let footer = read_footer(store, object).await?;
+    let footer = root_as_footer(&footer).map_err(|e| invalid(object, &e))?;
+    let schema = footer
+        .schema()
+        .ok_or_else(|| invalid(object, &"missing schema"))?;
+    let mut decoder = FileDecoder::new(Arc::new(fb_to_schema(schema)), footer.version());
+
+    // a block is a message, its metadata followed by its body
+    let (store, location) = (store.clone(), object.location.clone());
+    let fetch = move |block: &Block| {
+        let start = block.offset() as usize;
+        let end = start + block.metaDataLength() as usize + block.bodyLength() as usize;
+        let store = store.clone();
+        let location = location.clone();
+        async move {
+            Ok::<_, DataFusionError>(Buffer::from(store.get_range(&location, start..end).await?))
+        }
+    };
+
+    // dictionaries are needed to decode any of the record batches
+    for block in footer.dictionaries().into_iter().flatten() {
+        decoder.read_dictionary(block, &fetch(block).await?)?;
+    }
+
+    let blocks = footer
+        .recordBatches()
+        .map(|blocks| blocks.iter().copied().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let decoder = Arc::new(decoder);
+    let object = object.clone();
+    let batches = futures::stream::iter(blocks)
+        .then(move |block| {
+            let buffer = fetch(&block);
+            let decoder = decoder.clone();
+            let object = object.clone();
+            async move {
+                decoder
+                    .read_record_batch(&block, &buffer.await?)?
+                    .ok_or_else(|| invalid(&object, &"missing record batch"))
+            }
+        })
+        .boxed();
+
+    Ok(batches)
+}
+
+/// Decode the record batches of an Arrow IPC stream as its bytes arrive.
+async fn decode_stream(store: &Arc<dyn ObjectStore>, object: &ObjectMeta) -> Result<Batches> {
+    let bytes = store
+        .get(&object.location)
+        .await?
+        .into_stream()
+        .map_err(DataFusionError::from);
+
+    let state = (
+        bytes,
+        StreamDecoder::new(),
+        Buffer::from_vec(Vec::<u8>::new()),
+    );
+    let batches =
+        futures::stream::try_unfold(state, |(mut bytes, mut decoder, mut buffer)| async move {
+            loop {
+                if buffer.is_empty() {
+                    let Some(chunk) = bytes.try_next().await? else {
+                        decoder.finish()?;
+                        return Ok::<_, DataFusionError>(None);
+                    };
+                    buffer = Buffer::from(chunk);
+                }
+                if let Some(batch) = decoder.decode(&mut buffer)? {
+                    return Ok(Some((batch, (bytes, decoder, buffer))));
+                }
+            }
+        });
+
+    Ok(batches.boxed())
+}
+
+/// Arrow IPC file or stream writer, field metadata (and with it the GeoArrow
+/// extension types) is written as is.
+pub(crate) enum IpcWriter<W: Write> {
+    File(FileWriter<W>),
+    Stream(StreamWriter<W>),
+}
+
+impl<W: Write> IpcWriter<W> {
+    pub fn try_new(writer: W, schema: &Schema, ipc_format: IpcFormat) -> Result<Self> {
+        Ok(match ipc_format {
+            IpcFormat::File => Self::File(FileWriter::try_new(writer, schema)?),
+            IpcFormat::Stream => Self::Stream(StreamWriter::try_new(writer, schema)?),
+        })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        match self {
+            Self::File(writer) => writer.write(batch)?,
+            Self::Stream(writer) => writer.write(batch)?,
+        }
+        Ok(())
+    }
+
+    /// Write the footer of files, the end of stream marker of streams.
+    pub fn close(mut self) -> Result<()> {
+        match &mut self {
+            Self::File(writer) => writer.finish()?,
+            Self::Stream(writer) => writer.finish()?,
+        }
+        Ok(())
+    }
+}
+
+impl BatchEncoder for IpcWriter<SharedBuffer> {
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        IpcWriter::write(self, batch)
+    }
+
+    fn close(self: Box<Self>) -> Result<()> {
+        IpcWriter::close(*self)
+    }
+}
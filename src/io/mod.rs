@@ -0,0 +1,750 @@
+mod builder;
+pub mod csv;
+mod file_table;
+pub mod flatgeobuf;
+pub mod geojson;
+pub mod geopackage;
+pub mod geoparquet;
+pub mod ipc;
+pub mod osm;
+mod scan;
+pub mod shapefile;
+
+use std::{
+    any::Any,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        array::{AsArray, RecordBatch},
+        datatypes::{DataType, Field, Schema, SchemaRef},
+    },
+    catalog::{SchemaProvider, Session, TableProviderFactory},
+    common::not_impl_err,
+    dataframe::DataFrame,
+    datasource::{physical_plan::FileSinkConfig, provider::DefaultTableFactory, TableProvider},
+    error::{DataFusionError, Result},
+    execution::{context::SessionState, TaskContext},
+    logical_expr::{CreateExternalTable, Operator},
+    physical_expr::{
+        expressions::{BinaryExpr, Column as PhysicalColumn, Literal},
+        LexRequirement, PhysicalExpr, ScalarFunctionExpr,
+    },
+    physical_plan::{
+        common::collect,
+        execute_stream,
+        insert::{DataSink, DataSinkExec},
+        metrics::MetricsSet,
+        projection::ProjectionExec,
+        DisplayAs, DisplayFormatType, ExecutionPlan, SendableRecordBatchStream,
+    },
+    prelude::{Column, Expr},
+    scalar::ScalarValue,
+};
+use futures::StreamExt;
+use geoarrow::{
+    array::{AsNativeArray, CoordType, NativeArrayDyn, WKBArray},
+    datatypes::{Dimension, NativeType},
+    error::GeoArrowError,
+    ArrayBase,
+};
+use object_store::{buffered::BufWriter, path::Path as ObjectPath};
+use serde_json::{json, Map, Value};
+use tokio::io::AsyncWriteExt;
+
+use self::{
+    file_table::FileTable,
+    geoparquet::{GeoParquetWriter, GeoParquetWriterOptions},
+    ipc::{IpcFormat, IpcWriter},
+};
+use crate::{
+    compute::min_max_2d,
+    helpers::{has_measures, native_measures_error},
+    rules::{function_geometry_info, geometry_field},
+};
+
+/// Spatial extensions to [`DataFrame`].
+#[async_trait]
+pub trait SpatialDataFrameExt {
+    /// Execute the `DataFrame` and write the results to a GeoParquet file at `path`.
+    async fn write_geoparquet(self, path: &str, options: GeoParquetWriterOptions) -> Result<u64>;
+
+    /// Execute the `DataFrame` and write the results as a new feature table
+    /// `layer` to the GeoPackage at `path`.
+    async fn write_geopackage(self, path: &str, layer: &str) -> Result<u64>;
+
+    /// Execute the `DataFrame` and write the results as an Arrow IPC file or
+    /// stream at `path`, keeping the GeoArrow extension metadata.
+    async fn write_geoarrow(self, path: &str, ipc_format: IpcFormat) -> Result<u64>;
+}
+
+#[async_trait]
+impl SpatialDataFrameExt for DataFrame {
+    async fn write_geoparquet(self, path: &str, options: GeoParquetWriterOptions) -> Result<u64> {
+        let (schema, mut stream) = execute_with_geometry_schema(self).await?;
+        let file = std::fs::File::create(path)?;
+        let mut writer = GeoParquetWriter::try_new(file, schema.clone(), options)?;
+
+        let mut rows = 0;
+        while let Some(batch) = stream.next().await.transpose()? {
+            rows += batch.num_rows() as u64;
+            writer.write(&batch.with_schema(schema.clone())?)?;
+        }
+        writer.close()?;
+
+        Ok(rows)
+    }
+
+    async fn write_geopackage(self, path: &str, layer: &str) -> Result<u64> {
+        let (schema, stream) = execute_with_geometry_schema(self).await?;
+        let batches = collect(stream).await?;
+
+        geopackage::write_geopackage(Path::new(path), layer, schema, &batches)
+    }
+
+    async fn write_geoarrow(self, path: &str, ipc_format: IpcFormat) -> Result<u64> {
+        let (schema, mut stream) = execute_with_geometry_schema(self).await?;
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut writer = IpcWriter::try_new(file, &schema, ipc_format)?;
+
+        let mut rows = 0;
+        while let Some(batch) = stream.next().await.transpose()? {
+            rows += batch.num_rows() as u64;
+            writer.write(&batch.with_schema(schema.clone())?)?;
+        }
+        writer.close()?;
+
+        Ok(rows)
+    }
+}
+
+/// Execute `df`, returning the stream of its results and their schema with
+/// the geometry info of computed geometry columns, see [`geometry_schema`].
+async fn execute_with_geometry_schema(
+    df: DataFrame,
+) -> Result<(SchemaRef, SendableRecordBatchStream)> {
+    let context = Arc::new(df.task_ctx());
+    let plan = df.create_physical_plan().await?;
+    let schema = geometry_schema(&plan);
+
+    Ok((schema, execute_stream(plan, context)?))
+}
+
+/// Schema of the output of `plan` with the GeoArrow extension type, CRS and
+/// edges of geometry columns computed by spatial functions, e.g. the CRS set
+/// with `ST_SetSRID`.
+///
+/// Physical projections only keep the field metadata of plain columns, the
+/// geometry info is recovered from the arguments appended to the spatial
+/// functions by the [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule).
+pub(crate) fn geometry_schema(plan: &Arc<dyn ExecutionPlan>) -> SchemaRef {
+    let schema = plan.schema();
+    let fields = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match computed_geometry_info(plan, index) {
+            Some(info) => Arc::new(geometry_field(field, &info)),
+            None => field.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+/// Geometry info of the column `index` of `plan` if it is computed by a
+/// spatial function, following plain columns through projections and plans
+/// passing on their input like filters, sorts and repartitions.
+fn computed_geometry_info(plan: &Arc<dyn ExecutionPlan>, index: usize) -> Option<[Expr; 4]> {
+    if let Some(projection) = plan.as_any().downcast_ref::<ProjectionExec>() {
+        let (expr, _) = &projection.expr()[index];
+        if let Some(column) = expr.as_any().downcast_ref::<PhysicalColumn>() {
+            return computed_geometry_info(projection.input(), column.index());
+        }
+
+        let function = expr.as_any().downcast_ref::<ScalarFunctionExpr>()?;
+        if !function.name().starts_with("ST_") {
+            return None;
+        }
+        // the geometry info only depends on constant arguments
+        let args = function
+            .args()
+            .iter()
+            .map(|arg| match arg.as_any().downcast_ref::<Literal>() {
+                Some(literal) => Expr::Literal(literal.value().clone()),
+                None => Expr::Column(Column::from_name(arg.to_string())),
+            })
+            .collect::<Vec<_>>();
+        return function_geometry_info(function.name(), &args).ok()?;
+    }
+
+    match plan.children().as_slice() {
+        [input] if input.schema().fields() == plan.schema().fields() => {
+            computed_geometry_info(input, index)
+        }
+        _ => None,
+    }
+}
+
+/// Incremental encoder of a file format writing to a [`SharedBuffer`].
+pub(crate) trait BatchEncoder: Send {
+    /// Encode `batch`, flushing complete chunks of the output to the buffer.
+    fn write(&mut self, batch: &RecordBatch) -> Result<()>;
+
+    /// Finish the file, e.g. writing footers and metadata.
+    fn close(self: Box<Self>) -> Result<()>;
+}
+
+type EncoderFn = dyn Fn(SchemaRef, SharedBuffer) -> Result<Box<dyn BatchEncoder>> + Send + Sync;
+
+/// In memory buffer shared between an encoder and the sink draining it to the
+/// object store after every batch.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Take the bytes written so far.
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Encoder for formats which can only be written at once, e.g. because of a
+/// spatial index, collecting all batches until closed.
+struct CollectingEncoder<F> {
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+    buffer: SharedBuffer,
+    encode: Arc<F>,
+}
+
+impl<F> BatchEncoder for CollectingEncoder<F>
+where
+    F: Fn(SchemaRef, &[RecordBatch]) -> Result<Vec<u8>> + Send + Sync,
+{
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.batches.push(batch.clone());
+        Ok(())
+    }
+
+    fn close(mut self: Box<Self>) -> Result<()> {
+        let encoded = (self.encode)(self.schema.clone(), &self.batches)?;
+        self.buffer.write_all(&encoded)?;
+        Ok(())
+    }
+}
+
+/// Encoder factory for a format encoding all batches at once with `encode`.
+pub(crate) fn collecting_encoder<F>(
+    encode: F,
+) -> impl Fn(SchemaRef, SharedBuffer) -> Result<Box<dyn BatchEncoder>> + Send + Sync + 'static
+where
+    F: Fn(SchemaRef, &[RecordBatch]) -> Result<Vec<u8>> + Send + Sync + 'static,
+{
+    let encode = Arc::new(encode);
+    move |schema, buffer| {
+        Ok(Box::new(CollectingEncoder {
+            schema,
+            batches: vec![],
+            buffer,
+            encode: encode.clone(),
+        }))
+    }
+}
+
+/// Sink streaming the encoded input batches into a single file on the
+/// configured object store.
+struct FileSink {
+    format: &'static str,
+    config: FileSinkConfig,
+    /// Schema of the input with the geometry info of computed geometry columns
+    schema: SchemaRef,
+    encoder: Box<EncoderFn>,
+}
+
+impl fmt::Debug for FileSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileSink")
+            .field("format", &self.format)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl DisplayAs for FileSink {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(
+                    f,
+                    "{}Sink(path={})",
+                    self.format, self.config.table_paths[0]
+                )
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DataSink for FileSink {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        None
+    }
+
+    async fn write_all(
+        &self,
+        mut data: SendableRecordBatchStream,
+        context: &Arc<TaskContext>,
+    ) -> Result<u64> {
+        let store = context
+            .runtime_env()
+            .object_store(&self.config.object_store_url)?;
+        let url = &self.config.table_paths[0];
+        let path = if url.is_collection() {
+            ObjectPath::from(format!(
+                "{}/part-0.{}",
+                url.prefix(),
+                self.format.to_lowercase()
+            ))
+        } else {
+            url.prefix().clone()
+        };
+
+        let buffer = SharedBuffer::default();
+        let mut encoder = (self.encoder)(self.schema.clone(), buffer.clone())?;
+        let mut writer = BufWriter::new(store, path);
+
+        let mut rows = 0;
+        while let Some(batch) = data.next().await.transpose()? {
+            rows += batch.num_rows() as u64;
+            encoder.write(&batch.with_schema(self.schema.clone())?)?;
+            writer.write_all(&buffer.take()).await?;
+        }
+        encoder.close()?;
+        writer.write_all(&buffer.take()).await?;
+        writer.shutdown().await?;
+
+        Ok(rows)
+    }
+}
+
+/// Create the physical plan of a `COPY ... TO` writing a single file with the
+/// encoders created by `encoder`.
+pub(crate) fn create_writer_plan(
+    format: &'static str,
+    input: Arc<dyn ExecutionPlan>,
+    conf: FileSinkConfig,
+    order_requirements: Option<LexRequirement>,
+    encoder: impl Fn(SchemaRef, SharedBuffer) -> Result<Box<dyn BatchEncoder>> + Send + Sync + 'static,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    if !conf.table_partition_cols.is_empty() {
+        return not_impl_err!("Partitioned writes are not supported for {format}");
+    }
+
+    let sink_schema = conf.output_schema().clone();
+    let sink = Arc::new(FileSink {
+        format,
+        config: conf,
+        schema: geometry_schema(&input),
+        encoder: Box::new(encoder),
+    });
+
+    Ok(Arc::new(DataSinkExec::new(
+        input,
+        sink,
+        sink_schema,
+        order_requirements,
+    )))
+}
+
+/// Spatial predicates for which a bounding box of the second argument can be
+/// used to prefilter features.
+const BBOX_PREDICATES: &[&str] = &["ST_Intersects"];
+
+/// Extract a `(xmin, ymin, xmax, ymax)` bounding box from a pushed down filter.
+///
+/// Recognizes conjunctions of spatial predicates (see [`BBOX_PREDICATES`])
+/// whose other argument is a WKB literal or, once constant folded, a native
+/// polygon literal.
+pub(crate) fn bbox_from_filter(expr: &Arc<dyn PhysicalExpr>) -> Option<(f64, f64, f64, f64)> {
+    if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+        if binary.op() == &Operator::And {
+            return match (
+                bbox_from_filter(binary.left()),
+                bbox_from_filter(binary.right()),
+            ) {
+                (Some(l), Some(r)) => {
+                    Some((l.0.max(r.0), l.1.max(r.1), l.2.min(r.2), l.3.min(r.3)))
+                }
+                (bbox, None) | (None, bbox) => bbox,
+            };
+        }
+        return None;
+    }
+
+    let func = expr.as_any().downcast_ref::<ScalarFunctionExpr>()?;
+    if !BBOX_PREDICATES.contains(&func.name()) {
+        return None;
+    }
+
+    func.args().iter().take(2).find_map(bbox_from_arg)
+}
+
+fn bbox_from_arg(expr: &Arc<dyn PhysicalExpr>) -> Option<(f64, f64, f64, f64)> {
+    let literal = expr.as_any().downcast_ref::<Literal>()?;
+    if let ScalarValue::Binary(Some(bytes)) | ScalarValue::LargeBinary(Some(bytes)) =
+        literal.value()
+    {
+        let ((xmin, ymin), (xmax, ymax)) = crate::wkb::parse(bytes).ok()?.bounds()?;
+        return Some((xmin, ymin, xmax, ymax));
+    }
+
+    let array = literal.value().to_array().ok()?;
+    let field = NativeType::Polygon(CoordType::Separated, Dimension::XY).to_field("geometry", true);
+    let polygons = NativeArrayDyn::from_arrow_array(&array, &field).ok()?;
+    let coords = polygons.as_ref().as_polygon::<2>().coords();
+    if coords.is_empty() {
+        return None;
+    }
+
+    let ((xmin, ymin), (xmax, ymax)) = min_max_2d(coords, false);
+    Some((xmin, ymin, xmax, ymax))
+}
+
+/// Table provider factory for `CREATE EXTERNAL TABLE ... STORED AS <FORMAT>`
+/// with one of the spatial file formats.
+#[derive(Debug, Default)]
+pub struct SpatialTableFactory {}
+
+impl SpatialTableFactory {
+    /// File types handled by this factory.
+    pub const FILE_TYPES: &'static [&'static str] = &[
+        "CSV",
+        "GEOJSON",
+        "GEOJSONL",
+        "GEOPACKAGE",
+        "OSMPBF",
+        "SHAPEFILE",
+    ];
+
+    /// File types read through a registered file format, see [`register_file_formats`].
+    pub const FILE_FORMATS: &'static [&'static str] = &["FLATGEOBUF", "GEOARROW"];
+
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Register the factory for all supported file types with `state`.
+    pub fn register(state: &mut SessionState) {
+        let factory = Arc::new(Self::new());
+        for file_type in Self::FILE_TYPES {
+            state
+                .table_factories_mut()
+                .insert(file_type.to_string(), factory.clone());
+        }
+
+        let listing_factory = Arc::new(DefaultTableFactory::new());
+        for file_type in Self::FILE_FORMATS {
+            state
+                .table_factories_mut()
+                .insert(file_type.to_string(), listing_factory.clone());
+        }
+    }
+}
+
+#[async_trait]
+impl TableProviderFactory for SpatialTableFactory {
+    async fn create(
+        &self,
+        state: &dyn Session,
+        cmd: &CreateExternalTable,
+    ) -> Result<Arc<dyn TableProvider>> {
+        let file_type = cmd.file_type.to_uppercase();
+        if file_type == "CSV" {
+            return csv::create(state, cmd).await;
+        }
+
+        let options = cmd
+            .options
+            .iter()
+            .map(|(k, v)| (k.trim_start_matches("format.").to_string(), v.clone()))
+            .collect::<HashMap<_, _>>();
+
+        if file_type == "GEOPACKAGE" {
+            let path = local_path(&cmd.location);
+            let layer = match options.get("layer") {
+                Some(layer) => layer.to_owned(),
+                None => geopackage::GeoPackageSchemaProvider::try_new(&path)?
+                    .table_names()
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        DataFusionError::Plan(format!("No feature table in `{}`", cmd.location))
+                    })?,
+            };
+            let table = geopackage::GeoPackageTable::try_new(path, &layer, &options)?;
+            return Ok(Arc::new(table));
+        }
+
+        let table = match file_type.as_str() {
+            "GEOJSON" => FileTable::try_new(&cmd.location, &["geojson", "json"], move |path| {
+                geojson::read_geojson(path, &options)
+            })?,
+            "GEOJSONL" => FileTable::try_new(
+                &cmd.location,
+                &["geojsonl", "geojsons", "ndjson"],
+                move |path| geojson::read_geojson_lines(path, &options),
+            )?,
+            "OSMPBF" => {
+                let table = options.get("table").ok_or_else(|| {
+                    DataFusionError::Configuration(
+                        "Set the `table` option to one of `nodes`, `ways` or `relations`"
+                            .to_string(),
+                    )
+                })?;
+                let table = osm::OsmTable::try_from(table.as_str())?;
+                FileTable::try_new_with_schema(
+                    &cmd.location,
+                    &["pbf"],
+                    osm::table_schema(table)?,
+                    move |path| osm::read_osm_pbf(path, table, &options),
+                )?
+            }
+            "SHAPEFILE" => FileTable::try_new(&cmd.location, &["shp", "zip"], move |path| {
+                shapefile::read_shapefile(path, &options)
+            })?,
+            ft => {
+                return Err(DataFusionError::Configuration(format!(
+                    "Unsupported spatial file type `{ft}`"
+                )))
+            }
+        };
+
+        Ok(Arc::new(table))
+    }
+}
+
+/// Register the spatial file formats with `state`, enabling them for
+/// `COPY ... TO ... STORED AS <FORMAT>`.
+pub fn register_file_formats(state: &mut SessionState) -> Result<()> {
+    state.register_file_format(Arc::new(geoparquet::GeoParquetFormatFactory::new()), false)?;
+    state.register_file_format(Arc::new(flatgeobuf::FlatGeobufFormatFactory::new()), false)?;
+    state.register_file_format(Arc::new(ipc::GeoArrowFormatFactory::new()), false)?;
+
+    Ok(())
+}
+
+/// Local file system path of a table location.
+pub(crate) fn local_path(location: &str) -> PathBuf {
+    PathBuf::from(location.strip_prefix("file://").unwrap_or(location))
+}
+
+/// GeoParquet column encoding of a native type, `None` if it has no native encoding.
+pub(crate) fn native_encoding_name(native_type: &NativeType) -> Option<&'static str> {
+    match native_type {
+        NativeType::Point(_, _) => Some("point"),
+        NativeType::LineString(_, _) => Some("linestring"),
+        NativeType::Polygon(_, _) => Some("polygon"),
+        NativeType::MultiPoint(_, _) => Some("multipoint"),
+        NativeType::MultiLineString(_, _) => Some("multilinestring"),
+        NativeType::MultiPolygon(_, _) => Some("multipolygon"),
+        _ => None,
+    }
+}
+
+/// Native GeoArrow type able to hold all geometry types found in a column.
+pub(crate) fn native_target_type(geometry_types: &BTreeSet<String>) -> Result<NativeType> {
+    let mut dimensions = geometry_types
+        .iter()
+        .map(|t| t.split_once(' ').map(|(_, d)| d).unwrap_or(""))
+        .collect::<BTreeSet<_>>();
+    let dimension = match dimensions.pop_first() {
+        None | Some("") => Dimension::XY,
+        Some("Z") => Dimension::XYZ,
+        Some(d) => {
+            let measured = geometry_types.iter().find(|t| has_measures(t));
+            return Err(native_measures_error(measured.map_or(d, String::as_str)));
+        }
+    };
+    if !dimensions.is_empty() {
+        return Err(DataFusionError::Plan(
+            "Native GeoParquet encoding requires a single coordinate dimension".to_string(),
+        ));
+    }
+
+    let base = geometry_types
+        .iter()
+        .map(|t| t.split(' ').next().unwrap())
+        .collect::<BTreeSet<_>>();
+
+    let ct = CoordType::Separated;
+    let native_type = match base.into_iter().collect::<Vec<_>>().as_slice() {
+        [] | ["Point"] => NativeType::Point(ct, dimension),
+        ["LineString"] => NativeType::LineString(ct, dimension),
+        ["Polygon"] => NativeType::Polygon(ct, dimension),
+        ["MultiPoint"] | ["MultiPoint", "Point"] => NativeType::MultiPoint(ct, dimension),
+        ["LineString", "MultiLineString"] | ["MultiLineString"] => {
+            NativeType::MultiLineString(ct, dimension)
+        }
+        ["MultiPolygon"] | ["MultiPolygon", "Polygon"] => NativeType::MultiPolygon(ct, dimension),
+        types => {
+            return Err(DataFusionError::Plan(format!(
+                "Native GeoParquet encoding does not support geometry types {types:?}"
+            )))
+        }
+    };
+
+    Ok(native_type)
+}
+
+/// GeoParquet geometry type name of a native type, e.g. `Point Z`.
+pub(crate) fn native_geometry_type_name(native_type: &NativeType) -> String {
+    let (name, dimension) = match native_type {
+        NativeType::Point(_, d) => ("Point", d),
+        NativeType::LineString(_, d) => ("LineString", d),
+        NativeType::Polygon(_, d) => ("Polygon", d),
+        NativeType::MultiPoint(_, d) => ("MultiPoint", d),
+        NativeType::MultiLineString(_, d) => ("MultiLineString", d),
+        NativeType::MultiPolygon(_, d) => ("MultiPolygon", d),
+        NativeType::Mixed(_, d) => ("Geometry", d),
+        NativeType::GeometryCollection(_, d) => ("GeometryCollection", d),
+        NativeType::Rect(d) => ("Polygon", d),
+    };
+
+    match dimension {
+        Dimension::XY => name.to_string(),
+        Dimension::XYZ => format!("{name} Z"),
+    }
+}
+
+pub(crate) fn wkb_field(field: &Field) -> Field {
+    let mut metadata = field.metadata().clone();
+    metadata.insert(
+        "ARROW:extension:name".to_string(),
+        "geoarrow.wkb".to_string(),
+    );
+
+    Field::new(field.name(), DataType::Binary, field.is_nullable()).with_metadata(metadata)
+}
+
+/// Prepare batches read from a spatial file format for querying: GeoParquet
+/// style `geo` metadata is attached to the schema so that the
+/// [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule) can pick up the
+/// geometry types.
+///
+/// Native columns without a GeoParquet encoding (mixed geometries, geometry
+/// collections and rects) are passed through untouched, the spatial functions
+/// recognize them by their data type.
+pub(crate) fn prepare_batches(
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let mut fields = schema.fields().iter().cloned().collect::<Vec<_>>();
+    let mut arrays = batches
+        .iter()
+        .map(|batch| batch.columns().to_vec())
+        .collect::<Vec<_>>();
+    let mut columns = Map::new();
+
+    for (index, field) in schema.fields().iter().enumerate() {
+        let Some(extension) = field.metadata().get("ARROW:extension:name") else {
+            continue;
+        };
+        if !extension.starts_with("geoarrow.") {
+            continue;
+        }
+
+        let native_type = match extension.as_str() {
+            "geoarrow.wkb" => None,
+            _ => Some(
+                NativeType::try_from(field.as_ref())
+                    .map_err(|e: GeoArrowError| DataFusionError::External(Box::new(e)))?,
+            ),
+        };
+
+        let mut geometry_types = BTreeSet::new();
+        let encoding = match &native_type {
+            Some(native_type) => {
+                let Some(encoding) = native_encoding_name(native_type) else {
+                    continue;
+                };
+                geometry_types.insert(native_geometry_type_name(native_type));
+                encoding
+            }
+            None => {
+                fields[index] = wkb_field(field).into();
+                for columns in arrays.iter_mut() {
+                    let wkb = WKBArray::<i32>::try_from(columns[index].as_ref())
+                        .map_err(|e| DataFusionError::External(Box::new(e)))?
+                        .to_array_ref();
+                    // parsed by the crate's own reader to keep M dimensions
+                    for geom in wkb.as_binary::<i32>().iter().flatten() {
+                        geometry_types.insert(crate::wkb::parse(geom)?.type_name());
+                    }
+                    columns[index] = wkb;
+                }
+                "WKB"
+            }
+        };
+
+        let mut metadata = json!({
+            "encoding": encoding,
+            "geometry_types": geometry_types,
+        });
+        if let Some(extension) = field
+            .metadata()
+            .get("ARROW:extension:metadata")
+            .and_then(|m| serde_json::from_str::<Value>(m).ok())
+        {
+            for key in ["crs", "edges"] {
+                if let Some(value) = extension.get(key) {
+                    metadata[key] = value.clone();
+                }
+            }
+        }
+        columns.insert(field.name().to_owned(), metadata);
+    }
+
+    if columns.is_empty() {
+        return Ok((schema, batches));
+    }
+
+    let primary_column = columns.keys().next().unwrap().to_owned();
+    let geo = json!({
+        "version": "1.1.0",
+        "primary_column": primary_column,
+        "columns": columns,
+    });
+
+    let mut metadata = schema.metadata().clone();
+    metadata.insert("geo".to_string(), geo.to_string());
+    let schema = Arc::new(Schema::new_with_metadata(fields, metadata));
+
+    let batches = arrays
+        .into_iter()
+        .map(|columns| RecordBatch::try_new(schema.clone(), columns))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((schema, batches))
+}
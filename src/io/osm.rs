@@ -0,0 +1,547 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        array::{Int64Builder, ListBuilder, MapBuilder, RecordBatch, StringBuilder, StructBuilder},
+        datatypes::{DataType, Field, Fields, Schema, SchemaRef},
+    },
+    catalog::SchemaProvider,
+    datasource::TableProvider,
+    error::{DataFusionError, Result},
+};
+use geo::{Area, Contains, Coord, LineString, MultiPolygon, Point, Polygon};
+use geoarrow::{
+    array::{CoordType, WKBBuilder},
+    datatypes::{Dimension, NativeType},
+    io::wkb::from_wkb,
+    ArrayBase,
+};
+use osmpbfreader::{Node, OsmObj, OsmPbfReader, Relation, Tags, Way};
+
+use super::{builder::GeometryBuilder, file_table::FileTable, prepare_batches};
+
+const DEFAULT_BATCH_SIZE: usize = 8192;
+
+/// Keys making a closed way an area, unless tagged `area=no`.
+const AREA_KEYS: &[&str] = &[
+    "amenity", "building", "landuse", "leisure", "natural", "place", "shop", "tourism", "water",
+];
+
+/// Tables of an OpenStreetMap extract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OsmTable {
+    Nodes,
+    Ways,
+    Relations,
+}
+
+impl OsmTable {
+    pub const ALL: [OsmTable; 3] = [Self::Nodes, Self::Ways, Self::Relations];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Nodes => "nodes",
+            Self::Ways => "ways",
+            Self::Relations => "relations",
+        }
+    }
+}
+
+impl TryFrom<&str> for OsmTable {
+    type Error = DataFusionError;
+
+    fn try_from(value: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|table| table.name().eq_ignore_ascii_case(value))
+            .ok_or_else(|| {
+                DataFusionError::Configuration(format!(
+                    "Unknown OSM table `{value}`, expected one of `nodes`, `ways` or `relations`"
+                ))
+            })
+    }
+}
+
+/// Schema provider exposing the `nodes`, `ways` and `relations` of an
+/// OpenStreetMap PBF extract.
+///
+/// ```ignore
+/// let schema = OsmSchemaProvider::new("switzerland.osm.pbf");
+/// ctx.catalog("datafusion").unwrap().register_schema("osm", Arc::new(schema))?;
+/// ctx.sql("SELECT tags['name'], geometry FROM osm.ways WHERE tags['highway'] IS NOT NULL").await?;
+/// ```
+#[derive(Debug)]
+pub struct OsmSchemaProvider {
+    path: PathBuf,
+}
+
+impl OsmSchemaProvider {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+#[async_trait]
+impl SchemaProvider for OsmSchemaProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        OsmTable::ALL.iter().map(|t| t.name().to_string()).collect()
+    }
+
+    async fn table(&self, name: &str) -> Result<Option<Arc<dyn TableProvider>>> {
+        let Ok(table) = OsmTable::try_from(name) else {
+            return Ok(None);
+        };
+
+        let location = self.path.to_string_lossy();
+        let provider = FileTable::try_new_with_schema(
+            &location,
+            &["pbf"],
+            table_schema(table)?,
+            move |path| read_osm_pbf(path, table, &HashMap::new()),
+        )?;
+        Ok(Some(Arc::new(provider)))
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        OsmTable::try_from(name).is_ok()
+    }
+}
+
+/// Read one table of an OpenStreetMap PBF extract.
+///
+/// Nodes get point geometries, ways linestring or (closed and tagged as an
+/// area) polygon geometries and `multipolygon`/`boundary` relations
+/// multipolygon geometries assembled from their outer and inner member ways.
+/// Geometries referencing objects missing from the extract are null.
+///
+/// The objects are expected in the order of the usual extracts, nodes before
+/// ways before relations. Only the coordinates of the nodes and the node ids
+/// of the ways are kept to assemble the geometries, the objects of the table
+/// itself are converted batch by batch.
+pub fn read_osm_pbf(
+    path: &Path,
+    table: OsmTable,
+    options: &HashMap<String, String>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let batch_size = options
+        .get("batch_size")
+        .map(|size| {
+            size.parse::<usize>().map_err(|_| {
+                DataFusionError::Configuration(format!(
+                    "Expected positive integer for `batch_size`, got `{size}`"
+                ))
+            })
+        })
+        .transpose()?
+        .unwrap_or(DEFAULT_BATCH_SIZE)
+        .max(1);
+
+    let schema = schema(table);
+    let mut pending = Pending::new(table, schema.clone());
+    let mut batches = vec![];
+
+    let mut reader = OsmPbfReader::new(File::open(path)?);
+    for object in reader.iter() {
+        pending.push(object.map_err(|e| DataFusionError::External(Box::new(e)))?);
+        if pending.len() >= batch_size {
+            batches.push(pending.finish()?);
+        }
+    }
+    if pending.len() > 0 || batches.is_empty() {
+        batches.push(pending.finish()?);
+    }
+
+    prepare_batches(schema, batches)
+}
+
+/// Schema of a table as read by [`read_osm_pbf`], known without reading the
+/// extract.
+pub(crate) fn table_schema(table: OsmTable) -> Result<SchemaRef> {
+    let (schema, _) = prepare_batches(schema(table), vec![])?;
+    Ok(schema)
+}
+
+/// Objects of the table being read until a batch is full, and the node
+/// coordinates and way node ids needed to assemble their geometries.
+struct Pending {
+    table: OsmTable,
+    schema: SchemaRef,
+    coords: HashMap<i64, Coord>,
+    way_nodes: HashMap<i64, Vec<i64>>,
+    nodes: Vec<Node>,
+    ways: Vec<Way>,
+    relations: Vec<Relation>,
+}
+
+impl Pending {
+    fn new(table: OsmTable, schema: SchemaRef) -> Self {
+        Self {
+            table,
+            schema,
+            coords: HashMap::new(),
+            way_nodes: HashMap::new(),
+            nodes: vec![],
+            ways: vec![],
+            relations: vec![],
+        }
+    }
+
+    fn push(&mut self, object: OsmObj) {
+        match (self.table, object) {
+            (OsmTable::Nodes, OsmObj::Node(node)) => self.nodes.push(node),
+            (_, OsmObj::Node(node)) => {
+                self.coords
+                    .insert(node.id.0, Coord::from((node.lon(), node.lat())));
+            }
+            (OsmTable::Ways, OsmObj::Way(way)) => self.ways.push(way),
+            (OsmTable::Relations, OsmObj::Way(way)) => {
+                let nodes = way.nodes.iter().map(|node| node.0).collect();
+                self.way_nodes.insert(way.id.0, nodes);
+            }
+            (OsmTable::Relations, OsmObj::Relation(relation)) => self.relations.push(relation),
+            _ => {}
+        }
+    }
+
+    /// Number of objects of the table pending.
+    fn len(&self) -> usize {
+        self.nodes.len() + self.ways.len() + self.relations.len()
+    }
+
+    /// Convert the pending objects to a batch.
+    fn finish(&mut self) -> Result<RecordBatch> {
+        match self.table {
+            OsmTable::Nodes => nodes_batch(&self.schema, &std::mem::take(&mut self.nodes)),
+            OsmTable::Ways => {
+                ways_batch(&self.schema, &std::mem::take(&mut self.ways), &self.coords)
+            }
+            OsmTable::Relations => relations_batch(
+                &self.schema,
+                &std::mem::take(&mut self.relations),
+                &self.way_nodes,
+                &self.coords,
+            ),
+        }
+    }
+}
+
+fn nodes_batch(schema: &SchemaRef, nodes: &[Node]) -> Result<RecordBatch> {
+    let mut ids = Int64Builder::new();
+    let mut tags = tags_builder();
+    let mut geometries =
+        GeometryBuilder::try_new(&NativeType::Point(CoordType::Separated, Dimension::XY))?;
+    for node in nodes {
+        ids.append_value(node.id.0);
+        append_tags(&mut tags, &node.tags)?;
+        geometries.push_geometry(Some(&Point::new(node.lon(), node.lat())))?;
+    }
+
+    Ok(RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(ids.finish()),
+            Arc::new(tags.finish()),
+            geometries.finish()?,
+        ],
+    )?)
+}
+
+fn ways_batch(
+    schema: &SchemaRef,
+    ways: &[Way],
+    coords: &HashMap<i64, Coord>,
+) -> Result<RecordBatch> {
+    let mut ids = Int64Builder::new();
+    let mut tags = tags_builder();
+    let mut refs = ListBuilder::new(Int64Builder::new());
+    let mut geometries = WKBBuilder::<i32>::new();
+    for way in ways {
+        ids.append_value(way.id.0);
+        append_tags(&mut tags, &way.tags)?;
+        refs.append_value(way.nodes.iter().map(|node| Some(node.0)));
+
+        let line = way
+            .nodes
+            .iter()
+            .map(|node| coords.get(&node.0).copied())
+            .collect::<Option<Vec<_>>>()
+            .map(LineString::new);
+        match line {
+            Some(line) if is_area(&way.nodes, &way.tags) => {
+                geometries.push_geometry(Some(&Polygon::new(line, vec![])))
+            }
+            Some(line) if line.0.len() >= 2 => geometries.push_geometry(Some(&line)),
+            _ => geometries.push_geometry(None::<&LineString>),
+        }
+    }
+
+    // linestrings and polygons in one native mixed array
+    let geometries = from_wkb(&geometries.finish(), way_type(), false)
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    Ok(RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(ids.finish()),
+            Arc::new(tags.finish()),
+            Arc::new(refs.finish()),
+            geometries.to_array_ref(),
+        ],
+    )?)
+}
+
+fn relations_batch(
+    schema: &SchemaRef,
+    relations: &[Relation],
+    way_nodes: &HashMap<i64, Vec<i64>>,
+    coords: &HashMap<i64, Coord>,
+) -> Result<RecordBatch> {
+    let mut ids = Int64Builder::new();
+    let mut tags = tags_builder();
+    let mut members = members_builder();
+    let mut geometries = GeometryBuilder::try_new(&NativeType::MultiPolygon(
+        CoordType::Separated,
+        Dimension::XY,
+    ))?;
+    for relation in relations {
+        ids.append_value(relation.id.0);
+        append_tags(&mut tags, &relation.tags)?;
+
+        let member_builder = members.values();
+        for member in relation.refs.iter() {
+            let (member_type, member_ref) = match member.member {
+                osmpbfreader::OsmId::Node(id) => ("node", id.0),
+                osmpbfreader::OsmId::Way(id) => ("way", id.0),
+                osmpbfreader::OsmId::Relation(id) => ("relation", id.0),
+            };
+            member_builder
+                .field_builder::<StringBuilder>(0)
+                .unwrap()
+                .append_value(member_type);
+            member_builder
+                .field_builder::<Int64Builder>(1)
+                .unwrap()
+                .append_value(member_ref);
+            member_builder
+                .field_builder::<StringBuilder>(2)
+                .unwrap()
+                .append_value(member.role.as_str());
+            member_builder.append(true);
+        }
+        members.append(true);
+
+        let multipolygon = match relation.tags.get("type").map(|t| t.as_str()) {
+            Some("multipolygon" | "boundary") => {
+                let rings = |role: &str| {
+                    let ways = relation
+                        .refs
+                        .iter()
+                        .filter(|member| {
+                            member.role.as_str() == role
+                                || (role == "outer" && member.role.is_empty())
+                        })
+                        .filter_map(|member| member.member.way())
+                        .map(|id| way_nodes.get(&id.0).cloned())
+                        .collect::<Option<Vec<Vec<i64>>>>()?;
+                    assemble_rings(ways)?
+                        .into_iter()
+                        .map(|ring| {
+                            ring.iter()
+                                .map(|node| coords.get(node).copied())
+                                .collect::<Option<Vec<_>>>()
+                                .map(LineString::new)
+                        })
+                        .collect::<Option<Vec<_>>>()
+                };
+                rings("outer")
+                    .zip(rings("inner"))
+                    .map(|(outers, inners)| build_multipolygon(outers, inners))
+            }
+            _ => None,
+        };
+        geometries.push_geometry(multipolygon.as_ref())?;
+    }
+
+    Ok(RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(ids.finish()),
+            Arc::new(tags.finish()),
+            Arc::new(members.finish()),
+            geometries.finish()?,
+        ],
+    )?)
+}
+
+/// Native type of way geometries, linestrings or polygons.
+fn way_type() -> NativeType {
+    NativeType::Mixed(CoordType::Separated, Dimension::XY)
+}
+
+fn schema(table: OsmTable) -> SchemaRef {
+    let id = Field::new("id", DataType::Int64, false);
+    let tags = Field::new("tags", tags_builder().finish().data_type().clone(), true);
+
+    let fields = match table {
+        OsmTable::Nodes => vec![
+            id,
+            tags,
+            NativeType::Point(CoordType::Separated, Dimension::XY).to_field("geometry", true),
+        ],
+        OsmTable::Ways => vec![
+            id,
+            tags,
+            Field::new_list("nodes", Field::new("item", DataType::Int64, true), true),
+            way_type().to_field("geometry", true),
+        ],
+        OsmTable::Relations => vec![
+            id,
+            tags,
+            Field::new(
+                "members",
+                members_builder().finish().data_type().clone(),
+                true,
+            ),
+            NativeType::MultiPolygon(CoordType::Separated, Dimension::XY)
+                .to_field("geometry", true),
+        ],
+    };
+
+    Arc::new(Schema::new(fields))
+}
+
+fn tags_builder() -> MapBuilder<StringBuilder, StringBuilder> {
+    MapBuilder::new(None, StringBuilder::new(), StringBuilder::new())
+}
+
+fn append_tags(builder: &mut MapBuilder<StringBuilder, StringBuilder>, tags: &Tags) -> Result<()> {
+    for (key, value) in tags.iter() {
+        builder.keys().append_value(key.as_str());
+        builder.values().append_value(value.as_str());
+    }
+    builder.append(true)?;
+
+    Ok(())
+}
+
+fn members_builder() -> ListBuilder<StructBuilder> {
+    let fields = Fields::from(vec![
+        Field::new("type", DataType::Utf8, false),
+        Field::new("ref", DataType::Int64, false),
+        Field::new("role", DataType::Utf8, false),
+    ]);
+
+    ListBuilder::new(StructBuilder::from_fields(fields, 0))
+}
+
+/// Whether a way is a closed ring tagged as an area.
+fn is_area(nodes: &[osmpbfreader::NodeId], tags: &Tags) -> bool {
+    let closed = nodes.len() >= 4 && nodes.first() == nodes.last();
+    let area = match tags.get("area").map(|a| a.as_str()) {
+        Some("yes") => true,
+        Some("no") => false,
+        _ => AREA_KEYS.iter().any(|key| tags.contains_key(*key)),
+    };
+
+    closed && area
+}
+
+/// Join way segments (node id sequences) end to end into closed rings.
+/// Returns `None` if the segments do not form closed rings.
+fn assemble_rings(mut segments: Vec<Vec<i64>>) -> Option<Vec<Vec<i64>>> {
+    let mut rings = vec![];
+
+    while let Some(mut ring) = segments.pop() {
+        while ring.first() != ring.last() {
+            let end = *ring.last()?;
+            let index = segments
+                .iter()
+                .position(|s| s.first() == Some(&end) || s.last() == Some(&end))?;
+            let mut segment = segments.swap_remove(index);
+            if segment.first() != Some(&end) {
+                segment.reverse();
+            }
+            ring.extend(segment.into_iter().skip(1));
+        }
+        if ring.len() < 4 {
+            return None;
+        }
+        rings.push(ring);
+    }
+
+    Some(rings)
+}
+
+/// Assign inner rings to the smallest outer ring containing them entirely,
+/// inner rings outside all outer rings are dropped.
+fn build_multipolygon(outers: Vec<LineString>, inners: Vec<LineString>) -> MultiPolygon {
+    let mut polygons = outers
+        .into_iter()
+        .map(|outer| (Polygon::new(outer, vec![]), vec![]))
+        .collect::<Vec<_>>();
+
+    for inner in inners {
+        if let Some((_, holes)) = polygons
+            .iter_mut()
+            .filter(|(polygon, _)| polygon.contains(&inner))
+            .min_by(|(a, _), (b, _)| a.unsigned_area().total_cmp(&b.unsigned_area()))
+        {
+            holes.push(inner);
+        }
+    }
+
+    MultiPolygon::new(
+        polygons
+            .into_iter()
+            .map(|(polygon, holes)| Polygon::new(polygon.into_inner().0, holes))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rings() {
+        let rings = assemble_rings(vec![vec![1, 2, 3], vec![5, 4, 3], vec![5, 1]]).unwrap();
+        assert_eq!(rings.len(), 1);
+        assert_eq!(rings[0].first(), rings[0].last());
+        assert_eq!(rings[0].len(), 6);
+
+        assert!(assemble_rings(vec![vec![1, 2, 3], vec![3, 4]]).is_none());
+        assert_eq!(assemble_rings(vec![]), Some(vec![]));
+    }
+
+    #[test]
+    fn inner_rings() {
+        let ring = |coords: &[(f64, f64)]| LineString::from(coords.to_vec());
+        let outers = vec![
+            ring(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.), (0., 0.)]),
+            ring(&[(2., 2.), (8., 2.), (8., 8.), (2., 8.), (2., 2.)]),
+        ];
+        let inners = vec![
+            // inside both outer rings, a hole of the smaller one
+            ring(&[(4., 4.), (6., 4.), (6., 6.), (4., 6.), (4., 4.)]),
+            // starting inside the first outer ring but crossing it
+            ring(&[(1., 1.), (12., 1.), (12., 2.), (1., 2.), (1., 1.)]),
+        ];
+
+        let multipolygon = build_multipolygon(outers, inners);
+        assert_eq!(multipolygon.0[0].interiors().len(), 0);
+        assert_eq!(multipolygon.0[1].interiors().len(), 1);
+    }
+}
@@ -0,0 +1,177 @@
+//! Scan of the files of a [`FileScanConfig`] opened one after another.
+
+use std::{any::Any, collections::VecDeque, fmt, sync::Arc};
+
+use datafusion::{
+    arrow::array::RecordBatch,
+    common::not_impl_err,
+    datasource::physical_plan::FileScanConfig,
+    error::{DataFusionError, Result},
+    execution::TaskContext,
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionMode,
+        ExecutionPlan, Partitioning, PlanProperties, SendableRecordBatchStream,
+    },
+};
+use futures::{future::BoxFuture, stream::BoxStream, StreamExt};
+use object_store::{ObjectMeta, ObjectStore};
+
+/// Batches of an opened file, fetched and decoded as they are polled.
+pub(crate) type Batches = BoxStream<'static, Result<RecordBatch>>;
+
+type OpenFn =
+    dyn Fn(Arc<dyn ObjectStore>, ObjectMeta) -> BoxFuture<'static, Result<Batches>> + Send + Sync;
+
+/// Execution plan with one partition per file group, opening the files of a
+/// group one after another as the stream is polled.
+///
+/// Files are not opened at all once the limit of the scan is reached.
+pub(crate) struct ObjectScanExec {
+    name: &'static str,
+    conf: FileScanConfig,
+    /// Shown in the plan, e.g. a pushed down bounding box
+    details: String,
+    open: Arc<OpenFn>,
+    properties: PlanProperties,
+}
+
+impl ObjectScanExec {
+    pub(crate) fn try_new(
+        name: &'static str,
+        conf: FileScanConfig,
+        details: String,
+        open: impl Fn(Arc<dyn ObjectStore>, ObjectMeta) -> BoxFuture<'static, Result<Batches>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Result<Self> {
+        if !conf.table_partition_cols.is_empty() {
+            return not_impl_err!("Partition columns are not supported for {name}");
+        }
+
+        let schema = match &conf.projection {
+            Some(projection) => Arc::new(conf.file_schema.project(projection)?),
+            None => conf.file_schema.clone(),
+        };
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema),
+            Partitioning::UnknownPartitioning(conf.file_groups.len()),
+            ExecutionMode::Bounded,
+        );
+
+        Ok(Self {
+            name,
+            conf,
+            details,
+            open: Arc::new(open),
+            properties,
+        })
+    }
+}
+
+impl fmt::Debug for ObjectScanExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(self.name)
+            .field("conf", &self.conf)
+            .field("details", &self.details)
+            .finish()
+    }
+}
+
+impl DisplayAs for ObjectScanExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                let files = self.conf.file_groups.iter().map(Vec::len).sum::<usize>();
+                write!(f, "{}: files={files}", self.name)?;
+                if !self.details.is_empty() {
+                    write!(f, ", {}", self.details)?;
+                }
+                if let Some(limit) = self.conf.limit {
+                    write!(f, ", limit={limit}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for ObjectScanExec {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let store = context
+            .runtime_env()
+            .object_store(&self.conf.object_store_url)?;
+        let files = self.conf.file_groups[partition]
+            .iter()
+            .map(|file| file.object_meta.clone())
+            .collect::<VecDeque<_>>();
+        let file_schema = self.conf.file_schema.clone();
+        let projection = self.conf.projection.clone();
+        let open = self.open.clone();
+
+        let empty: Batches = futures::stream::empty().boxed();
+        let state = (files, empty, self.conf.limit);
+        let batches = futures::stream::try_unfold(state, move |(mut files, mut batches, limit)| {
+            let store = store.clone();
+            let file_schema = file_schema.clone();
+            let projection = projection.clone();
+            let open = open.clone();
+            async move {
+                loop {
+                    if limit == Some(0) {
+                        return Ok::<_, DataFusionError>(None);
+                    }
+                    if let Some(batch) = batches.next().await.transpose()? {
+                        let rows = limit.map_or(batch.num_rows(), |l| l.min(batch.num_rows()));
+                        let batch =
+                            RecordBatch::try_new(file_schema.clone(), batch.columns().to_vec())?
+                                .slice(0, rows);
+                        let batch = match &projection {
+                            Some(projection) => batch.project(projection)?,
+                            None => batch,
+                        };
+                        let limit = limit.map(|l| l - rows);
+                        return Ok(Some((batch, (files, batches, limit))));
+                    }
+                    let Some(file) = files.pop_front() else {
+                        return Ok(None);
+                    };
+                    batches = open(store.clone(), file).await?;
+                }
+            }
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.properties.eq_properties.schema().clone(),
+            batches,
+        )))
+    }
+}
@@ -0,0 +1,503 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Cursor, Read, Seek},
+    path::Path,
+    sync::Arc,
+};
+
+use ::shapefile::{
+    dbase::{self, encoding::EncodingRs, FieldType, FieldValue},
+    PointM, PointZ, PolygonRing, Shape, ShapeReader, ShapeType,
+};
+use datafusion::{
+    arrow::{
+        array::{
+            ArrayRef, BinaryBuilder, BooleanBuilder, Date32Builder, Float64Builder, Int32Builder,
+            RecordBatch, StringBuilder,
+        },
+        datatypes::{DataType, Field, Schema, SchemaRef},
+    },
+    error::{DataFusionError, Result},
+};
+use geo_traits::Dimensions;
+use geoarrow::{
+    array::CoordType,
+    datatypes::{Dimension, NativeType},
+};
+use serde_json::json;
+
+use super::{builder::GeometryBuilder, prepare_batches, wkb_field};
+use crate::wkb::{self, Kind};
+
+const DEFAULT_BATCH_SIZE: usize = 8192;
+
+/// Sidecar files of a shapefile, read into memory.
+struct Sources {
+    shp: Vec<u8>,
+    dbf: Vec<u8>,
+    cpg: Option<String>,
+    prj: Option<String>,
+}
+
+impl Sources {
+    /// Read `path.shp` and its sidecar files from the file system.
+    fn from_path(path: &Path) -> Result<Self> {
+        let sidecar = |extension: &str| -> Result<Option<Vec<u8>>> {
+            let path = path.with_extension(extension);
+            if path.exists() {
+                Ok(Some(std::fs::read(path)?))
+            } else {
+                Ok(None)
+            }
+        };
+
+        Ok(Self {
+            shp: std::fs::read(path)?,
+            dbf: sidecar("dbf")?.ok_or_else(|| {
+                DataFusionError::Plan(format!("Missing `.dbf` file for `{}`", path.display()))
+            })?,
+            cpg: sidecar("cpg")?.map(|b| String::from_utf8_lossy(&b).trim().to_string()),
+            prj: sidecar("prj")?.map(|b| String::from_utf8_lossy(&b).trim().to_string()),
+        })
+    }
+
+    /// Read the first shapefile in a zip archive.
+    fn from_zip<R: Read + Seek>(reader: R) -> Result<Self> {
+        let mut archive =
+            zip::ZipArchive::new(reader).map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+        let names = archive.file_names().map(String::from).collect::<Vec<_>>();
+        let shp = names
+            .iter()
+            .find(|name| name.to_lowercase().ends_with(".shp"))
+            .ok_or_else(|| DataFusionError::Plan("No `.shp` file in zip archive".to_string()))?;
+        let stem = &shp[..shp.len() - 4];
+
+        let mut sidecar = |extension: &str| -> Result<Option<Vec<u8>>> {
+            let Some(name) = names.iter().find(|name| {
+                name.len() == stem.len() + 4
+                    && name.starts_with(stem)
+                    && name[stem.len() + 1..].eq_ignore_ascii_case(extension)
+            }) else {
+                return Ok(None);
+            };
+            let mut file = archive
+                .by_name(name)
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+            let mut buffer = vec![];
+            file.read_to_end(&mut buffer)?;
+            Ok(Some(buffer))
+        };
+
+        Ok(Self {
+            shp: sidecar("shp")?.unwrap(),
+            dbf: sidecar("dbf")?
+                .ok_or_else(|| DataFusionError::Plan(format!("Missing `.dbf` file for `{shp}`")))?,
+            cpg: sidecar("cpg")?.map(|b| String::from_utf8_lossy(&b).trim().to_string()),
+            prj: sidecar("prj")?.map(|b| String::from_utf8_lossy(&b).trim().to_string()),
+        })
+    }
+}
+
+/// Read a shapefile (`.shp` with `.dbf`, `.cpg` and `.prj` sidecar files) or a
+/// zip archive containing one.
+///
+/// Attributes are decoded with the encoding given in the `.cpg` file (falling
+/// back to the `encoding` option and UTF-8), the `.prj` CRS is recorded in the
+/// geometry field's extension metadata.
+pub fn read_shapefile(
+    path: &Path,
+    options: &HashMap<String, String>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let sources = if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+    {
+        Sources::from_zip(File::open(path)?)?
+    } else {
+        Sources::from_path(path)?
+    };
+
+    let batch_size = options
+        .get("batch_size")
+        .map(|size| {
+            size.parse::<usize>().map_err(|_| {
+                DataFusionError::Configuration(format!(
+                    "Expected positive integer for `batch_size`, got `{size}`"
+                ))
+            })
+        })
+        .transpose()?
+        .unwrap_or(DEFAULT_BATCH_SIZE);
+
+    let label = sources
+        .cpg
+        .as_deref()
+        .or(options.get("encoding").map(String::as_str))
+        .unwrap_or("UTF-8");
+    let encoding = encoding_rs::Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+        DataFusionError::Configuration(format!("Unsupported attribute encoding `{label}`"))
+    })?;
+
+    let shape_reader = ShapeReader::new(Cursor::new(sources.shp))
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    let shape_type = shape_reader.header().shape_type;
+    let dbase_reader =
+        dbase::Reader::new_with_encoding(Cursor::new(sources.dbf), EncodingRs::from(encoding))
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    let dbase_fields = dbase_reader
+        .fields()
+        .iter()
+        .filter(|field| field.name() != "DeletionFlag")
+        .map(|field| (field.name().to_string(), field.field_type()))
+        .collect::<Vec<_>>();
+
+    let geometry_type = native_type(shape_type)?;
+    let mut geometries = ShapeBuilder::try_new(shape_type, &geometry_type)?;
+    let geometry_field = match geometries {
+        ShapeBuilder::Native(_) => geometry_type.to_field("geometry", true),
+        ShapeBuilder::Wkb(_) => wkb_field(&Field::new("geometry", DataType::Binary, true)),
+    };
+    let mut geometry_metadata = geometry_field.metadata().clone();
+    if let Some(prj) = &sources.prj {
+        geometry_metadata.insert(
+            "ARROW:extension:metadata".to_string(),
+            json!({ "crs": prj }).to_string(),
+        );
+    }
+
+    let mut fields = dbase_fields
+        .iter()
+        .map(|(name, field_type)| Field::new(name, data_type(*field_type), true))
+        .collect::<Vec<_>>();
+    fields.push(geometry_field.with_metadata(geometry_metadata));
+    let schema = Arc::new(Schema::new(fields));
+
+    let mut reader = ::shapefile::Reader::new(shape_reader, dbase_reader);
+    let mut batches = vec![];
+    let mut attributes = dbase_fields
+        .iter()
+        .map(|(_, field_type)| AttributeBuilder::new(*field_type))
+        .collect::<Vec<_>>();
+
+    for shape_record in reader.iter_shapes_and_records() {
+        let (shape, mut record) =
+            shape_record.map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+        for ((name, _), builder) in dbase_fields.iter().zip(attributes.iter_mut()) {
+            builder.append(record.remove(name))?;
+        }
+        geometries.push(shape)?;
+
+        if geometries.len() == batch_size {
+            batches.push(finish_batch(&schema, &mut attributes, &mut geometries)?);
+        }
+    }
+    if geometries.len() > 0 || batches.is_empty() {
+        batches.push(finish_batch(&schema, &mut attributes, &mut geometries)?);
+    }
+
+    prepare_batches(schema, batches)
+}
+
+fn finish_batch(
+    schema: &SchemaRef,
+    attributes: &mut [AttributeBuilder],
+    geometries: &mut ShapeBuilder,
+) -> Result<RecordBatch> {
+    let mut columns = attributes
+        .iter_mut()
+        .map(AttributeBuilder::finish)
+        .collect::<Vec<_>>();
+    columns.push(geometries.finish()?);
+
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// Builds the geometry column: native for plain shapes, WKB keeping Z and M
+/// ordinates for measured and 3D shapes.
+enum ShapeBuilder {
+    Native(GeometryBuilder),
+    Wkb(BinaryBuilder),
+}
+
+impl ShapeBuilder {
+    fn try_new(shape_type: ShapeType, native_type: &NativeType) -> Result<Self> {
+        use ShapeType::*;
+
+        let measured = matches!(
+            shape_type,
+            PointM
+                | PointZ
+                | MultipointM
+                | MultipointZ
+                | PolylineM
+                | PolylineZ
+                | PolygonM
+                | PolygonZ
+        );
+        match measured {
+            true => Ok(Self::Wkb(BinaryBuilder::new())),
+            false => Ok(Self::Native(GeometryBuilder::try_new(native_type)?)),
+        }
+    }
+
+    fn push(&mut self, shape: Shape) -> Result<()> {
+        match self {
+            Self::Native(builder) => {
+                let geometry = match shape {
+                    Shape::NullShape => None,
+                    shape => Some(
+                        geo::Geometry::<f64>::try_from(shape)
+                            .map_err(|e| DataFusionError::External(Box::new(e)))?,
+                    ),
+                };
+                builder.push_geometry(geometry.as_ref())
+            }
+            Self::Wkb(builder) => {
+                builder.append_option(measured_geometry(&shape)?.map(|g| g.to_wkb()));
+                Ok(())
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Native(builder) => builder.len(),
+            Self::Wkb(builder) => builder.len(),
+        }
+    }
+
+    fn finish(&mut self) -> Result<ArrayRef> {
+        match self {
+            Self::Native(builder) => builder.finish(),
+            Self::Wkb(builder) => Ok(Arc::new(builder.finish())),
+        }
+    }
+}
+
+/// Measure of a shape point, values below -1e38 mean no data.
+fn measure(m: f64) -> f64 {
+    match m < -1e38 {
+        true => f64::NAN,
+        false => m,
+    }
+}
+
+/// Outer ring flags and XYZM points of polygon rings.
+fn rings<P>(rings: &[PolygonRing<P>], f: impl Fn(&P) -> [f64; 4]) -> Vec<(bool, Vec<[f64; 4]>)> {
+    rings
+        .iter()
+        .map(|ring| {
+            let outer = matches!(ring, PolygonRing::Outer(_));
+            (outer, ring.points().iter().map(&f).collect())
+        })
+        .collect()
+}
+
+/// Geometry of an M or Z shape, with Z and M ordinates. M is dropped if no
+/// point of the shape has a measure.
+fn measured_geometry(shape: &Shape) -> Result<Option<wkb::Geometry>> {
+    let xym = |p: &PointM| [p.x, p.y, f64::NAN, measure(p.m)];
+    let xyzm = |p: &PointZ| [p.x, p.y, p.z, measure(p.m)];
+
+    // parts with an outer ring flag, XYZM points with NaN for missing ordinates
+    let (kind, has_z, parts): (Kind, bool, Vec<(bool, Vec<[f64; 4]>)>) = match shape {
+        Shape::NullShape => return Ok(None),
+        Shape::PointM(p) => (Kind::Point, false, vec![(true, vec![xym(p)])]),
+        Shape::PointZ(p) => (Kind::Point, true, vec![(true, vec![xyzm(p)])]),
+        Shape::MultipointM(mp) => (
+            Kind::MultiPoint,
+            false,
+            mp.points().iter().map(|p| (true, vec![xym(p)])).collect(),
+        ),
+        Shape::MultipointZ(mp) => (
+            Kind::MultiPoint,
+            true,
+            mp.points().iter().map(|p| (true, vec![xyzm(p)])).collect(),
+        ),
+        Shape::PolylineM(pl) => (
+            Kind::MultiLineString,
+            false,
+            pl.parts()
+                .iter()
+                .map(|part| (true, part.iter().map(xym).collect()))
+                .collect(),
+        ),
+        Shape::PolylineZ(pl) => (
+            Kind::MultiLineString,
+            true,
+            pl.parts()
+                .iter()
+                .map(|part| (true, part.iter().map(xyzm).collect()))
+                .collect(),
+        ),
+        Shape::PolygonM(pg) => (Kind::MultiPolygon, false, rings(pg.rings(), xym)),
+        Shape::PolygonZ(pg) => (Kind::MultiPolygon, true, rings(pg.rings(), xyzm)),
+        shape => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Unsupported shape type `{}`",
+                shape.shapetype()
+            )))
+        }
+    };
+
+    let has_m = parts
+        .iter()
+        .flat_map(|(_, points)| points)
+        .any(|p| !p[3].is_nan());
+    let dim = match (has_z, has_m) {
+        (false, false) => Dimensions::Xy,
+        (true, false) => Dimensions::Xyz,
+        (false, true) => Dimensions::Xym,
+        (true, true) => Dimensions::Xyzm,
+    };
+    let coords = |points: &[[f64; 4]]| {
+        points
+            .iter()
+            .flat_map(|p| {
+                [
+                    Some(p[0]),
+                    Some(p[1]),
+                    has_z.then_some(p[2]),
+                    has_m.then_some(p[3]),
+                ]
+                .into_iter()
+                .flatten()
+            })
+            .collect::<Vec<_>>()
+    };
+    let part = |kind, points: &[[f64; 4]]| wkb::Geometry::new(kind, dim, coords(points), vec![]);
+
+    let geometry = match kind {
+        Kind::Point => part(Kind::Point, &parts[0].1),
+        Kind::MultiPoint | Kind::MultiLineString => {
+            let part_kind = match kind {
+                Kind::MultiPoint => Kind::Point,
+                _ => Kind::LineString,
+            };
+            let parts = parts.iter().map(|(_, p)| part(part_kind, p)).collect();
+            wkb::Geometry::new(kind, dim, vec![], parts)
+        }
+        _ => {
+            // inner rings belong to the preceding outer ring
+            let mut polygons: Vec<wkb::Geometry> = vec![];
+            for (outer, ring) in &parts {
+                let ring = part(Kind::LineString, ring);
+                match polygons.last_mut() {
+                    Some(polygon) if !outer => polygon.parts.push(ring),
+                    _ => polygons.push(wkb::Geometry::new(Kind::Polygon, dim, vec![], vec![ring])),
+                }
+            }
+            wkb::Geometry::new(Kind::MultiPolygon, dim, vec![], polygons)
+        }
+    };
+
+    Ok(Some(geometry))
+}
+
+/// Native geometry type of the shapes in a file, shapes with Z or M ordinates
+/// are read as WKB instead.
+fn native_type(shape_type: ShapeType) -> Result<NativeType> {
+    let ct = CoordType::Separated;
+
+    match shape_type {
+        ShapeType::Point | ShapeType::PointM | ShapeType::PointZ => {
+            Ok(NativeType::Point(ct, Dimension::XY))
+        }
+        ShapeType::Multipoint | ShapeType::MultipointM | ShapeType::MultipointZ => {
+            Ok(NativeType::MultiPoint(ct, Dimension::XY))
+        }
+        ShapeType::Polyline | ShapeType::PolylineM | ShapeType::PolylineZ => {
+            Ok(NativeType::MultiLineString(ct, Dimension::XY))
+        }
+        ShapeType::Polygon | ShapeType::PolygonM | ShapeType::PolygonZ => {
+            Ok(NativeType::MultiPolygon(ct, Dimension::XY))
+        }
+        st => Err(DataFusionError::NotImplemented(format!(
+            "Unsupported shape type `{st}`"
+        ))),
+    }
+}
+
+fn data_type(field_type: FieldType) -> DataType {
+    match field_type {
+        FieldType::Numeric | FieldType::Float | FieldType::Double | FieldType::Currency => {
+            DataType::Float64
+        }
+        FieldType::Integer => DataType::Int32,
+        FieldType::Logical => DataType::Boolean,
+        FieldType::Date => DataType::Date32,
+        _ => DataType::Utf8,
+    }
+}
+
+enum AttributeBuilder {
+    Utf8(StringBuilder),
+    Float64(Float64Builder),
+    Int32(Int32Builder),
+    Boolean(BooleanBuilder),
+    Date32(Date32Builder),
+}
+
+impl AttributeBuilder {
+    fn new(field_type: FieldType) -> Self {
+        match data_type(field_type) {
+            DataType::Float64 => Self::Float64(Float64Builder::new()),
+            DataType::Int32 => Self::Int32(Int32Builder::new()),
+            DataType::Boolean => Self::Boolean(BooleanBuilder::new()),
+            DataType::Date32 => Self::Date32(Date32Builder::new()),
+            _ => Self::Utf8(StringBuilder::new()),
+        }
+    }
+
+    fn append(&mut self, value: Option<FieldValue>) -> Result<()> {
+        match (self, value) {
+            (Self::Utf8(b), Some(FieldValue::Character(v))) => b.append_option(v),
+            (Self::Utf8(b), Some(FieldValue::Memo(v))) => b.append_value(v),
+            (Self::Utf8(b), Some(FieldValue::DateTime(v))) => b.append_value(format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                v.date().year(),
+                v.date().month(),
+                v.date().day(),
+                v.time().hours(),
+                v.time().minutes(),
+                v.time().seconds()
+            )),
+            (Self::Float64(b), Some(FieldValue::Numeric(v))) => b.append_option(v),
+            (Self::Float64(b), Some(FieldValue::Float(v))) => b.append_option(v.map(f64::from)),
+            (Self::Float64(b), Some(FieldValue::Double(v) | FieldValue::Currency(v))) => {
+                b.append_value(v)
+            }
+            (Self::Int32(b), Some(FieldValue::Integer(v))) => b.append_value(v),
+            (Self::Boolean(b), Some(FieldValue::Logical(v))) => b.append_option(v),
+            (Self::Date32(b), Some(FieldValue::Date(v))) => {
+                b.append_option(v.map(|date| date.to_unix_days()))
+            }
+            (Self::Utf8(b), None) => b.append_null(),
+            (Self::Float64(b), None) => b.append_null(),
+            (Self::Int32(b), None) => b.append_null(),
+            (Self::Boolean(b), None) => b.append_null(),
+            (Self::Date32(b), None) => b.append_null(),
+            (_, Some(value)) => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unexpected dBASE value `{value:?}`"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Utf8(b) => Arc::new(b.finish()),
+            Self::Float64(b) => Arc::new(b.finish()),
+            Self::Int32(b) => Arc::new(b.finish()),
+            Self::Boolean(b) => Arc::new(b.finish()),
+            Self::Date32(b) => Arc::new(b.finish()),
+        }
+    }
+}
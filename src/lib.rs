@@ -1,6 +1,12 @@
+pub(crate) mod cluster;
 pub(crate) mod compute;
 pub(crate) mod helpers;
+pub mod io;
 pub mod rules;
+pub(crate) mod spherical;
 pub mod udafs;
 pub mod udfs;
+pub mod udtfs;
+pub mod udwfs;
+pub(crate) mod wkb;
 pub(crate) mod wkt;
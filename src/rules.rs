@@ -1,31 +1,51 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use datafusion::{
+    arrow::datatypes::{DataType, Field},
     common::{
         tree_node::{Transformed, TreeNode, TreeNodeRecursion},
-        Column,
+        Column, DFSchema,
     },
     config::ConfigOptions,
     error::{DataFusionError, Result},
     logical_expr::{
-        expr::{AggregateFunction, ScalarFunction},
-        LogicalPlan, TableScan,
+        expr::{AggregateFunction, ScalarFunction, WindowFunction},
+        Aggregate, ExprSchemable, LogicalPlan, Projection, ScalarUDF, SubqueryAlias, TableScan,
+        WindowFunctionDefinition, WindowUDF,
     },
     optimizer::AnalyzerRule,
     parquet::errors::ParquetError,
     prelude::{lit, Expr},
+    scalar::ScalarValue,
+};
+use geoarrow::{datatypes::NativeType, io::parquet::metadata::GeoParquetMetadata};
+use serde_json::{json, Value};
+
+use crate::{
+    helpers::{has_measures, native_measures_error, srid},
+    io::{native_encoding_name, native_geometry_type_name},
+    udfs, udwfs,
 };
-use geoarrow::io::parquet::metadata::GeoParquetMetadata;
 
 pub struct SpatialAnalyzerRule {}
 
 impl AnalyzerRule for SpatialAnalyzerRule {
     fn analyze(&self, plan: LogicalPlan, _config: &ConfigOptions) -> Result<LogicalPlan> {
-        let mut geometa: HashMap<String, GeoParquetMetadata> = HashMap::new();
+        let mut geometa: HashMap<String, TableGeometry> = HashMap::new();
+        // geometry info of spatial aggregates and computed geometry columns
+        // by (qualified) output column
+        let mut aggregates: HashMap<Column, [Expr; 4]> = HashMap::new();
 
         let plan = plan.transform_up(|data| {
-            // println!("PLAN: {}\n", data.display());
-
+            let data = match data {
+                LogicalPlan::Aggregate(aggregate) => {
+                    scalar_aggregate(aggregate, &geometa, &aggregates)?
+                }
+                data => data,
+            };
             let transformed = match &data {
                 LogicalPlan::TableScan(TableScan {
                     table_name,
@@ -36,17 +56,8 @@ impl AnalyzerRule for SpatialAnalyzerRule {
                     fetch: _,
                 }) => {
                     // extract geo metadata
-                    if let Some(metadata) = projected_schema.metadata().get("geo") {
-                        if !geometa.contains_key(table_name.table()) {
-                            let geo: GeoParquetMetadata =
-                                serde_json::from_str(metadata).map_err(|e| {
-                                    DataFusionError::ParquetError(ParquetError::General(format!(
-                                        "Malformed `geo` metadata: {e}"
-                                    )))
-                                })?;
-                            // println!("GEO: {:#?}\n", &geo);
-                            geometa.insert(table_name.table().to_string(), geo);
-                        }
+                    if let Some(geo) = table_geo_metadata(projected_schema)? {
+                        geometa.entry(table_name.table().to_string()).or_insert(geo);
 
                         Transformed::no(data)
                     } else {
@@ -60,14 +71,13 @@ impl AnalyzerRule for SpatialAnalyzerRule {
                 _ => {
                     // rewrite spatial operations
                     data.map_expressions(|expr| {
-                        // println!("EXPR: {}\n", expr);
-
                         let expr = expr.transform_up(|expr| match &expr {
                             Expr::ScalarFunction(ScalarFunction { func, args }) => {
                                 if func.name().starts_with("ST_") {
                                     let name = expr.name_for_alias()?;
                                     let mut args = args.to_owned();
-                                    let additions = infer_encoding_and_type(&expr, &geometa)?;
+                                    let additions =
+                                        infer_encoding_and_type(&expr, &geometa, &aggregates)?;
                                     args.extend_from_slice(&additions);
                                     Ok(Transformed::yes(
                                         Expr::ScalarFunction(ScalarFunction {
@@ -90,9 +100,14 @@ impl AnalyzerRule for SpatialAnalyzerRule {
                             }) => {
                                 if func.name().starts_with("ST_") {
                                     let name = expr.name_for_alias()?;
-                                    let additions = infer_encoding_and_type(&expr, &geometa)?;
+                                    let additions =
+                                        infer_encoding_and_type(&expr, &geometa, &aggregates)?;
                                     let mut args = args.to_owned();
                                     args.extend_from_slice(&additions);
+                                    let input = std::array::from_fn(|i| additions[i].clone());
+                                    if let Some(info) = derived_info(func.name(), input) {
+                                        aggregates.insert(Column::from_name(&name), info);
+                                    }
                                     Ok(Transformed::yes(
                                         Expr::AggregateFunction(AggregateFunction {
                                             func: func.clone(),
@@ -108,6 +123,33 @@ impl AnalyzerRule for SpatialAnalyzerRule {
                                     Ok(Transformed::no(expr))
                                 }
                             }
+                            Expr::WindowFunction(window) => {
+                                let udf = match &window.fun {
+                                    WindowFunctionDefinition::AggregateUDF(udaf) => {
+                                        window_form(udaf.name())
+                                    }
+                                    WindowFunctionDefinition::WindowUDF(udf)
+                                        if udf.name().starts_with("ST_") =>
+                                    {
+                                        Some(udf.clone())
+                                    }
+                                    _ => None,
+                                };
+                                let Some(udf) = udf else {
+                                    return Ok(Transformed::no(expr));
+                                };
+
+                                let name = expr.name_for_alias()?;
+                                let mut window = window.clone();
+                                window.fun = WindowFunctionDefinition::WindowUDF(udf);
+                                let additions = infer_encoding_and_type(
+                                    &Expr::WindowFunction(window.clone()),
+                                    &geometa,
+                                    &aggregates,
+                                )?;
+                                window.args.extend_from_slice(&additions);
+                                Ok(Transformed::yes(Expr::WindowFunction(window).alias(name)))
+                            }
                             _ => Ok(Transformed::no(expr)),
                         })?;
 
@@ -116,10 +158,17 @@ impl AnalyzerRule for SpatialAnalyzerRule {
                 }
             };
 
-            Ok(Transformed::no(transformed.data))
+            let plan = annotate_geometry_columns(transformed.data, &geometa, &mut aggregates)?;
+            if let LogicalPlan::SubqueryAlias(SubqueryAlias { alias, schema, .. }) = &plan {
+                // columns of subqueries and CTEs are qualified by their alias
+                if let Some(geo) = table_geo_metadata(schema)? {
+                    geometa.insert(alias.table().to_string(), geo);
+                }
+            }
+
+            Ok(Transformed::no(plan))
         })?;
 
-        // let plan = plan.data.recompute_schema()?;
         Ok(plan.data)
     }
 
@@ -128,50 +177,497 @@ impl AnalyzerRule for SpatialAnalyzerRule {
     }
 }
 
-fn infer_encoding_and_type(
+/// Window function of a spatial aggregate sharing its name, as DataFusion
+/// resolves windowed calls to aggregates first.
+fn window_form(name: &str) -> Option<Arc<WindowUDF>> {
+    match name {
+        "ST_ClusterIntersecting" => {
+            Some(Arc::new(WindowUDF::from(udwfs::ClusterIntersecting::new())))
+        }
+        "ST_ClusterWithin" => Some(Arc::new(WindowUDF::from(udwfs::ClusterWithin::new()))),
+        _ => None,
+    }
+}
+
+/// Aggregate of calls to the scalar `ST_Collect` or `ST_MakeLine`, rewritten
+/// to a projection of the scalar calls. The scalar functions are registered
+/// under their exact names only, so DataFusion resolves `ST_Collect(a, b)`
+/// to the aggregate sharing the name.
+fn scalar_aggregate(
+    aggregate: Aggregate,
+    geometa: &HashMap<String, TableGeometry>,
+    aggregates: &HashMap<Column, [Expr; 4]>,
+) -> Result<LogicalPlan> {
+    let calls = aggregate
+        .aggr_expr
+        .iter()
+        .map(|expr| scalar_form(expr, aggregate.input.schema(), geometa, aggregates))
+        .collect::<Result<Vec<_>>>()?;
+    if calls.iter().all(Option::is_none) {
+        return Ok(LogicalPlan::Aggregate(aggregate));
+    }
+    if !aggregate.group_expr.is_empty() || calls.iter().any(Option::is_none) {
+        return Err(DataFusionError::Plan(
+            "ST_Collect and ST_MakeLine of two geometries or an array of points can't be \
+             grouped or combined with aggregates"
+                .to_string(),
+        ));
+    }
+
+    Ok(LogicalPlan::Projection(Projection::try_new(
+        calls.into_iter().flatten().collect(),
+        aggregate.input,
+    )?))
+}
+
+/// Scalar call of an aggregate `ST_Collect` or `ST_MakeLine` call with two
+/// geometries, or of `ST_MakeLine` with an array of points, named like the
+/// aggregate call.
+fn scalar_form(
     expr: &Expr,
-    geometa: &HashMap<String, GeoParquetMetadata>,
-) -> Result<[Expr; 2]> {
-    let mut output: [Expr; 2] = Default::default();
-
-    expr.apply_children(|expr| match &expr {
-        Expr::Column(Column { relation, name }) => {
-            if let Some(table_reference) = relation {
-                if let Some(meta) = geometa.get(table_reference.table()) {
-                    if let Some(column) = meta.columns.get(name.as_str()) {
-                        let encoding = lit(column.encoding.to_string());
-                        let geometry_type = match column.geometry_types.len() {
-                            0 => lit("Unknown"),
-                            1 => lit(column.geometry_types.iter().next().unwrap().to_string()),
-                            2.. => lit("Mixed"),
-                        };
-
-                        output = [geometry_type, encoding];
-
-                        return Ok(TreeNodeRecursion::Stop);
-                    }
+    schema: &DFSchema,
+    geometa: &HashMap<String, TableGeometry>,
+    aggregates: &HashMap<Column, [Expr; 4]>,
+) -> Result<Option<Expr>> {
+    let Expr::AggregateFunction(AggregateFunction {
+        func,
+        args,
+        distinct,
+        filter,
+        order_by,
+        ..
+    }) = expr
+    else {
+        return Ok(None);
+    };
+
+    // arrays, unlike native line strings, have no geometry info
+    let array = |arg: &Expr| -> Result<bool> {
+        Ok(matches!(arg.get_type(schema)?, DataType::List(_))
+            && geometry_info(arg, geometa, aggregates)?.is_none())
+    };
+    let udf = match (func.name(), args.as_slice()) {
+        ("ST_Collect", [_, _]) => ScalarUDF::from(udfs::Collect::new()),
+        ("ST_MakeLine", [_, _]) => ScalarUDF::from(udfs::MakeLine::new()),
+        ("ST_MakeLine", [points]) if array(points)? => ScalarUDF::from(udfs::MakeLine::new()),
+        _ => return Ok(None),
+    };
+    if *distinct || filter.is_some() || order_by.is_some() {
+        return Err(DataFusionError::Plan(format!(
+            "{}: DISTINCT, FILTER and ORDER BY apply to the aggregate only",
+            func.name()
+        )));
+    }
+
+    let name = expr.name_for_alias()?;
+    Ok(Some(
+        Expr::ScalarFunction(ScalarFunction::new_udf(Arc::new(udf), args.clone())).alias(name),
+    ))
+}
+
+/// Recompute the schema of a rewritten plan, adding the geometry info of
+/// geometry columns computed by spatial functions in projections to their
+/// fields as GeoArrow extension metadata. This way the CRS set with e.g.
+/// `ST_SetSRID` is known to the queries consuming a subquery or CTE.
+fn annotate_geometry_columns(
+    plan: LogicalPlan,
+    geometa: &HashMap<String, TableGeometry>,
+    aggregates: &mut HashMap<Column, [Expr; 4]>,
+) -> Result<LogicalPlan> {
+    let plan = match plan {
+        LogicalPlan::TableScan(_) => return Ok(plan),
+        plan => plan.recompute_schema()?,
+    };
+    let LogicalPlan::Projection(projection) = plan else {
+        return Ok(plan);
+    };
+
+    let mut annotated = false;
+    let mut fields = vec![];
+    for (expr, (qualifier, field)) in projection.expr.iter().zip(projection.schema.iter()) {
+        let info = match expr {
+            // plain columns keep the metadata of their input field
+            Expr::Column(Column {
+                relation: Some(_), ..
+            }) => None,
+            expr => geometry_info(expr, geometa, aggregates)?,
+        };
+        let field = match info {
+            Some(info) => {
+                let column = Column::new(qualifier.cloned(), field.name());
+                aggregates.insert(column, info.clone());
+                annotated = true;
+                Arc::new(geometry_field(field, &info))
+            }
+            None => field.clone(),
+        };
+        fields.push((qualifier.cloned(), field));
+    }
+    if !annotated {
+        return Ok(LogicalPlan::Projection(projection));
+    }
+
+    let schema = DFSchema::new_with_metadata(fields, projection.schema.metadata().clone())?
+        .with_functional_dependencies(projection.schema.functional_dependencies().clone())?;
+
+    Ok(LogicalPlan::Projection(Projection::try_new_with_schema(
+        projection.expr,
+        projection.input,
+        Arc::new(schema),
+    )?))
+}
+
+/// `field` with the GeoArrow extension type and the CRS and edges of the
+/// geometry info of a computed geometry column. Native geometries without a
+/// GeoParquet encoding (mixed, geometry collections) are left as they are.
+pub(crate) fn geometry_field(field: &Field, info: &[Expr; 4]) -> Field {
+    let extension = match (field.data_type(), str_literal(&info[1])) {
+        (DataType::Binary | DataType::LargeBinary, _) => "geoarrow.wkb".to_string(),
+        (_, Some(encoding)) if encoding != "WKB" => format!("geoarrow.{encoding}"),
+        _ => return field.clone(),
+    };
+
+    let mut metadata = json!({ "crs": null });
+    if let Some(crs) = str_literal(&info[2]) {
+        // PROJJSON is embedded as an object
+        metadata["crs"] = match serde_json::from_str::<Value>(crs) {
+            Ok(projjson) if projjson.is_object() => projjson,
+            _ => json!(crs),
+        };
+    }
+    if let Some(edges) = str_literal(&info[3]) {
+        metadata["edges"] = json!(edges);
+    }
+
+    let mut field_metadata = field.metadata().clone();
+    field_metadata.insert("ARROW:extension:name".to_string(), extension);
+    field_metadata.insert("ARROW:extension:metadata".to_string(), metadata.to_string());
+    field.clone().with_metadata(field_metadata)
+}
+
+/// Geometry columns of a scanned table.
+struct TableGeometry {
+    metadata: GeoParquetMetadata,
+    /// Columns with `spherical` edges, i.e. of the geography type.
+    spherical: HashSet<String>,
+}
+
+/// GeoParquet metadata of a scanned table, taken from the `geo` schema
+/// metadata and the GeoArrow extension types of its fields. A native
+/// extension type on the field takes precedence over the `geo` metadata.
+fn table_geo_metadata(schema: &DFSchema) -> Result<Option<TableGeometry>> {
+    let mut geo = match schema.metadata().get("geo") {
+        Some(metadata) => serde_json::from_str::<Value>(metadata).map_err(|e| {
+            DataFusionError::ParquetError(ParquetError::General(format!(
+                "Malformed `geo` metadata: {e}"
+            )))
+        })?,
+        None => json!({ "version": "1.1.0", "primary_column": "", "columns": {} }),
+    };
+
+    for field in schema.fields() {
+        let Some(extension) = field.metadata().get("ARROW:extension:name") else {
+            continue;
+        };
+
+        let column = match extension.as_str() {
+            "geoarrow.wkb" => match geo["columns"].get(field.name()) {
+                Some(column) => column.clone(),
+                None => json!({ "encoding": "WKB", "geometry_types": [] }),
+            },
+            name if name.starts_with("geoarrow.") => {
+                let Ok(native_type) = NativeType::try_from(field.as_ref()) else {
+                    continue;
+                };
+                let Some(encoding) = native_encoding_name(&native_type) else {
+                    continue;
+                };
+                json!({
+                    "encoding": encoding,
+                    "geometry_types": [native_geometry_type_name(&native_type)],
+                })
+            }
+            _ => continue,
+        };
+
+        let mut column = column;
+        if let Some(metadata) = field
+            .metadata()
+            .get("ARROW:extension:metadata")
+            .and_then(|m| serde_json::from_str::<Value>(m).ok())
+        {
+            for key in ["crs", "edges"] {
+                if let Some(value) = metadata.get(key) {
+                    column[key] = value.clone();
                 }
             }
-            Ok(TreeNodeRecursion::Continue)
         }
-        Expr::ScalarFunction(ScalarFunction { func, args: _ }) => {
-            if func.name().starts_with("ST_") {
-                match func.name() {
-                    "ST_Envelope" => output = [lit("Polygon"), lit("polygon")],
-                    st => todo!("io mapping for {st}"),
+
+        if geo["primary_column"] == "" {
+            geo["primary_column"] = json!(field.name());
+        }
+        geo["columns"][field.name()] = column;
+    }
+
+    if geo["primary_column"] == "" {
+        return Ok(None);
+    }
+
+    // native columns of GeoParquet files with measures can't be decoded
+    for column in geo["columns"]
+        .as_object()
+        .into_iter()
+        .flat_map(|c| c.values())
+    {
+        if column["encoding"].as_str().is_some_and(|e| e != "WKB") {
+            let types = column["geometry_types"].as_array().into_iter().flatten();
+            if let Some(t) = types.filter_map(Value::as_str).find(|t| has_measures(t)) {
+                return Err(native_measures_error(t));
+            }
+        }
+    }
+
+    let spherical = geo["columns"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, column)| column["edges"] == "spherical")
+        .map(|(name, _)| name.to_owned())
+        .collect();
+
+    let metadata = serde_json::from_value(geo).map_err(|e| {
+        DataFusionError::ParquetError(ParquetError::General(format!(
+            "Malformed `geo` metadata: {e}"
+        )))
+    })?;
+
+    Ok(Some(TableGeometry {
+        metadata,
+        spherical,
+    }))
+}
+
+/// Geometry type, encoding, CRS and edges of the geometry arguments of a
+/// spatial function, appended to its arguments by the analyzer (four
+/// arguments per geometry argument, four nulls if none is known). Geometry
+/// arguments with different CRSs or edges are rejected.
+fn infer_encoding_and_type(
+    expr: &Expr,
+    geometa: &HashMap<String, TableGeometry>,
+    aggregates: &HashMap<Column, [Expr; 4]>,
+) -> Result<Vec<Expr>> {
+    let mut output: Vec<[Expr; 4]> = vec![];
+    let name = expr_function_name(expr).unwrap_or("Spatial function");
+
+    expr.apply_children(|arg| {
+        let Some(info) = geometry_info(arg, geometa, aggregates)? else {
+            return Ok(TreeNodeRecursion::Continue);
+        };
+
+        if let Some(first) = output.first() {
+            if let (Some(a), Some(b)) = (str_literal(&first[2]), str_literal(&info[2])) {
+                if !same_crs(a, b) {
+                    return Err(DataFusionError::Plan(format!(
+                        "{name}: geometry arguments have different CRSs `{a}` and `{b}`"
+                    )));
                 }
             }
-            return Ok(TreeNodeRecursion::Stop);
+            if str_literal(&first[3]) != str_literal(&info[3]) {
+                return Err(DataFusionError::Plan(format!(
+                    "{name}: cannot mix geometry and geography arguments"
+                )));
+            }
         }
-        _ => Ok(TreeNodeRecursion::Continue),
+        output.push(info);
+
+        Ok(TreeNodeRecursion::Continue)
     })?;
 
-    Ok(output)
+    if output.is_empty() {
+        output.push(Default::default());
+    }
+
+    Ok(output.into_iter().flatten().collect())
+}
+
+/// Geometry type, encoding, CRS and edges of a geometry valued expression.
+fn geometry_info(
+    expr: &Expr,
+    geometa: &HashMap<String, TableGeometry>,
+    aggregates: &HashMap<Column, [Expr; 4]>,
+) -> Result<Option<[Expr; 4]>> {
+    match expr {
+        Expr::Alias(alias) => geometry_info(&alias.expr, geometa, aggregates),
+        Expr::Column(column) if aggregates.contains_key(column) => {
+            Ok(aggregates.get(column).cloned())
+        }
+        Expr::Column(Column { relation: None, .. }) => Ok(None),
+        Expr::Column(Column {
+            relation: Some(table_reference),
+            name,
+        }) => {
+            let Some(table) = geometa.get(table_reference.table()) else {
+                return Ok(None);
+            };
+            let Some(column) = table.metadata.columns.get(name.as_str()) else {
+                return Ok(None);
+            };
+
+            let encoding = lit(column.encoding.to_string());
+            let geometry_type = match column.geometry_types.len() {
+                0 => lit("Unknown"),
+                1 => lit(column.geometry_types.iter().next().unwrap().to_string()),
+                2.. => lit("Mixed"),
+            };
+            let crs = match &column.crs {
+                None | Some(Value::Null) => Expr::default(),
+                Some(Value::String(crs)) => lit(crs.to_owned()),
+                Some(crs) => lit(crs.to_string()),
+            };
+            let edges = match table.spherical.contains(name) {
+                true => lit("spherical"),
+                false => Expr::default(),
+            };
+
+            Ok(Some([geometry_type, encoding, crs, edges]))
+        }
+        Expr::ScalarFunction(ScalarFunction { func, args }) if func.name().starts_with("ST_") => {
+            function_geometry_info(func.name(), args)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Geometry type, encoding, CRS and edges of the result of the spatial
+/// function `name`, given its analyzed arguments: the geometry type,
+/// encoding, CRS and edges of its (first) geometry argument follow its own
+/// arguments.
+pub(crate) fn function_geometry_info(name: &str, args: &[Expr]) -> Result<Option<[Expr; 4]>> {
+    let input = |offset: usize| -> [Expr; 4] {
+        std::array::from_fn(|i| args.get(offset + i).cloned().unwrap_or_default())
+    };
+
+    match name {
+        "ST_Envelope" => {
+            let [_, _, crs, edges] = input(1);
+            Ok(Some([lit("Polygon"), lit("polygon"), crs, edges]))
+        }
+        // Box3D structs are laid out like native XYZ rects
+        "ST_3DEnvelope" => {
+            let [_, _, crs, edges] = input(1);
+            Ok(Some([lit("Polygon Z"), lit("box"), crs, edges]))
+        }
+        "ST_SetSRID" => {
+            let [geometry_type, encoding, _, edges] = input(2);
+            Ok(Some([
+                geometry_type,
+                encoding,
+                srid_to_crs(&args[1])?,
+                edges,
+            ]))
+        }
+        "ST_Transform" => {
+            let [geometry_type, encoding, _, _] = input(args.len() - 4);
+            let crs = match &args[1] {
+                Expr::Literal(ScalarValue::Utf8(Some(crs))) => lit(crs.to_owned()),
+                srid => srid_to_crs(srid)?,
+            };
+            Ok(Some([geometry_type, encoding, crs, Expr::default()]))
+        }
+        "ST_Geography" => {
+            let [geometry_type, encoding, crs, _] = input(1);
+            Ok(Some([geometry_type, encoding, crs, lit("spherical")]))
+        }
+        "ST_Geometry" => {
+            let [geometry_type, encoding, crs, _] = input(1);
+            Ok(Some([geometry_type, encoding, crs, Expr::default()]))
+        }
+        "ST_Collect" | "ST_MakeLine" => {
+            let offset = if args.len() == 5 { 1 } else { 2 };
+            Ok(derived_info(name, input(offset)))
+        }
+        // measures, predicates and other non geometry results
+        "ST_Area" | "ST_AsText" | "ST_Distance" | "ST_GeometryType" | "ST_Intersects"
+        | "ST_Length" | "ST_M" | "ST_SRID" => Ok(None),
+        // lists of path and geometry structs, not geometries themselves
+        "ST_Dump" | "ST_DumpPoints" | "ST_DumpRings" => Ok(None),
+        // spatial functions registered by others
+        _ => Ok(None),
+    }
+}
+
+/// Geometry info of the result of a spatial function building geometries,
+/// given the info of its (first) geometry argument.
+fn derived_info(name: &str, input: [Expr; 4]) -> Option<[Expr; 4]> {
+    let [geometry_type, encoding, crs, edges] = input;
+    // native points, line strings and polygons are collected into native
+    // multi geometries, points are made into native line strings, anything
+    // else into WKB
+    let native_input = str_literal(&encoding).is_some_and(|encoding| encoding != "WKB");
+    let native_type = match (str_literal(&geometry_type), str_literal(&encoding)) {
+        (Some(geometry_type), Some(encoding @ ("point" | "linestring" | "polygon"))) => {
+            Some((geometry_type.to_owned(), encoding))
+        }
+        _ => None,
+    };
+    let [geometry_type, encoding] = match (name, native_type) {
+        ("ST_Collect", Some((part_type, encoding))) => [
+            lit(format!("Multi{part_type}")),
+            lit(format!("multi{encoding}")),
+        ],
+        ("ST_MakeLine", Some((point_type, "point"))) => [
+            lit(point_type.replace("Point", "LineString")),
+            lit("linestring"),
+        ],
+        ("ST_MakeLine", _) => [lit("LineString"), lit("WKB")],
+        ("ST_Envelope_Agg", _) => [lit("Polygon"), lit("polygon")],
+        ("ST_Centroid_Agg", _) => [lit("Point"), lit("point")],
+        // hulls of native geometries are native mixed geometries
+        ("ST_ConvexHull_Agg", _) if native_input => [lit("Unknown"), lit("geometry")],
+        ("ST_Collect" | "ST_ConvexHull_Agg" | "ST_Union_Agg", _) => [lit("Unknown"), lit("WKB")],
+        _ => return None,
+    };
+    Some([geometry_type, encoding, crs, edges])
+}
+
+/// CRS of an `ST_SetSRID` or `ST_Transform` SRID argument, SRID 0 meaning unknown.
+fn srid_to_crs(srid: &Expr) -> Result<Expr> {
+    match srid {
+        Expr::Literal(value) if value.is_null() => Ok(Expr::default()),
+        Expr::Literal(value) if value.data_type().is_integer() => match value.to_string() {
+            srid if srid == "0" => Ok(Expr::default()),
+            srid => Ok(lit(format!("EPSG:{srid}"))),
+        },
+        expr => Err(DataFusionError::Plan(format!(
+            "Expected a constant integer SRID, got `{expr}`"
+        ))),
+    }
+}
+
+fn str_literal(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Literal(ScalarValue::Utf8(Some(s))) => Some(s.as_str()),
+        _ => None,
+    }
 }
 
-// fn map_input_to_output(name: &str, args: &[Expr]) -> Result<[Expr; 2]> {
-//     match (name, args) {
-//         ("ST_AsText", args) => todo!(),
-//         (name, args) => unimplemented!("{name}: {args:?}")
-//     }
-// }
+/// Whether two CRSs are the same, comparing EPSG codes where known.
+fn same_crs(a: &str, b: &str) -> bool {
+    match (srid(a), srid(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn expr_function_name(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Alias(alias) => expr_function_name(&alias.expr),
+        Expr::ScalarFunction(ScalarFunction { func, .. }) => Some(func.name()),
+        Expr::AggregateFunction(AggregateFunction { func, .. }) => Some(func.name()),
+        Expr::WindowFunction(WindowFunction {
+            fun: WindowFunctionDefinition::WindowUDF(udf),
+            ..
+        }) => Some(udf.name()),
+        _ => None,
+    }
+}
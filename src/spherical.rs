@@ -0,0 +1,643 @@
+//! Computations on lon/lat geometries with great-circle (spherical) edges.
+
+use core::f64;
+
+use geo::{Coord, Geometry, LineString};
+
+/// Mean earth radius in meters.
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+type Vector = [f64; 3];
+
+fn to_vector(coord: Coord) -> Vector {
+    let (lon, lat) = (coord.x.to_radians(), coord.y.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+fn to_coord(v: Vector) -> Coord {
+    Coord {
+        x: v[1].atan2(v[0]).to_degrees(),
+        y: v[2].atan2(v[0].hypot(v[1])).to_degrees(),
+    }
+}
+
+fn dot(a: Vector, b: Vector) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vector, b: Vector) -> Vector {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn norm(a: Vector) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Angle between two unit vectors in radians.
+fn angle(a: Vector, b: Vector) -> f64 {
+    norm(cross(a, b)).atan2(dot(a, b))
+}
+
+/// Whether `v`, on the great circle through `a` and `b` with normal `n`, lies
+/// on the (shorter) arc from `a` to `b`.
+fn on_arc(a: Vector, b: Vector, n: Vector, v: Vector) -> bool {
+    dot(cross(a, v), n) >= 0. && dot(cross(v, b), n) >= 0.
+}
+
+/// Line strings (rings included) of a geometry, points as single coordinate
+/// line strings.
+fn line_strings(geometry: &Geometry) -> Vec<LineString> {
+    match geometry {
+        Geometry::Point(p) => vec![LineString(vec![p.0])],
+        Geometry::Line(l) => vec![LineString(vec![l.start, l.end])],
+        Geometry::LineString(ls) => vec![ls.clone()],
+        Geometry::Polygon(p) => std::iter::once(p.exterior().clone())
+            .chain(p.interiors().iter().cloned())
+            .collect(),
+        Geometry::MultiPoint(mp) => mp.iter().map(|p| LineString(vec![p.0])).collect(),
+        Geometry::MultiLineString(mls) => mls.0.clone(),
+        Geometry::MultiPolygon(mp) => mp
+            .iter()
+            .flat_map(|p| {
+                std::iter::once(p.exterior().clone()).chain(p.interiors().iter().cloned())
+            })
+            .collect(),
+        Geometry::GeometryCollection(gc) => gc.iter().flat_map(line_strings).collect(),
+        Geometry::Rect(r) => vec![r.to_polygon().exterior().clone()],
+        Geometry::Triangle(t) => vec![t.to_polygon().exterior().clone()],
+    }
+}
+
+/// Exterior rings of the polygons of a geometry.
+fn exteriors(geometry: &Geometry) -> Vec<&LineString> {
+    match geometry {
+        Geometry::Polygon(p) => vec![p.exterior()],
+        Geometry::MultiPolygon(mp) => mp.iter().map(|p| p.exterior()).collect(),
+        Geometry::GeometryCollection(gc) => gc.iter().flat_map(exteriors).collect(),
+        _ => vec![],
+    }
+}
+
+/// Longitude difference from `a` to `b` in `(-180, 180]`.
+fn lon_delta(a: f64, b: f64) -> f64 {
+    let delta = (b - a).rem_euclid(360.);
+    if delta > 180. {
+        delta - 360.
+    } else {
+        delta
+    }
+}
+
+/// Smallest longitude range covering all `intervals`, given as `(west, east)`
+/// with `west > east` for intervals crossing the antimeridian. The result
+/// follows the same convention, `(-180, 180)` if the intervals leave no gap.
+pub fn lon_bounds(intervals: &[(f64, f64)]) -> (f64, f64) {
+    let mut parts = vec![];
+    for &(west, east) in intervals {
+        if west <= east {
+            parts.push((west, east));
+        } else {
+            parts.push((west, 180.));
+            parts.push((-180., east));
+        }
+    }
+    parts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut merged: Vec<(f64, f64)> = vec![];
+    for (west, east) in parts {
+        match merged.last_mut() {
+            Some(last) if west <= last.1 => last.1 = last.1.max(east),
+            _ => merged.push((west, east)),
+        }
+    }
+
+    let Some(first) = merged.first() else {
+        return (f64::MAX, f64::MIN);
+    };
+    let last = merged.last().unwrap();
+
+    // the largest gap between covered ranges, the gap across the antimeridian first
+    let mut gap = (first.0 + 360. - last.1, first.0, last.1);
+    for pair in merged.windows(2) {
+        if pair[1].0 - pair[0].1 > gap.0 {
+            gap = (pair[1].0 - pair[0].1, pair[1].0, pair[0].1);
+        }
+    }
+
+    if gap.0 <= 0. {
+        (-180., 180.)
+    } else if gap.1 == first.0 && gap.2 == last.1 {
+        (first.0, last.1)
+    } else {
+        (gap.1, gap.2)
+    }
+}
+
+/// Bounds of a lon/lat geometry with great-circle edges as
+/// `(xmin, ymin, xmax, ymax)`.
+///
+/// Geometries crossing the antimeridian have `xmin > xmax`, polygons
+/// containing a pole span all longitudes up to latitude ±90. Latitudes are
+/// extended where an edge bulges beyond its vertices.
+pub fn spherical_bounds(geometry: &Geometry) -> Option<(f64, f64, f64, f64)> {
+    let mut lons = vec![];
+    let (mut ymin, mut ymax) = (f64::MAX, f64::MIN);
+
+    for line_string in line_strings(geometry) {
+        let coords = line_string
+            .0
+            .iter()
+            .filter(|c| !c.x.is_nan() && !c.y.is_nan())
+            .collect::<Vec<_>>();
+
+        for coord in coords.iter() {
+            ymin = ymin.min(coord.y);
+            ymax = ymax.max(coord.y);
+            lons.push((coord.x, coord.x));
+        }
+
+        for pair in coords.windows(2) {
+            let (a, b) = (to_vector(*pair[0]), to_vector(*pair[1]));
+            let n = cross(a, b);
+            let length = norm(n);
+            if length < 1e-12 {
+                continue;
+            }
+            let n = n.map(|v| v / length);
+
+            // the points of the great circle closest to the poles
+            let top = [-n[2] * n[0], -n[2] * n[1], 1. - n[2] * n[2]];
+            let top_length = norm(top);
+            if top_length > 1e-12 {
+                let top = top.map(|v| v / top_length);
+                if on_arc(a, b, n, top) {
+                    ymax = ymax.max(to_coord(top).y);
+                }
+                let bottom = top.map(|v| -v);
+                if on_arc(a, b, n, bottom) {
+                    ymin = ymin.min(to_coord(bottom).y);
+                }
+            }
+
+            let delta = lon_delta(pair[0].x, pair[1].x);
+            let (west, east) = if delta >= 0. {
+                (pair[0].x, pair[0].x + delta)
+            } else {
+                (pair[0].x + delta, pair[0].x)
+            };
+            let wrap = |lon: f64| (lon + 180.).rem_euclid(360.) - 180.;
+            lons.push((wrap(west), wrap(east)));
+        }
+    }
+
+    if lons.is_empty() {
+        return None;
+    }
+
+    let (mut xmin, mut xmax) = lon_bounds(&lons);
+
+    // rings winding around a pole contain it
+    for ring in exteriors(geometry) {
+        let winding: f64 = ring.0.windows(2).map(|c| lon_delta(c[0].x, c[1].x)).sum();
+        if winding.abs() > 180. {
+            (xmin, xmax) = (-180., 180.);
+            let mean = ring.0.iter().map(|c| c.y).sum::<f64>();
+            if mean >= 0. {
+                ymax = 90.;
+            } else {
+                ymin = -90.;
+            }
+        }
+    }
+
+    Some((xmin, ymin, xmax, ymax))
+}
+
+/// Angle in radians below which two points on the unit sphere are the same
+/// (about 6 micrometers on the earth).
+const EPSILON: f64 = 1e-12;
+
+fn normalize(v: Vector) -> Vector {
+    let length = norm(v);
+    v.map(|x| x / length)
+}
+
+/// Whether `v` lies on the (shorter) arc from `a` to `b`, within [`EPSILON`].
+fn on_segment(v: Vector, a: Vector, b: Vector) -> bool {
+    angle(a, v) + angle(v, b) - angle(a, b) < EPSILON
+}
+
+/// Whether the great-circle arcs `a0`-`a1` and `b0`-`b1` intersect or touch.
+/// Degenerate arcs (`a0 == a1`) are points.
+fn arcs_intersect(a0: Vector, a1: Vector, b0: Vector, b1: Vector) -> bool {
+    let candidates = cross(cross(a0, a1), cross(b0, b1));
+    if norm(candidates) < EPSILON {
+        // arcs on the same great circle or points: they overlap if one
+        // contains an end point of the other
+        return on_segment(b0, a0, a1)
+            || on_segment(b1, a0, a1)
+            || on_segment(a0, b0, b1)
+            || on_segment(a1, b0, b1);
+    }
+
+    // the great circles intersect in two antipodal points
+    let v = normalize(candidates);
+    [v, v.map(|x| -x)]
+        .into_iter()
+        .any(|v| on_segment(v, a0, a1) && on_segment(v, b0, b1))
+}
+
+/// Ring of a polygon as unit vectors, oriented so that its interior is on
+/// the left. Without an orientation convention for geographies, the
+/// interior of a ring is the smaller of the two regions it bounds.
+fn oriented_ring(ring: &LineString) -> Vec<Vector> {
+    let mut vectors = ring.0.iter().map(|c| to_vector(*c)).collect::<Vec<_>>();
+    if vectors.first() != vectors.last() {
+        vectors.extend(vectors.first().copied());
+    }
+
+    // with negative turning the area on the left is more than a hemisphere
+    if turning(&vectors) < 0. {
+        vectors.reverse();
+    }
+
+    vectors
+}
+
+/// Sum of the turning angles of a closed ring of unit vectors, the area to
+/// its left is 2π minus this sum.
+fn turning(ring: &[Vector]) -> f64 {
+    let n = ring.len().saturating_sub(1);
+    let mut turning = 0.;
+    for i in 0..n {
+        let (u, v, w) = (ring[(i + n - 1) % n], ring[i], ring[i + 1]);
+        let (incoming, outgoing) = (cross(cross(u, v), v), cross(cross(v, w), v));
+        if norm(incoming) < EPSILON || norm(outgoing) < EPSILON {
+            continue;
+        }
+        turning += dot(v, cross(incoming, outgoing)).atan2(dot(incoming, outgoing));
+    }
+    turning
+}
+
+/// Whether the interior of an oriented ring (see [`oriented_ring`]) contains
+/// `p`, counting the crossings of the arc to a point just inside the ring.
+fn ring_contains(ring: &[Vector], p: Vector) -> bool {
+    // a point just left of the first proper edge is inside
+    let Some(inside) = ring.windows(2).find_map(|edge| {
+        let n = cross(edge[0], edge[1]);
+        (norm(n) > EPSILON).then(|| {
+            let mid = normalize([0, 1, 2].map(|k| edge[0][k] + edge[1][k]));
+            normalize([0, 1, 2].map(|k| mid[k] + 1e-9 * n[k] / norm(n)))
+        })
+    }) else {
+        return false;
+    };
+
+    // arcs shorter than a half circle, via a point perpendicular to both ends
+    // if `p` is close to the antipode
+    let path = match angle(inside, p) < f64::consts::FRAC_PI_2 {
+        true => vec![inside, p],
+        false => {
+            let mid = cross(inside, p);
+            let mid = match norm(mid) < EPSILON {
+                // antipodal, any perpendicular direction
+                true => match inside[0].abs() < 0.9 {
+                    true => normalize(cross(inside, [1., 0., 0.])),
+                    false => normalize(cross(inside, [0., 1., 0.])),
+                },
+                false => normalize(mid),
+            };
+            let mid = normalize(cross(mid, inside));
+            vec![inside, mid, p]
+        }
+    };
+
+    let mut crossings = 0;
+    for leg in path.windows(2) {
+        let (q, p) = (leg[0], leg[1]);
+        let n = cross(q, p);
+        for edge in ring.windows(2) {
+            // half-open, a vertex on the path is counted for one edge only
+            if (dot(edge[0], n) > 0.) == (dot(edge[1], n) > 0.) {
+                continue;
+            }
+            let x = cross(n, cross(edge[0], edge[1]));
+            if norm(x) < EPSILON {
+                continue;
+            }
+            let x = normalize(x);
+            let x = match dot(x, [0, 1, 2].map(|k| edge[0][k] + edge[1][k])) >= 0. {
+                true => x,
+                false => x.map(|v| -v),
+            };
+            if on_arc(q, p, n, x) {
+                crossings += 1;
+            }
+        }
+    }
+
+    crossings % 2 == 0
+}
+
+/// Polygons of a geometry as oriented exterior ring and holes.
+fn polygons(geometry: &Geometry) -> Vec<Vec<Vec<Vector>>> {
+    match geometry {
+        Geometry::Polygon(p) => vec![polygon_rings(p)],
+        Geometry::MultiPolygon(mp) => mp.iter().map(polygon_rings).collect(),
+        Geometry::GeometryCollection(gc) => gc.iter().flat_map(polygons).collect(),
+        Geometry::Rect(r) => vec![polygon_rings(&r.to_polygon())],
+        Geometry::Triangle(t) => vec![polygon_rings(&t.to_polygon())],
+        _ => vec![],
+    }
+    .into_iter()
+    .filter(|rings| !rings.is_empty())
+    .collect()
+}
+
+/// Oriented exterior ring and holes of a polygon, without empty rings.
+fn polygon_rings(polygon: &geo::Polygon) -> Vec<Vec<Vector>> {
+    std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .filter(|ring| !ring.0.is_empty())
+        .map(oriented_ring)
+        .collect()
+}
+
+/// Whether two geographies intersect, with exact great-circle edges.
+///
+/// They intersect if any of their edges (points being degenerate edges)
+/// intersect, or otherwise if one lies within a polygon of the other: without
+/// crossing edges, a part lies within a polygon if its first vertex does.
+pub fn intersects(a: &Geometry, b: &Geometry) -> bool {
+    let edges = |geometry: &Geometry| {
+        line_strings(geometry)
+            .iter()
+            .filter(|ls| !ls.0.is_empty())
+            .map(|ls| {
+                let vectors = ls.0.iter().map(|c| to_vector(*c)).collect::<Vec<_>>();
+                match vectors.len() {
+                    1 => vec![(vectors[0], vectors[0])],
+                    _ => vectors.windows(2).map(|e| (e[0], e[1])).collect(),
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let (a_parts, b_parts) = (edges(a), edges(b));
+
+    for (a0, a1) in a_parts.iter().flatten() {
+        for (b0, b1) in b_parts.iter().flatten() {
+            if arcs_intersect(*a0, *a1, *b0, *b1) {
+                return true;
+            }
+        }
+    }
+
+    let within = |parts: &[Vec<(Vector, Vector)>], polygons: &[Vec<Vec<Vector>>]| {
+        parts.iter().filter_map(|part| part.first()).any(|(v, _)| {
+            polygons.iter().any(|rings| {
+                ring_contains(&rings[0], *v) && !rings[1..].iter().any(|r| ring_contains(r, *v))
+            })
+        })
+    };
+
+    within(&a_parts, &polygons(b)) || within(&b_parts, &polygons(a))
+}
+
+/// Great-circle distance in meters between two lon/lat coordinates.
+pub fn distance(a: Coord, b: Coord) -> f64 {
+    angle(to_vector(a), to_vector(b)) * EARTH_RADIUS
+}
+
+/// Great-circle length in meters of the linear parts of a geometry.
+pub fn length(geometry: &Geometry) -> f64 {
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => 0.,
+        Geometry::Polygon(_) | Geometry::MultiPolygon(_) => 0.,
+        Geometry::Rect(_) | Geometry::Triangle(_) => 0.,
+        Geometry::GeometryCollection(gc) => gc.iter().map(length).sum(),
+        geometry => line_strings(geometry)
+            .iter()
+            .flat_map(|ls| ls.0.windows(2).map(|c| distance(c[0], c[1])))
+            .sum(),
+    }
+}
+
+/// Great-circle distance in meters between two geographies, 0 if they
+/// [intersect](intersects).
+///
+/// Two great-circle edges that do not cross are closest at an end point of
+/// one of them, so the distance is the smallest distance between the
+/// vertices of one geography and the edges of the other.
+pub fn geometry_distance(a: &Geometry, b: &Geometry) -> Option<f64> {
+    if intersects(a, b) {
+        return Some(0.);
+    }
+
+    fn point_to_line_string(point: Coord, line_string: &LineString) -> f64 {
+        let p = to_vector(point);
+        let mut min = match line_string.0.first() {
+            Some(coord) => angle(p, to_vector(*coord)),
+            None => f64::MAX,
+        };
+
+        for pair in line_string.0.windows(2) {
+            let (a, b) = (to_vector(pair[0]), to_vector(pair[1]));
+            min = min.min(angle(p, b));
+
+            let n = cross(a, b);
+            let length = norm(n);
+            if length < 1e-12 {
+                continue;
+            }
+            let n = n.map(|v| v / length);
+
+            // projection of the point onto the great circle of the edge
+            let offset = dot(p, n);
+            let projected = [0, 1, 2].map(|k| p[k] - offset * n[k]);
+            if norm(projected) > 1e-12 && on_arc(a, b, n, projected) {
+                min = min.min(offset.abs().asin());
+            }
+        }
+
+        min
+    }
+
+    let (a, b) = (line_strings(a), line_strings(b));
+    let mut min = f64::MAX;
+    for (from, to) in [(&a, &b), (&b, &a)] {
+        for coord in from.iter().flat_map(|ls| ls.0.iter()) {
+            for line_string in to.iter() {
+                min = min.min(point_to_line_string(*coord, line_string));
+            }
+        }
+    }
+
+    (min != f64::MAX).then_some(min * EARTH_RADIUS)
+}
+
+/// Mean unit vector of a part of a geography with its weight, the length in
+/// radians of an edge or the area in steradians of a polygon.
+pub type CentroidPart = ([f64; 3], f64);
+
+/// Parts of a geography by dimension for a centroid: its points, edges and
+/// polygons. Like in the plane, edges of zero length count as points and
+/// polygons without area as their exterior ring.
+pub fn centroid_parts(geometry: &Geometry) -> [Vec<CentroidPart>; 3] {
+    let mut parts = <[Vec<_>; 3]>::default();
+    add_centroid_parts(geometry, &mut parts);
+    parts
+}
+
+fn add_centroid_parts(geometry: &Geometry, parts: &mut [Vec<CentroidPart>; 3]) {
+    match geometry {
+        Geometry::Point(p) if p.x().is_nan() => {}
+        Geometry::Point(p) => parts[0].push((to_vector(p.0), 1.)),
+        Geometry::Polygon(p) => {
+            // the integral of the position over the area to the left of a
+            // ring is half the sum of the edge normals scaled by their length
+            let (mut area, mut integral) = (0., [0.; 3]);
+            for (i, ring) in polygon_rings(p).iter().enumerate() {
+                let sign = if i == 0 { 1. } else { -1. };
+                area += sign * (2. * f64::consts::PI - turning(ring));
+                for edge in ring.windows(2) {
+                    let n = cross(edge[0], edge[1]);
+                    if norm(n) > EPSILON {
+                        let n = normalize(n);
+                        let length = angle(edge[0], edge[1]);
+                        integral = [0, 1, 2].map(|k| integral[k] + sign * length * n[k] / 2.);
+                    }
+                }
+            }
+            match area > EPSILON {
+                true => parts[2].push((integral.map(|v| v / area), area)),
+                false => add_line_parts(p.exterior(), parts),
+            }
+        }
+        Geometry::GeometryCollection(gc) => gc.iter().for_each(|g| add_centroid_parts(g, parts)),
+        Geometry::MultiPoint(mp) => mp
+            .iter()
+            .for_each(|p| add_centroid_parts(&Geometry::Point(*p), parts)),
+        Geometry::MultiPolygon(mp) => mp
+            .iter()
+            .for_each(|p| add_centroid_parts(&Geometry::Polygon(p.clone()), parts)),
+        Geometry::Rect(r) => add_centroid_parts(&Geometry::Polygon(r.to_polygon()), parts),
+        Geometry::Triangle(t) => add_centroid_parts(&Geometry::Polygon(t.to_polygon()), parts),
+        geometry => line_strings(geometry)
+            .iter()
+            .for_each(|ls| add_line_parts(ls, parts)),
+    }
+}
+
+/// Edges of a line string, the mean of the unit vectors along an arc of
+/// length θ being its midpoint scaled by `2 sin(θ/2) / θ`.
+fn add_line_parts(line_string: &LineString, parts: &mut [Vec<CentroidPart>; 3]) {
+    let vectors = line_string
+        .0
+        .iter()
+        .map(|c| to_vector(*c))
+        .collect::<Vec<_>>();
+    let edges = vectors
+        .windows(2)
+        .filter_map(|edge| {
+            let length = angle(edge[0], edge[1]);
+            let mid = [0, 1, 2].map(|k| edge[0][k] + edge[1][k]);
+            (length > EPSILON && norm(mid) > EPSILON).then(|| {
+                let scale = 2. * (length / 2.).sin() / length;
+                (normalize(mid).map(|v| v * scale), length)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    match edges.is_empty() {
+        true => parts[0].extend(vectors.into_iter().map(|v| (v, 1.))),
+        false => parts[1].extend(edges),
+    }
+}
+
+/// Lon/lat coordinate of the direction of a mean of unit vectors, `None` if
+/// it is (close to) zero, e.g. for antipodal points.
+pub fn mean_coord(mean: [f64; 3]) -> Option<Coord> {
+    (norm(mean) > EPSILON).then(|| to_coord(mean))
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{line_string, polygon};
+
+    use super::*;
+
+    #[test]
+    fn antimeridian() {
+        let line = Geometry::LineString(line_string![(x: 170., y: 0.), (x: -170., y: 10.)]);
+        let (xmin, _, xmax, _) = spherical_bounds(&line).unwrap();
+        assert_eq!((xmin, xmax), (170., -170.));
+
+        assert_eq!(lon_bounds(&[(170., -170.), (-175., 10.)]), (170., 10.));
+        assert_eq!(lon_bounds(&[(-10., 10.), (20., 30.)]), (-10., 30.));
+    }
+
+    #[test]
+    fn edge_bulge() {
+        let line = Geometry::LineString(line_string![(x: 0., y: 45.), (x: 90., y: 45.)]);
+        let (_, ymin, _, ymax) = spherical_bounds(&line).unwrap();
+        assert_eq!(ymin, 45.);
+        assert!((ymax - 54.7356).abs() < 1e-4);
+    }
+
+    #[test]
+    fn polar() {
+        let cap = Geometry::Polygon(polygon![
+            (x: 0., y: 80.),
+            (x: 90., y: 80.),
+            (x: 180., y: 80.),
+            (x: -90., y: 80.),
+            (x: 0., y: 80.),
+        ]);
+        assert_eq!(spherical_bounds(&cap).unwrap().3, 90.);
+        assert_eq!(spherical_bounds(&cap).unwrap().0, -180.);
+    }
+
+    #[test]
+    fn great_circle_intersection() {
+        // both lines pass the north pole, their lon/lat lines are parallel
+        let a = Geometry::LineString(line_string![(x: -90., y: 45.), (x: 90., y: 45.)]);
+        let b = Geometry::LineString(line_string![(x: 0., y: 80.), (x: 180., y: 80.)]);
+        assert!(intersects(&a, &b));
+
+        // a point on the bulge of an edge along a parallel
+        let edge = Geometry::LineString(line_string![(x: 0., y: 45.), (x: 90., y: 45.)]);
+        let top = Geometry::Point(geo::point!(x: 45., y: 54.735610317245346));
+        assert!(intersects(&edge, &top));
+        let below = Geometry::Point(geo::point!(x: 45., y: 50.));
+        assert!(!intersects(&edge, &below));
+        let d = geometry_distance(&edge, &below).unwrap();
+        assert!((d - 4.735610317245346 * 111_195.).abs() < 100., "{d}");
+    }
+
+    #[test]
+    fn polygon_containment() {
+        let ring = [(0., 80.), (90., 80.), (180., 80.), (-90., 80.), (0., 80.)];
+        let cap =
+            |coords: Vec<(f64, f64)>| Geometry::Polygon(geo::Polygon::new(coords.into(), vec![]));
+        let pole = Geometry::Point(geo::point!(x: 10., y: 89.));
+        let equator = Geometry::Point(geo::point!(x: 10., y: 0.));
+
+        // the smaller region is the interior, whatever the orientation
+        for coords in [ring.to_vec(), ring.iter().rev().copied().collect()] {
+            assert!(intersects(&cap(coords.clone()), &pole));
+            assert!(!intersects(&cap(coords.clone()), &equator));
+            assert_eq!(geometry_distance(&pole, &cap(coords)), Some(0.));
+        }
+    }
+
+    #[test]
+    fn great_circle_distance() {
+        let d = distance(Coord { x: 0., y: 0. }, Coord { x: 0., y: 1. });
+        assert!((d - 111_195.).abs() < 1.);
+    }
+}
@@ -0,0 +1,432 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, AsArray},
+        compute::cast,
+        datatypes::{DataType, Field, Float64Type},
+    },
+    error::{DataFusionError, Result},
+    logical_expr::{
+        function::{AccumulatorArgs, StateFieldsArgs},
+        utils::format_state_name,
+        Accumulator, AggregateUDFImpl, ColumnarValue, Signature, TypeSignature, Volatility,
+    },
+    physical_expr::expressions::Literal,
+    scalar::ScalarValue,
+};
+use geo::{Area, Centroid, Coord, Geometry, LineString, Point};
+use geoarrow::{
+    array::{CoordType, PointBuilder},
+    datatypes::{Dimension, NativeType},
+    ArrayBase,
+};
+
+use crate::{helpers::geo_geometries, spherical};
+
+/// `ST_Centroid_Agg` aggregate function.
+///
+/// Centroid of all geometries of a group as a native point, optionally
+/// weighted by a second argument. Like `ST_Centroid` only the parts of the
+/// highest dimension count: polygons weighted by area, else lines weighted by
+/// length, else points. The partial state is the running weighted mean of
+/// each dimension, which stays accurate for large coordinates.
+///
+/// Weights must not be negative, rows with a zero or NULL weight are skipped
+/// so a group with only such rows has a NULL centroid. The centroid of
+/// geographies (spherical edges) is the direction of the weighted mean of the
+/// unit vectors of their parts, with lengths and areas on the sphere.
+#[derive(Debug)]
+pub struct CentroidAgg {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl CentroidAgg {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Any(1),
+                    TypeSignature::Any(2),
+                    TypeSignature::Any(5),
+                    TypeSignature::Any(6),
+                ],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_centroid_agg".to_string()],
+        }
+    }
+}
+
+impl AggregateUDFImpl for CentroidAgg {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_Centroid_Agg"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(NativeType::Point(CoordType::Separated, Dimension::XY).to_data_type())
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        // same order as `CentroidAccumulator::state`
+        Ok((0..3)
+            .flat_map(|dim| ["weight", "x", "y", "z"].map(|field| format!("{field}{dim}")))
+            .map(|name| {
+                Field::new(
+                    format_state_name(args.name, &name),
+                    DataType::Float64,
+                    false,
+                )
+            })
+            .collect())
+    }
+
+    fn accumulator(&self, acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        // the edges argument appended by the analyzer comes last
+        let spherical = acc_args.exprs.len() > 2
+            && acc_args
+                .exprs
+                .last()
+                .and_then(|expr| expr.as_any().downcast_ref::<Literal>())
+                .is_some_and(|edges| {
+                    matches!(edges.value(), ScalarValue::Utf8(Some(edges)) if edges == "spherical")
+                });
+
+        Ok(Box::new(CentroidAccumulator {
+            weighted: matches!(acc_args.exprs.len(), 2 | 6),
+            spherical,
+            means: Default::default(),
+        }))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Running weighted mean of coordinates, updated incrementally rather than
+/// summing weighted coordinates so large coordinates don't lose precision.
+/// `z` is only used by the unit vectors of geographies.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct WeightedMean {
+    weight: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl WeightedMean {
+    fn add(&mut self, other: WeightedMean) {
+        if other.weight.is_nan() || other.weight <= 0. {
+            return;
+        }
+        self.weight += other.weight;
+        let ratio = other.weight / self.weight;
+        self.x += (other.x - self.x) * ratio;
+        self.y += (other.y - self.y) * ratio;
+        self.z += (other.z - self.z) * ratio;
+    }
+
+    fn add_coord(&mut self, coord: Coord, weight: f64) {
+        self.add(WeightedMean {
+            weight,
+            x: coord.x,
+            y: coord.y,
+            z: 0.,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CentroidAccumulator {
+    /// Whether the second argument is a weight.
+    weighted: bool,
+    /// Geographies, whose means are of unit vectors.
+    spherical: bool,
+    /// Weighted means of points, lines and polygons.
+    means: [WeightedMean; 3],
+}
+
+impl CentroidAccumulator {
+    /// Add the parts of a geometry, their weights scaled by `weight`.
+    fn add(&mut self, geometry: &Geometry, weight: f64) {
+        match geometry {
+            Geometry::Point(p) if p.x().is_nan() => {}
+            Geometry::Point(p) => self.means[0].add_coord(p.0, weight),
+            Geometry::MultiPoint(mp) => mp
+                .iter()
+                .for_each(|p| self.add(&Geometry::Point(*p), weight)),
+            Geometry::Line(l) => {
+                self.add_line_string(&LineString::new(vec![l.start, l.end]), weight)
+            }
+            Geometry::LineString(ls) => self.add_line_string(ls, weight),
+            Geometry::MultiLineString(mls) => {
+                mls.iter().for_each(|ls| self.add_line_string(ls, weight))
+            }
+            Geometry::Polygon(p) => match (p.unsigned_area(), p.centroid()) {
+                (area, Some(centroid)) if area > 0. => {
+                    self.means[2].add_coord(centroid.0, area * weight)
+                }
+                _ => self.add_line_string(p.exterior(), weight),
+            },
+            Geometry::MultiPolygon(mp) => mp
+                .iter()
+                .for_each(|p| self.add(&Geometry::Polygon(p.clone()), weight)),
+            Geometry::Rect(r) => self.add(&Geometry::Polygon(r.to_polygon()), weight),
+            Geometry::Triangle(t) => self.add(&Geometry::Polygon(t.to_polygon()), weight),
+            Geometry::GeometryCollection(gc) => gc.iter().for_each(|g| self.add(g, weight)),
+        }
+    }
+
+    /// Add the segments of a line string weighted by their length.
+    fn add_line_string(&mut self, ls: &LineString, weight: f64) {
+        let mut mean = WeightedMean::default();
+        for line in ls.lines() {
+            let length = line.dx().hypot(line.dy());
+            mean.add_coord((line.start + line.end) / 2., length * weight);
+        }
+        match mean.weight > 0. {
+            true => self.means[1].add(mean),
+            // degenerate lines count as points
+            false => ls
+                .coords()
+                .for_each(|c| self.means[0].add_coord(*c, weight)),
+        }
+    }
+
+    /// Add the parts of a geography as unit vectors, their weights scaled by
+    /// `weight`.
+    fn add_spherical(&mut self, geometry: &Geometry, weight: f64) {
+        for (mean, parts) in self
+            .means
+            .iter_mut()
+            .zip(spherical::centroid_parts(geometry))
+        {
+            for ([x, y, z], part_weight) in parts {
+                mean.add(WeightedMean {
+                    weight: part_weight * weight,
+                    x,
+                    y,
+                    z,
+                });
+            }
+        }
+    }
+
+    /// Centroid of the highest dimension with any weight.
+    fn centroid(&self) -> Option<Point> {
+        let mean = self.means.iter().rev().find(|mean| mean.weight > 0.)?;
+        match self.spherical {
+            true => spherical::mean_coord([mean.x, mean.y, mean.z]).map(Point::from),
+            false => Some(Point::new(mean.x, mean.y)),
+        }
+    }
+}
+
+impl Accumulator for CentroidAccumulator {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        Ok(self
+            .means
+            .iter()
+            .flat_map(|mean| [mean.weight, mean.x, mean.y, mean.z])
+            .map(ScalarValue::from)
+            .collect())
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        let mut builder: PointBuilder<2> =
+            PointBuilder::new_with_options(CoordType::Separated, Default::default());
+        builder.push_point(self.centroid().as_ref());
+
+        ScalarValue::try_from_array(&(builder.finish().to_array_ref() as ArrayRef), 0)
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        if values[0].is_empty() {
+            return Ok(());
+        }
+
+        let offset = if self.weighted { 2 } else { 1 };
+        let geometry_type = match values.get(offset) {
+            Some(geometry_type) => ScalarValue::try_from_array(geometry_type, 0)?,
+            None => ScalarValue::Null,
+        };
+        let geometries = geo_geometries(&values[0], &ColumnarValue::Scalar(geometry_type))?;
+
+        let weights = match self.weighted {
+            true => cast(&values[1], &DataType::Float64)?
+                .as_primitive::<Float64Type>()
+                .iter()
+                .collect(),
+            false => vec![Some(1.); geometries.len()],
+        };
+
+        for (geometry, weight) in geometries.iter().zip(weights) {
+            let (Some(geometry), Some(weight)) = (geometry, weight) else {
+                continue;
+            };
+            if weight.is_nan() || weight < 0. {
+                return Err(DataFusionError::Execution(format!(
+                    "ST_Centroid_Agg: weights must not be negative, got {weight}"
+                )));
+            }
+            match self.spherical {
+                true => self.add_spherical(geometry, weight),
+                false => self.add(geometry, weight),
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        // state order: weight, x, y and z of points, lines and polygons
+        let states = states
+            .iter()
+            .map(|state| state.as_primitive::<Float64Type>())
+            .collect::<Vec<_>>();
+        for row in 0..states[0].len() {
+            for (dim, mean) in self.means.iter_mut().enumerate() {
+                let [weight, x, y, z] = [0, 1, 2, 3].map(|i| states[dim * 4 + i].value(row));
+                mean.add(WeightedMean { weight, x, y, z });
+            }
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use datafusion::arrow::array::{BinaryArray, Float64Array};
+    use geo::{line_string, point, polygon};
+
+    use super::*;
+    use crate::wkb;
+
+    fn accumulator() -> CentroidAccumulator {
+        CentroidAccumulator {
+            weighted: false,
+            spherical: false,
+            means: Default::default(),
+        }
+    }
+
+    /// Update a weighted accumulator with WKT geometries and their weights.
+    fn update(centroid: &mut CentroidAccumulator, rows: &[(&str, f64)]) -> Result<()> {
+        let wkb = |text: &str| wkb::from_wkt(&wkt::Wkt::from_str(text).unwrap()).to_wkb();
+        let geoms = BinaryArray::from_iter_values(rows.iter().map(|(text, _)| wkb(text)));
+        let weights = Float64Array::from_iter_values(rows.iter().map(|(_, weight)| *weight));
+        centroid.update_batch(&[Arc::new(geoms), Arc::new(weights)])
+    }
+
+    #[test]
+    fn weights() -> Result<()> {
+        let mut centroid = CentroidAccumulator {
+            weighted: true,
+            ..accumulator()
+        };
+        update(&mut centroid, &[("POINT (0 0)", 0.)])?;
+        assert_eq!(centroid.centroid(), None);
+
+        // rows with zero weight are skipped
+        update(&mut centroid, &[("POINT (2 2)", 1.), ("POINT (8 8)", 0.)])?;
+        assert_eq!(centroid.centroid(), Some(point!(x: 2., y: 2.)));
+
+        let error = update(&mut centroid, &[("POINT (4 4)", -1.)]).unwrap_err();
+        assert!(
+            error.to_string().contains("must not be negative"),
+            "{error}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn spherical_centroid() {
+        let geography = || CentroidAccumulator {
+            spherical: true,
+            ..accumulator()
+        };
+
+        // points on both sides of the antimeridian
+        let mut centroid = geography();
+        centroid.add_spherical(&point!(x: 179., y: 10.).into(), 1.);
+        centroid.add_spherical(&point!(x: -179., y: 10.).into(), 1.);
+        let point = centroid.centroid().unwrap();
+        assert!((point.x().abs() - 180.).abs() < 1e-9, "{point:?}");
+        assert!(point.y() > 10., "{point:?}");
+
+        // a quarter of the equator and a cap around the pole, either way round
+        let mut centroid = geography();
+        centroid.add_spherical(&line_string![(x: 0., y: 0.), (x: 90., y: 0.)].into(), 1.);
+        let point = centroid.centroid().unwrap();
+        assert!((point.x() - 45.).abs() < 1e-9 && point.y().abs() < 1e-9);
+        let cap = polygon![
+            (x: 0., y: 80.),
+            (x: 90., y: 80.),
+            (x: 180., y: 80.),
+            (x: -90., y: 80.),
+            (x: 0., y: 80.),
+        ];
+        let reversed = LineString(cap.exterior().0.iter().rev().copied().collect());
+        for cap in [cap.clone(), geo::Polygon::new(reversed, vec![])] {
+            let mut centroid = geography();
+            centroid.add_spherical(&cap.into(), 1.);
+            assert!((centroid.centroid().unwrap().y() - 90.).abs() < 1e-9);
+        }
+
+        // partial states merge
+        let (mut a, mut b) = (geography(), geography());
+        a.add_spherical(&point!(x: 0., y: 0.).into(), 1.);
+        b.add_spherical(&point!(x: 90., y: 0.).into(), 1.);
+        let states = b
+            .state()
+            .unwrap()
+            .iter()
+            .map(|value| value.to_array())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        a.merge_batch(&states).unwrap();
+        let point = a.centroid().unwrap();
+        assert!((point.x() - 45.).abs() < 1e-9 && point.y().abs() < 1e-9);
+    }
+
+    #[test]
+    fn highest_dimension() {
+        let mut centroid = accumulator();
+        centroid.add(&point!(x: 10., y: 10.).into(), 1.);
+        centroid.add(&line_string![(x: 0., y: 0.), (x: 4., y: 0.)].into(), 1.);
+        assert_eq!(centroid.centroid(), Some(point!(x: 2., y: 0.)));
+
+        centroid.add(
+            &polygon![(x: 0., y: 0.), (x: 2., y: 0.), (x: 2., y: 2.), (x: 0., y: 2.)].into(),
+            3.,
+        );
+        assert_eq!(centroid.centroid(), Some(point!(x: 1., y: 1.)));
+    }
+
+    #[test]
+    fn large_coordinates() {
+        // merging partial means of far away points stays exact
+        let (mut a, mut b) = (accumulator(), accumulator());
+        a.add(&point!(x: 1e15 + 1., y: 1.).into(), 1.);
+        a.add(&point!(x: 1e15 + 3., y: 3.).into(), 1.);
+        b.add(&point!(x: 1e15 + 5., y: 5.).into(), 2.);
+        a.means[0].add(b.means[0]);
+        assert_eq!(a.centroid(), Some(point!(x: 1e15 + 3.5, y: 3.5)));
+    }
+}
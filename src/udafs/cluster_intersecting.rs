@@ -0,0 +1,260 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, AsArray, BinaryArray, ListArray},
+        buffer::OffsetBuffer,
+        datatypes::{DataType, Field, Float64Type},
+    },
+    error::{DataFusionError, Result},
+    logical_expr::{
+        function::{AccumulatorArgs, StateFieldsArgs},
+        utils::format_state_name,
+        Accumulator, AggregateUDFImpl, ColumnarValue, Signature, TypeSignature, Volatility,
+    },
+    scalar::ScalarValue,
+};
+
+use crate::{cluster::components, helpers::wkb_geometries, wkb};
+
+/// `ST_ClusterIntersecting` aggregate function.
+///
+/// Groups the geometries of a group into connected components of
+/// intersecting geometries and returns them as an array of WKB geometry
+/// collections, NULL and empty geometries are ignored.
+#[derive(Debug)]
+pub struct ClusterIntersecting {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl ClusterIntersecting {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_clusterintersecting".to_string()],
+        }
+    }
+}
+
+impl AggregateUDFImpl for ClusterIntersecting {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_ClusterIntersecting"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(clusters_type())
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            format_state_name(args.name, "geometries"),
+            DataType::Binary,
+            true,
+        )])
+    }
+
+    fn accumulator(&self, _acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ClusterAccumulator::new(false)))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// List of WKB geometry collections, one per cluster.
+pub(super) fn clusters_type() -> DataType {
+    DataType::List(Arc::new(Field::new("item", DataType::Binary, true)))
+}
+
+/// Gathers the geometries of a group and clusters them on evaluation, the
+/// connected components depending on all geometries at once. The partial
+/// state is a geometry collection of the gathered geometries, followed by
+/// the distance for `ST_ClusterWithin`.
+#[derive(Debug)]
+pub(super) struct ClusterAccumulator {
+    /// Whether the second argument is a distance.
+    within: bool,
+    distance: Option<f64>,
+    geometries: Vec<wkb::Geometry>,
+}
+
+impl ClusterAccumulator {
+    pub(super) fn new(within: bool) -> Self {
+        Self {
+            within,
+            distance: None,
+            geometries: vec![],
+        }
+    }
+
+    /// Geometry collection of the gathered geometries, their dimensions
+    /// may differ.
+    fn collection(&self) -> ScalarValue {
+        match self.geometries.first() {
+            None => ScalarValue::Binary(None),
+            Some(first) => ScalarValue::Binary(Some(
+                wkb::Geometry::new(
+                    wkb::Kind::GeometryCollection,
+                    first.dim,
+                    vec![],
+                    self.geometries.clone(),
+                )
+                .to_wkb(),
+            )),
+        }
+    }
+
+    fn clusters(&self) -> Result<ScalarValue> {
+        if self.geometries.is_empty() {
+            return ScalarValue::try_from(clusters_type());
+        }
+
+        let geometries = self
+            .geometries
+            .iter()
+            .map(|geometry| Some(geometry.to_geo()))
+            .collect::<Vec<_>>();
+        let numbers = components(&geometries, self.distance.unwrap_or(0.));
+
+        let mut clusters = vec![];
+        for (geometry, number) in self.geometries.iter().zip(numbers) {
+            let Some(number) = number else { continue };
+            if clusters.len() <= number as usize {
+                clusters.push(vec![]);
+            }
+            clusters[number as usize].push(geometry.clone());
+        }
+
+        let collections = BinaryArray::from_iter_values(clusters.into_iter().map(|parts| {
+            let dim = parts[0].dim;
+            wkb::Geometry::new(wkb::Kind::GeometryCollection, dim, vec![], parts).to_wkb()
+        }));
+        let list = ListArray::try_new(
+            Arc::new(Field::new("item", DataType::Binary, true)),
+            OffsetBuffer::from_lengths([collections.len()]),
+            Arc::new(collections),
+            None,
+        )?;
+        Ok(ScalarValue::List(Arc::new(list)))
+    }
+}
+
+impl Accumulator for ClusterAccumulator {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        match self.within {
+            true => Ok(vec![self.collection(), ScalarValue::Float64(self.distance)]),
+            false => Ok(vec![self.collection()]),
+        }
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        self.clusters()
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        if values[0].is_empty() {
+            return Ok(());
+        }
+
+        if self.within && self.distance.is_none() {
+            let distance =
+                ScalarValue::try_from_array(&values[1], 0)?.cast_to(&DataType::Float64)?;
+            self.distance = match distance {
+                ScalarValue::Float64(Some(distance)) if distance >= 0. => Some(distance),
+                distance => {
+                    return Err(DataFusionError::Execution(format!(
+                        "ST_ClusterWithin: expected a non-negative distance, got {distance}"
+                    )))
+                }
+            };
+        }
+
+        let offset = if self.within { 2 } else { 1 };
+        let geometry_type = match values.get(offset) {
+            Some(geometry_type) => ScalarValue::try_from_array(geometry_type, 0)?,
+            None => ScalarValue::Null,
+        };
+        let geometries = wkb_geometries(&values[0], &ColumnarValue::Scalar(geometry_type))?;
+        self.geometries.extend(geometries.into_iter().flatten());
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        for state in states[0].as_binary::<i32>().iter().flatten() {
+            self.geometries.extend(wkb::parse(state)?.parts);
+        }
+        if self.within && self.distance.is_none() {
+            self.distance = states[1]
+                .as_primitive::<Float64Type>()
+                .iter()
+                .flatten()
+                .next();
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.geometries.capacity() * std::mem::size_of::<wkb::Geometry>()
+            + self
+                .geometries
+                .iter()
+                .map(wkb::Geometry::heap_size)
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn wkb(text: &str) -> Vec<u8> {
+        wkb::from_wkt(&wkt::Wkt::from_str(text).unwrap()).to_wkb()
+    }
+
+    #[test]
+    fn merge_states() -> Result<()> {
+        let (mut a, mut b) = (
+            ClusterAccumulator::new(false),
+            ClusterAccumulator::new(false),
+        );
+        let empty = a.size();
+        a.update_batch(&[Arc::new(BinaryArray::from_iter_values([wkb(
+            "LINESTRING (0 0, 1 1)",
+        )]))])?;
+        b.update_batch(&[Arc::new(BinaryArray::from_iter_values([
+            wkb("LINESTRING (1 1, 2 0)"),
+            wkb("POLYGON ((5 5, 6 5, 6 6, 5 5))"),
+        ]))])?;
+        // the coordinates and rings count
+        assert!(b.size() > empty + 2 * std::mem::size_of::<wkb::Geometry>() + 10 * 8);
+
+        let states = b
+            .state()?
+            .iter()
+            .map(|state| state.to_array())
+            .collect::<Result<Vec<_>>>()?;
+        a.merge_batch(&states)?;
+        let ScalarValue::List(clusters) = a.evaluate()? else {
+            panic!("expected a list");
+        };
+        assert_eq!(clusters.value(0).len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::datatypes::{DataType, Field},
+    error::Result,
+    logical_expr::{
+        function::{AccumulatorArgs, StateFieldsArgs},
+        utils::format_state_name,
+        Accumulator, AggregateUDFImpl, Signature, TypeSignature, Volatility,
+    },
+};
+
+use super::cluster_intersecting::{clusters_type, ClusterAccumulator};
+
+/// `ST_ClusterWithin` aggregate function.
+///
+/// Like `ST_ClusterIntersecting`, but geometries within `distance` of each
+/// other are connected, distances being measured between the geometries.
+#[derive(Debug)]
+pub struct ClusterWithin {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl ClusterWithin {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(2), TypeSignature::Any(6)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_clusterwithin".to_string()],
+        }
+    }
+}
+
+impl AggregateUDFImpl for ClusterWithin {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_ClusterWithin"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(clusters_type())
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        // same order as `ClusterAccumulator::state`
+        Ok(vec![
+            Field::new(
+                format_state_name(args.name, "geometries"),
+                DataType::Binary,
+                true,
+            ),
+            Field::new(
+                format_state_name(args.name, "distance"),
+                DataType::Float64,
+                true,
+            ),
+        ])
+    }
+
+    fn accumulator(&self, _acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ClusterAccumulator::new(true)))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
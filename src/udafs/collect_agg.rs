@@ -0,0 +1,300 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{Array, ArrayRef, AsArray, ListArray},
+        buffer::OffsetBuffer,
+        compute::{concat, filter, is_not_null},
+        datatypes::{DataType, Field},
+    },
+    error::Result,
+    logical_expr::{
+        function::{AccumulatorArgs, StateFieldsArgs},
+        utils::format_state_name,
+        Accumulator, AggregateUDFImpl, ColumnarValue, Signature, TypeSignature, Volatility,
+    },
+    scalar::ScalarValue,
+};
+
+use crate::{
+    helpers::{multi_type, wkb_geometries},
+    wkb,
+};
+
+/// `ST_Collect` aggregate function.
+///
+/// Collects the geometries of a group into a multi geometry, or a geometry
+/// collection if their kinds differ, NULL geometries are ignored. Native
+/// points, line strings and polygons are gathered into a native multi
+/// geometry without decoding them, other geometries are collected as WKB.
+/// The partial state has the type of the result.
+///
+/// Shares its name with the scalar [`Collect`](crate::udfs::Collect), which
+/// is only registered under its exact name so that SQL resolves the
+/// (lowercased) `ST_Collect(geom)` to this aggregate. Calls with two
+/// geometries are accepted as well, the analyzer rewrites them to the scalar.
+#[derive(Debug)]
+pub struct CollectAgg {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl CollectAgg {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Any(1),
+                    TypeSignature::Any(2),
+                    TypeSignature::Any(5),
+                ],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_collect".to_string()],
+        }
+    }
+}
+
+impl AggregateUDFImpl for CollectAgg {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_Collect"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        Ok(multi_type(&arg_types[0]).unwrap_or(DataType::Binary))
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            format_state_name(args.name, "collect"),
+            args.return_type.clone(),
+            true,
+        )])
+    }
+
+    fn accumulator(&self, acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        match acc_args.return_type {
+            DataType::List(field) => Ok(Box::new(NativeCollectAccumulator {
+                field: field.clone(),
+                parts: vec![],
+            })),
+            _ => Ok(Box::new(CollectAccumulator::default())),
+        }
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Collects native points, line strings or polygons without decoding them,
+/// the collected parts keep their coordinate (and ring) offsets.
+#[derive(Debug)]
+struct NativeCollectAccumulator {
+    field: Arc<Field>,
+    /// Valid parts of each batch.
+    parts: Vec<ArrayRef>,
+}
+
+impl NativeCollectAccumulator {
+    fn multi(&mut self) -> Result<ScalarValue> {
+        if self.parts.iter().all(|parts| parts.is_empty()) {
+            return ScalarValue::try_from(DataType::List(self.field.clone()));
+        }
+
+        let parts = concat(
+            &self
+                .parts
+                .iter()
+                .map(|p| p.as_ref())
+                .collect::<Vec<&dyn Array>>(),
+        )?;
+        self.parts = vec![parts.clone()];
+
+        let multi = ListArray::try_new(
+            self.field.clone(),
+            OffsetBuffer::from_lengths([parts.len()]),
+            parts,
+            None,
+        )?;
+        Ok(ScalarValue::List(Arc::new(multi)))
+    }
+}
+
+impl Accumulator for NativeCollectAccumulator {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![self.multi()?])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        self.multi()
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let parts = filter(&values[0], &is_not_null(&values[0])?)?;
+        self.parts.push(parts);
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        let multis = states[0].as_list::<i32>();
+        for multi in multis.iter().flatten() {
+            self.parts.push(multi);
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self
+                .parts
+                .iter()
+                .map(|parts| parts.get_array_memory_size())
+                .sum::<usize>()
+    }
+}
+
+/// Collects WKB geometries.
+#[derive(Debug, Default)]
+struct CollectAccumulator {
+    parts: Vec<wkb::Geometry>,
+}
+
+impl CollectAccumulator {
+    fn wkb(&self) -> Result<ScalarValue> {
+        match self.parts.is_empty() {
+            true => Ok(ScalarValue::Binary(None)),
+            false => Ok(ScalarValue::Binary(Some(
+                wkb::collect(self.parts.clone())?.to_wkb(),
+            ))),
+        }
+    }
+}
+
+impl Accumulator for CollectAccumulator {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![self.wkb()?])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        self.wkb()
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        if values[0].is_empty() {
+            return Ok(());
+        }
+
+        let geometry_type = match values.get(1) {
+            Some(geometry_type) => ScalarValue::try_from_array(geometry_type, 0)?,
+            None => ScalarValue::Null,
+        };
+        let geometries = wkb_geometries(&values[0], &ColumnarValue::Scalar(geometry_type))?;
+        self.parts.extend(geometries.into_iter().flatten());
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        // states are collections, their members are the collected geometries
+        for state in states[0].as_binary::<i32>().iter().flatten() {
+            self.parts.extend(wkb::parse(state)?.parts);
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.parts.capacity() * std::mem::size_of::<wkb::Geometry>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use datafusion::arrow::array::BinaryArray;
+    use geoarrow::{
+        array::{CoordType, LineStringBuilder},
+        datatypes::{Dimension, NativeType},
+        ArrayBase,
+    };
+
+    use super::*;
+
+    fn wkb(text: &str) -> Vec<u8> {
+        wkb::from_wkt(&wkt::Wkt::from_str(text).unwrap()).to_wkb()
+    }
+
+    fn line_strings(lines: &[Option<geo::LineString>]) -> ArrayRef {
+        let mut builder: LineStringBuilder<2> =
+            LineStringBuilder::new_with_options(CoordType::Separated, Default::default());
+        for line in lines {
+            builder.push_line_string(line.as_ref()).unwrap();
+        }
+        builder.finish().to_array_ref()
+    }
+
+    #[test]
+    fn native_line_strings() {
+        let line_type = NativeType::LineString(CoordType::Separated, Dimension::XY).to_data_type();
+        let Some(DataType::List(field)) = multi_type(&line_type) else {
+            unreachable!()
+        };
+        assert_eq!(field.name(), "linestrings");
+
+        let line = |coords: [(f64, f64); 2]| Some(geo::LineString::from(coords.to_vec()));
+        let mut a = NativeCollectAccumulator {
+            field: field.clone(),
+            parts: vec![],
+        };
+        a.update_batch(&[line_strings(&[line([(0., 0.), (1., 1.)]), None])])
+            .unwrap();
+
+        // partial state of another partition
+        let mut b = NativeCollectAccumulator {
+            field,
+            parts: vec![],
+        };
+        b.update_batch(&[line_strings(&[None, line([(2., 2.), (3., 3.)])])])
+            .unwrap();
+        let state = b.state().unwrap()[0].to_array().unwrap();
+        a.merge_batch(&[state]).unwrap();
+
+        let ScalarValue::List(multi) = a.evaluate().unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(multi.data_type(), &multi_type(&line_type).unwrap());
+        let lines = multi.value(0);
+        let lines = lines.as_list::<i32>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines.null_count(), 0);
+        // the vertices of both line strings, below their own offsets
+        assert_eq!(lines.value_offsets(), &[0, 2, 4]);
+    }
+
+    #[test]
+    fn mixed_kinds() {
+        let (point, line) = (wkb("POINT (1 2)"), wkb("LINESTRING (0 0, 1 1)"));
+        let geoms = BinaryArray::from(vec![Some(point.as_slice()), None, Some(line.as_slice())]);
+
+        let mut accumulator = CollectAccumulator::default();
+        accumulator.update_batch(&[Arc::new(geoms)]).unwrap();
+        let ScalarValue::Binary(Some(collected)) = accumulator.evaluate().unwrap() else {
+            unreachable!()
+        };
+        let collected = wkb::parse(&collected).unwrap();
+        assert_eq!(collected.type_name(), "GeometryCollection");
+        assert_eq!(collected.parts.len(), 2);
+
+        let mut empty = CollectAccumulator::default();
+        assert_eq!(empty.evaluate().unwrap(), ScalarValue::Binary(None));
+    }
+}
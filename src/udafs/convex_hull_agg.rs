@@ -0,0 +1,215 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, AsArray},
+        datatypes::{DataType, Field},
+    },
+    error::{DataFusionError, Result},
+    logical_expr::{
+        function::{AccumulatorArgs, StateFieldsArgs},
+        utils::format_state_name,
+        Accumulator, AggregateUDFImpl, ColumnarValue, Signature, TypeSignature, Volatility,
+    },
+    scalar::ScalarValue,
+};
+use geo::{algorithm::convex_hull::quick_hull, Coord, CoordsIter, Geometry, LineString, Polygon};
+use geoarrow::{
+    array::{CoordType, WKBArray},
+    datatypes::{Dimension, NativeType},
+    io::wkb::from_wkb,
+    ArrayBase,
+};
+
+use crate::{helpers::geo_geometries, wkb};
+
+/// `ST_ConvexHull_Agg` aggregate function.
+///
+/// Convex hull of all geometries of a group: a polygon, or a line string or
+/// point if all coordinates are collinear or equal. WKB input gives a WKB
+/// hull, native input a native mixed geometry, as the kind of the hull is
+/// only known at the end. Only the hull vertices are kept between batches,
+/// and they are the (WKB) partial state.
+#[derive(Debug)]
+pub struct ConvexHullAgg {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl ConvexHullAgg {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_convexhull_agg".to_string()],
+        }
+    }
+}
+
+impl AggregateUDFImpl for ConvexHullAgg {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_ConvexHull_Agg"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        match &arg_types[0] {
+            DataType::Binary | DataType::LargeBinary => Ok(DataType::Binary),
+            _ => Ok(hull_type().to_data_type()),
+        }
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            format_state_name(args.name, "hull"),
+            DataType::Binary,
+            true,
+        )])
+    }
+
+    fn accumulator(&self, acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ConvexHullAccumulator {
+            native: acc_args.return_type != &DataType::Binary,
+            hull: vec![],
+        }))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Native type of the hulls of native geometries.
+fn hull_type() -> NativeType {
+    NativeType::Mixed(CoordType::Separated, Dimension::XY)
+}
+
+#[derive(Debug, Default)]
+struct ConvexHullAccumulator {
+    /// Whether the hull is evaluated to a native geometry rather than WKB.
+    native: bool,
+    /// Vertices of the hull so far, counter-clockwise and not closed.
+    hull: Vec<Coord>,
+}
+
+impl ConvexHullAccumulator {
+    /// Replace the hull by the hull of its vertices and `coords`.
+    fn extend(&mut self, coords: impl Iterator<Item = Coord>) {
+        let mut points = std::mem::take(&mut self.hull);
+        points.extend(coords.filter(|c| !c.x.is_nan() && !c.y.is_nan()));
+        if points.is_empty() {
+            return;
+        }
+
+        // closed ring, a single point is repeated
+        let mut hull = quick_hull(&mut points).0;
+        hull.pop();
+        hull.dedup();
+        self.hull = hull;
+    }
+
+    fn wkb(&self) -> ScalarValue {
+        let hull = match self.hull.len() {
+            0 => return ScalarValue::Binary(None),
+            1 => Geometry::Point(self.hull[0].into()),
+            2 => Geometry::LineString(LineString::from(self.hull.clone())),
+            _ => Geometry::Polygon(Polygon::new(LineString::from(self.hull.clone()), vec![])),
+        };
+        ScalarValue::Binary(Some(wkb::from_geo(&hull).to_wkb()))
+    }
+}
+
+impl Accumulator for ConvexHullAccumulator {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![self.wkb()])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        let hull = self.wkb();
+        match (self.native, self.hull.is_empty()) {
+            (false, _) => return Ok(hull),
+            (true, true) => return ScalarValue::try_from(hull_type().to_data_type()),
+            (true, false) => {}
+        }
+
+        let wkb = WKBArray::<i32>::try_from(hull.to_array()?.as_ref())
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let hull = from_wkb(&wkb, hull_type(), false)
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        ScalarValue::try_from_array(&hull.to_array_ref(), 0)
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        if values[0].is_empty() {
+            return Ok(());
+        }
+
+        let geometry_type = match values.get(1) {
+            Some(geometry_type) => ScalarValue::try_from_array(geometry_type, 0)?,
+            None => ScalarValue::Null,
+        };
+        let geometries = geo_geometries(&values[0], &ColumnarValue::Scalar(geometry_type))?;
+        self.extend(geometries.iter().flatten().flat_map(|g| g.coords_iter()));
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        for state in states[0].as_binary::<i32>().iter().flatten() {
+            self.extend(wkb::parse(state)?.to_geo().coords_iter());
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.hull.capacity() * std::mem::size_of::<Coord>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::coord;
+
+    use super::*;
+
+    #[test]
+    fn incremental_hull() {
+        let mut accumulator = ConvexHullAccumulator::default();
+        accumulator.extend([coord! { x: 1., y: 1. }].into_iter());
+        assert_eq!(accumulator.hull.len(), 1);
+
+        accumulator.extend([coord! { x: 3., y: 3. }, coord! { x: 2., y: 2. }].into_iter());
+        assert_eq!(accumulator.hull.len(), 2);
+
+        let square = [(0., 0.), (4., 0.), (4., 4.), (0., 4.), (2., 1.)];
+        accumulator.extend(square.iter().map(|&(x, y)| coord! { x: x, y: y }));
+        assert_eq!(accumulator.hull.len(), 4);
+        assert!(!accumulator.hull.contains(&coord! { x: 3., y: 3. }));
+    }
+
+    #[test]
+    fn native_hull() {
+        let mut accumulator = ConvexHullAccumulator {
+            native: true,
+            hull: vec![],
+        };
+        let empty = accumulator.evaluate().unwrap();
+        assert!(empty.is_null());
+        assert_eq!(empty.data_type(), hull_type().to_data_type());
+
+        let square = [(0., 0.), (4., 0.), (4., 4.), (0., 4.)];
+        accumulator.extend(square.iter().map(|&(x, y)| coord! { x: x, y: y }));
+        let hull = accumulator.evaluate().unwrap();
+        assert!(!hull.is_null());
+        assert_eq!(hull.data_type(), hull_type().to_data_type());
+    }
+}
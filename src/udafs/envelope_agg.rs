@@ -0,0 +1,183 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::{
+        array::ArrayRef,
+        datatypes::{DataType, Field},
+    },
+    error::{DataFusionError, Result},
+    logical_expr::{
+        function::{AccumulatorArgs, StateFieldsArgs},
+        Accumulator, AggregateUDFImpl, Signature, TypeSignature, Volatility,
+    },
+    scalar::ScalarValue,
+};
+use geo::polygon;
+use geoarrow::{
+    array::{CoordType, PolygonBuilder},
+    datatypes::{Dimension, NativeType},
+    ArrayBase,
+};
+
+use super::extent::{extent_state_fields, ExtentAccumulator};
+
+/// `ST_Envelope_Agg` aggregate function.
+///
+/// Like `ST_Extent`, but returns the extent as a native polygon like
+/// `ST_Envelope` does, so it can be passed to other spatial functions. The
+/// partial state is the extent's bounds.
+#[derive(Debug)]
+pub struct EnvelopeAgg {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl EnvelopeAgg {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_envelope_agg".to_string()],
+        }
+    }
+}
+
+impl AggregateUDFImpl for EnvelopeAgg {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_Envelope_Agg"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(NativeType::Polygon(CoordType::Separated, Dimension::XY).to_data_type())
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        Ok(extent_state_fields(args.name))
+    }
+
+    fn accumulator(&self, acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(EnvelopeAccumulator(ExtentAccumulator::from_args(
+            &acc_args,
+        ))))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Extent accumulator evaluating to a polygon.
+#[derive(Debug)]
+struct EnvelopeAccumulator(ExtentAccumulator);
+
+impl Accumulator for EnvelopeAccumulator {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        self.0.state()
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        // extents of geographies crossing the antimeridian have xmin > xmax,
+        // like envelopes of `ST_Envelope`
+        let envelope = self.0.bounds().map(|(xmin, ymin, xmax, ymax)| {
+            polygon![
+                (x: xmin, y: ymin),
+                (x: xmax, y: ymin),
+                (x: xmax, y: ymax),
+                (x: xmin, y: ymax),
+                (x: xmin, y: ymin),
+            ]
+        });
+
+        let mut builder: PolygonBuilder<2> =
+            PolygonBuilder::new_with_options(CoordType::Separated, Default::default());
+        builder
+            .push_polygon(envelope.as_ref())
+            .map_err(|e| DataFusionError::Internal(e.to_string()))?;
+
+        ScalarValue::try_from_array(&(builder.finish().to_array_ref() as ArrayRef), 0)
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        self.0.update_batch(values)
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        self.0.merge_batch(states)
+    }
+
+    fn size(&self) -> usize {
+        self.0.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use datafusion::{
+        arrow::array::{Array, AsArray, BinaryArray},
+        logical_expr::ColumnarValue,
+    };
+    use geo::CoordsIter;
+
+    use super::*;
+    use crate::{helpers::geo_geometries, wkb};
+
+    fn update(accumulator: &mut EnvelopeAccumulator, wkt: &[Option<&str>]) {
+        let wkb = |text: &str| wkb::from_wkt(&wkt::Wkt::from_str(text).unwrap()).to_wkb();
+        let geoms = wkt.iter().map(|text| text.map(wkb)).collect::<Vec<_>>();
+        let geoms = BinaryArray::from_iter(geoms.iter().map(Option::as_deref));
+        let mut values = vec![Arc::new(geoms) as ArrayRef];
+        values.extend((0..4).map(|_| ScalarValue::Null.to_array_of_size(wkt.len()).unwrap()));
+        accumulator.update_batch(&values).unwrap();
+    }
+
+    /// Corners of the evaluated envelope polygon.
+    fn corners(accumulator: &mut EnvelopeAccumulator) -> Option<Vec<(f64, f64)>> {
+        let envelope = accumulator.evaluate().unwrap().to_array().unwrap();
+        let geometry_type = ColumnarValue::Scalar(ScalarValue::from("Polygon"));
+        let polygon = geo_geometries(&envelope, &geometry_type)
+            .unwrap()
+            .remove(0)?;
+        Some(polygon.coords_iter().map(|c| (c.x, c.y)).collect())
+    }
+
+    #[test]
+    fn envelope_and_merge() {
+        let mut a = EnvelopeAccumulator(ExtentAccumulator::new(false));
+        assert_eq!(corners(&mut a), None);
+
+        update(&mut a, &[Some("POINT (1 2)"), None]);
+        let mut b = EnvelopeAccumulator(ExtentAccumulator::new(false));
+        update(&mut b, &[Some("LINESTRING (-1 0, 3 5)")]);
+        let states = b
+            .state()
+            .unwrap()
+            .iter()
+            .map(|state| state.to_array().unwrap())
+            .collect::<Vec<_>>();
+        a.merge_batch(&states).unwrap();
+
+        let envelope = a.evaluate().unwrap();
+        assert_eq!(
+            envelope.data_type(),
+            NativeType::Polygon(CoordType::Separated, Dimension::XY).to_data_type()
+        );
+        assert_eq!(
+            corners(&mut a).unwrap(),
+            [(-1., 0.), (3., 0.), (3., 5.), (-1., 5.), (-1., 0.)]
+        );
+        assert!(envelope.to_array().unwrap().as_list::<i32>().is_valid(0));
+    }
+}
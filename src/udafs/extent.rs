@@ -1,29 +1,38 @@
 use core::f64;
-use std::{any::Any, str::FromStr};
+use std::any::Any;
 
 use datafusion::{
     arrow::{
-        array::{ArrayRef, AsArray},
-        compute::min,
+        array::{
+            Array, ArrayRef, AsArray, Float64Array, GenericBinaryArray, OffsetSizeTrait,
+            StructArray,
+        },
+        compute::{max, min},
         datatypes::{DataType, Field, Fields, Float64Type},
     },
     common::scalar::ScalarStructBuilder,
     error::{DataFusionError, Result},
     logical_expr::{
-        function::AccumulatorArgs, Accumulator, AggregateUDFImpl, ColumnarValue, Signature,
-        TypeSignature, Volatility,
+        function::{AccumulatorArgs, StateFieldsArgs},
+        utils::format_state_name,
+        Accumulator, AggregateUDFImpl, ColumnarValue, Signature, TypeSignature, Volatility,
     },
+    physical_expr::expressions::Literal,
     scalar::ScalarValue,
 };
 use geoarrow::{
-    array::{AsNativeArray, NativeArrayDyn, WKBArray},
+    array::AsNativeArray,
     datatypes::{Dimension, NativeType},
-    error::GeoArrowError,
-    io::parquet::metadata::GeoParquetGeometryType,
+    trait_::ArrayAccessor,
     NativeArray,
 };
 
-use crate::{compute::min_max_2d, helpers::native_type};
+use crate::{
+    compute::{is_compact, min_max_2d, update_min_max_2d},
+    helpers::{geo_geometries, native_array},
+    spherical::{lon_bounds, spherical_bounds},
+    wkb,
+};
 
 #[derive(Debug)]
 pub struct Extent {
@@ -35,7 +44,7 @@ impl Extent {
     pub fn new() -> Self {
         Self {
             signature: Signature::one_of(
-                vec![TypeSignature::Any(1), TypeSignature::Any(3)],
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
                 Volatility::Immutable,
             ),
             aliases: vec!["st_extent".to_string()],
@@ -57,16 +66,15 @@ impl AggregateUDFImpl for Extent {
     }
 
     fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
-        Ok(DataType::Struct(Fields::from(vec![
-            Field::new("xmin", DataType::Float64, false),
-            Field::new("ymin", DataType::Float64, false),
-            Field::new("xmax", DataType::Float64, false),
-            Field::new("ymax", DataType::Float64, false),
-        ])))
+        Ok(DataType::Struct(extent_fields()))
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        Ok(extent_state_fields(args.name))
     }
 
-    fn accumulator(&self, _acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
-        Ok(Box::new(ExtentAccumulator::new()))
+    fn accumulator(&self, acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ExtentAccumulator::from_args(&acc_args)))
     }
 
     fn aliases(&self) -> &[String] {
@@ -74,22 +82,71 @@ impl AggregateUDFImpl for Extent {
     }
 }
 
+/// State fields of an [`ExtentAccumulator`].
+pub(super) fn extent_state_fields(name: &str) -> Vec<Field> {
+    // same order as `ExtentAccumulator::state`
+    ["xmin", "xmax", "ymin", "ymax"]
+        .into_iter()
+        .map(|field| Field::new(format_state_name(name, field), DataType::Float64, false))
+        .collect()
+}
+
 #[derive(Debug)]
-struct ExtentAccumulator {
+pub(super) struct ExtentAccumulator {
     xmin: f64,
     ymin: f64,
     xmax: f64,
     ymax: f64,
+    /// Geographies, whose extent may cross the antimeridian (`xmin > xmax`).
+    spherical: bool,
 }
 
 impl ExtentAccumulator {
-    fn new() -> Self {
+    pub(super) fn from_args(acc_args: &AccumulatorArgs) -> Self {
+        // the edges argument appended by the analyzer comes last
+        let spherical = acc_args
+            .exprs
+            .last()
+            .and_then(|expr| expr.as_any().downcast_ref::<Literal>())
+            .is_some_and(|edges| {
+                matches!(edges.value(), ScalarValue::Utf8(Some(edges)) if edges == "spherical")
+            });
+
+        Self::new(spherical)
+    }
+
+    pub(super) fn new(spherical: bool) -> Self {
         Self {
             xmin: f64::MAX,
             ymin: f64::MAX,
             xmax: f64::MIN,
             ymax: f64::MIN,
+            spherical,
+        }
+    }
+
+    /// Bounds as `(xmin, ymin, xmax, ymax)`, `None` if there were no (non
+    /// empty) geometries.
+    pub(super) fn bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        match self.ymin > self.ymax {
+            true => None,
+            false => Some((self.xmin, self.ymin, self.xmax, self.ymax)),
+        }
+    }
+
+    /// Extend the extent of geographies by the given longitude intervals and
+    /// latitude range.
+    fn update_spherical(&mut self, mut lons: Vec<(f64, f64)>, ymin: f64, ymax: f64) {
+        if self.xmin != f64::MAX {
+            lons.push((self.xmin, self.xmax));
+        }
+        if lons.is_empty() {
+            return;
         }
+
+        (self.xmin, self.xmax) = lon_bounds(&lons);
+        self.ymin = self.ymin.min(ymin);
+        self.ymax = self.ymax.max(ymax);
     }
 }
 
@@ -104,122 +161,138 @@ impl Accumulator for ExtentAccumulator {
     }
 
     fn evaluate(&mut self) -> Result<ScalarValue> {
-        ScalarStructBuilder::new()
-            .with_scalar(
-                Field::new("xmin", DataType::Float64, false),
-                ScalarValue::Float64(Some(self.xmin)),
-            )
-            .with_scalar(
-                Field::new("ymin", DataType::Float64, false),
-                ScalarValue::Float64(Some(self.ymin)),
-            )
-            .with_scalar(
-                Field::new("xmax", DataType::Float64, false),
-                ScalarValue::Float64(Some(self.xmax)),
-            )
-            .with_scalar(
-                Field::new("ymax", DataType::Float64, false),
-                ScalarValue::Float64(Some(self.ymax)),
-            )
+        // no (non empty) geometries
+        if self.bounds().is_none() {
+            return ScalarValue::try_from(DataType::Struct(extent_fields()));
+        }
+
+        let values = [self.xmin, self.ymin, self.xmax, self.ymax];
+        extent_fields()
+            .iter()
+            .zip(values)
+            .fold(ScalarStructBuilder::new(), |builder, (field, v)| {
+                builder.with_scalar(field.clone(), ScalarValue::Float64(Some(v)))
+            })
             .build()
     }
 
     fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
-        assert_eq!(values.len(), 3);
+        assert_eq!(values.len(), 5);
 
-        match &values[0].data_type() {
-            DataType::Binary => {
-                let _wkb: WKBArray<i32> = WKBArray::try_from(values[0].as_ref())
-                    .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
-
-                todo!()
-            }
-            DataType::LargeBinary => {
-                let _wkb: WKBArray<i64> = WKBArray::try_from(values[0].as_ref())
-                    .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
+        if values[0].is_empty() {
+            return Ok(());
+        }
 
-                todo!()
+        if self.spherical {
+            let geometry_type = ColumnarValue::Scalar(ScalarValue::try_from_array(&values[1], 0)?);
+            let (mut ymin, mut ymax) = (f64::MAX, f64::MIN);
+            let mut lons = vec![];
+            for geom in geo_geometries(&values[0], &geometry_type)?.iter().flatten() {
+                if let Some((west, south, east, north)) = spherical_bounds(geom) {
+                    lons.push((west, east));
+                    ymin = ymin.min(south);
+                    ymax = ymax.max(north);
+                }
             }
-            _ => {
-                let geomtype =
-                    GeoParquetGeometryType::from_str(values[1].as_string::<i32>().value(0))
-                        .map_err(|e| DataFusionError::Internal(e.to_string()))?;
+            self.update_spherical(lons, ymin, ymax);
 
-                let native_type = native_type(&ColumnarValue::Array(values[0].clone()), geomtype);
+            return Ok(());
+        }
 
-                let geoms = NativeArrayDyn::from_arrow_array(
-                    &values[0],
-                    &native_type.to_field("geometry", true),
-                )
-                .unwrap();
+        let ((xmin, ymin), (xmax, ymax)) = match &values[0].data_type() {
+            DataType::Binary => wkb_bounds(values[0].as_binary::<i32>())?,
+            DataType::LargeBinary => wkb_bounds(values[0].as_binary::<i64>())?,
+            _ => {
+                let geometry_type =
+                    ColumnarValue::Scalar(ScalarValue::try_from_array(&values[1], 0)?);
+                let geoms = native_array(&values[0], &geometry_type)?;
 
                 use Dimension::*;
 
-                let ((xmin, ymin), (xmax, ymax)) = match geoms.data_type() {
-                    NativeType::Point(_, XY) => {
-                        min_max_2d(geoms.as_ref().as_point::<2>().coords(), true)
-                    }
-                    NativeType::Point(_, XYZ) => {
-                        min_max_2d(geoms.as_ref().as_point::<3>().coords(), true)
-                    }
-                    NativeType::LineString(_, XY) => {
-                        min_max_2d(geoms.as_ref().as_line_string::<2>().coords(), false)
-                    }
-                    NativeType::LineString(_, XYZ) => {
-                        min_max_2d(geoms.as_ref().as_line_string::<3>().coords(), false)
-                    }
-                    NativeType::Polygon(_, XY) => {
-                        min_max_2d(geoms.as_ref().as_polygon::<2>().coords(), false)
-                    }
-                    NativeType::Polygon(_, XYZ) => {
-                        min_max_2d(geoms.as_ref().as_polygon::<3>().coords(), false)
-                    }
-                    NativeType::MultiPoint(_, XY) => {
-                        min_max_2d(geoms.as_ref().as_multi_point::<2>().coords(), false)
+                // coordinate buffers of arrays with nulls or sliced lists
+                // contain coordinates of rows outside the batch
+                if values[0].null_count() > 0 || !is_compact(values[0].as_ref()) {
+                    geometry_bounds(geoms.as_ref())
+                } else {
+                    match geoms.data_type() {
+                        NativeType::Point(_, XY) => {
+                            min_max_2d(geoms.as_ref().as_point::<2>().coords(), true)
+                        }
+                        NativeType::Point(_, XYZ) => {
+                            min_max_2d(geoms.as_ref().as_point::<3>().coords(), true)
+                        }
+                        NativeType::LineString(_, XY) => {
+                            min_max_2d(geoms.as_ref().as_line_string::<2>().coords(), false)
+                        }
+                        NativeType::LineString(_, XYZ) => {
+                            min_max_2d(geoms.as_ref().as_line_string::<3>().coords(), false)
+                        }
+                        NativeType::Polygon(_, XY) => {
+                            min_max_2d(geoms.as_ref().as_polygon::<2>().coords(), false)
+                        }
+                        NativeType::Polygon(_, XYZ) => {
+                            min_max_2d(geoms.as_ref().as_polygon::<3>().coords(), false)
+                        }
+                        NativeType::MultiPoint(_, XY) => {
+                            min_max_2d(geoms.as_ref().as_multi_point::<2>().coords(), false)
+                        }
+                        NativeType::MultiPoint(_, XYZ) => {
+                            min_max_2d(geoms.as_ref().as_multi_point::<3>().coords(), false)
+                        }
+                        NativeType::MultiLineString(_, XY) => {
+                            min_max_2d(geoms.as_ref().as_multi_line_string::<2>().coords(), false)
+                        }
+                        NativeType::MultiLineString(_, XYZ) => {
+                            min_max_2d(geoms.as_ref().as_multi_line_string::<3>().coords(), false)
+                        }
+                        NativeType::MultiPolygon(_, XY) => {
+                            min_max_2d(geoms.as_ref().as_multi_polygon::<2>().coords(), false)
+                        }
+                        NativeType::MultiPolygon(_, XYZ) => {
+                            min_max_2d(geoms.as_ref().as_multi_polygon::<3>().coords(), false)
+                        }
+                        NativeType::Rect(_) => rect_bounds(values[0].as_struct()),
+                        NativeType::Mixed(_, _) | NativeType::GeometryCollection(_, _) => {
+                            geometry_bounds(geoms.as_ref())
+                        }
                     }
-                    NativeType::MultiPoint(_, XYZ) => {
-                        min_max_2d(geoms.as_ref().as_multi_point::<3>().coords(), false)
-                    }
-                    NativeType::MultiLineString(_, XY) => {
-                        min_max_2d(geoms.as_ref().as_multi_line_string::<2>().coords(), false)
-                    }
-                    NativeType::MultiLineString(_, XYZ) => {
-                        min_max_2d(geoms.as_ref().as_multi_line_string::<3>().coords(), false)
-                    }
-                    NativeType::MultiPolygon(_, XY) => {
-                        min_max_2d(geoms.as_ref().as_multi_polygon::<2>().coords(), false)
-                    }
-                    NativeType::MultiPolygon(_, XYZ) => {
-                        min_max_2d(geoms.as_ref().as_multi_polygon::<3>().coords(), false)
-                    }
-                    NativeType::Mixed(_, _) => unimplemented!(),
-                    NativeType::GeometryCollection(_, _) => unimplemented!(),
-                    NativeType::Rect(_) => unimplemented!(),
-                };
-
-                self.xmin = self.xmin.min(xmin);
-                self.ymin = self.ymin.min(ymin);
-                self.xmax = self.xmax.max(xmax);
-                self.ymax = self.ymax.max(ymax);
+                }
             }
-        }
+        };
+
+        self.xmin = self.xmin.min(xmin);
+        self.ymin = self.ymin.min(ymin);
+        self.xmax = self.xmax.max(xmax);
+        self.ymax = self.ymax.max(ymax);
 
         Ok(())
     }
 
     fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
-        self.xmin = self
-            .xmin
-            .min(min(states[0].as_primitive::<Float64Type>()).unwrap());
-        self.ymin = self
-            .ymin
-            .min(min(states[1].as_primitive::<Float64Type>()).unwrap());
-        self.xmax = self
-            .xmax
-            .min(min(states[2].as_primitive::<Float64Type>()).unwrap());
-        self.ymax = self
-            .ymax
-            .min(min(states[3].as_primitive::<Float64Type>()).unwrap());
+        // state order: xmin, xmax, ymin, ymax
+        let [xmin, xmax, ymin, ymax] =
+            [0, 1, 2, 3].map(|i| states[i].as_primitive::<Float64Type>());
+
+        if self.spherical {
+            let lons = xmin
+                .values()
+                .iter()
+                .zip(xmax.values().iter())
+                .filter(|(xmin, _)| **xmin != f64::MAX)
+                .map(|(xmin, xmax)| (*xmin, *xmax))
+                .collect();
+            self.update_spherical(
+                lons,
+                min(ymin).unwrap_or(f64::MAX),
+                max(ymax).unwrap_or(f64::MIN),
+            );
+            return Ok(());
+        }
+
+        self.xmin = self.xmin.min(min(xmin).unwrap_or(f64::MAX));
+        self.xmax = self.xmax.max(max(xmax).unwrap_or(f64::MIN));
+        self.ymin = self.ymin.min(min(ymin).unwrap_or(f64::MAX));
+        self.ymax = self.ymax.max(max(ymax).unwrap_or(f64::MIN));
         Ok(())
     }
 
@@ -227,3 +300,159 @@ impl Accumulator for ExtentAccumulator {
         std::mem::size_of_val(self)
     }
 }
+
+/// Fields of the extent struct, nullable like the struct itself which is
+/// NULL without (non empty) geometries.
+fn extent_fields() -> Fields {
+    ["xmin", "ymin", "xmax", "ymax"]
+        .into_iter()
+        .map(|name| Field::new(name, DataType::Float64, true))
+        .collect()
+}
+
+/// Bounds of all geometries of a native array, visiting the coordinates of
+/// each (non null) geometry. NaN (empty point) coordinates are skipped.
+fn geometry_bounds(geoms: &dyn NativeArray) -> ((f64, f64), (f64, f64)) {
+    let mut bounds = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+
+    macro_rules! update {
+        ($array:expr) => {
+            for geometry in $array.iter().flatten() {
+                update_min_max_2d(&geometry, &mut bounds);
+            }
+        };
+    }
+
+    use Dimension::*;
+
+    match geoms.data_type() {
+        NativeType::Point(_, XY) => update!(geoms.as_point::<2>()),
+        NativeType::Point(_, XYZ) => update!(geoms.as_point::<3>()),
+        NativeType::LineString(_, XY) => update!(geoms.as_line_string::<2>()),
+        NativeType::LineString(_, XYZ) => update!(geoms.as_line_string::<3>()),
+        NativeType::Polygon(_, XY) => update!(geoms.as_polygon::<2>()),
+        NativeType::Polygon(_, XYZ) => update!(geoms.as_polygon::<3>()),
+        NativeType::MultiPoint(_, XY) => update!(geoms.as_multi_point::<2>()),
+        NativeType::MultiPoint(_, XYZ) => update!(geoms.as_multi_point::<3>()),
+        NativeType::MultiLineString(_, XY) => update!(geoms.as_multi_line_string::<2>()),
+        NativeType::MultiLineString(_, XYZ) => update!(geoms.as_multi_line_string::<3>()),
+        NativeType::MultiPolygon(_, XY) => update!(geoms.as_multi_polygon::<2>()),
+        NativeType::MultiPolygon(_, XYZ) => update!(geoms.as_multi_polygon::<3>()),
+        NativeType::Mixed(_, XY) => update!(geoms.as_mixed::<2>()),
+        NativeType::Mixed(_, XYZ) => update!(geoms.as_mixed::<3>()),
+        NativeType::GeometryCollection(_, XY) => update!(geoms.as_geometry_collection::<2>()),
+        NativeType::GeometryCollection(_, XYZ) => update!(geoms.as_geometry_collection::<3>()),
+        NativeType::Rect(XY) => update!(geoms.as_rect::<2>()),
+        NativeType::Rect(XYZ) => update!(geoms.as_rect::<3>()),
+    }
+
+    bounds
+}
+
+/// Bounds of a native rect array from its `xmin`, `ymin`, `xmax` and `ymax`
+/// columns, the null rows are skipped by the aggregate kernels.
+fn rect_bounds(rects: &StructArray) -> ((f64, f64), (f64, f64)) {
+    let dims = rects.num_columns() / 2;
+    let column = |i: usize| rects.column(i).as_primitive::<Float64Type>();
+
+    // the validity of the struct applies to its columns
+    let bound = |i: usize, aggregate: fn(&Float64Array) -> Option<f64>, default: f64| {
+        let values = Float64Array::new(column(i).values().clone(), rects.nulls().cloned());
+        aggregate(&values).unwrap_or(default)
+    };
+
+    (
+        (bound(0, min, f64::MAX), bound(1, min, f64::MAX)),
+        (bound(dims, max, f64::MIN), bound(dims + 1, max, f64::MIN)),
+    )
+}
+
+/// Bounds of all geometries of a WKB array, NaN (empty point) coordinates are skipped.
+fn wkb_bounds<O: OffsetSizeTrait>(wkb: &GenericBinaryArray<O>) -> Result<((f64, f64), (f64, f64))> {
+    let mut bounds = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+    for geom in wkb.iter().flatten() {
+        if let Some(((xmin, ymin), (xmax, ymax))) = wkb::parse(geom)?.bounds() {
+            bounds.0 .0 = bounds.0 .0.min(xmin);
+            bounds.0 .1 = bounds.0 .1.min(ymin);
+            bounds.1 .0 = bounds.1 .0.max(xmax);
+            bounds.1 .1 = bounds.1 .1.max(ymax);
+        }
+    }
+    Ok(bounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::{array::BinaryArray, buffer::NullBuffer};
+    use geoarrow::{
+        array::{CoordType, WKBArray},
+        io::wkb::from_wkb,
+    };
+
+    use super::*;
+
+    /// Little endian WKB of a 2D geometry of the given WKB type.
+    fn wkb(geometry_type: u32, coords: &[(f64, f64)]) -> Vec<u8> {
+        let mut wkb = vec![1];
+        wkb.extend_from_slice(&geometry_type.to_le_bytes());
+        if geometry_type != 1 {
+            wkb.extend_from_slice(&(coords.len() as u32).to_le_bytes());
+        }
+        for (x, y) in coords {
+            wkb.extend_from_slice(&x.to_le_bytes());
+            wkb.extend_from_slice(&y.to_le_bytes());
+        }
+        wkb
+    }
+
+    #[test]
+    fn mixed_bounds() {
+        let point = wkb(1, &[(1., 2.)]);
+        let line = wkb(2, &[(-3., 5.), (4., -1.)]);
+        let array = BinaryArray::from(vec![Some(point.as_slice()), None, Some(line.as_slice())]);
+        let wkb = WKBArray::<i32>::try_from(&array as &dyn Array).unwrap();
+        let mixed = from_wkb(
+            &wkb,
+            NativeType::Mixed(CoordType::Interleaved, Dimension::XY),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(geometry_bounds(mixed.as_ref()), ((-3., -1.), (4., 5.)));
+    }
+
+    #[test]
+    fn rect_bounds_skip_nulls() {
+        let DataType::Struct(fields) = NativeType::Rect(Dimension::XY).to_data_type() else {
+            unreachable!()
+        };
+        let columns = [[0., -9.], [1., -9.], [2., 9.], [3., 9.]]
+            .map(|values| Arc::new(Float64Array::from(values.to_vec())) as ArrayRef);
+        let rects = StructArray::try_new(
+            fields,
+            columns.to_vec(),
+            Some(NullBuffer::from(vec![true, false])),
+        )
+        .unwrap();
+
+        assert_eq!(rect_bounds(&rects), ((0., 1.), (2., 3.)));
+    }
+
+    #[test]
+    fn evaluate_return_type() {
+        let mut accumulator = ExtentAccumulator::new(false);
+        let return_type = DataType::Struct(extent_fields());
+
+        let empty = accumulator.evaluate().unwrap();
+        assert!(empty.is_null());
+        assert_eq!(empty.data_type(), return_type);
+
+        (accumulator.xmin, accumulator.ymin) = (1., 2.);
+        (accumulator.xmax, accumulator.ymax) = (3., 4.);
+        let extent = accumulator.evaluate().unwrap();
+        assert!(!extent.is_null());
+        assert_eq!(extent.data_type(), return_type);
+    }
+}
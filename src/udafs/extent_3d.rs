@@ -0,0 +1,294 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, AsArray},
+        compute::{max, min},
+        datatypes::{DataType, Field, Float64Type},
+    },
+    common::scalar::ScalarStructBuilder,
+    error::Result,
+    logical_expr::{
+        function::{AccumulatorArgs, StateFieldsArgs},
+        utils::format_state_name,
+        Accumulator, AggregateUDFImpl, ColumnarValue, Signature, TypeSignature, Volatility,
+    },
+    scalar::ScalarValue,
+};
+use geoarrow::{
+    array::AsNativeArray,
+    datatypes::{Dimension, NativeType},
+    NativeArray,
+};
+
+use crate::{
+    compute::{is_compact, min_max},
+    helpers::{box3d_fields, native_array, wkb_geometries},
+};
+
+/// `ST_3DExtent` aggregate function.
+///
+/// 3D bounding box (Box3D) of all geometries, see
+/// [`Envelope3D`](crate::udfs::Envelope3D). Z bounds are 0 for geometries
+/// without Z, NULL if there are no (non empty) geometries.
+#[derive(Debug)]
+pub struct Extent3D {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl Extent3D {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_3dextent".to_string()],
+        }
+    }
+}
+
+impl AggregateUDFImpl for Extent3D {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_3DExtent"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Struct(box3d_fields()))
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        Ok(box3d_fields()
+            .iter()
+            .map(|field| {
+                Field::new(
+                    format_state_name(args.name, field.name()),
+                    DataType::Float64,
+                    false,
+                )
+            })
+            .collect())
+    }
+
+    fn accumulator(&self, _acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(Extent3DAccumulator::new()))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[derive(Debug)]
+struct Extent3DAccumulator {
+    mins: [f64; 3],
+    maxs: [f64; 3],
+}
+
+impl Extent3DAccumulator {
+    fn new() -> Self {
+        Self {
+            mins: [f64::MAX; 3],
+            maxs: [f64::MIN; 3],
+        }
+    }
+
+    fn update(&mut self, (mins, maxs): ([f64; 3], [f64; 3])) {
+        for (acc, v) in self.mins.iter_mut().zip(mins) {
+            *acc = acc.min(v);
+        }
+        for (acc, v) in self.maxs.iter_mut().zip(maxs) {
+            *acc = acc.max(v);
+        }
+    }
+}
+
+impl Accumulator for Extent3DAccumulator {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        Ok(self
+            .mins
+            .iter()
+            .chain(self.maxs.iter())
+            .map(|v| ScalarValue::from(*v))
+            .collect())
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        let fields = box3d_fields();
+
+        // no (non empty) geometries
+        if self.mins[0] > self.maxs[0] {
+            return ScalarValue::try_from(DataType::Struct(fields));
+        }
+
+        let values = self.mins.iter().chain(self.maxs.iter());
+        fields
+            .iter()
+            .zip(values)
+            .fold(ScalarStructBuilder::new(), |builder, (field, v)| {
+                builder.with_scalar(field.clone(), ScalarValue::Float64(Some(*v)))
+            })
+            .build()
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        assert_eq!(values.len(), 5);
+
+        if values[0].is_empty() {
+            return Ok(());
+        }
+
+        let geometry_type = ColumnarValue::Scalar(ScalarValue::try_from_array(&values[1], 0)?);
+
+        // coordinate buffers are only used for plain native arrays, see `ST_Extent`
+        let native = !matches!(
+            values[0].data_type(),
+            DataType::Binary | DataType::LargeBinary
+        ) && values[0].null_count() == 0
+            && is_compact(values[0].as_ref());
+        if native {
+            let geoms = native_array(&values[0], &geometry_type)?;
+            let geoms = geoms.as_ref();
+
+            use Dimension::*;
+
+            let bounds = match geoms.data_type() {
+                NativeType::Point(_, XY) => {
+                    Some(pad(min_max(geoms.as_point::<2>().coords(), true)))
+                }
+                NativeType::Point(_, XYZ) => Some(min_max(geoms.as_point::<3>().coords(), true)),
+                NativeType::LineString(_, XY) => {
+                    Some(pad(min_max(geoms.as_line_string::<2>().coords(), false)))
+                }
+                NativeType::LineString(_, XYZ) => {
+                    Some(min_max(geoms.as_line_string::<3>().coords(), false))
+                }
+                NativeType::Polygon(_, XY) => {
+                    Some(pad(min_max(geoms.as_polygon::<2>().coords(), false)))
+                }
+                NativeType::Polygon(_, XYZ) => {
+                    Some(min_max(geoms.as_polygon::<3>().coords(), false))
+                }
+                NativeType::MultiPoint(_, XY) => {
+                    Some(pad(min_max(geoms.as_multi_point::<2>().coords(), false)))
+                }
+                NativeType::MultiPoint(_, XYZ) => {
+                    Some(min_max(geoms.as_multi_point::<3>().coords(), false))
+                }
+                NativeType::MultiLineString(_, XY) => Some(pad(min_max(
+                    geoms.as_multi_line_string::<2>().coords(),
+                    false,
+                ))),
+                NativeType::MultiLineString(_, XYZ) => {
+                    Some(min_max(geoms.as_multi_line_string::<3>().coords(), false))
+                }
+                NativeType::MultiPolygon(_, XY) => {
+                    Some(pad(min_max(geoms.as_multi_polygon::<2>().coords(), false)))
+                }
+                NativeType::MultiPolygon(_, XYZ) => {
+                    Some(min_max(geoms.as_multi_polygon::<3>().coords(), false))
+                }
+                NativeType::Mixed(_, _)
+                | NativeType::GeometryCollection(_, _)
+                | NativeType::Rect(_) => None,
+            };
+
+            if let Some(bounds) = bounds {
+                self.update(bounds);
+                return Ok(());
+            }
+        }
+
+        for geom in wkb_geometries(&values[0], &geometry_type)?.iter().flatten() {
+            if let Some(bounds) = geom.bounds_3d() {
+                self.update(bounds);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        // state order: xmin, ymin, zmin, xmax, ymax, zmax
+        let states = states
+            .iter()
+            .map(|state| state.as_primitive::<Float64Type>())
+            .collect::<Vec<_>>();
+        self.update((
+            [0, 1, 2].map(|i| min(states[i]).unwrap_or(f64::MAX)),
+            [3, 4, 5].map(|i| max(states[i]).unwrap_or(f64::MIN)),
+        ));
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+/// 3D bounds of 2D coordinates, with Z 0 unless empty.
+fn pad((mins, maxs): ([f64; 2], [f64; 2])) -> ([f64; 3], [f64; 3]) {
+    match mins[0] <= maxs[0] {
+        true => ([mins[0], mins[1], 0.], [maxs[0], maxs[1], 0.]),
+        false => ([f64::MAX; 3], [f64::MIN; 3]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use datafusion::arrow::array::BinaryArray;
+
+    use super::*;
+    use crate::wkb;
+
+    fn wkb(text: &str) -> Vec<u8> {
+        wkb::from_wkt(&wkt::Wkt::from_str(text).unwrap()).to_wkb()
+    }
+
+    fn update(accumulator: &mut Extent3DAccumulator, wkt: &[Option<&str>]) {
+        let geoms = wkt.iter().map(|text| text.map(wkb)).collect::<Vec<_>>();
+        let geoms = BinaryArray::from_iter(geoms.iter().map(Option::as_deref));
+        let mut values = vec![Arc::new(geoms) as ArrayRef];
+        values.extend((0..4).map(|_| ScalarValue::Null.to_array_of_size(wkt.len()).unwrap()));
+        accumulator.update_batch(&values).unwrap();
+    }
+
+    #[test]
+    fn extent_and_merge() {
+        let mut a = Extent3DAccumulator::new();
+        assert!(a.evaluate().unwrap().is_null());
+
+        update(&mut a, &[Some("POINT Z (1 2 3)"), None]);
+        update(&mut a, &[Some("LINESTRING Z (0 4 -1, 2 3 5)")]);
+        assert_eq!(a.mins, [0., 2., -1.]);
+        assert_eq!(a.maxs, [2., 4., 5.]);
+
+        // partial states of another partition, with a 2D geometry
+        let mut b = Extent3DAccumulator::new();
+        update(&mut b, &[Some("POINT (-3 9)")]);
+        let states = b
+            .state()
+            .unwrap()
+            .iter()
+            .map(|state| state.to_array().unwrap())
+            .collect::<Vec<_>>();
+        a.merge_batch(&states).unwrap();
+        assert_eq!(a.mins, [-3., 2., -1.]);
+        assert_eq!(a.maxs, [2., 9., 5.]);
+
+        let extent = a.evaluate().unwrap();
+        assert_eq!(extent.data_type(), DataType::Struct(box3d_fields()));
+    }
+}
@@ -0,0 +1,310 @@
+use std::{any::Any, cmp::Ordering, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{new_empty_array, Array, ArrayRef, AsArray, ListArray, StructArray},
+        buffer::OffsetBuffer,
+        compute::{interleave, SortOptions},
+        datatypes::{DataType, Field, Fields},
+    },
+    common::utils::compare_rows,
+    error::Result,
+    logical_expr::{
+        function::{AccumulatorArgs, StateFieldsArgs},
+        utils::{format_state_name, AggregateOrderSensitivity},
+        Accumulator, AggregateUDFImpl, ColumnarValue, Signature, TypeSignature, Volatility,
+    },
+    scalar::ScalarValue,
+};
+
+use crate::{
+    helpers::{is_point_type, line_string_type, wkb_array},
+    wkb,
+};
+
+/// `ST_MakeLine` aggregate function.
+///
+/// Line string through the points of a group in the order of the aggregate's
+/// `ORDER BY`, e.g. `ST_MakeLine(point ORDER BY ts)`, NULL points are
+/// skipped. Native points give a native line string, other geometries
+/// (points, multi points and line strings) a WKB line string.
+///
+/// Shares its name with the scalar [`MakeLine`](crate::udfs::MakeLine),
+/// which is only registered under its exact name so that SQL resolves the
+/// (lowercased) `ST_MakeLine(point ORDER BY ts)` to this aggregate. Calls
+/// with two geometries or an array of points are accepted as well, the
+/// analyzer rewrites them to the scalar.
+///
+/// The partial state holds the points with their ordering values, so partial
+/// aggregates of several partitions merge into a single ordered line.
+#[derive(Debug)]
+pub struct MakeLineAgg {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl MakeLineAgg {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Any(1),
+                    TypeSignature::Any(2),
+                    TypeSignature::Any(5),
+                ],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_makeline".to_string()],
+        }
+    }
+}
+
+/// Type of the collected vertices, native points or WKB.
+fn vertex_type(geometry_type: &DataType) -> DataType {
+    match is_point_type(geometry_type) {
+        true => geometry_type.clone(),
+        false => DataType::Binary,
+    }
+}
+
+impl AggregateUDFImpl for MakeLineAgg {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_MakeLine"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        match is_point_type(&arg_types[0]) {
+            true => Ok(line_string_type(&arg_types[0])),
+            false => Ok(DataType::Binary),
+        }
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        let vertex = Field::new("item", vertex_type(&args.input_types[0]), true);
+        let mut fields = vec![Field::new_list(
+            format_state_name(args.name, "vertices"),
+            vertex,
+            true,
+        )];
+        if !args.ordering_fields.is_empty() {
+            let orderings = Fields::from(args.ordering_fields.to_vec());
+            fields.push(Field::new_list(
+                format_state_name(args.name, "orderings"),
+                Field::new("item", DataType::Struct(orderings), true),
+                true,
+            ));
+        }
+        Ok(fields)
+    }
+
+    fn accumulator(&self, acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        let geometry_type = acc_args.exprs[0].data_type(acc_args.schema)?;
+        Ok(Box::new(MakeLineAccumulator {
+            vertex_type: vertex_type(&geometry_type),
+            native: is_point_type(&geometry_type),
+            args: acc_args.exprs.len(),
+            sort_options: acc_args.ordering_req.iter().map(|e| e.options).collect(),
+            ordering_fields: acc_args
+                .ordering_req
+                .iter()
+                .map(|e| {
+                    let data_type = e.expr.data_type(acc_args.schema)?;
+                    Ok(Field::new(e.expr.to_string(), data_type, true))
+                })
+                .collect::<Result<_>>()?,
+            arrays: vec![],
+            vertices: vec![],
+        }))
+    }
+
+    fn order_sensitivity(&self) -> AggregateOrderSensitivity {
+        AggregateOrderSensitivity::HardRequirement
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[derive(Debug)]
+struct MakeLineAccumulator {
+    vertex_type: DataType,
+    /// Whether the vertices are native points rather than WKB.
+    native: bool,
+    /// Number of arguments, the ordering values follow them.
+    args: usize,
+    sort_options: Vec<SortOptions>,
+    ordering_fields: Fields,
+    /// Vertex arrays of the batches and states seen.
+    arrays: Vec<ArrayRef>,
+    /// Array and row index of each vertex with its ordering values.
+    vertices: Vec<((usize, usize), Vec<ScalarValue>)>,
+}
+
+impl MakeLineAccumulator {
+    fn push(&mut self, array: ArrayRef, orderings: Option<&StructArray>) -> Result<()> {
+        for row in (0..array.len()).filter(|&row| array.is_valid(row)) {
+            let ordering = match orderings {
+                Some(orderings) => orderings
+                    .columns()
+                    .iter()
+                    .map(|column| ScalarValue::try_from_array(column, row))
+                    .collect::<Result<_>>()?,
+                None => vec![],
+            };
+            self.vertices.push(((self.arrays.len(), row), ordering));
+        }
+        self.arrays.push(array);
+        Ok(())
+    }
+
+    /// Sort the vertices by their ordering values and gather them into a
+    /// single array.
+    fn sort(&mut self) -> Result<ArrayRef> {
+        if !self.sort_options.is_empty() {
+            self.vertices.sort_by(|(_, a), (_, b)| {
+                compare_rows(a, b, &self.sort_options).unwrap_or(Ordering::Equal)
+            });
+        }
+
+        let indices = self
+            .vertices
+            .iter()
+            .map(|(index, _)| *index)
+            .collect::<Vec<_>>();
+        let arrays = self.arrays.iter().map(|a| a.as_ref()).collect::<Vec<_>>();
+        let vertices = match arrays.is_empty() {
+            true => new_empty_array(&self.vertex_type),
+            false => interleave(&arrays, &indices)?,
+        };
+
+        self.arrays = vec![vertices.clone()];
+        for (i, ((array, row), _)) in self.vertices.iter_mut().enumerate() {
+            (*array, *row) = (0, i);
+        }
+        Ok(vertices)
+    }
+
+    fn orderings(&self) -> Result<ArrayRef> {
+        let columns = (0..self.ordering_fields.len())
+            .map(|i| {
+                let values = self
+                    .vertices
+                    .iter()
+                    .map(|(_, ordering)| ordering[i].clone());
+                match self.vertices.is_empty() {
+                    true => Ok(new_empty_array(self.ordering_fields[i].data_type())),
+                    false => ScalarValue::iter_to_array(values),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(StructArray::try_new(
+            self.ordering_fields.clone(),
+            columns,
+            None,
+        )?))
+    }
+}
+
+/// Single row list of `values`.
+fn list(values: ArrayRef, name: &str) -> Result<ScalarValue> {
+    let field = Arc::new(Field::new(name, values.data_type().clone(), true));
+    let list = ListArray::try_new(
+        field,
+        OffsetBuffer::from_lengths([values.len()]),
+        values,
+        None,
+    )?;
+    Ok(ScalarValue::List(Arc::new(list)))
+}
+
+impl Accumulator for MakeLineAccumulator {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        let mut state = vec![list(self.sort()?, "item")?];
+        if !self.sort_options.is_empty() {
+            state.push(list(self.orderings()?, "item")?);
+        }
+        Ok(state)
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        let vertices = self.sort()?;
+        match (vertices.is_empty(), self.native) {
+            (true, true) => ScalarValue::try_from(line_string_type(&self.vertex_type)),
+            (true, false) => Ok(ScalarValue::Binary(None)),
+            (false, true) => list(vertices, "vertices"),
+            (false, false) => {
+                let geometries = vertices
+                    .as_binary::<i32>()
+                    .iter()
+                    .flatten()
+                    .map(wkb::parse)
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(ScalarValue::Binary(Some(
+                    wkb::make_line(&geometries)?.to_wkb(),
+                )))
+            }
+        }
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let vertices = match self.native {
+            true => values[0].clone(),
+            false => {
+                let geometry_type = match (self.args, values.get(1)) {
+                    (5, Some(geometry_type)) if !geometry_type.is_empty() => {
+                        ScalarValue::try_from_array(geometry_type, 0)?
+                    }
+                    _ => ScalarValue::Null,
+                };
+                wkb_array(&values[0], &ColumnarValue::Scalar(geometry_type))?
+            }
+        };
+
+        let orderings = match self.sort_options.is_empty() {
+            true => None,
+            false => Some(StructArray::try_new(
+                self.ordering_fields.clone(),
+                values[self.args..].to_vec(),
+                None,
+            )?),
+        };
+        self.push(vertices, orderings.as_ref())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        let vertices = states[0].as_list::<i32>();
+        let orderings = states.get(1).map(|orderings| orderings.as_list::<i32>());
+        for row in (0..vertices.len()).filter(|&row| vertices.is_valid(row)) {
+            let ordering = orderings.map(|orderings| orderings.value(row));
+            self.push(
+                vertices.value(row),
+                ordering.as_ref().map(|ordering| ordering.as_struct()),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self
+                .arrays
+                .iter()
+                .map(|array| array.get_array_memory_size())
+                .sum::<usize>()
+            + self
+                .vertices
+                .iter()
+                .map(|(_, ordering)| ScalarValue::size_of_vec(ordering))
+                .sum::<usize>()
+    }
+}
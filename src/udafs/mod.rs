@@ -1,3 +1,21 @@
+mod centroid_agg;
+mod cluster_intersecting;
+mod cluster_within;
+mod collect_agg;
+mod convex_hull_agg;
+mod envelope_agg;
 mod extent;
+mod extent_3d;
+mod make_line_agg;
+mod union_agg;
 
+pub use centroid_agg::CentroidAgg;
+pub use cluster_intersecting::ClusterIntersecting;
+pub use cluster_within::ClusterWithin;
+pub use collect_agg::CollectAgg;
+pub use convex_hull_agg::ConvexHullAgg;
+pub use envelope_agg::EnvelopeAgg;
 pub use extent::Extent;
+pub use extent_3d::Extent3D;
+pub use make_line_agg::MakeLineAgg;
+pub use union_agg::UnionAgg;
@@ -0,0 +1,201 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, AsArray},
+        datatypes::{DataType, Field},
+    },
+    error::{DataFusionError, Result},
+    logical_expr::{
+        function::{AccumulatorArgs, StateFieldsArgs},
+        utils::format_state_name,
+        Accumulator, AggregateUDFImpl, ColumnarValue, Signature, TypeSignature, Volatility,
+    },
+    scalar::ScalarValue,
+};
+use geo::{BooleanOps, Geometry, HasDimensions, MultiPolygon};
+
+use crate::{helpers::geo_geometries, wkb};
+
+/// `ST_Union_Agg` aggregate function.
+///
+/// Dissolves the polygons of a group into a single WKB (multi) polygon with a
+/// cascaded union. The partial state is the union so far as WKB, so partial
+/// aggregates of several partitions merge like any other geometry.
+#[derive(Debug)]
+pub struct UnionAgg {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl UnionAgg {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_union_agg".to_string(), "st_union".to_string()],
+        }
+    }
+}
+
+impl AggregateUDFImpl for UnionAgg {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_Union_Agg"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Binary)
+    }
+
+    fn state_fields(&self, args: StateFieldsArgs) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            format_state_name(args.name, "union"),
+            DataType::Binary,
+            true,
+        )])
+    }
+
+    fn accumulator(&self, _acc_args: AccumulatorArgs) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(UnionAccumulator::default()))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[derive(Debug, Default)]
+struct UnionAccumulator {
+    /// Partial unions indexed by level, the union at level `i` merges `2^i`
+    /// inputs. Pushing a polygon carries like incrementing a binary counter,
+    /// so every union involves geometries of similar size.
+    levels: Vec<Option<MultiPolygon>>,
+    /// Whether there were any non null geometries, the union of those is
+    /// empty rather than NULL.
+    seen: bool,
+}
+
+impl UnionAccumulator {
+    fn push(&mut self, geometry: &Geometry) -> Result<()> {
+        self.seen = true;
+        let mut polygons = vec![];
+        self::polygons(geometry, &mut polygons)?;
+        for polygon in polygons {
+            self.carry(polygon);
+        }
+        Ok(())
+    }
+
+    /// Add a partial union at level 0, merging it with the occupied levels
+    /// until a free one is found.
+    fn carry(&mut self, mut union: MultiPolygon) {
+        for level in self.levels.iter_mut() {
+            match level.take() {
+                Some(partial) => union = partial.union(&union),
+                None => {
+                    *level = Some(union);
+                    return;
+                }
+            }
+        }
+        self.levels.push(Some(union));
+    }
+
+    /// Merge all levels, smallest first, into the top level.
+    fn union(&mut self) -> MultiPolygon {
+        let union = std::mem::take(&mut self.levels)
+            .into_iter()
+            .flatten()
+            .reduce(|union, partial| partial.union(&union))
+            .unwrap_or_else(|| MultiPolygon::new(vec![]));
+        self.levels.push(Some(union.clone()));
+        union
+    }
+
+    fn wkb(&mut self) -> ScalarValue {
+        match self.seen {
+            true => ScalarValue::Binary(Some(
+                wkb::from_geo(&Geometry::MultiPolygon(self.union())).to_wkb(),
+            )),
+            false => ScalarValue::Binary(None),
+        }
+    }
+}
+
+impl Accumulator for UnionAccumulator {
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![self.wkb()])
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        Ok(self.wkb())
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        assert_eq!(values.len(), 5);
+
+        if values[0].is_empty() {
+            return Ok(());
+        }
+
+        let geometry_type = ColumnarValue::Scalar(ScalarValue::try_from_array(&values[1], 0)?);
+        for geometry in geo_geometries(&values[0], &geometry_type)?.iter().flatten() {
+            self.push(geometry)?;
+        }
+
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        for state in states[0].as_binary::<i32>().iter().flatten() {
+            self.push(&wkb::parse(state)?.to_geo())?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        let coords = self
+            .levels
+            .iter()
+            .flatten()
+            .flat_map(|mp| mp.iter())
+            .map(|p| p.exterior().0.len() + p.interiors().iter().map(|r| r.0.len()).sum::<usize>())
+            .sum::<usize>();
+        std::mem::size_of_val(self) + coords * std::mem::size_of::<geo::Coord>()
+    }
+}
+
+/// Collect the polygons of a polygonal geometry, empty geometries of other
+/// types are skipped.
+fn polygons(geometry: &Geometry, polygons: &mut Vec<MultiPolygon>) -> Result<()> {
+    match geometry {
+        Geometry::Polygon(p) => polygons.push(MultiPolygon::new(vec![p.clone()])),
+        Geometry::MultiPolygon(mp) => polygons.push(mp.clone()),
+        Geometry::Rect(r) => polygons.push(MultiPolygon::new(vec![r.to_polygon()])),
+        Geometry::Triangle(t) => polygons.push(MultiPolygon::new(vec![t.to_polygon()])),
+        Geometry::GeometryCollection(gc) => {
+            for geometry in gc.iter() {
+                self::polygons(geometry, polygons)?;
+            }
+        }
+        Geometry::Point(p) if p.x().is_nan() => {}
+        geometry if geometry.is_empty() => {}
+        _ => {
+            return Err(DataFusionError::NotImplemented(
+                "ST_Union_Agg of non-polygonal geometries".to_string(),
+            ))
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, Float64Array},
+        datatypes::DataType,
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+use geo::{Area as _, ChamberlainDuquetteArea};
+
+use crate::helpers::{geometry_args, is_spherical};
+
+/// `ST_Area` user defined function (UDF) implementation.
+///
+/// Area of polygonal geometries in squared CRS units, for geographies the area
+/// on the sphere in square meters.
+#[derive(Debug, Clone)]
+pub struct Area {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl Area {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_area".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for Area {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_Area"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(DataType::Float64)
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert_eq!(args.len(), 5);
+
+        let spherical = is_spherical(&args[4]);
+        let [geoms] = <[_; 1]>::try_from(geometry_args(args, 1)?).unwrap();
+
+        let areas = geoms
+            .iter()
+            .map(|geom| {
+                geom.as_ref().map(|geom| match spherical {
+                    true => geom.chamberlain_duquette_unsigned_area(),
+                    false => geom.unsigned_area(),
+                })
+            })
+            .collect::<Float64Array>();
+
+        Ok(ColumnarValue::from(Arc::new(areas) as ArrayRef))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
@@ -1,20 +1,16 @@
 use std::any::Any;
 
 use datafusion::{
-    arrow::{array::ArrayRef, datatypes::DataType},
+    arrow::{
+        array::{ArrayRef, AsArray, LargeStringArray, StringArray},
+        datatypes::DataType,
+    },
     error::DataFusionError,
     logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
 };
-use geoarrow::{
-    array::{NativeArrayDyn, SerializedArray, WKBArray},
-    error::GeoArrowError,
-    ArrayBase, NativeArray,
-};
+use geoarrow::{array::SerializedArray, ArrayBase, NativeArray};
 
-use crate::{
-    helpers::{geom_type, native_type},
-    wkt::array::ToWKT,
-};
+use crate::{helpers::native_array, wkb, wkt::array::ToWKT};
 
 /// `ST_AsText` user defined function (UDF) implementation.
 #[derive(Debug, Clone)]
@@ -28,7 +24,7 @@ impl AsText {
     pub fn new() -> Self {
         Self {
             signature: Signature::one_of(
-                vec![TypeSignature::Any(1), TypeSignature::Any(3)],
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
                 Volatility::Immutable,
             ),
             aliases: vec!["st_astext".to_string()],
@@ -62,7 +58,7 @@ impl ScalarUDFImpl for AsText {
             DataType::List(_) => Ok(DataType::Utf8),             // geometries \ point
             DataType::FixedSizeList(_, _) => Ok(DataType::Utf8), // coords (interleaved)
             DataType::Struct(_) => Ok(DataType::Utf8),           // coords (separated)
-            // DataType::Union(union_fields, union_mode) => todo!(),
+            DataType::Union(_, _) => Ok(DataType::Utf8),         // mixed geometries
             dt => Err(DataFusionError::Internal(format!(
                 "Unsupported data type: `{dt}`"
             ))),
@@ -73,46 +69,36 @@ impl ScalarUDFImpl for AsText {
     fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
         // DataFusion has arranged for the correct inputs to be passed to this
         // function, but we check again to make sure
-        assert_eq!(args.len(), 3);
+        assert_eq!(args.len(), 5);
 
         let geoms = match &args[0] {
             ColumnarValue::Array(array) => array,
             ColumnarValue::Scalar(scalar) => &scalar.to_array()?,
         };
-        let geomtype = geom_type(&args[1])?;
 
         match geoms.data_type() {
+            // WKB is decoded by the crate's own reader to keep measures
             DataType::Binary => {
-                let geoms: WKBArray<i32> = WKBArray::try_from(geoms.as_ref())
-                    .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
-
                 let wkt = geoms
-                    .as_ref()
-                    .to_wkt::<i32>()
-                    .map_err(|e| DataFusionError::Internal(e.to_string()))?;
+                    .as_binary::<i32>()
+                    .iter()
+                    .map(|wkb| wkb.map(|wkb| Ok(wkb::parse(wkb)?.to_wkt())).transpose())
+                    .collect::<Result<StringArray, DataFusionError>>()?;
 
-                Ok(ColumnarValue::from(wkt.to_array_ref() as ArrayRef))
+                Ok(ColumnarValue::from(std::sync::Arc::new(wkt) as ArrayRef))
             }
 
             DataType::LargeBinary => {
-                let geoms: WKBArray<i64> = WKBArray::try_from(geoms.as_ref())
-                    .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
-
                 let wkt = geoms
-                    .as_ref()
-                    .to_wkt::<i64>()
-                    .map_err(|e| DataFusionError::Internal(e.to_string()))?;
+                    .as_binary::<i64>()
+                    .iter()
+                    .map(|wkb| wkb.map(|wkb| Ok(wkb::parse(wkb)?.to_wkt())).transpose())
+                    .collect::<Result<LargeStringArray, DataFusionError>>()?;
 
-                Ok(ColumnarValue::from(wkt.to_array_ref() as ArrayRef))
+                Ok(ColumnarValue::from(std::sync::Arc::new(wkt) as ArrayRef))
             }
             _ => {
-                let native_type = native_type(&args[0], geomtype);
-
-                let geoms = NativeArrayDyn::from_arrow_array(
-                    &geoms,
-                    &native_type.to_field("geometry", true),
-                )
-                .unwrap();
+                let geoms = native_array(geoms, &args[1])?;
 
                 let wkt = geoms
                     .as_ref()
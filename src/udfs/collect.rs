@@ -0,0 +1,144 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{Array, ArrayRef, BinaryArray, ListArray},
+        buffer::{NullBuffer, OffsetBuffer},
+        compute::interleave,
+        datatypes::{DataType, Field},
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+
+use crate::{
+    helpers::{multi_type, wkb_geometry_args},
+    wkb,
+};
+
+/// `ST_Collect` user defined function (UDF) implementation.
+///
+/// Collects two geometries into a multi geometry, or a geometry collection if
+/// their kinds differ, NULL geometries are ignored. Native points, line
+/// strings and polygons of the same type are gathered into a native multi
+/// geometry without decoding them, other geometries are collected as WKB.
+///
+/// The aggregate [`CollectAgg`](crate::udafs::CollectAgg) shares its name.
+/// This function is only registered under its exact name, so SQL resolves
+/// `ST_Collect(a, b)` to the aggregate, and the
+/// [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule) rewrites
+/// aggregate calls with two geometries to this function.
+#[derive(Debug, Clone)]
+pub struct Collect {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl Collect {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Any(2),
+                    TypeSignature::Any(6),
+                    TypeSignature::Any(10),
+                ],
+                Volatility::Immutable,
+            ),
+            aliases: vec![],
+        }
+    }
+}
+
+impl ScalarUDFImpl for Collect {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_Collect"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        match multi_type(&arg_types[0]) {
+            Some(multi_type) if arg_types[0] == arg_types[1] => Ok(multi_type),
+            _ => Ok(DataType::Binary),
+        }
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert!(args.len() == 6 || args.len() == 10);
+
+        let arrays = ColumnarValue::values_to_arrays(&args[..2])?;
+        if let Some(DataType::List(field)) = multi_type(arrays[0].data_type()) {
+            if arrays[0].data_type() == arrays[1].data_type() {
+                return Ok(ColumnarValue::from(collect_native(
+                    field, &arrays[0], &arrays[1],
+                )?));
+            }
+        }
+
+        let [a, b] = <[_; 2]>::try_from(wkb_geometry_args(args, 2)?).unwrap();
+        let collected = a
+            .into_iter()
+            .zip(b)
+            .map(|(a, b)| {
+                let parts = a.into_iter().chain(b).collect::<Vec<_>>();
+                match parts.is_empty() {
+                    true => Ok(None),
+                    false => Ok(Some(wkb::collect(parts)?.to_wkb())),
+                }
+            })
+            .collect::<Result<BinaryArray, DataFusionError>>()?;
+
+        Ok(ColumnarValue::from(Arc::new(collected) as ArrayRef))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Multi geometries of the valid geometries of `a` and `b` row by row,
+/// gathered from the native arrays without decoding them: the parts keep
+/// their coordinate (and ring) offsets below the new list of parts.
+fn collect_native(
+    field: Arc<Field>,
+    a: &ArrayRef,
+    b: &ArrayRef,
+) -> Result<ArrayRef, DataFusionError> {
+    let mut indices = Vec::with_capacity(a.len() * 2);
+    let mut lengths = Vec::with_capacity(a.len());
+    for row in 0..a.len() {
+        let start = indices.len();
+        indices.extend(
+            [(0, row), (1, row)]
+                .into_iter()
+                .filter(|&(array, row)| [a, b][array].is_valid(row)),
+        );
+        lengths.push(indices.len() - start);
+    }
+
+    let parts = interleave(&[a.as_ref(), b.as_ref()], &indices)?;
+    let nulls = NullBuffer::from_iter(lengths.iter().map(|&length| length > 0));
+    let multis = ListArray::try_new(
+        field,
+        OffsetBuffer::from_lengths(lengths),
+        parts,
+        Some(nulls),
+    )?;
+
+    Ok(Arc::new(multis))
+}
@@ -0,0 +1,93 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, Float64Array},
+        datatypes::DataType,
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+use geo::{Distance as _, Euclidean};
+
+use crate::{
+    helpers::{geometry_args, is_spherical},
+    spherical,
+};
+
+/// `ST_Distance` user defined function (UDF) implementation.
+///
+/// Planar distance in CRS units, for geographies the great-circle distance in
+/// meters.
+#[derive(Debug, Clone)]
+pub struct Distance {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl Distance {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Any(2),
+                    TypeSignature::Any(6),
+                    TypeSignature::Any(10),
+                ],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_distance".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for Distance {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_Distance"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(DataType::Float64)
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert!(args.len() == 6 || args.len() == 10);
+
+        let spherical = is_spherical(&args[5]);
+        let [a, b] = <[_; 2]>::try_from(geometry_args(args, 2)?).unwrap();
+
+        let distances = a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| match (a, b) {
+                (Some(a), Some(b)) if spherical => {
+                    Some(spherical::geometry_distance(a, b).unwrap_or(f64::NAN))
+                }
+                (Some(a), Some(b)) => Some(Euclidean::distance(a, b)),
+                _ => None,
+            })
+            .collect::<Float64Array>();
+
+        Ok(ColumnarValue::from(Arc::new(distances) as ArrayRef))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
@@ -0,0 +1,292 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{Array, ArrayRef, AsArray, BinaryBuilder, ListArray, StructArray},
+        buffer::{NullBuffer, OffsetBuffer},
+        datatypes::{DataType, Field, Fields, Int32Type},
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+    scalar::ScalarValue,
+};
+
+use crate::{
+    helpers::{is_point_type, wkb_array},
+    wkb,
+};
+
+/// `ST_Dump` user defined function (UDF) implementation.
+///
+/// Parts of each geometry as an array of `path`/`geom` structs to `unnest`,
+/// the path holding the (1 based) indices of the part in its multi geometry
+/// or nested collections, empty for single geometries. Native multi
+/// geometries are dumped without copying their parts, other geometries as
+/// WKB parts copied from their WKB bytes.
+#[derive(Debug, Clone)]
+pub struct Dump {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl Dump {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_dump".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for Dump {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_Dump"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(dump_type(
+            native_part_type(&arg_types[0]).unwrap_or(DataType::Binary),
+        ))
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        let geoms = ColumnarValue::values_to_arrays(&args[..1])?.remove(0);
+        let dump = match native_part_type(geoms.data_type()) {
+            Some(_) => native_parts(&geoms)?,
+            None => dump_wkb(&geoms, args, |bytes, span, path, parts| {
+                parts_of(bytes, span, path, parts);
+                Ok(())
+            })?,
+        };
+
+        Ok(ColumnarValue::from(dump))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Type of the dumped parts of native geometries, `None` for WKB and mixed
+/// geometries.
+fn native_part_type(data_type: &DataType) -> Option<DataType> {
+    match data_type {
+        data_type if is_point_type(data_type) => Some(data_type.clone()),
+        DataType::List(field) => match field.name().as_str() {
+            "vertices" | "rings" => Some(data_type.clone()),
+            "points" | "linestrings" | "polygons" => Some(field.data_type().clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parts of native geometries, the children of multi geometries or the
+/// geometries themselves.
+fn native_parts(geoms: &ArrayRef) -> Result<ArrayRef, DataFusionError> {
+    match geoms.data_type() {
+        DataType::List(field)
+            if matches!(field.name().as_str(), "points" | "linestrings" | "polygons") =>
+        {
+            let multi = geoms.as_list::<i32>();
+            let mut paths = vec![vec![]; multi.values().len()];
+            for range in multi.offsets().windows(2) {
+                for (k, part) in (range[0]..range[1]).enumerate() {
+                    paths[part as usize] = vec![k as i32 + 1];
+                }
+            }
+            dump_array(
+                multi.offsets().clone(),
+                multi.nulls().cloned(),
+                paths,
+                multi.values().clone(),
+            )
+        }
+        _ => dump_array(
+            OffsetBuffer::from_lengths(vec![1; geoms.len()]),
+            geoms.nulls().cloned(),
+            vec![vec![]; geoms.len()],
+            geoms.clone(),
+        ),
+    }
+}
+
+/// Parts of a WKB geometry, recursing into multi geometries and collections.
+fn parts_of(bytes: &[u8], span: &wkb::Span, path: &mut Vec<i32>, parts: &mut WkbParts) {
+    match span.kind {
+        wkb::Kind::Point | wkb::Kind::LineString | wkb::Kind::Polygon => {
+            parts.push(path, &bytes[span.bytes.clone()])
+        }
+        _ => {
+            for (k, part) in span.parts.iter().enumerate() {
+                path.push(k as i32 + 1);
+                parts_of(bytes, part, path, parts);
+                path.pop();
+            }
+        }
+    }
+}
+
+/// Array of `path`/`geom` structs of the dumped geometries.
+pub(super) fn dump_type(geom_type: DataType) -> DataType {
+    DataType::List(Arc::new(Field::new(
+        "item",
+        DataType::Struct(dump_fields(geom_type)),
+        true,
+    )))
+}
+
+fn dump_fields(geom_type: DataType) -> Fields {
+    Fields::from(vec![
+        Field::new(
+            "path",
+            DataType::List(Arc::new(Field::new("item", DataType::Int32, true))),
+            true,
+        ),
+        Field::new("geom", geom_type, true),
+    ])
+}
+
+/// Dump array of rows with the given offsets into the paths and geometries.
+pub(super) fn dump_array(
+    offsets: OffsetBuffer<i32>,
+    nulls: Option<NullBuffer>,
+    paths: Vec<Vec<i32>>,
+    geoms: ArrayRef,
+) -> Result<ArrayRef, DataFusionError> {
+    let paths = ListArray::from_iter_primitive::<Int32Type, _, _>(
+        paths
+            .into_iter()
+            .map(|path| Some(path.into_iter().map(Some))),
+    );
+    let fields = dump_fields(geoms.data_type().clone());
+    let dump = StructArray::try_new(fields.clone(), vec![Arc::new(paths), geoms], None)?;
+
+    let dump = ListArray::try_new(
+        Arc::new(Field::new("item", DataType::Struct(fields), true)),
+        offsets,
+        Arc::new(dump),
+        nulls,
+    )?;
+    Ok(Arc::new(dump))
+}
+
+/// WKB parts dumped from geometries with their paths.
+#[derive(Default)]
+pub(super) struct WkbParts {
+    paths: Vec<Vec<i32>>,
+    geoms: BinaryBuilder,
+    /// Scratch buffer of [`WkbParts::push_with`].
+    buffer: Vec<u8>,
+}
+
+impl WkbParts {
+    /// Part copied from the bytes of a geometry.
+    pub(super) fn push(&mut self, path: &[i32], wkb: &[u8]) {
+        self.paths.push(path.to_vec());
+        self.geoms.append_value(wkb);
+    }
+
+    /// Part written by `write`, e.g. a new header followed by bytes copied
+    /// from a geometry.
+    pub(super) fn push_with(&mut self, path: &[i32], write: impl FnOnce(&mut Vec<u8>)) {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        write(&mut buffer);
+        self.push(path, &buffer);
+        self.buffer = buffer;
+    }
+}
+
+/// Dump array of WKB geometries, native geometries being encoded as WKB
+/// first. `dump` pushes the parts of a geometry with their paths given its
+/// bytes and their [`wkb::Span`], copying the bytes of the parts rather than
+/// decoding and re-encoding them.
+pub(super) fn dump_wkb(
+    geoms: &ArrayRef,
+    args: &[ColumnarValue],
+    dump: impl Fn(&[u8], &wkb::Span, &mut Vec<i32>, &mut WkbParts) -> Result<(), DataFusionError>,
+) -> Result<ArrayRef, DataFusionError> {
+    let geometry_type = args
+        .get(1)
+        .cloned()
+        .unwrap_or(ColumnarValue::Scalar(ScalarValue::Null));
+    let wkb = wkb_array(geoms, &geometry_type)?;
+    let wkb = wkb.as_binary::<i32>();
+
+    let mut parts = WkbParts::default();
+    let mut lengths = vec![];
+    for bytes in wkb.iter() {
+        let start = parts.paths.len();
+        if let Some(bytes) = bytes {
+            dump(bytes, &wkb::scan(bytes)?, &mut vec![], &mut parts)?;
+        }
+        lengths.push(parts.paths.len() - start);
+    }
+
+    dump_array(
+        OffsetBuffer::from_lengths(lengths),
+        wkb.nulls().cloned().filter(|nulls| nulls.null_count() > 0),
+        parts.paths,
+        Arc::new(parts.geoms.finish()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_traits::Dimensions;
+
+    use super::*;
+
+    #[test]
+    fn nested_parts() {
+        let point = |x| wkb::Geometry::new(wkb::Kind::Point, Dimensions::Xy, vec![x, 0.], vec![]);
+        let multi = wkb::Geometry::new(
+            wkb::Kind::MultiPoint,
+            Dimensions::Xy,
+            vec![],
+            vec![point(1.), point(2.)],
+        );
+        let collection = wkb::Geometry::new(
+            wkb::Kind::GeometryCollection,
+            Dimensions::Xy,
+            vec![],
+            vec![point(0.), multi],
+        )
+        .to_wkb();
+
+        let mut parts = WkbParts::default();
+        let span = wkb::scan(&collection).unwrap();
+        parts_of(&collection, &span, &mut vec![], &mut parts);
+        assert_eq!(parts.paths, vec![vec![1], vec![2, 1], vec![2, 2]]);
+        assert_eq!(parts.geoms.finish().value(2), point(2.).to_wkb());
+
+        let mut parts = WkbParts::default();
+        let single = point(3.).to_wkb();
+        parts_of(
+            &single,
+            &wkb::scan(&single).unwrap(),
+            &mut vec![],
+            &mut parts,
+        );
+        assert_eq!(parts.paths, vec![Vec::<i32>::new()]);
+        assert_eq!(parts.geoms.finish().value(0), single);
+    }
+}
@@ -0,0 +1,172 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::{
+        array::{Array, ArrayRef, AsArray},
+        buffer::{OffsetBuffer, ScalarBuffer},
+        datatypes::DataType,
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+
+use super::dump::{dump_array, dump_type, dump_wkb, WkbParts};
+use crate::{helpers::is_point_type, wkb};
+
+/// `ST_DumpPoints` user defined function (UDF) implementation.
+///
+/// Vertices of each geometry as an array of `path`/`geom` structs to
+/// `unnest`, the path holding the (1 based) indices of the part, ring and
+/// vertex. Native geometries are dumped from their offsets, the points
+/// being the coordinates of the input; other geometries as WKB points
+/// written from the coordinate bytes of their WKB.
+#[derive(Debug, Clone)]
+pub struct DumpPoints {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl DumpPoints {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_dumppoints".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for DumpPoints {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_DumpPoints"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(dump_type(
+            native_point_type(&arg_types[0]).unwrap_or(DataType::Binary),
+        ))
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        let geoms = ColumnarValue::values_to_arrays(&args[..1])?.remove(0);
+        let dump = match native_point_type(geoms.data_type()) {
+            Some(_) => {
+                let (points, bounds, mut paths) = native_vertices(&geoms, false);
+                // paths are gathered innermost index first
+                paths.iter_mut().for_each(|path| path.reverse());
+                let offsets: Vec<i32> = bounds.into_iter().map(|bound| bound as i32).collect();
+                dump_array(
+                    OffsetBuffer::new(ScalarBuffer::from(offsets)),
+                    geoms.nulls().cloned(),
+                    paths,
+                    points,
+                )?
+            }
+            None => dump_wkb(&geoms, args, |bytes, span, path, vertices| {
+                vertices_of(bytes, span, path, vertices);
+                Ok(())
+            })?,
+        };
+
+        Ok(ColumnarValue::from(dump))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Point type of native geometries, `None` for WKB and mixed geometries.
+fn native_point_type(data_type: &DataType) -> Option<DataType> {
+    match data_type {
+        data_type if is_point_type(data_type) => Some(data_type.clone()),
+        DataType::List(field)
+            if matches!(
+                field.name().as_str(),
+                "vertices" | "rings" | "points" | "linestrings" | "polygons"
+            ) =>
+        {
+            native_point_type(field.data_type())
+        }
+        _ => None,
+    }
+}
+
+/// Points of a native geometry array, the bounds of the points of each
+/// geometry and the reversed path of each point. `sequence` tells whether
+/// the geometries are the vertices of line strings or rings, whose index is
+/// the last of the path, other points ending with 1.
+fn native_vertices(geoms: &ArrayRef, sequence: bool) -> (ArrayRef, Vec<usize>, Vec<Vec<i32>>) {
+    let DataType::List(field) = geoms.data_type() else {
+        let path = if sequence { vec![] } else { vec![1] };
+        return (
+            geoms.clone(),
+            (0..=geoms.len()).collect(),
+            vec![path; geoms.len()],
+        );
+    };
+
+    let list = geoms.as_list::<i32>();
+    let (points, bounds, mut paths) = native_vertices(list.values(), field.name() == "vertices");
+    for range in list.offsets().windows(2) {
+        for (k, child) in (range[0] as usize..range[1] as usize).enumerate() {
+            for path in &mut paths[bounds[child]..bounds[child + 1]] {
+                path.push(k as i32 + 1);
+            }
+        }
+    }
+
+    let bounds = list.offsets().iter().map(|&offset| bounds[offset as usize]);
+    (points, bounds.collect(), paths)
+}
+
+/// Vertices of a WKB geometry as WKB points in the byte order of the
+/// geometry, empty points are skipped.
+fn vertices_of(bytes: &[u8], span: &wkb::Span, path: &mut Vec<i32>, vertices: &mut WkbParts) {
+    match span.kind {
+        // line strings and polygon rings
+        wkb::Kind::Point | wkb::Kind::LineString => {
+            let coords = bytes[span.coords.clone()].chunks(wkb::size(span.dim) * 8);
+            for (i, coord) in coords.enumerate() {
+                let x: [u8; 8] = coord[..8].try_into().unwrap();
+                let x = match span.little_endian {
+                    true => f64::from_le_bytes(x),
+                    false => f64::from_be_bytes(x),
+                };
+                if x.is_nan() {
+                    continue;
+                }
+
+                path.push(i as i32 + 1);
+                vertices.push_with(path, |buffer| {
+                    wkb::write_header(buffer, wkb::Kind::Point, span.dim, span.little_endian);
+                    buffer.extend_from_slice(coord);
+                });
+                path.pop();
+            }
+        }
+        _ => {
+            for (k, part) in span.parts.iter().enumerate() {
+                path.push(k as i32 + 1);
+                vertices_of(bytes, part, path, vertices);
+                path.pop();
+            }
+        }
+    }
+}
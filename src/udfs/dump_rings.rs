@@ -0,0 +1,194 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{Array, ArrayRef, AsArray, ListArray},
+        buffer::{OffsetBuffer, ScalarBuffer},
+        datatypes::DataType,
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+
+use super::dump::{dump_array, dump_type, dump_wkb, WkbParts};
+use crate::wkb;
+
+/// `ST_DumpRings` user defined function (UDF) implementation.
+///
+/// Rings of each polygon as an array of `path`/`geom` structs to `unnest`,
+/// each ring being a polygon and the path holding its index, 0 for the
+/// exterior ring, preceded by the (1 based) polygon index for multi
+/// polygons. Native polygons are dumped by wrapping their rings in new
+/// offsets, other geometries as WKB polygons around the ring bytes of their
+/// WKB.
+#[derive(Debug, Clone)]
+pub struct DumpRings {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl DumpRings {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_dumprings".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for DumpRings {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_DumpRings"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(dump_type(
+            native_polygon_type(&arg_types[0]).unwrap_or(DataType::Binary),
+        ))
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        let geoms = ColumnarValue::values_to_arrays(&args[..1])?.remove(0);
+        let dump = match geoms.data_type() {
+            DataType::List(field) if field.name() == "rings" => {
+                let polygons = geoms.as_list::<i32>();
+                let paths = ring_paths(polygons.offsets(), polygons.values().len())
+                    .into_iter()
+                    .map(|ring| vec![ring])
+                    .collect();
+                dump_array(
+                    polygons.offsets().clone(),
+                    polygons.nulls().cloned(),
+                    paths,
+                    ring_polygons(polygons)?,
+                )?
+            }
+            DataType::List(field) if field.name() == "polygons" => {
+                let multi = geoms.as_list::<i32>();
+                let polygons = multi.values().as_list::<i32>();
+                let rings = ring_paths(polygons.offsets(), polygons.values().len());
+                let mut paths = rings
+                    .into_iter()
+                    .map(|ring| vec![0, ring])
+                    .collect::<Vec<_>>();
+                for range in multi.offsets().windows(2) {
+                    for (k, polygon) in (range[0] as usize..range[1] as usize).enumerate() {
+                        let rings = polygons.offsets()[polygon]..polygons.offsets()[polygon + 1];
+                        for ring in rings {
+                            paths[ring as usize][0] = k as i32 + 1;
+                        }
+                    }
+                }
+
+                let offsets: Vec<i32> = multi
+                    .offsets()
+                    .iter()
+                    .map(|&offset| polygons.offsets()[offset as usize])
+                    .collect();
+                dump_array(
+                    OffsetBuffer::new(ScalarBuffer::from(offsets)),
+                    multi.nulls().cloned(),
+                    paths,
+                    ring_polygons(polygons)?,
+                )?
+            }
+            _ => dump_wkb(&geoms, args, rings_of)?,
+        };
+
+        Ok(ColumnarValue::from(dump))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Polygon type of native polygons and multi polygons, `None` otherwise.
+fn native_polygon_type(data_type: &DataType) -> Option<DataType> {
+    match data_type {
+        DataType::List(field) if field.name() == "rings" => Some(data_type.clone()),
+        DataType::List(field) if field.name() == "polygons" => Some(field.data_type().clone()),
+        _ => None,
+    }
+}
+
+/// Index of each ring in its polygon.
+fn ring_paths(offsets: &OffsetBuffer<i32>, rings: usize) -> Vec<i32> {
+    let mut paths = vec![0; rings];
+    for range in offsets.windows(2) {
+        for (k, ring) in (range[0]..range[1]).enumerate() {
+            paths[ring as usize] = k as i32;
+        }
+    }
+    paths
+}
+
+/// Single ring polygons of the rings of native polygons, sharing their
+/// vertices.
+fn ring_polygons(polygons: &ListArray) -> Result<ArrayRef, DataFusionError> {
+    let DataType::List(field) = polygons.data_type() else {
+        unreachable!()
+    };
+    let rings = polygons.values();
+    let polygons = ListArray::try_new(
+        field.clone(),
+        OffsetBuffer::from_lengths(vec![1; rings.len()]),
+        rings.clone(),
+        None,
+    )?;
+    Ok(Arc::new(polygons))
+}
+
+/// Rings of a WKB polygon or multi polygon as WKB polygons in the byte
+/// order of the geometry.
+fn rings_of(
+    bytes: &[u8],
+    span: &wkb::Span,
+    path: &mut Vec<i32>,
+    rings: &mut WkbParts,
+) -> Result<(), DataFusionError> {
+    match span.kind {
+        wkb::Kind::Polygon => {
+            for (k, ring) in span.parts.iter().enumerate() {
+                path.push(k as i32);
+                rings.push_with(path, |buffer| {
+                    wkb::write_header(buffer, wkb::Kind::Polygon, span.dim, span.little_endian);
+                    buffer.extend(wkb::u32_bytes(1, span.little_endian));
+                    buffer.extend_from_slice(&bytes[ring.bytes.clone()]);
+                });
+                path.pop();
+            }
+        }
+        wkb::Kind::MultiPolygon => {
+            for (k, polygon) in span.parts.iter().enumerate() {
+                path.push(k as i32 + 1);
+                rings_of(bytes, polygon, path, rings)?;
+                path.pop();
+            }
+        }
+        _ => {
+            return Err(DataFusionError::Execution(format!(
+                "ST_DumpRings expects polygons, got a {}",
+                span.type_name()
+            )))
+        }
+    }
+    Ok(())
+}
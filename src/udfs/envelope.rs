@@ -1,19 +1,22 @@
 use std::any::Any;
 
 use datafusion::{
-    arrow::{array::ArrayRef, buffer::OffsetBuffer, datatypes::DataType},
+    arrow::{
+        array::{ArrayRef, AsArray},
+        buffer::OffsetBuffer,
+        datatypes::DataType,
+    },
     error::DataFusionError,
     logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
 };
-use geo::BoundingRect;
+use geo::{polygon, BoundingRect, Rect};
 use geoarrow::{
     array::{
-        AsNativeArray, CoordBuffer, CoordType, LineStringArray, MultiLineStringArray,
-        MultiPointArray, MultiPolygonArray, NativeArrayDyn, PointArray, PolygonArray,
-        PolygonBuilder, PolygonCapacity, SeparatedCoordBufferBuilder, WKBArray,
+        AsNativeArray, CoordBuffer, CoordType, GeometryCollectionArray, LineStringArray,
+        MixedGeometryArray, MultiLineStringArray, MultiPointArray, MultiPolygonArray, PointArray,
+        PolygonArray, PolygonBuilder, PolygonCapacity, RectArray, SeparatedCoordBufferBuilder,
     },
     datatypes::{Dimension, NativeType},
-    error::GeoArrowError,
     scalar::OwnedPolygon,
     trait_::ArrayAccessor,
     ArrayBase, NativeArray,
@@ -21,10 +24,15 @@ use geoarrow::{
 
 use crate::{
     compute::min_max_2d,
-    helpers::{coord_type, geom_type, native_type},
+    helpers::{coord_type, geo_geometries, is_spherical, native_array},
+    spherical::spherical_bounds,
+    wkb,
 };
 
 /// `ST_Envelope` user defined function (UDF) implementation.
+///
+/// Envelopes of geographies follow great-circle edges and may cross the
+/// antimeridian, in which case `xmin > xmax`.
 #[derive(Debug, Clone)]
 pub struct Envelope {
     signature: Signature,
@@ -36,7 +44,7 @@ impl Envelope {
     pub fn new() -> Self {
         Self {
             signature: Signature::one_of(
-                vec![TypeSignature::Any(1), TypeSignature::Any(3)],
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
                 Volatility::Immutable,
             ),
             aliases: vec!["st_envelope".to_string()],
@@ -83,43 +91,55 @@ impl ScalarUDFImpl for Envelope {
     fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
         // DataFusion has arranged for the correct inputs to be passed to this
         // function, but we check again to make sure
-        assert_eq!(args.len(), 3);
+        assert_eq!(args.len(), 5);
 
         let geoms = match &args[0] {
             ColumnarValue::Array(array) => array,
             ColumnarValue::Scalar(scalar) => &scalar.to_array()?,
         };
 
-        let geomtype = geom_type(&args[1])?;
-
         let mut builder: PolygonBuilder<2> =
             PolygonBuilder::new_with_options(CoordType::Separated, Default::default());
 
-        match &geoms.data_type() {
-            DataType::Binary => {
-                let wkb: WKBArray<i32> = WKBArray::try_from(geoms.as_ref())
-                    .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
-
-                for geom in wkb.iter_geo() {
-                    builder
-                        .push_polygon(
-                            geom.and_then(|g| g.bounding_rect())
-                                .map(|rect| rect.to_polygon())
-                                .as_ref(),
-                        )
-                        .map_err(|e| DataFusionError::Internal(e.to_string()))?;
-                }
+        if is_spherical(&args[4]) {
+            // longitudes of envelopes crossing the antimeridian run from xmin
+            // eastwards to xmax < xmin
+            for geom in geo_geometries(geoms, &args[1])? {
+                let envelope =
+                    geom.as_ref()
+                        .and_then(spherical_bounds)
+                        .map(|(xmin, ymin, xmax, ymax)| {
+                            polygon![
+                                (x: xmin, y: ymin),
+                                (x: xmax, y: ymin),
+                                (x: xmax, y: ymax),
+                                (x: xmin, y: ymax),
+                                (x: xmin, y: ymin),
+                            ]
+                        });
+                builder
+                    .push_polygon(envelope.as_ref())
+                    .map_err(|e| DataFusionError::Internal(e.to_string()))?;
             }
 
-            DataType::LargeBinary => {
-                let wkb: WKBArray<i64> = WKBArray::try_from(geoms.as_ref())
-                    .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
+            return Ok(ColumnarValue::from(
+                builder.finish().to_array_ref() as ArrayRef
+            ));
+        }
+
+        match &geoms.data_type() {
+            DataType::Binary | DataType::LargeBinary => {
+                let wkbs: Vec<Option<&[u8]>> = match geoms.data_type() {
+                    DataType::Binary => geoms.as_binary::<i32>().iter().collect(),
+                    _ => geoms.as_binary::<i64>().iter().collect(),
+                };
 
-                for geom in wkb.iter_geo() {
+                for wkb in wkbs {
+                    let bounds = wkb.map(wkb::parse).transpose()?.and_then(|g| g.bounds());
                     builder
                         .push_polygon(
-                            geom.and_then(|g| g.bounding_rect())
-                                .map(|rect| rect.to_polygon())
+                            bounds
+                                .map(|(min, max)| Rect::new(min, max).to_polygon())
                                 .as_ref(),
                         )
                         .map_err(|e| DataFusionError::Internal(e.to_string()))?;
@@ -127,13 +147,7 @@ impl ScalarUDFImpl for Envelope {
             }
 
             _ => {
-                let native_type = native_type(&args[0], geomtype);
-
-                let geoms = NativeArrayDyn::from_arrow_array(
-                    &geoms,
-                    &native_type.to_field("geometry", true),
-                )
-                .unwrap();
+                let geoms = native_array(geoms, &args[1])?;
 
                 let envelopes = geoms.as_ref().envelope();
 
@@ -193,9 +207,38 @@ array_envelope_impl!(PolygonArray<D>, polygon_coord_buffer);
 array_envelope_impl!(MultiPointArray<D>, multi_point_coord_buffer);
 array_envelope_impl!(MultiLineStringArray<D>, multi_line_string_coord_buffer);
 array_envelope_impl!(MultiPolygonArray<D>, multi_polygon_coord_buffer);
-// array_envelope_impl!(MixedGeometryArray<D>);
-// array_envelope_impl!(GeometryCollectionArray<D>);
-// envelope_array_impl!(RectArray<D>);
+
+/// Implementation that iterates over geo geometries
+macro_rules! geo_envelope_impl {
+    ($type:ty) => {
+        impl<const D: usize> EnvelopeTrait for $type {
+            type Output = PolygonArray<2>;
+
+            fn envelope(&self) -> Self::Output {
+                let mut envelopes = PolygonBuilder::with_capacity_and_options(
+                    PolygonCapacity::new(self.len() * 5, self.len(), self.len()),
+                    CoordType::Separated,
+                    Default::default(),
+                );
+
+                for geom in self.iter_geo() {
+                    let envelope =
+                        geom.map(|geom| match geo::Geometry::from(geom).bounding_rect() {
+                            Some(rect) => rect.to_polygon(),
+                            None => geo::Polygon::new(geo::LineString::new(vec![]), vec![]),
+                        });
+                    envelopes.push_polygon(envelope.as_ref()).unwrap();
+                }
+
+                envelopes.finish().into()
+            }
+        }
+    };
+}
+
+geo_envelope_impl!(MixedGeometryArray<D>);
+geo_envelope_impl!(GeometryCollectionArray<D>);
+geo_envelope_impl!(RectArray<D>);
 
 impl EnvelopeTrait for &dyn NativeArray {
     type Output = PolygonArray<2>;
@@ -211,18 +254,18 @@ impl EnvelopeTrait for &dyn NativeArray {
             MultiPoint(_, XY) => self.as_multi_point::<2>().envelope(),
             MultiLineString(_, XY) => self.as_multi_line_string::<2>().envelope(),
             MultiPolygon(_, XY) => self.as_multi_polygon::<2>().envelope(),
-            Mixed(_, XY) => unimplemented!(),
-            GeometryCollection(_, XY) => unimplemented!(),
-            Rect(XY) => unimplemented!(),
+            Mixed(_, XY) => self.as_mixed::<2>().envelope(),
+            GeometryCollection(_, XY) => self.as_geometry_collection::<2>().envelope(),
+            Rect(XY) => self.as_rect::<2>().envelope(),
             Point(_, XYZ) => self.as_point::<3>().envelope(),
             LineString(_, XYZ) => self.as_line_string::<3>().envelope(),
             Polygon(_, XYZ) => self.as_polygon::<3>().envelope(),
             MultiPoint(_, XYZ) => self.as_multi_point::<3>().envelope(),
             MultiLineString(_, XYZ) => self.as_multi_line_string::<3>().envelope(),
             MultiPolygon(_, XYZ) => self.as_multi_polygon::<3>().envelope(),
-            Mixed(_, XYZ) => unimplemented!(),
-            GeometryCollection(_, XYZ) => unimplemented!(),
-            Rect(XYZ) => unimplemented!(),
+            Mixed(_, XYZ) => self.as_mixed::<3>().envelope(),
+            GeometryCollection(_, XYZ) => self.as_geometry_collection::<3>().envelope(),
+            Rect(XYZ) => self.as_rect::<3>().envelope(),
         }
     }
 }
@@ -0,0 +1,140 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, Float64Array, StructArray},
+        buffer::NullBuffer,
+        datatypes::DataType,
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+
+use crate::helpers::{box3d_fields, wkb_geometries};
+
+/// `ST_3DEnvelope` user defined function (UDF) implementation.
+///
+/// 3D bounding box (Box3D) of each geometry as a struct of `xmin`, `ymin`,
+/// `zmin`, `xmax`, `ymax` and `zmax`, NULL for empty geometries. Z bounds are
+/// 0 for geometries without Z.
+#[derive(Debug, Clone)]
+pub struct Envelope3D {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl Envelope3D {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_3denvelope".to_string(), "box3d".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for Envelope3D {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_3DEnvelope"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(DataType::Struct(box3d_fields()))
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert_eq!(args.len(), 5);
+
+        let geoms = match &args[0] {
+            ColumnarValue::Array(array) => array,
+            ColumnarValue::Scalar(scalar) => &scalar.to_array()?,
+        };
+
+        let boxes = wkb_geometries(geoms, &args[1])?
+            .iter()
+            .map(|geom| geom.as_ref().and_then(|geom| geom.bounds_3d()))
+            .collect::<Vec<_>>();
+
+        let columns = (0..6)
+            .map(|i| {
+                let ordinates = boxes.iter().map(|b| match b {
+                    Some((mins, maxs)) => [mins, maxs][i / 3][i % 3],
+                    None => 0.,
+                });
+                Arc::new(Float64Array::from_iter_values(ordinates)) as ArrayRef
+            })
+            .collect();
+        let nulls = NullBuffer::from_iter(boxes.iter().map(Option::is_some));
+
+        let boxes = StructArray::try_new(box3d_fields(), columns, Some(nulls))?;
+
+        Ok(ColumnarValue::from(Arc::new(boxes) as ArrayRef))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use datafusion::{
+        arrow::{
+            array::{Array, AsArray, BinaryArray},
+            datatypes::Float64Type,
+        },
+        scalar::ScalarValue,
+    };
+
+    use super::*;
+    use crate::wkb;
+
+    fn wkb(text: &str) -> Vec<u8> {
+        wkb::from_wkt(&wkt::Wkt::from_str(text).unwrap()).to_wkb()
+    }
+
+    #[test]
+    fn boxes() {
+        let line = wkb("LINESTRING Z (1 5 3, 4 2 6)");
+        let point = wkb("POINT (7 8)");
+        let geoms = BinaryArray::from(vec![Some(line.as_slice()), None, Some(point.as_slice())]);
+
+        let mut args = vec![ColumnarValue::Array(Arc::new(geoms))];
+        args.extend((0..4).map(|_| ColumnarValue::Scalar(ScalarValue::Null)));
+        let ColumnarValue::Array(boxes) = Envelope3D::new().invoke(&args).unwrap() else {
+            unreachable!()
+        };
+
+        let boxes = boxes.as_struct();
+        let ordinates = |row: usize| {
+            (0..6)
+                .map(|i| boxes.column(i).as_primitive::<Float64Type>().value(row))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(boxes.data_type(), &DataType::Struct(box3d_fields()));
+        assert_eq!(ordinates(0), [1., 2., 3., 4., 5., 6.]);
+        assert!(boxes.is_null(1));
+        // 2D geometries have Z bounds 0
+        assert_eq!(ordinates(2), [7., 8., 0., 7., 8., 0.]);
+    }
+}
@@ -0,0 +1,66 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::datatypes::DataType,
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+
+/// `ST_Geography` user defined function (UDF) implementation.
+///
+/// The geometries are returned unchanged and tracked as geography by the
+/// [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule): functions
+/// consuming them use great-circle (spherical) edges on lon/lat coordinates.
+#[derive(Debug, Clone)]
+pub struct ToGeography {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl ToGeography {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_geography".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for ToGeography {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_Geography"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(arg_types[0].clone())
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert_eq!(args.len(), 5);
+
+        Ok(args[0].clone())
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
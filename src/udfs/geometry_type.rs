@@ -2,7 +2,7 @@ use std::{any::Any, sync::Arc};
 
 use datafusion::{
     arrow::{
-        array::{ArrayRef, OffsetSizeTrait, StringArray},
+        array::{ArrayRef, AsArray, StringArray},
         datatypes::DataType,
     },
     error::DataFusionError,
@@ -10,10 +10,15 @@ use datafusion::{
     scalar::ScalarValue,
 };
 use geoarrow::{
-    array::WKBArray, error::GeoArrowError, io::wkb::WKBType, scalar::WKB, trait_::ArrayAccessor,
+    datatypes::{Dimension, NativeType},
+    io::wkb::to_wkb,
+    ArrayBase,
 };
 
-use crate::helpers::scalar_arg_as_str;
+use crate::{
+    helpers::{native_array, native_type, scalar_arg_as_str},
+    wkb,
+};
 
 /// `ST_GeometryType` user defined function (UDF) implementation.
 #[derive(Debug, Clone)]
@@ -27,7 +32,7 @@ impl GeometryType {
     pub fn new() -> Self {
         Self {
             signature: Signature::one_of(
-                vec![TypeSignature::Any(1), TypeSignature::Any(3)],
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
                 Volatility::Immutable,
             ),
             aliases: vec!["st_geometrytype".to_string()],
@@ -60,20 +65,17 @@ impl ScalarUDFImpl for GeometryType {
     fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
         // DataFusion has arranged for the correct inputs to be passed to this
         // function, but we check again to make sure
-        assert_eq!(args.len(), 3);
+        assert_eq!(args.len(), 5);
 
         let geoms = match &args[0] {
             ColumnarValue::Array(array) => array,
             ColumnarValue::Scalar(scalar) => &scalar.to_array()?,
         };
-        let geomtype = scalar_arg_as_str(&args[1])?;
 
         match geoms.data_type() {
             DataType::Binary => {
-                let geoms: WKBArray<i32> = WKBArray::try_from(geoms.as_ref())
-                    .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
-
                 let array = geoms
+                    .as_binary::<i32>()
                     .iter()
                     .map(wkb_geom_to_type)
                     .collect::<Result<StringArray, DataFusionError>>()?;
@@ -81,10 +83,8 @@ impl ScalarUDFImpl for GeometryType {
             }
 
             DataType::LargeBinary => {
-                let geoms: WKBArray<i64> = WKBArray::try_from(geoms.as_ref())
-                    .map_err(|e: GeoArrowError| DataFusionError::Internal(e.to_string()))?;
-
                 let array = geoms
+                    .as_binary::<i64>()
                     .iter()
                     .map(wkb_geom_to_type)
                     .collect::<Result<StringArray, DataFusionError>>()?;
@@ -92,6 +92,23 @@ impl ScalarUDFImpl for GeometryType {
                 Ok(ColumnarValue::from(Arc::new(array) as ArrayRef))
             }
             _ => {
+                let geomtype = match native_type(geoms, &args[1])? {
+                    NativeType::Mixed(_, _) => {
+                        // the type differs per row
+                        let array = to_wkb::<i32>(native_array(geoms, &args[1])?.as_ref())
+                            .to_array_ref()
+                            .as_binary::<i32>()
+                            .iter()
+                            .map(wkb_geom_to_type)
+                            .collect::<Result<StringArray, DataFusionError>>()?;
+
+                        return Ok(ColumnarValue::from(Arc::new(array) as ArrayRef));
+                    }
+                    NativeType::GeometryCollection(_, Dimension::XY) => "GeometryCollection",
+                    NativeType::GeometryCollection(_, Dimension::XYZ) => "GeometryCollectionZ",
+                    NativeType::Rect(_) => "Polygon",
+                    _ => scalar_arg_as_str(&args[1])?,
+                };
                 let geometry_type = format!("ST_{}", geomtype.replace(' ', ""));
                 if geoms.as_ref().null_count() > 0 {
                     Ok(ColumnarValue::Array(Arc::new(StringArray::from_iter(
@@ -116,31 +133,12 @@ impl ScalarUDFImpl for GeometryType {
     }
 }
 
-fn wkb_geom_to_type<O: OffsetSizeTrait>(
-    geom: Option<WKB<O>>,
-) -> Result<Option<String>, DataFusionError> {
-    if let Some(wkb) = geom {
-        wkb.wkb_type()
-            .map_err(|e| DataFusionError::Internal(e.to_string()))
-            .map(|wkb_type| {
-                Some(match wkb_type {
-                    WKBType::Point => "ST_Point".to_string(),
-                    WKBType::LineString => "ST_LineString".to_string(),
-                    WKBType::Polygon => "ST_Polygon".to_string(),
-                    WKBType::MultiPoint => "ST_MultiPoint".to_string(),
-                    WKBType::MultiLineString => "ST_MultiLineString".to_string(),
-                    WKBType::MultiPolygon => "ST_MultiPolygon".to_string(),
-                    WKBType::GeometryCollection => "ST_GeometryCollection".to_string(),
-                    WKBType::PointZ => "ST_PointZ".to_string(),
-                    WKBType::LineStringZ => "ST_LineStringZ".to_string(),
-                    WKBType::PolygonZ => "ST_PolygonZ".to_string(),
-                    WKBType::MultiPointZ => "ST_MultiPointZ".to_string(),
-                    WKBType::MultiLineStringZ => "ST_MultiLineStringZ".to_string(),
-                    WKBType::MultiPolygonZ => "ST_MultiPolygonZ".to_string(),
-                    WKBType::GeometryCollectionZ => "ST_GeometryCollectionZ".to_string(),
-                })
-            })
-    } else {
-        Ok(None)
-    }
+fn wkb_geom_to_type(geom: Option<&[u8]>) -> Result<Option<String>, DataFusionError> {
+    geom.map(|wkb| {
+        Ok(format!(
+            "ST_{}",
+            wkb::parse(wkb)?.type_name().replace(' ', "")
+        ))
+    })
+    .transpose()
 }
@@ -0,0 +1,100 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, Float64Array},
+        datatypes::DataType,
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+use geo::{Geometry, Line, LineString};
+
+use crate::{
+    helpers::{geometry_args, is_spherical},
+    spherical,
+};
+
+/// `ST_Length` user defined function (UDF) implementation.
+///
+/// Length of linear geometries in CRS units, for geographies the great-circle
+/// length in meters. Points and polygons have length 0.
+#[derive(Debug, Clone)]
+pub struct Length {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl Length {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_length".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for Length {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_Length"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(DataType::Float64)
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert_eq!(args.len(), 5);
+
+        let spherical = is_spherical(&args[4]);
+        let [geoms] = <[_; 1]>::try_from(geometry_args(args, 1)?).unwrap();
+
+        let lengths = geoms
+            .iter()
+            .map(|geom| {
+                geom.as_ref().map(|geom| match spherical {
+                    true => spherical::length(geom),
+                    false => planar_length(geom),
+                })
+            })
+            .collect::<Float64Array>();
+
+        Ok(ColumnarValue::from(Arc::new(lengths) as ArrayRef))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Planar length of the linear parts of a geometry.
+fn planar_length(geometry: &Geometry) -> f64 {
+    let length = |ls: &LineString| ls.lines().map(|l: Line| l.dx().hypot(l.dy())).sum::<f64>();
+
+    match geometry {
+        Geometry::Line(line) => line.dx().hypot(line.dy()),
+        Geometry::LineString(ls) => length(ls),
+        Geometry::MultiLineString(mls) => mls.iter().map(length).sum(),
+        Geometry::GeometryCollection(gc) => gc.iter().map(planar_length).sum(),
+        _ => 0.,
+    }
+}
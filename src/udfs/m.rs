@@ -0,0 +1,98 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{new_null_array, ArrayRef, AsArray, Float64Array},
+        datatypes::DataType,
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+
+use crate::wkb;
+
+/// `ST_M` user defined function (UDF) implementation.
+///
+/// Measure of a point, NULL for other geometries and geometries without
+/// measures. Measures are only kept in WKB, native arrays always return NULL.
+#[derive(Debug, Clone)]
+pub struct M {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl M {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_m".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for M {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_M"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(DataType::Float64)
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert_eq!(args.len(), 5);
+
+        let geoms = match &args[0] {
+            ColumnarValue::Array(array) => array,
+            ColumnarValue::Scalar(scalar) => &scalar.to_array()?,
+        };
+
+        let measure = |wkb: Option<&[u8]>| -> Result<Option<f64>, DataFusionError> {
+            Ok(wkb.map(wkb::parse).transpose()?.and_then(|geom| geom.m()))
+        };
+
+        let measures = match geoms.data_type() {
+            DataType::Binary => geoms
+                .as_binary::<i32>()
+                .iter()
+                .map(measure)
+                .collect::<Result<Float64Array, DataFusionError>>()?,
+            DataType::LargeBinary => geoms
+                .as_binary::<i64>()
+                .iter()
+                .map(measure)
+                .collect::<Result<Float64Array, DataFusionError>>()?,
+            _ => {
+                return Ok(ColumnarValue::from(new_null_array(
+                    &DataType::Float64,
+                    geoms.len(),
+                )))
+            }
+        };
+
+        Ok(ColumnarValue::from(Arc::new(measures) as ArrayRef))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
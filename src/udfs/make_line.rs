@@ -0,0 +1,213 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{Array, ArrayRef, AsArray, BinaryArray, ListArray, UInt32Array},
+        buffer::{NullBuffer, OffsetBuffer},
+        compute::{interleave, take},
+        datatypes::{DataType, Field},
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+    scalar::ScalarValue,
+};
+
+use crate::{
+    helpers::{is_point_type, line_string_type, wkb_geometries, wkb_geometry_args},
+    wkb,
+};
+
+/// `ST_MakeLine` user defined function (UDF) implementation.
+///
+/// Line string through two geometries, or through the points of an array,
+/// where NULL elements are skipped. Native points of the same type give a
+/// native line string, other geometries a WKB line string.
+///
+/// The aggregate [`MakeLineAgg`](crate::udafs::MakeLineAgg) shares its name.
+/// This function is only registered under its exact name, so SQL resolves
+/// `ST_MakeLine(a, b)` to the aggregate, and the
+/// [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule) rewrites
+/// aggregate calls with two geometries or an array of points to this
+/// function.
+#[derive(Debug, Clone)]
+pub struct MakeLine {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl MakeLine {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Any(1),
+                    TypeSignature::Any(2),
+                    TypeSignature::Any(5),
+                    TypeSignature::Any(6),
+                    TypeSignature::Any(10),
+                ],
+                Volatility::Immutable,
+            ),
+            aliases: vec![],
+        }
+    }
+}
+
+impl ScalarUDFImpl for MakeLine {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_MakeLine"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        match arg_types.len() {
+            // array of points
+            1 | 5 => match &arg_types[0] {
+                DataType::List(field) if is_point_type(field.data_type()) => {
+                    Ok(line_string_type(field.data_type()))
+                }
+                DataType::List(field)
+                    if matches!(field.data_type(), DataType::Binary | DataType::LargeBinary) =>
+                {
+                    Ok(DataType::Binary)
+                }
+                dt => Err(DataFusionError::Plan(format!(
+                    "ST_MakeLine expects two geometries or an array of points, got `{dt}`"
+                ))),
+            },
+            _ if is_point_type(&arg_types[0]) && arg_types[0] == arg_types[1] => {
+                Ok(line_string_type(&arg_types[0]))
+            }
+            _ => Ok(DataType::Binary),
+        }
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        let lines = match args.len() {
+            1 | 5 => {
+                let arrays = ColumnarValue::values_to_arrays(&args[..1])?;
+                line_of_array(arrays[0].as_list::<i32>())?
+            }
+            2 | 6 | 10 => {
+                let arrays = ColumnarValue::values_to_arrays(&args[..2])?;
+                match is_point_type(arrays[0].data_type())
+                    && arrays[0].data_type() == arrays[1].data_type()
+                {
+                    true => line_of_points(&arrays[0], &arrays[1])?,
+                    false => line_of_geometries(args)?,
+                }
+            }
+            n => {
+                return Err(DataFusionError::Internal(format!(
+                    "ST_MakeLine called with {n} arguments"
+                )))
+            }
+        };
+
+        Ok(ColumnarValue::from(lines))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Line strings through the valid points of each array row. Without NULL
+/// points the offsets and points are reused as they are.
+fn line_of_array(arrays: &ListArray) -> Result<ArrayRef, DataFusionError> {
+    let points = arrays.values();
+    if !is_point_type(points.data_type()) {
+        let lines = arrays
+            .iter()
+            .map(|geometries| {
+                geometries
+                    .map(|geometries| {
+                        let unknown = ColumnarValue::Scalar(ScalarValue::Null);
+                        let geometries = wkb_geometries(&geometries, &unknown)?;
+                        let geometries = geometries.into_iter().flatten().collect::<Vec<_>>();
+                        Ok(wkb::make_line(&geometries)?.to_wkb())
+                    })
+                    .transpose()
+            })
+            .collect::<Result<BinaryArray, DataFusionError>>()?;
+        return Ok(Arc::new(lines));
+    }
+
+    let field = Arc::new(Field::new("vertices", points.data_type().clone(), true));
+    if points.null_count() == 0 {
+        let lines = ListArray::try_new(
+            field,
+            arrays.offsets().clone(),
+            points.clone(),
+            arrays.nulls().cloned(),
+        )?;
+        return Ok(Arc::new(lines));
+    }
+
+    let mut indices = vec![];
+    let mut lengths = vec![];
+    for range in arrays.offsets().windows(2) {
+        let start = indices.len();
+        indices.extend(
+            (range[0] as u32..range[1] as u32).filter(|&index| points.is_valid(index as usize)),
+        );
+        lengths.push(indices.len() - start);
+    }
+
+    let lines = ListArray::try_new(
+        field,
+        OffsetBuffer::from_lengths(lengths),
+        take(points, &UInt32Array::from(indices), None)?,
+        arrays.nulls().cloned(),
+    )?;
+    Ok(Arc::new(lines))
+}
+
+/// Two point line strings, NULL if either point is, gathered from the point
+/// arrays without decoding them.
+fn line_of_points(a: &ArrayRef, b: &ArrayRef) -> Result<ArrayRef, DataFusionError> {
+    let valid = (0..a.len())
+        .map(|row| a.is_valid(row) && b.is_valid(row))
+        .collect::<Vec<_>>();
+    let indices = valid
+        .iter()
+        .enumerate()
+        .filter(|(_, &valid)| valid)
+        .flat_map(|(row, _)| [(0, row), (1, row)])
+        .collect::<Vec<_>>();
+
+    let lines = ListArray::try_new(
+        Arc::new(Field::new("vertices", a.data_type().clone(), true)),
+        OffsetBuffer::from_lengths(valid.iter().map(|&valid| 2 * valid as usize)),
+        interleave(&[a.as_ref(), b.as_ref()], &indices)?,
+        Some(NullBuffer::from(valid)),
+    )?;
+    Ok(Arc::new(lines))
+}
+
+/// WKB line strings through two geometries, NULL if either geometry is.
+fn line_of_geometries(args: &[ColumnarValue]) -> Result<ArrayRef, DataFusionError> {
+    let [a, b] = <[_; 2]>::try_from(wkb_geometry_args(args, 2)?).unwrap();
+    let lines = a
+        .into_iter()
+        .zip(b)
+        .map(|geometries| match geometries {
+            (Some(a), Some(b)) => Ok(Some(wkb::make_line(&[a, b])?.to_wkb())),
+            _ => Ok(None),
+        })
+        .collect::<Result<BinaryArray, DataFusionError>>()?;
+    Ok(Arc::new(lines))
+}
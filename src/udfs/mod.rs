@@ -1,7 +1,39 @@
+mod area;
 mod as_text;
+mod collect;
+mod distance;
+mod dump;
+mod dump_points;
+mod dump_rings;
 mod envelope;
+mod envelope_3d;
+mod geography;
+mod geometry;
 mod geometry_type;
+mod intersects;
+mod length;
+mod m;
+mod make_line;
+mod set_srid;
+mod srid;
+mod transform;
 
+pub use area::Area;
 pub use as_text::AsText;
+pub use collect::Collect;
+pub use distance::Distance;
+pub use dump::Dump;
+pub use dump_points::DumpPoints;
+pub use dump_rings::DumpRings;
 pub use envelope::Envelope;
+pub use envelope_3d::Envelope3D;
+pub use geography::ToGeography;
+pub use geometry::ToGeometry;
 pub use geometry_type::GeometryType;
+pub use intersects::Intersects;
+pub use length::Length;
+pub use m::M;
+pub use make_line::MakeLine;
+pub use set_srid::SetSrid;
+pub use srid::Srid;
+pub use transform::Transform;
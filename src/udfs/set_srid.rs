@@ -0,0 +1,75 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::datatypes::DataType,
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+};
+
+/// `ST_SetSRID` user defined function (UDF) implementation.
+///
+/// The geometries are returned unchanged, the new CRS (`EPSG:<srid>`) is
+/// tracked by the [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule):
+/// it is passed to the functions consuming the geometries and written to the
+/// `ARROW:extension:metadata` of the resulting column, for subqueries, CTEs
+/// and the file writers.
+#[derive(Debug, Clone)]
+pub struct SetSrid {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl SetSrid {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(2), TypeSignature::Any(6)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_setsrid".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for SetSrid {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_SetSRID"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        if !arg_types[1].is_integer() && arg_types[1] != DataType::Null {
+            return Err(DataFusionError::Plan(format!(
+                "ST_SetSRID expects an integer SRID, got `{}`",
+                arg_types[1]
+            )));
+        }
+
+        Ok(arg_types[0].clone())
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert_eq!(args.len(), 6);
+
+        Ok(args[0].clone())
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
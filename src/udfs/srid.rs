@@ -0,0 +1,162 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{Array, ArrayRef, Int32Array},
+        datatypes::DataType,
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+    scalar::ScalarValue,
+};
+
+use crate::helpers::{crs_arg, srid};
+
+/// `ST_SRID` user defined function (UDF) implementation.
+///
+/// Returns the EPSG code of the geometry's CRS, 0 if the CRS is unknown or
+/// has no EPSG code. NULL for NULL geometries and geometries the
+/// [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule) knows nothing
+/// about, e.g. computed by functions without geometry info.
+#[derive(Debug, Clone)]
+pub struct Srid {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl Srid {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_srid".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for Srid {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_SRID"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(DataType::Int32)
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert_eq!(args.len(), 5);
+
+        // geometries of columns unknown to the analyzer have no known CRS,
+        // not even an unknown one
+        let resolved = !matches!(&args[2], ColumnarValue::Scalar(encoding) if encoding.is_null());
+        let srid = match resolved {
+            true => Some(crs_arg(&args[3])?.and_then(srid).unwrap_or(0)),
+            false => None,
+        };
+
+        match &args[0] {
+            ColumnarValue::Scalar(geom) => Ok(ColumnarValue::Scalar(ScalarValue::Int32(
+                srid.filter(|_| !geom.is_null()),
+            ))),
+            ColumnarValue::Array(geoms) => Ok(ColumnarValue::Array(Arc::new(
+                (0..geoms.len())
+                    .map(|i| srid.filter(|_| geoms.is_valid(i)))
+                    .collect::<Int32Array>(),
+            ) as ArrayRef)),
+        }
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::BinaryArray;
+
+    use super::*;
+
+    fn invoke(geoms: ColumnarValue, encoding: ScalarValue, crs: ScalarValue) -> ColumnarValue {
+        let args = [
+            geoms,
+            ColumnarValue::Scalar(ScalarValue::from("Point")),
+            ColumnarValue::Scalar(encoding),
+            ColumnarValue::Scalar(crs),
+            ColumnarValue::Scalar(ScalarValue::Null),
+        ];
+        Srid::new().invoke(&args).unwrap()
+    }
+
+    #[test]
+    fn srid_of_array() {
+        let geoms: ArrayRef = Arc::new(BinaryArray::from(vec![Some(&[1u8][..]), None]));
+        let geoms = ColumnarValue::Array(geoms);
+
+        let ColumnarValue::Array(srids) = invoke(
+            geoms.clone(),
+            ScalarValue::from("WKB"),
+            ScalarValue::from("EPSG:2056"),
+        ) else {
+            unreachable!()
+        };
+        assert_eq!(
+            srids.as_ref(),
+            &Int32Array::from(vec![Some(2056), None]) as &dyn Array
+        );
+
+        // unknown CRS
+        let ColumnarValue::Array(srids) =
+            invoke(geoms.clone(), ScalarValue::from("WKB"), ScalarValue::Null)
+        else {
+            unreachable!()
+        };
+        assert_eq!(
+            srids.as_ref(),
+            &Int32Array::from(vec![Some(0), None]) as &dyn Array
+        );
+
+        // column unknown to the analyzer
+        let ColumnarValue::Array(srids) = invoke(geoms, ScalarValue::Null, ScalarValue::Null)
+        else {
+            unreachable!()
+        };
+        assert_eq!(srids.null_count(), 2);
+    }
+
+    #[test]
+    fn srid_of_scalar() {
+        let point = ColumnarValue::Scalar(ScalarValue::Binary(Some(vec![1])));
+        let null = ColumnarValue::Scalar(ScalarValue::Binary(None));
+        let crs = ScalarValue::from("OGC:CRS84");
+
+        let ColumnarValue::Scalar(srid) = invoke(point, ScalarValue::from("WKB"), crs.clone())
+        else {
+            unreachable!()
+        };
+        assert_eq!(srid, ScalarValue::Int32(Some(4326)));
+
+        let ColumnarValue::Scalar(srid) = invoke(null, ScalarValue::from("WKB"), crs) else {
+            unreachable!()
+        };
+        assert_eq!(srid, ScalarValue::Int32(None));
+    }
+}
@@ -0,0 +1,335 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{
+            Array, ArrayRef, AsArray, Float64Array, GenericBinaryArray, OffsetSizeTrait,
+            StructArray,
+        },
+        datatypes::{DataType, Float64Type},
+    },
+    error::DataFusionError,
+    logical_expr::{ColumnarValue, ScalarUDFImpl, Signature, TypeSignature, Volatility},
+    scalar::ScalarValue,
+};
+use geo_traits::Dimensions;
+use proj4rs::{transform::transform, Proj};
+
+use crate::{
+    compute::map_coords,
+    helpers::{crs_arg, srid},
+    wkb,
+};
+
+/// `ST_Transform` user defined function (UDF) implementation.
+///
+/// `ST_Transform(geom, target_crs [, source_crs])` reprojects geometries with
+/// [proj4rs](https://crates.io/crates/proj4rs). CRSs are given as EPSG codes,
+/// `EPSG:xxxx` or PROJ strings, the source CRS defaults to the one tracked by
+/// the [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule), which
+/// also records the target CRS in the extension metadata of the result.
+///
+/// Rects (bounding boxes) are reprojected to the bounds of their reprojected
+/// boundary.
+#[derive(Debug, Clone)]
+pub struct Transform {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl Transform {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Any(2),
+                    TypeSignature::Any(3),
+                    TypeSignature::Any(6),
+                    TypeSignature::Any(7),
+                ],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_transform".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for Transform {
+    /// To downcast the ScalarUDFImpl trait object
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Return the name of this function
+    fn name(&self) -> &str {
+        "ST_Transform"
+    }
+
+    /// Return the "signature" of this function -- namely what types of arguments it will take
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The type of value that will be returned by this function.
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType, DataFusionError> {
+        Ok(arg_types[0].clone())
+    }
+
+    /// This is the function that actually calculates the results.
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // DataFusion has arranged for the correct inputs to be passed to this
+        // function, but we check again to make sure
+        assert!(args.len() == 6 || args.len() == 7);
+
+        let geoms = match &args[0] {
+            ColumnarValue::Array(array) => array,
+            ColumnarValue::Scalar(scalar) => &scalar.to_array()?,
+        };
+        let scalar = matches!(&args[0], ColumnarValue::Scalar(_));
+        let [_geomtype, _encoding, crs, _edges] = &args[args.len() - 4..] else {
+            unreachable!()
+        };
+
+        let target = crs_value(&args[1])?.ok_or_else(|| {
+            DataFusionError::Execution("ST_Transform: target CRS must not be null".to_string())
+        })?;
+        let source = match args.len() {
+            7 => crs_value(&args[2])?,
+            _ => crs_arg(crs)?.map(String::from),
+        }
+        .ok_or_else(|| {
+            DataFusionError::Execution(
+                "ST_Transform: unknown source CRS, pass it as third argument or use ST_SetSRID"
+                    .to_string(),
+            )
+        })?;
+
+        let source = proj(&source)?;
+        let target = proj(&target)?;
+        let reproject = |coord: &mut [f64]| {
+            let mut point = (coord[0], coord[1], coord.get(2).copied().unwrap_or(0.));
+            if source.is_latlong() {
+                point.0 = point.0.to_radians();
+                point.1 = point.1.to_radians();
+            }
+            transform(&source, &target, &mut point)
+                .map_err(|e| DataFusionError::Execution(format!("ST_Transform: {e}")))?;
+            if target.is_latlong() {
+                point.0 = point.0.to_degrees();
+                point.1 = point.1.to_degrees();
+            }
+
+            coord[0] = point.0;
+            coord[1] = point.1;
+            if let Some(z) = coord.get_mut(2) {
+                *z = point.2;
+            }
+            Ok(())
+        };
+
+        let transformed = match geoms.data_type() {
+            DataType::Binary => map_wkb_coords(geoms.as_binary::<i32>(), &reproject)?,
+            DataType::LargeBinary => map_wkb_coords(geoms.as_binary::<i64>(), &reproject)?,
+            DataType::Struct(fields) if fields.first().is_some_and(|f| f.name() == "xmin") => {
+                map_rect_coords(geoms.as_struct(), &reproject)?
+            }
+            _ => map_coords(geoms, &reproject)?,
+        };
+
+        match scalar {
+            true => Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                &transformed,
+                0,
+            )?)),
+            false => Ok(ColumnarValue::from(transformed)),
+        }
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+/// Apply `f` to the XY(Z) ordinates of every coordinate of a WKB array,
+/// measures are kept as they are.
+fn map_wkb_coords<O: OffsetSizeTrait>(
+    array: &GenericBinaryArray<O>,
+    f: &dyn Fn(&mut [f64]) -> Result<(), DataFusionError>,
+) -> Result<ArrayRef, DataFusionError> {
+    let wkb = array
+        .iter()
+        .map(|wkb| {
+            wkb.map(|wkb| {
+                let mut geom = wkb::parse(wkb)?;
+                let n = match geom.dim {
+                    Dimensions::Xyz | Dimensions::Xyzm => 3,
+                    _ => 2,
+                };
+                geom.for_each_coord(&mut |coord| f(&mut coord[..n]))?;
+                Ok(geom.to_wkb())
+            })
+            .transpose()
+        })
+        .collect::<Result<GenericBinaryArray<O>, DataFusionError>>()?;
+
+    Ok(Arc::new(wkb))
+}
+
+/// Number of points per edge at which the boundary of a rect is reprojected.
+const RECT_DENSIFY_POINTS: usize = 21;
+
+/// Reproject native rects (bounding boxes) to the bounds of their reprojected
+/// boundaries, densified with [`RECT_DENSIFY_POINTS`] points per edge as the
+/// edges of a rect are generally curved in the target CRS. The Z range of 3D
+/// rects is the range of the boundaries at the lower and upper Z.
+fn map_rect_coords(
+    rects: &StructArray,
+    f: &dyn Fn(&mut [f64]) -> Result<(), DataFusionError>,
+) -> Result<ArrayRef, DataFusionError> {
+    let dims = rects.num_columns() / 2;
+    let bounds = rects
+        .columns()
+        .iter()
+        .map(|column| column.as_primitive::<Float64Type>())
+        .collect::<Vec<_>>();
+    let mut values = vec![vec![f64::NAN; rects.len()]; 2 * dims];
+
+    for row in 0..rects.len() {
+        let [min, max] = [0, dims].map(|offset| {
+            (0..dims)
+                .map(|d| bounds[offset + d].value(row))
+                .collect::<Vec<_>>()
+        });
+        if rects.is_null(row) || min[0].is_nan() {
+            continue;
+        }
+
+        let mut lower = vec![f64::INFINITY; dims];
+        let mut upper = vec![f64::NEG_INFINITY; dims];
+        let zs = match dims {
+            3 => vec![min[2], max[2]],
+            _ => vec![0.],
+        };
+        for z in zs {
+            for i in 0..RECT_DENSIFY_POINTS {
+                let t = i as f64 / (RECT_DENSIFY_POINTS - 1) as f64;
+                let x = min[0] + t * (max[0] - min[0]);
+                let y = min[1] + t * (max[1] - min[1]);
+                for (x, y) in [(x, min[1]), (x, max[1]), (min[0], y), (max[0], y)] {
+                    let mut coord = [x, y, z];
+                    f(&mut coord[..dims])?;
+                    for d in 0..dims {
+                        lower[d] = lower[d].min(coord[d]);
+                        upper[d] = upper[d].max(coord[d]);
+                    }
+                }
+            }
+        }
+
+        for d in 0..dims {
+            values[d][row] = lower[d];
+            values[dims + d][row] = upper[d];
+        }
+    }
+
+    Ok(Arc::new(StructArray::try_new(
+        rects.fields().clone(),
+        values
+            .into_iter()
+            .map(|values| Arc::new(Float64Array::from(values)) as ArrayRef)
+            .collect(),
+        rects.nulls().cloned(),
+    )?))
+}
+
+/// CRS given as EPSG code or string argument.
+fn crs_value(arg: &ColumnarValue) -> Result<Option<String>, DataFusionError> {
+    match arg {
+        ColumnarValue::Scalar(ScalarValue::Utf8(s) | ScalarValue::Utf8View(s)) => Ok(s.clone()),
+        ColumnarValue::Scalar(scalar) if scalar.data_type().is_integer() => {
+            Ok((!scalar.is_null()).then(|| format!("EPSG:{scalar}")))
+        }
+        ColumnarValue::Scalar(ScalarValue::Null) => Ok(None),
+        arg => Err(DataFusionError::Execution(format!(
+            "ST_Transform: expected a constant CRS, got `{arg:?}`"
+        ))),
+    }
+}
+
+/// Projection for a PROJ string or a CRS with an EPSG code.
+fn proj(crs: &str) -> Result<Proj, DataFusionError> {
+    let definition = if crs.trim_start().starts_with('+') {
+        crs
+    } else {
+        srid(crs)
+            .and_then(|code| u16::try_from(code).ok())
+            .and_then(crs_definitions::from_code)
+            .map(|def| def.proj4)
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!("ST_Transform: unsupported CRS `{crs}`"))
+            })?
+    };
+
+    Proj::from_proj_string(definition)
+        .map_err(|e| DataFusionError::Execution(format!("ST_Transform: `{crs}`: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::buffer::NullBuffer;
+    use geoarrow::datatypes::{Dimension, NativeType};
+
+    use super::*;
+
+    #[test]
+    fn rect() {
+        let DataType::Struct(fields) = NativeType::Rect(Dimension::XY).to_data_type() else {
+            unreachable!()
+        };
+        let bounds = [[7., 0.], [46., 0.], [8., 0.], [47., 0.]]
+            .map(|values| Arc::new(Float64Array::from(values.to_vec())) as ArrayRef);
+        let rects = StructArray::try_new(
+            fields,
+            bounds.to_vec(),
+            Some(NullBuffer::from(vec![true, false])),
+        )
+        .unwrap();
+
+        let args = [
+            ColumnarValue::Array(Arc::new(rects)),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(2056))),
+            ColumnarValue::Scalar(ScalarValue::Null),
+            ColumnarValue::Scalar(ScalarValue::Null),
+            ColumnarValue::Scalar(ScalarValue::from("EPSG:4326")),
+            ColumnarValue::Scalar(ScalarValue::Null),
+        ];
+        let ColumnarValue::Array(transformed) = Transform::new().invoke(&args).unwrap() else {
+            unreachable!()
+        };
+
+        let rects = transformed.as_struct();
+        let bound = |i: usize| rects.column(i).as_primitive::<Float64Type>().value(0);
+        let (xmin, ymin, xmax, ymax) = (bound(0), bound(1), bound(2), bound(3));
+        // bounds of the densified boundary around the corners in LV95
+        assert!((2_500_000.0..2_600_000.0).contains(&xmin), "{xmin}");
+        assert!((1_090_000.0..1_110_000.0).contains(&ymin), "{ymin}");
+        assert!((2_570_000.0..2_680_000.0).contains(&xmax), "{xmax}");
+        assert!((1_200_000.0..1_230_000.0).contains(&ymax), "{ymax}");
+        assert!(xmin < xmax && ymin < ymax);
+        assert!(rects.is_null(1));
+    }
+
+    #[test]
+    fn lv95() {
+        let source = proj("EPSG:4326").unwrap();
+        let target = proj("EPSG:2056").unwrap();
+
+        // projection center, old observatory of Bern
+        let mut point = (7.439583_f64.to_radians(), 46.952406_f64.to_radians(), 0.);
+        transform(&source, &target, &mut point).unwrap();
+
+        assert!((point.0 - 2_600_000.0).abs() < 100.);
+        assert!((point.1 - 1_200_000.0).abs() < 100.);
+    }
+}
@@ -0,0 +1,401 @@
+//! Regular grids of cells covering a geometry, generated batch by batch.
+
+use std::{any::Any, fmt, ops::RangeInclusive, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, Int64Array, RecordBatch},
+        datatypes::{DataType, Field, Schema, SchemaRef},
+    },
+    catalog::Session,
+    datasource::TableProvider,
+    error::{DataFusionError, Result},
+    execution::TaskContext,
+    logical_expr::{Expr, TableType},
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        stream::RecordBatchStreamAdapter, DisplayAs, DisplayFormatType, ExecutionMode,
+        ExecutionPlan, Partitioning, PlanProperties, SendableRecordBatchStream,
+    },
+    scalar::ScalarValue,
+};
+use geo::{BoundingRect, Intersects, LineString, Polygon, Rect};
+use geoarrow::{
+    array::{CoordType, PolygonBuilder},
+    datatypes::{Dimension, NativeType},
+    ArrayBase,
+};
+
+use crate::wkb;
+
+/// Shape of the cells of a grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Tiling {
+    Square,
+    Hexagon,
+}
+
+/// Grid of cells of a given size covering a bounds geometry.
+#[derive(Debug)]
+pub(super) struct Grid {
+    tiling: Tiling,
+    size: f64,
+    bounds: geo::Geometry,
+    /// Bounding box of the bounds, `None` if they are empty.
+    rect: Option<Rect>,
+}
+
+impl Grid {
+    /// Grid of the constant `size` and `bounds` (WKT or WKB) arguments of
+    /// the table function `name`.
+    pub(super) fn try_new(name: &str, tiling: Tiling, args: &[Expr]) -> Result<Self> {
+        let [size, bounds] = args else {
+            return Err(DataFusionError::Plan(format!(
+                "{name} expects a cell size and bounds, got {} arguments",
+                args.len()
+            )));
+        };
+
+        let size = match size {
+            Expr::Literal(size) => size.cast_to(&DataType::Float64).ok(),
+            _ => None,
+        };
+        let size = match size {
+            Some(ScalarValue::Float64(Some(size))) if size > 0. && size.is_finite() => size,
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "{name} expects a positive constant cell size, got `{}`",
+                    args[0]
+                )))
+            }
+        };
+
+        let bounds = match bounds {
+            Expr::Literal(
+                ScalarValue::Utf8(Some(text))
+                | ScalarValue::Utf8View(Some(text))
+                | ScalarValue::LargeUtf8(Some(text)),
+            ) => {
+                let wkt = wkt::Wkt::<f64>::from_str(text).map_err(|e| {
+                    DataFusionError::Plan(format!("{name}: invalid WKT bounds `{text}`: {e}"))
+                })?;
+                wkb::from_wkt(&wkt).to_geo()
+            }
+            Expr::Literal(
+                ScalarValue::Binary(Some(bytes)) | ScalarValue::LargeBinary(Some(bytes)),
+            ) => wkb::parse(bytes)?.to_geo(),
+            bounds => {
+                return Err(DataFusionError::Plan(format!(
+                    "{name} expects constant WKT or WKB bounds, got `{bounds}`"
+                )))
+            }
+        };
+
+        Ok(Self {
+            tiling,
+            size,
+            rect: bounds.bounding_rect(),
+            bounds,
+        })
+    }
+
+    /// Height of a row of cells.
+    fn height(&self) -> f64 {
+        match self.tiling {
+            Tiling::Square => self.size,
+            Tiling::Hexagon => self.size * 3f64.sqrt(),
+        }
+    }
+
+    /// Columns and rows of the cells that may intersect the bounds, empty
+    /// if they are empty.
+    #[allow(clippy::reversed_empty_ranges)]
+    fn ranges(&self) -> (RangeInclusive<i64>, RangeInclusive<i64>) {
+        let Some(rect) = self.rect else {
+            return (1..=0, 1..=0);
+        };
+
+        let (min, max, height) = (rect.min(), rect.max(), self.height());
+        match self.tiling {
+            Tiling::Square => (
+                (min.x / self.size).floor() as i64..=(max.x / self.size).floor() as i64,
+                (min.y / height).floor() as i64..=(max.y / height).floor() as i64,
+            ),
+            // hexagons reach `size` left and right of their center, and
+            // half a row up and down, shifted by half a row in odd columns
+            Tiling::Hexagon => {
+                let width = 1.5 * self.size;
+                (
+                    ((min.x - self.size) / width).floor() as i64
+                        ..=((max.x + self.size) / width).ceil() as i64,
+                    ((min.y - height) / height).floor() as i64..=(max.y / height).ceil() as i64,
+                )
+            }
+        }
+    }
+
+    /// Polygon of the cell in column `i` and row `j`.
+    fn cell(&self, i: i64, j: i64) -> Polygon {
+        let (size, height) = (self.size, self.height());
+        let ring = match self.tiling {
+            Tiling::Square => {
+                let (x, y) = (i as f64 * size, j as f64 * size);
+                vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)]
+            }
+            Tiling::Hexagon => {
+                let x = i as f64 * 1.5 * size;
+                let y = (j as f64 + 0.5 * i.rem_euclid(2) as f64) * height;
+                let (dx, dy) = (size / 2., height / 2.);
+                vec![
+                    (x + size, y),
+                    (x + dx, y + dy),
+                    (x - dx, y + dy),
+                    (x - size, y),
+                    (x - dx, y - dy),
+                    (x + dx, y - dy),
+                ]
+            }
+        };
+        // closed by `Polygon::new`
+        Polygon::new(LineString::from(ring), vec![])
+    }
+
+    /// Cells intersecting the bounds with their column and row, column by
+    /// column.
+    fn cells(self: Arc<Self>) -> impl Iterator<Item = (i64, i64, Polygon)> {
+        let (columns, rows) = self.ranges();
+        columns
+            .flat_map(move |i| rows.clone().map(move |j| (i, j)))
+            .filter_map(move |(i, j)| {
+                let cell = self.cell(i, j);
+                self.bounds.intersects(&cell).then_some((i, j, cell))
+            })
+    }
+}
+
+/// Columns of a grid: the cell polygon, its column and its row.
+fn grid_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        NativeType::Polygon(CoordType::Separated, Dimension::XY).to_field("geom", false),
+        Field::new("i", DataType::Int64, false),
+        Field::new("j", DataType::Int64, false),
+    ]))
+}
+
+/// Table returned by the grid table functions.
+#[derive(Debug)]
+pub(super) struct GridTable {
+    grid: Arc<Grid>,
+    schema: SchemaRef,
+}
+
+impl GridTable {
+    pub(super) fn new(grid: Grid) -> Self {
+        Self {
+            grid: Arc::new(grid),
+            schema: grid_schema(),
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for GridTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Temporary
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(GridExec::try_new(
+            self.grid.clone(),
+            projection.cloned(),
+            limit,
+        )?))
+    }
+}
+
+/// Execution plan generating the cells of a grid in batches of the
+/// session's batch size, in a single partition.
+#[derive(Debug)]
+struct GridExec {
+    grid: Arc<Grid>,
+    projection: Option<Vec<usize>>,
+    /// Number of cells to generate at most.
+    limit: Option<usize>,
+    properties: PlanProperties,
+}
+
+impl GridExec {
+    fn try_new(
+        grid: Arc<Grid>,
+        projection: Option<Vec<usize>>,
+        limit: Option<usize>,
+    ) -> Result<Self> {
+        let schema = match &projection {
+            Some(projection) => Arc::new(grid_schema().project(projection)?),
+            None => grid_schema(),
+        };
+
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema),
+            Partitioning::UnknownPartitioning(1),
+            ExecutionMode::Bounded,
+        );
+
+        Ok(Self {
+            grid,
+            projection,
+            limit,
+            properties,
+        })
+    }
+}
+
+impl DisplayAs for GridExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(
+                    f,
+                    "GridExec: tiling={:?}, size={}, limit={:?}",
+                    self.grid.tiling, self.grid.size, self.limit
+                )
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for GridExec {
+    fn name(&self) -> &str {
+        "GridExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        _partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let batch_size = context.session_config().batch_size();
+        let projection = self.projection.clone();
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let mut cells = self.grid.clone().cells().take(limit);
+
+        // cells are only generated as batches are polled
+        let batches = std::iter::from_fn(move || {
+            let cells = cells.by_ref().take(batch_size).collect::<Vec<_>>();
+            if cells.is_empty() {
+                return None;
+            }
+            let batch = cells_batch(cells).and_then(|batch| match &projection {
+                Some(projection) => Ok(batch.project(projection)?),
+                None => Ok(batch),
+            });
+            Some(batch)
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.properties.eq_properties.schema().clone(),
+            futures::stream::iter(batches),
+        )))
+    }
+}
+
+/// Record batch of grid cells.
+fn cells_batch(cells: Vec<(i64, i64, Polygon)>) -> Result<RecordBatch> {
+    let mut builder: PolygonBuilder<2> =
+        PolygonBuilder::new_with_options(CoordType::Separated, Default::default());
+    for (_, _, cell) in &cells {
+        builder
+            .push_polygon(Some(cell))
+            .map_err(|e| DataFusionError::Internal(e.to_string()))?;
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        builder.finish().to_array_ref(),
+        Arc::new(Int64Array::from_iter_values(cells.iter().map(|c| c.0))),
+        Arc::new(Int64Array::from_iter_values(cells.iter().map(|c| c.1))),
+    ];
+    Ok(RecordBatch::try_new(grid_schema(), columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{point, Contains};
+
+    use super::*;
+
+    fn grid(tiling: Tiling, size: f64, bounds: &str) -> Grid {
+        let bounds = Expr::Literal(ScalarValue::Utf8(Some(bounds.to_string())));
+        Grid::try_new(
+            "grid",
+            tiling,
+            &[Expr::Literal(ScalarValue::from(size)), bounds],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn square_cells() {
+        let bounds = "POLYGON ((0.5 0.5, 2.5 0.5, 2.5 1.5, 0.5 1.5, 0.5 0.5))";
+        let cells = Arc::new(grid(Tiling::Square, 1., bounds)).cells();
+        let indices = cells.map(|(i, j, _)| (i, j)).collect::<Vec<_>>();
+        assert_eq!(
+            indices,
+            vec![(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)]
+        );
+
+        let empty = Arc::new(grid(Tiling::Square, 1., "POLYGON EMPTY"));
+        assert_eq!(empty.cells().count(), 0);
+    }
+
+    #[test]
+    fn hexagon_cells() {
+        let grid = Arc::new(grid(Tiling::Hexagon, 2., "POINT (0 0)"));
+        assert!(grid.cell(0, 0).contains(&point!(x: 0., y: 0.)));
+        assert_eq!(
+            grid.clone()
+                .cells()
+                .map(|(i, j, _)| (i, j))
+                .collect::<Vec<_>>(),
+            vec![(0, 0)]
+        );
+
+        // odd columns are shifted up half a row, hexagons share their edges
+        let (a, b) = (grid.cell(0, 0), grid.cell(1, 0));
+        assert!(a.exterior().0.contains(&b.exterior().0[3]));
+        assert!(a.exterior().0.contains(&b.exterior().0[4]));
+    }
+}
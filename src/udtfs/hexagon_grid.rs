@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use datafusion::{
+    datasource::{function::TableFunctionImpl, TableProvider},
+    error::Result,
+    logical_expr::Expr,
+};
+
+use super::grid::{Grid, GridTable, Tiling};
+
+/// `ST_HexagonGrid(size, bounds)` user defined table function (UDTF).
+///
+/// Flat topped hexagons of edge length `size` covering the bounds geometry,
+/// given as WKT or WKB, as native polygons with their column `i` and row
+/// `j`. Hexagon (0, 0) is centered on the origin and odd columns are shifted
+/// up by half a row. Cells are generated lazily, batch by batch.
+#[derive(Debug)]
+pub struct HexagonGrid;
+
+impl TableFunctionImpl for HexagonGrid {
+    fn call(&self, args: &[Expr]) -> Result<Arc<dyn TableProvider>> {
+        let grid = Grid::try_new("ST_HexagonGrid", Tiling::Hexagon, args)?;
+        Ok(Arc::new(GridTable::new(grid)))
+    }
+}
@@ -0,0 +1,6 @@
+mod grid;
+mod hexagon_grid;
+mod square_grid;
+
+pub use hexagon_grid::HexagonGrid;
+pub use square_grid::SquareGrid;
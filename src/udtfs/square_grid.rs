@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use datafusion::{
+    datasource::{function::TableFunctionImpl, TableProvider},
+    error::Result,
+    logical_expr::Expr,
+};
+
+use super::grid::{Grid, GridTable, Tiling};
+
+/// `ST_SquareGrid(size, bounds)` user defined table function (UDTF).
+///
+/// Square cells of side `size` aligned on the origin covering the bounds
+/// geometry, given as WKT or WKB, as native polygons with their column `i`
+/// and row `j`. Cells are generated lazily, batch by batch.
+#[derive(Debug)]
+pub struct SquareGrid;
+
+impl TableFunctionImpl for SquareGrid {
+    fn call(&self, args: &[Expr]) -> Result<Arc<dyn TableProvider>> {
+        let grid = Grid::try_new("ST_SquareGrid", Tiling::Square, args)?;
+        Ok(Arc::new(GridTable::new(grid)))
+    }
+}
@@ -0,0 +1,109 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, UInt32Array},
+        datatypes::{DataType, Field},
+    },
+    error::{DataFusionError, Result},
+    logical_expr::{
+        function::WindowUDFFieldArgs, ColumnarValue, PartitionEvaluator, Signature, TypeSignature,
+        Volatility, WindowUDFImpl,
+    },
+    scalar::ScalarValue,
+};
+
+use crate::{cluster::dbscan, helpers::geo_geometries};
+
+/// `ST_ClusterDBSCAN` user defined window function (UDWF) implementation.
+///
+/// DBSCAN cluster number of each geometry of a window partition, NULL for
+/// noise. Geometries with at least `minpoints` geometries (themselves
+/// included) within distance `eps` are cluster cores, distances are measured
+/// between geometries rather than their centroids.
+#[derive(Debug)]
+pub struct ClusterDbscan {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl ClusterDbscan {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(3), TypeSignature::Any(7)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_clusterdbscan".to_string()],
+        }
+    }
+}
+
+impl WindowUDFImpl for ClusterDbscan {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_ClusterDBSCAN"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn partition_evaluator(&self) -> Result<Box<dyn PartitionEvaluator>> {
+        Ok(Box::new(DbscanEvaluator))
+    }
+
+    fn field(&self, field_args: WindowUDFFieldArgs) -> Result<Field> {
+        Ok(Field::new(field_args.name(), DataType::UInt32, true))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[derive(Debug)]
+struct DbscanEvaluator;
+
+impl PartitionEvaluator for DbscanEvaluator {
+    fn evaluate_all(&mut self, values: &[ArrayRef], num_rows: usize) -> Result<ArrayRef> {
+        if num_rows == 0 {
+            return Ok(Arc::new(UInt32Array::from(Vec::<u32>::new())));
+        }
+
+        let eps = ScalarValue::try_from_array(&values[1], 0)?.cast_to(&DataType::Float64)?;
+        let eps = match eps {
+            ScalarValue::Float64(Some(eps)) if eps >= 0. => eps,
+            eps => {
+                return Err(DataFusionError::Execution(format!(
+                    "ST_ClusterDBSCAN: expected a non-negative eps, got {eps}"
+                )))
+            }
+        };
+        let min_points = ScalarValue::try_from_array(&values[2], 0)?.cast_to(&DataType::Int64)?;
+        let min_points = match min_points {
+            ScalarValue::Int64(Some(min_points)) if min_points >= 0 => min_points as usize,
+            min_points => {
+                return Err(DataFusionError::Execution(format!(
+                    "ST_ClusterDBSCAN: expected a non-negative minpoints, got {min_points}"
+                )))
+            }
+        };
+
+        let geometry_type = match values.get(3) {
+            Some(geometry_type) => ScalarValue::try_from_array(geometry_type, 0)?,
+            None => ScalarValue::Null,
+        };
+        let geometries = geo_geometries(&values[0], &ColumnarValue::Scalar(geometry_type))?;
+
+        Ok(Arc::new(UInt32Array::from(dbscan(
+            &geometries,
+            eps,
+            min_points,
+        ))))
+    }
+}
@@ -0,0 +1,111 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, UInt32Array},
+        datatypes::{DataType, Field},
+    },
+    error::{DataFusionError, Result},
+    logical_expr::{
+        function::WindowUDFFieldArgs, ColumnarValue, PartitionEvaluator, Signature, TypeSignature,
+        Volatility, WindowUDFImpl,
+    },
+    scalar::ScalarValue,
+};
+
+use crate::{cluster::components, helpers::geo_geometries};
+
+/// `ST_ClusterIntersecting` user defined window function (UDWF)
+/// implementation.
+///
+/// Connected component number of each geometry of a window partition,
+/// geometries being connected when they intersect, NULL for NULL and empty
+/// geometries. DataFusion resolves windowed calls to the aggregate of the
+/// same name, [`SpatialAnalyzerRule`](crate::rules::SpatialAnalyzerRule)
+/// replaces them with this window function.
+#[derive(Debug)]
+pub struct ClusterIntersecting {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl ClusterIntersecting {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(1), TypeSignature::Any(5)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_clusterintersecting".to_string()],
+        }
+    }
+}
+
+impl WindowUDFImpl for ClusterIntersecting {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_ClusterIntersecting"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn partition_evaluator(&self) -> Result<Box<dyn PartitionEvaluator>> {
+        Ok(Box::new(ComponentsEvaluator { within: false }))
+    }
+
+    fn field(&self, field_args: WindowUDFFieldArgs) -> Result<Field> {
+        Ok(Field::new(field_args.name(), DataType::UInt32, true))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct ComponentsEvaluator {
+    /// Whether the second argument is a distance.
+    pub(super) within: bool,
+}
+
+impl PartitionEvaluator for ComponentsEvaluator {
+    fn evaluate_all(&mut self, values: &[ArrayRef], num_rows: usize) -> Result<ArrayRef> {
+        if num_rows == 0 {
+            return Ok(Arc::new(UInt32Array::from(Vec::<u32>::new())));
+        }
+
+        let distance = match self.within {
+            true => {
+                let distance =
+                    ScalarValue::try_from_array(&values[1], 0)?.cast_to(&DataType::Float64)?;
+                match distance {
+                    ScalarValue::Float64(Some(distance)) if distance >= 0. => distance,
+                    distance => {
+                        return Err(DataFusionError::Execution(format!(
+                            "ST_ClusterWithin: expected a non-negative distance, got {distance}"
+                        )))
+                    }
+                }
+            }
+            false => 0.,
+        };
+
+        let offset = if self.within { 2 } else { 1 };
+        let geometry_type = match values.get(offset) {
+            Some(geometry_type) => ScalarValue::try_from_array(geometry_type, 0)?,
+            None => ScalarValue::Null,
+        };
+        let geometries = geo_geometries(&values[0], &ColumnarValue::Scalar(geometry_type))?;
+
+        Ok(Arc::new(UInt32Array::from(components(
+            &geometries,
+            distance,
+        ))))
+    }
+}
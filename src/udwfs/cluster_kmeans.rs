@@ -0,0 +1,123 @@
+use std::{any::Any, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::{ArrayRef, UInt32Array},
+        datatypes::{DataType, Field},
+    },
+    error::{DataFusionError, Result},
+    logical_expr::{
+        function::WindowUDFFieldArgs, ColumnarValue, PartitionEvaluator, Signature, TypeSignature,
+        Volatility, WindowUDFImpl,
+    },
+    scalar::ScalarValue,
+};
+use geo::Centroid;
+
+use crate::{cluster::kmeans, helpers::geo_geometries};
+
+/// `ST_ClusterKMeans` user defined window function (UDWF) implementation.
+///
+/// K-means cluster number of each geometry of a window partition by the
+/// geometries' centroids, NULL for NULL and empty geometries. With a
+/// `max_radius` more than `k` clusters are formed so that no geometry is
+/// farther than that from its cluster's center. Seeding is deterministic and
+/// independent of the row order.
+#[derive(Debug)]
+pub struct ClusterKMeans {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl ClusterKMeans {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Any(2),
+                    TypeSignature::Any(3),
+                    TypeSignature::Any(6),
+                    TypeSignature::Any(7),
+                ],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_clusterkmeans".to_string()],
+        }
+    }
+}
+
+impl WindowUDFImpl for ClusterKMeans {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_ClusterKMeans"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn partition_evaluator(&self) -> Result<Box<dyn PartitionEvaluator>> {
+        Ok(Box::new(KMeansEvaluator))
+    }
+
+    fn field(&self, field_args: WindowUDFFieldArgs) -> Result<Field> {
+        Ok(Field::new(field_args.name(), DataType::UInt32, true))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
+
+#[derive(Debug)]
+struct KMeansEvaluator;
+
+impl PartitionEvaluator for KMeansEvaluator {
+    fn evaluate_all(&mut self, values: &[ArrayRef], num_rows: usize) -> Result<ArrayRef> {
+        if num_rows == 0 {
+            return Ok(Arc::new(UInt32Array::from(Vec::<u32>::new())));
+        }
+
+        let k = ScalarValue::try_from_array(&values[1], 0)?.cast_to(&DataType::Int64)?;
+        let k = match k {
+            ScalarValue::Int64(Some(k)) if k > 0 => k as usize,
+            k => {
+                return Err(DataFusionError::Execution(format!(
+                    "ST_ClusterKMeans: expected a positive number of clusters, got {k}"
+                )))
+            }
+        };
+
+        // the analyzer appends four arguments to the two or three of the call
+        let radius = matches!(values.len(), 3 | 7);
+        let max_radius = match radius {
+            true => {
+                match ScalarValue::try_from_array(&values[2], 0)?.cast_to(&DataType::Float64)? {
+                    ScalarValue::Float64(max_radius) => max_radius,
+                    _ => unreachable!(),
+                }
+            }
+            false => None,
+        };
+
+        let offset = if radius { 3 } else { 2 };
+        let geometry_type = match values.get(offset) {
+            Some(geometry_type) => ScalarValue::try_from_array(geometry_type, 0)?,
+            None => ScalarValue::Null,
+        };
+        let centroids = geo_geometries(&values[0], &ColumnarValue::Scalar(geometry_type))?
+            .iter()
+            .map(|geometry| Some(geometry.as_ref()?.centroid()?.0))
+            // empty points
+            .map(|centroid| centroid.filter(|c| !c.x.is_nan()))
+            .collect::<Vec<_>>();
+
+        Ok(Arc::new(UInt32Array::from(kmeans(
+            &centroids, k, max_radius,
+        ))))
+    }
+}
@@ -0,0 +1,61 @@
+use std::any::Any;
+
+use datafusion::{
+    arrow::datatypes::{DataType, Field},
+    error::Result,
+    logical_expr::{
+        function::WindowUDFFieldArgs, PartitionEvaluator, Signature, TypeSignature, Volatility,
+        WindowUDFImpl,
+    },
+};
+
+use super::cluster_intersecting::ComponentsEvaluator;
+
+/// `ST_ClusterWithin` user defined window function (UDWF) implementation.
+///
+/// Like the `ST_ClusterIntersecting` window function, but geometries within
+/// `distance` of each other are connected.
+#[derive(Debug)]
+pub struct ClusterWithin {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl ClusterWithin {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Any(2), TypeSignature::Any(6)],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["st_clusterwithin".to_string()],
+        }
+    }
+}
+
+impl WindowUDFImpl for ClusterWithin {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "ST_ClusterWithin"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn partition_evaluator(&self) -> Result<Box<dyn PartitionEvaluator>> {
+        Ok(Box::new(ComponentsEvaluator { within: true }))
+    }
+
+    fn field(&self, field_args: WindowUDFFieldArgs) -> Result<Field> {
+        Ok(Field::new(field_args.name(), DataType::UInt32, true))
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+}
@@ -0,0 +1,9 @@
+mod cluster_dbscan;
+mod cluster_intersecting;
+mod cluster_kmeans;
+mod cluster_within;
+
+pub use cluster_dbscan::ClusterDbscan;
+pub use cluster_intersecting::ClusterIntersecting;
+pub use cluster_kmeans::ClusterKMeans;
+pub use cluster_within::ClusterWithin;
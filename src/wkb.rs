@@ -0,0 +1,869 @@
+//! WKB decoding and encoding for all coordinate dimensions.
+//!
+//! Native GeoArrow arrays hold XY and XYZ coordinates only, geometries with
+//! measures (XYM and XYZM) are kept as WKB and handled here. ISO WKB and
+//! EWKB are read in both byte orders, ISO WKB is written in little endian.
+
+use std::{fmt::Write, ops::Range};
+
+use datafusion::error::{DataFusionError, Result};
+use geo_traits::Dimensions;
+
+/// Geometry kinds with their WKB type codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Point = 1,
+    LineString = 2,
+    Polygon = 3,
+    MultiPoint = 4,
+    MultiLineString = 5,
+    MultiPolygon = 6,
+    GeometryCollection = 7,
+}
+
+impl Kind {
+    /// Multi geometry kind holding parts of this kind.
+    fn multi(&self) -> Option<Self> {
+        match self {
+            Self::Point => Some(Self::MultiPoint),
+            Self::LineString => Some(Self::MultiLineString),
+            Self::Polygon => Some(Self::MultiPolygon),
+            _ => None,
+        }
+    }
+
+    fn try_from_code(code: u32) -> Result<Self> {
+        Ok(match code {
+            1 => Self::Point,
+            2 => Self::LineString,
+            3 => Self::Polygon,
+            4 => Self::MultiPoint,
+            5 => Self::MultiLineString,
+            6 => Self::MultiPolygon,
+            7 => Self::GeometryCollection,
+            code => {
+                return Err(DataFusionError::Execution(format!(
+                    "Unsupported WKB geometry type {code}"
+                )))
+            }
+        })
+    }
+
+    /// GeoParquet geometry type name.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Point => "Point",
+            Self::LineString => "LineString",
+            Self::Polygon => "Polygon",
+            Self::MultiPoint => "MultiPoint",
+            Self::MultiLineString => "MultiLineString",
+            Self::MultiPolygon => "MultiPolygon",
+            Self::GeometryCollection => "GeometryCollection",
+        }
+    }
+}
+
+/// Number of ordinates of a coordinate.
+pub fn size(dim: Dimensions) -> usize {
+    match dim {
+        Dimensions::Xy => 2,
+        Dimensions::Xyz | Dimensions::Xym => 3,
+        Dimensions::Xyzm => 4,
+        Dimensions::Unknown(n) => n,
+    }
+}
+
+/// GeoParquet geometry type name, e.g. `Point ZM`.
+fn type_name(kind: Kind, dim: Dimensions) -> String {
+    match dim {
+        Dimensions::Xyz => format!("{} Z", kind.name()),
+        Dimensions::Xym => format!("{} M", kind.name()),
+        Dimensions::Xyzm => format!("{} ZM", kind.name()),
+        _ => kind.name().to_string(),
+    }
+}
+
+/// `value` in the given byte order.
+pub fn u32_bytes(value: u32, little_endian: bool) -> [u8; 4] {
+    match little_endian {
+        true => value.to_le_bytes(),
+        false => value.to_be_bytes(),
+    }
+}
+
+/// Write the byte order and ISO type code of a geometry.
+pub fn write_header(buffer: &mut Vec<u8>, kind: Kind, dim: Dimensions, little_endian: bool) {
+    let offset = match dim {
+        Dimensions::Xyz => 1000,
+        Dimensions::Xym => 2000,
+        Dimensions::Xyzm => 3000,
+        _ => 0,
+    };
+    buffer.push(little_endian as u8);
+    buffer.extend(u32_bytes(kind as u32 + offset, little_endian));
+}
+
+/// Index of the measure in a coordinate.
+pub fn m_index(dim: Dimensions) -> Option<usize> {
+    match dim {
+        Dimensions::Xym => Some(2),
+        Dimensions::Xyzm => Some(3),
+        _ => None,
+    }
+}
+
+/// Decoded WKB geometry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geometry {
+    pub kind: Kind,
+    pub dim: Dimensions,
+    /// Interleaved ordinates of points and line strings, NaN for empty points.
+    pub coords: Vec<f64>,
+    /// Rings of polygons, members of multi geometries and collections.
+    pub parts: Vec<Geometry>,
+}
+
+impl Geometry {
+    pub fn new(kind: Kind, dim: Dimensions, coords: Vec<f64>, parts: Vec<Geometry>) -> Self {
+        Self {
+            kind,
+            dim,
+            coords,
+            parts,
+        }
+    }
+
+    /// GeoParquet geometry type name, e.g. `Point ZM`.
+    pub fn type_name(&self) -> String {
+        type_name(self.kind, self.dim)
+    }
+
+    fn is_empty(&self) -> bool {
+        match self.kind {
+            Kind::Point => self.coords.iter().all(|c| c.is_nan()),
+            Kind::LineString => self.coords.is_empty(),
+            _ => self.parts.is_empty(),
+        }
+    }
+
+    /// Bytes allocated on the heap by the coordinates and parts.
+    pub fn heap_size(&self) -> usize {
+        self.coords.capacity() * std::mem::size_of::<f64>()
+            + self.parts.capacity() * std::mem::size_of::<Geometry>()
+            + self.parts.iter().map(Geometry::heap_size).sum::<usize>()
+    }
+
+    /// Apply `f` to every (non empty) coordinate, measures included.
+    pub fn for_each_coord(&mut self, f: &mut dyn FnMut(&mut [f64]) -> Result<()>) -> Result<()> {
+        let size = size(self.dim);
+        for coord in self.coords.chunks_mut(size) {
+            if !coord[0].is_nan() {
+                f(coord)?;
+            }
+        }
+        for part in self.parts.iter_mut() {
+            part.for_each_coord(f)?;
+        }
+        Ok(())
+    }
+
+    /// 2D bounds, `None` for empty geometries.
+    pub fn bounds(&self) -> Option<((f64, f64), (f64, f64))> {
+        let mut bounds = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+        self.clone()
+            .for_each_coord(&mut |c| {
+                let ((xmin, ymin), (xmax, ymax)) = &mut bounds;
+                *xmin = xmin.min(c[0]);
+                *ymin = ymin.min(c[1]);
+                *xmax = xmax.max(c[0]);
+                *ymax = ymax.max(c[1]);
+                Ok(())
+            })
+            .ok()?;
+
+        (bounds.0 .0 <= bounds.1 .0).then_some(bounds)
+    }
+
+    /// 3D bounds as `([xmin, ymin, zmin], [xmax, ymax, zmax])`, Z is 0 for
+    /// geometries without Z. `None` for empty geometries.
+    pub fn bounds_3d(&self) -> Option<([f64; 3], [f64; 3])> {
+        let has_z = matches!(self.dim, Dimensions::Xyz | Dimensions::Xyzm);
+        let mut bounds = ([f64::MAX; 3], [f64::MIN; 3]);
+        self.clone()
+            .for_each_coord(&mut |c| {
+                let z = if has_z { c[2] } else { 0. };
+                for (i, ordinate) in [c[0], c[1], z].into_iter().enumerate() {
+                    bounds.0[i] = bounds.0[i].min(ordinate);
+                    bounds.1[i] = bounds.1[i].max(ordinate);
+                }
+                Ok(())
+            })
+            .ok()?;
+
+        (bounds.0[0] <= bounds.1[0]).then_some(bounds)
+    }
+
+    /// Measure of a point, `None` for other or empty geometries and geometries
+    /// without measures.
+    pub fn m(&self) -> Option<f64> {
+        let index = m_index(self.dim)?;
+        match self.kind {
+            Kind::Point if !self.is_empty() => Some(self.coords[index]),
+            _ => None,
+        }
+    }
+
+    /// Encode as little endian ISO WKB.
+    pub fn to_wkb(&self) -> Vec<u8> {
+        let mut buffer = vec![];
+        self.write_wkb(&mut buffer);
+        buffer
+    }
+
+    fn write_wkb(&self, buffer: &mut Vec<u8>) {
+        write_header(buffer, self.kind, self.dim, true);
+
+        let size = size(self.dim);
+        match self.kind {
+            Kind::Point => {
+                let coords = match self.coords.is_empty() {
+                    true => vec![f64::NAN; size],
+                    false => self.coords.clone(),
+                };
+                coords.iter().for_each(|c| buffer.extend(c.to_le_bytes()));
+            }
+            Kind::LineString => {
+                buffer.extend(((self.coords.len() / size) as u32).to_le_bytes());
+                self.coords
+                    .iter()
+                    .for_each(|c| buffer.extend(c.to_le_bytes()));
+            }
+            Kind::Polygon => {
+                buffer.extend((self.parts.len() as u32).to_le_bytes());
+                for ring in self.parts.iter() {
+                    buffer.extend(((ring.coords.len() / size) as u32).to_le_bytes());
+                    ring.coords
+                        .iter()
+                        .for_each(|c| buffer.extend(c.to_le_bytes()));
+                }
+            }
+            _ => {
+                buffer.extend((self.parts.len() as u32).to_le_bytes());
+                self.parts.iter().for_each(|part| part.write_wkb(buffer));
+            }
+        }
+    }
+
+    /// WKT representation, formatted like [`crate::wkt`].
+    pub fn to_wkt(&self) -> String {
+        let mut wkt = String::new();
+        self.write_wkt(&mut wkt).unwrap();
+        wkt
+    }
+
+    fn write_wkt(&self, writer: &mut String) -> std::fmt::Result {
+        writer.write_str(&self.kind.name().to_uppercase())?;
+        if self.is_empty() {
+            return writer.write_str(" EMPTY");
+        }
+
+        match self.dim {
+            Dimensions::Xyz => writer.write_str(" Z ")?,
+            Dimensions::Xym => writer.write_str(" M ")?,
+            Dimensions::Xyzm => writer.write_str(" ZM ")?,
+            _ => writer.write_char(' ')?,
+        }
+
+        match self.kind {
+            Kind::Point | Kind::LineString => self.write_wkt_coords(writer),
+            Kind::GeometryCollection => {
+                writer.write_char('(')?;
+                for (i, part) in self.parts.iter().enumerate() {
+                    if i > 0 {
+                        writer.write_char(',')?;
+                    }
+                    part.write_wkt(writer)?;
+                }
+                writer.write_char(')')
+            }
+            _ => self.write_wkt_parts(writer),
+        }
+    }
+
+    fn write_wkt_coords(&self, writer: &mut String) -> std::fmt::Result {
+        writer.write_char('(')?;
+        for (i, coord) in self.coords.chunks(size(self.dim)).enumerate() {
+            if i > 0 {
+                writer.write_char(',')?;
+            }
+            for (j, ordinate) in coord.iter().enumerate() {
+                if j > 0 {
+                    writer.write_char(' ')?;
+                }
+                write!(writer, "{ordinate:?}")?;
+            }
+        }
+        writer.write_char(')')
+    }
+
+    fn write_wkt_parts(&self, writer: &mut String) -> std::fmt::Result {
+        writer.write_char('(')?;
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 {
+                writer.write_char(',')?;
+            }
+            match part.kind {
+                Kind::Point | Kind::LineString => part.write_wkt_coords(writer)?,
+                _ => part.write_wkt_parts(writer)?,
+            }
+        }
+        writer.write_char(')')
+    }
+
+    /// Convert into a [`geo`] geometry, measures are dropped.
+    pub fn to_geo(&self) -> geo::Geometry {
+        let size = size(self.dim);
+        let coords = |coords: &[f64]| {
+            geo::LineString::from(
+                coords
+                    .chunks(size)
+                    .map(|c| geo::Coord { x: c[0], y: c[1] })
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let polygon = |polygon: &Geometry| {
+            let mut rings = polygon.parts.iter().map(|r| coords(&r.coords));
+            geo::Polygon::new(
+                rings.next().unwrap_or_else(|| geo::LineString::new(vec![])),
+                rings.collect(),
+            )
+        };
+
+        match self.kind {
+            Kind::Point => match self.coords.as_slice() {
+                [x, y, ..] => geo::Geometry::Point(geo::Point::new(*x, *y)),
+                _ => geo::Geometry::Point(geo::Point::new(f64::NAN, f64::NAN)),
+            },
+            Kind::LineString => geo::Geometry::LineString(coords(&self.coords)),
+            Kind::Polygon => geo::Geometry::Polygon(polygon(self)),
+            Kind::MultiPoint => geo::Geometry::MultiPoint(
+                self.parts
+                    .iter()
+                    .filter_map(|p| match p.to_geo() {
+                        geo::Geometry::Point(p) => Some(p),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            Kind::MultiLineString => geo::Geometry::MultiLineString(geo::MultiLineString(
+                self.parts.iter().map(|p| coords(&p.coords)).collect(),
+            )),
+            Kind::MultiPolygon => geo::Geometry::MultiPolygon(geo::MultiPolygon(
+                self.parts.iter().map(polygon).collect(),
+            )),
+            Kind::GeometryCollection => geo::Geometry::GeometryCollection(geo::GeometryCollection(
+                self.parts.iter().map(Geometry::to_geo).collect(),
+            )),
+        }
+    }
+}
+
+/// Decode an ISO WKB or EWKB geometry.
+pub fn parse(bytes: &[u8]) -> Result<Geometry> {
+    let mut reader = Reader { bytes, pos: 0 };
+    reader.geometry()
+}
+
+/// Location of a geometry or polygon ring within WKB bytes, to copy its
+/// parts without decoding and re-encoding their coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub kind: Kind,
+    pub dim: Dimensions,
+    pub little_endian: bool,
+    /// Bytes of the geometry, of a ring its point count and coordinates.
+    pub bytes: Range<usize>,
+    /// Coordinates of points, line strings and rings.
+    pub coords: Range<usize>,
+    /// Rings of polygons, members of multi geometries and collections.
+    pub parts: Vec<Span>,
+}
+
+impl Span {
+    /// GeoParquet geometry type name, e.g. `Point ZM`.
+    pub fn type_name(&self) -> String {
+        type_name(self.kind, self.dim)
+    }
+}
+
+/// Locate the parts of a WKB geometry, see [`Span`].
+pub fn scan(bytes: &[u8]) -> Result<Span> {
+    let mut reader = Reader { bytes, pos: 0 };
+    reader.span()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| DataFusionError::Execution("Truncated WKB".to_string()))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self, little_endian: bool) -> Result<u32> {
+        let bytes: [u8; 4] = self.take(4)?.try_into().unwrap();
+        Ok(match little_endian {
+            true => u32::from_le_bytes(bytes),
+            false => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn f64s(&mut self, n: usize, little_endian: bool, coords: &mut Vec<f64>) -> Result<()> {
+        for chunk in self.take(n * 8)?.chunks(8) {
+            let bytes: [u8; 8] = chunk.try_into().unwrap();
+            coords.push(match little_endian {
+                true => f64::from_le_bytes(bytes),
+                false => f64::from_be_bytes(bytes),
+            });
+        }
+        Ok(())
+    }
+
+    /// Skip `n` bytes, returning their range.
+    fn skip(&mut self, n: usize) -> Result<Range<usize>> {
+        let start = self.pos;
+        self.take(n)?;
+        Ok(start..self.pos)
+    }
+
+    /// Byte order, kind and dimensions of the next geometry.
+    fn header(&mut self) -> Result<(bool, Kind, Dimensions)> {
+        let little_endian = match self.take(1)?[0] {
+            0 => false,
+            1 => true,
+            order => {
+                return Err(DataFusionError::Execution(format!(
+                    "Invalid WKB byte order {order}"
+                )))
+            }
+        };
+        let code = self.u32(little_endian)?;
+
+        // EWKB flags
+        let mut has_z = code & 0x8000_0000 != 0;
+        let mut has_m = code & 0x4000_0000 != 0;
+        if code & 0x2000_0000 != 0 {
+            // SRID, tracked by the analyzer instead
+            self.u32(little_endian)?;
+        }
+
+        // ISO dimension offsets
+        let code = code & 0x0fff_ffff;
+        match code / 1000 {
+            1 => has_z = true,
+            2 => has_m = true,
+            3 => (has_z, has_m) = (true, true),
+            _ => {}
+        }
+        let kind = Kind::try_from_code(code % 1000)?;
+        let dim = match (has_z, has_m) {
+            (false, false) => Dimensions::Xy,
+            (true, false) => Dimensions::Xyz,
+            (false, true) => Dimensions::Xym,
+            (true, true) => Dimensions::Xyzm,
+        };
+        Ok((little_endian, kind, dim))
+    }
+
+    fn geometry(&mut self) -> Result<Geometry> {
+        let (little_endian, kind, dim) = self.header()?;
+        let size = size(dim);
+
+        let mut geometry = Geometry::new(kind, dim, vec![], vec![]);
+        match kind {
+            Kind::Point => self.f64s(size, little_endian, &mut geometry.coords)?,
+            Kind::LineString => {
+                let n = self.u32(little_endian)? as usize;
+                self.f64s(n * size, little_endian, &mut geometry.coords)?;
+            }
+            Kind::Polygon => {
+                for _ in 0..self.u32(little_endian)? {
+                    let n = self.u32(little_endian)? as usize;
+                    let mut ring = Geometry::new(Kind::LineString, dim, vec![], vec![]);
+                    self.f64s(n * size, little_endian, &mut ring.coords)?;
+                    geometry.parts.push(ring);
+                }
+            }
+            _ => {
+                for _ in 0..self.u32(little_endian)? {
+                    geometry.parts.push(self.geometry()?);
+                }
+            }
+        }
+
+        Ok(geometry)
+    }
+
+    fn span(&mut self) -> Result<Span> {
+        let start = self.pos;
+        let (little_endian, kind, dim) = self.header()?;
+        let size = size(dim) * 8;
+
+        let mut span = Span {
+            kind,
+            dim,
+            little_endian,
+            bytes: start..start,
+            coords: start..start,
+            parts: vec![],
+        };
+        match kind {
+            Kind::Point => span.coords = self.skip(size)?,
+            Kind::LineString => {
+                let n = self.u32(little_endian)? as usize;
+                span.coords = self.skip(n * size)?;
+            }
+            Kind::Polygon => {
+                for _ in 0..self.u32(little_endian)? {
+                    let ring_start = self.pos;
+                    let n = self.u32(little_endian)? as usize;
+                    let coords = self.skip(n * size)?;
+                    span.parts.push(Span {
+                        kind: Kind::LineString,
+                        dim,
+                        little_endian,
+                        bytes: ring_start..self.pos,
+                        coords,
+                        parts: vec![],
+                    });
+                }
+            }
+            _ => {
+                for _ in 0..self.u32(little_endian)? {
+                    span.parts.push(self.span()?);
+                }
+            }
+        }
+
+        span.bytes = start..self.pos;
+        Ok(span)
+    }
+}
+
+/// Collect geometries into a multi geometry if they all share a single
+/// geometry kind, into a geometry collection otherwise. Like PostGIS, multi
+/// geometries are kept as members rather than flattened.
+pub fn collect(parts: Vec<Geometry>) -> Result<Geometry> {
+    let dim = parts.first().map(|part| part.dim).unwrap_or(Dimensions::Xy);
+    if parts.iter().any(|part| part.dim != dim) {
+        return Err(DataFusionError::Execution(
+            "ST_Collect of geometries with mixed dimensions".to_string(),
+        ));
+    }
+
+    let kind = match parts.first().and_then(|part| part.kind.multi()) {
+        Some(kind) if parts.iter().all(|part| part.kind == parts[0].kind) => kind,
+        _ => Kind::GeometryCollection,
+    };
+    Ok(Geometry::new(kind, dim, vec![], parts))
+}
+
+/// Line string through the vertices of points, multi points and line
+/// strings, empty points are skipped.
+pub fn make_line(geometries: &[Geometry]) -> Result<Geometry> {
+    let dim = geometries.first().map(|g| g.dim).unwrap_or(Dimensions::Xy);
+    let mut coords = vec![];
+    for geometry in geometries {
+        if geometry.dim != dim {
+            return Err(DataFusionError::Execution(
+                "ST_MakeLine of geometries with mixed dimensions".to_string(),
+            ));
+        }
+        match geometry.kind {
+            Kind::Point if geometry.is_empty() => {}
+            Kind::Point | Kind::LineString => coords.extend_from_slice(&geometry.coords),
+            Kind::MultiPoint => geometry
+                .parts
+                .iter()
+                .filter(|point| !point.is_empty())
+                .for_each(|point| coords.extend_from_slice(&point.coords)),
+            _ => {
+                return Err(DataFusionError::Execution(format!(
+                    "ST_MakeLine of {} geometries",
+                    geometry.kind.name()
+                )))
+            }
+        }
+    }
+    Ok(Geometry::new(Kind::LineString, dim, coords, vec![]))
+}
+
+/// Convert a [`geo`] (XY) geometry, lines, rects and triangles become line
+/// strings and polygons.
+pub fn from_geo(geometry: &geo::Geometry) -> Geometry {
+    let dim = Dimensions::Xy;
+    let line_string = |ls: &geo::LineString| {
+        let coords = ls.coords().flat_map(|c| [c.x, c.y]).collect();
+        Geometry::new(Kind::LineString, dim, coords, vec![])
+    };
+    let point = |p: &geo::Point| Geometry::new(Kind::Point, dim, vec![p.x(), p.y()], vec![]);
+    let polygon = |p: &geo::Polygon| {
+        // empty polygons have no rings
+        let rings = std::iter::once(p.exterior())
+            .filter(|exterior| !exterior.0.is_empty())
+            .chain(p.interiors())
+            .map(line_string)
+            .collect();
+        Geometry::new(Kind::Polygon, dim, vec![], rings)
+    };
+
+    match geometry {
+        geo::Geometry::Point(p) => point(p),
+        geo::Geometry::Line(l) => line_string(&geo::LineString::from(*l)),
+        geo::Geometry::LineString(ls) => line_string(ls),
+        geo::Geometry::Polygon(p) => polygon(p),
+        geo::Geometry::MultiPoint(mp) => Geometry::new(
+            Kind::MultiPoint,
+            dim,
+            vec![],
+            mp.iter().map(point).collect(),
+        ),
+        geo::Geometry::MultiLineString(mls) => Geometry::new(
+            Kind::MultiLineString,
+            dim,
+            vec![],
+            mls.iter().map(line_string).collect(),
+        ),
+        geo::Geometry::MultiPolygon(mp) => Geometry::new(
+            Kind::MultiPolygon,
+            dim,
+            vec![],
+            mp.iter().map(polygon).collect(),
+        ),
+        geo::Geometry::GeometryCollection(gc) => Geometry::new(
+            Kind::GeometryCollection,
+            dim,
+            vec![],
+            gc.iter().map(from_geo).collect(),
+        ),
+        geo::Geometry::Rect(r) => polygon(&r.to_polygon()),
+        geo::Geometry::Triangle(t) => polygon(&t.to_polygon()),
+    }
+}
+
+/// Convert a parsed WKT geometry, keeping its Z and M coordinates.
+pub fn from_wkt(wkt: &wkt::Wkt<f64>) -> Geometry {
+    fn dimension(coord: &wkt::types::Coord<f64>) -> Dimensions {
+        match (coord.z.is_some(), coord.m.is_some()) {
+            (false, false) => Dimensions::Xy,
+            (true, false) => Dimensions::Xyz,
+            (false, true) => Dimensions::Xym,
+            (true, true) => Dimensions::Xyzm,
+        }
+    }
+
+    fn push(coord: &wkt::types::Coord<f64>, coords: &mut Vec<f64>) {
+        coords.extend(
+            [Some(coord.x), Some(coord.y), coord.z, coord.m]
+                .into_iter()
+                .flatten(),
+        );
+    }
+
+    fn line_string(ls: &wkt::types::LineString<f64>, dim: Dimensions) -> Geometry {
+        let mut coords = vec![];
+        ls.0.iter().for_each(|c| push(c, &mut coords));
+        Geometry::new(Kind::LineString, dim, coords, vec![])
+    }
+
+    fn point(p: &wkt::types::Point<f64>, dim: Dimensions) -> Geometry {
+        let mut coords = vec![];
+        match &p.0 {
+            Some(c) => push(c, &mut coords),
+            None => coords.resize(size(dim), f64::NAN),
+        }
+        Geometry::new(Kind::Point, dim, coords, vec![])
+    }
+
+    fn polygon(p: &wkt::types::Polygon<f64>, dim: Dimensions) -> Geometry {
+        let rings = p.0.iter().map(|r| line_string(r, dim)).collect();
+        Geometry::new(Kind::Polygon, dim, vec![], rings)
+    }
+
+    // the dimension of the first coordinate applies to the whole geometry
+    fn first_dimension(wkt: &wkt::Wkt<f64>) -> Option<Dimensions> {
+        use wkt::Wkt::*;
+
+        match wkt {
+            Point(p) => p.0.as_ref().map(dimension),
+            LineString(ls) => ls.0.first().map(dimension),
+            Polygon(p) => p.0.iter().flat_map(|r| r.0.first()).next().map(dimension),
+            MultiPoint(mp) => mp.0.iter().flat_map(|p| p.0.as_ref()).next().map(dimension),
+            MultiLineString(mls) => mls.0.iter().flat_map(|l| l.0.first()).next().map(dimension),
+            MultiPolygon(mp) => {
+                mp.0.iter()
+                    .flat_map(|p| p.0.iter().flat_map(|r| r.0.first()))
+                    .next()
+                    .map(dimension)
+            }
+            GeometryCollection(gc) => gc.0.iter().find_map(first_dimension),
+        }
+    }
+
+    let dim = first_dimension(wkt).unwrap_or(Dimensions::Xy);
+
+    match wkt {
+        wkt::Wkt::Point(p) => point(p, dim),
+        wkt::Wkt::LineString(ls) => line_string(ls, dim),
+        wkt::Wkt::Polygon(p) => polygon(p, dim),
+        wkt::Wkt::MultiPoint(mp) => Geometry::new(
+            Kind::MultiPoint,
+            dim,
+            vec![],
+            mp.0.iter().map(|p| point(p, dim)).collect(),
+        ),
+        wkt::Wkt::MultiLineString(mls) => Geometry::new(
+            Kind::MultiLineString,
+            dim,
+            vec![],
+            mls.0.iter().map(|ls| line_string(ls, dim)).collect(),
+        ),
+        wkt::Wkt::MultiPolygon(mp) => Geometry::new(
+            Kind::MultiPolygon,
+            dim,
+            vec![],
+            mp.0.iter().map(|p| polygon(p, dim)).collect(),
+        ),
+        wkt::Wkt::GeometryCollection(gc) => Geometry::new(
+            Kind::GeometryCollection,
+            dim,
+            vec![],
+            gc.0.iter().map(from_wkt).collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        for text in [
+            "POINT (1.0 2.0)",
+            "POINT M (1.0 2.0 3.0)",
+            "LINESTRING ZM (1.0 2.0 3.0 4.0,5.0 6.0 7.0 8.0)",
+            "POLYGON M ((0.0 0.0 1.0,1.0 0.0 2.0,1.0 1.0 3.0,0.0 0.0 1.0))",
+            "MULTIPOINT ZM ((1.0 2.0 3.0 4.0),(5.0 6.0 7.0 8.0))",
+            "GEOMETRYCOLLECTION M (POINT M (1.0 2.0 3.0),LINESTRING M (1.0 2.0 3.0,4.0 5.0 6.0))",
+        ] {
+            let wkt = wkt::Wkt::<f64>::from_str(text).unwrap();
+            let geometry = from_wkt(&wkt);
+            assert_eq!(geometry.to_wkt(), text);
+            assert_eq!(parse(&geometry.to_wkb()).unwrap(), geometry);
+        }
+    }
+
+    #[test]
+    fn spans() {
+        let text = "GEOMETRYCOLLECTION M (POINT M (1 2 3),POLYGON M ((0 0 1,1 0 2,1 1 3,0 0 1)))";
+        let geometry = from_wkt(&wkt::Wkt::<f64>::from_str(text).unwrap());
+        let bytes = geometry.to_wkb();
+        let span = scan(&bytes).unwrap();
+        assert_eq!(span.bytes, 0..bytes.len());
+        assert_eq!(span.type_name(), "GeometryCollection M");
+
+        // members are complete WKB geometries
+        let [point, polygon] = [&span.parts[0], &span.parts[1]];
+        assert_eq!(
+            parse(&bytes[point.bytes.clone()]).unwrap(),
+            geometry.parts[0]
+        );
+        assert_eq!(
+            parse(&bytes[polygon.bytes.clone()]).unwrap(),
+            geometry.parts[1]
+        );
+        assert_eq!(point.coords.len(), 3 * 8);
+
+        // rings are their point count and coordinates
+        let ring = &polygon.parts[0];
+        assert_eq!(ring.bytes.len(), 4 + 4 * 3 * 8);
+        assert_eq!(ring.coords.start, ring.bytes.start + 4);
+    }
+
+    #[test]
+    fn measures() {
+        let wkt = wkt::Wkt::<f64>::from_str("POINT ZM (1 2 3 4)").unwrap();
+        let geometry = from_wkt(&wkt);
+        assert_eq!(geometry.type_name(), "Point ZM");
+        assert_eq!(geometry.m(), Some(4.));
+
+        // EWKB point with M flag, big endian
+        let mut ewkb = vec![0];
+        ewkb.extend((0x4000_0001_u32).to_be_bytes());
+        for ordinate in [1_f64, 2., 3.] {
+            ewkb.extend(ordinate.to_be_bytes());
+        }
+        assert_eq!(parse(&ewkb).unwrap().to_wkt(), "POINT M (1.0 2.0 3.0)");
+    }
+
+    #[test]
+    fn collect() {
+        let collect = |texts: &[&str]| {
+            let parts = texts
+                .iter()
+                .map(|text| from_wkt(&wkt::Wkt::<f64>::from_str(text).unwrap()))
+                .collect();
+            super::collect(parts).map(|geometry| geometry.to_wkt())
+        };
+        assert_eq!(
+            collect(&["POINT M (1 2 3)", "POINT M (4 5 6)"]).unwrap(),
+            "MULTIPOINT M ((1.0 2.0 3.0),(4.0 5.0 6.0))"
+        );
+        assert_eq!(
+            collect(&["POINT (1 2)", "MULTIPOINT ((3 4))"]).unwrap(),
+            "GEOMETRYCOLLECTION (POINT (1.0 2.0),MULTIPOINT ((3.0 4.0)))"
+        );
+        assert!(collect(&["POINT (1 2)", "POINT Z (1 2 3)"]).is_err());
+    }
+
+    #[test]
+    fn make_line() {
+        let geometries = [
+            "POINT Z (1 2 3)",
+            "POINT EMPTY",
+            "LINESTRING Z (4 5 6, 7 8 9)",
+        ]
+        .map(|text| from_wkt(&wkt::Wkt::<f64>::from_str(text).unwrap()));
+        assert!(super::make_line(&geometries).is_err());
+        assert_eq!(
+            super::make_line(&[geometries[0].clone(), geometries[2].clone()])
+                .unwrap()
+                .to_wkt(),
+            "LINESTRING Z (1.0 2.0 3.0,4.0 5.0 6.0,7.0 8.0 9.0)"
+        );
+    }
+
+    #[test]
+    fn bounds_3d() {
+        let bounds = |text| from_wkt(&wkt::Wkt::<f64>::from_str(text).unwrap()).bounds_3d();
+        assert_eq!(
+            bounds("LINESTRING ZM (1 2 3 4, -1 5 -3 0)"),
+            Some(([-1., 2., -3.], [1., 5., 3.]))
+        );
+        assert_eq!(
+            bounds("LINESTRING M (1 2 3, 4 5 6)"),
+            Some(([1., 2., 0.], [4., 5., 0.]))
+        );
+        assert_eq!(bounds("POINT EMPTY"), None);
+    }
+}
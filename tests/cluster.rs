@@ -0,0 +1,136 @@
+//! `ST_ClusterIntersecting` and `ST_ClusterWithin` as aggregates and, with
+//! `OVER`, as window functions.
+
+use datafusion::{
+    arrow::{
+        array::{Array, AsArray},
+        compute::concat,
+        datatypes::{Int64Type, UInt32Type},
+    },
+    error::Result,
+    logical_expr::{AggregateUDF, WindowUDF},
+    prelude::{SessionConfig, SessionContext},
+};
+use datafusion_spatial::{
+    udafs::{ClusterIntersecting, ClusterWithin},
+    udwfs,
+};
+
+mod common;
+
+/// Lines of two blocks: in `a` two touching lines and a distant one, in `b`
+/// two lines 2 apart.
+const LINES: &str = r#"id,block,geometry
+1,a,"LINESTRING (0 0, 1 1)"
+2,a,"LINESTRING (1 1, 2 0)"
+3,a,"LINESTRING (5 5, 6 6)"
+4,b,"LINESTRING (0 0, 0 1)"
+5,b,"LINESTRING (0 3, 0 4)"
+"#;
+
+/// Session with one row per batch spread over several partitions, with a CSV
+/// table `lines` of WKT line strings.
+async fn context(name: &str) -> Result<(SessionContext, std::path::PathBuf)> {
+    let config = SessionConfig::new()
+        .with_target_partitions(4)
+        .with_batch_size(1);
+    let udfs = vec![
+        AggregateUDF::from(ClusterIntersecting::new()).into(),
+        AggregateUDF::from(ClusterWithin::new()).into(),
+        WindowUDF::from(udwfs::ClusterIntersecting::new()).into(),
+        WindowUDF::from(udwfs::ClusterWithin::new()).into(),
+    ];
+    common::context(name, config, udfs, "wkt", &[("lines", LINES)]).await
+}
+
+/// Number of clusters in each row of the last column.
+async fn cluster_counts(ctx: &SessionContext, sql: &str) -> Result<Vec<usize>> {
+    let batches = ctx.sql(sql).await?.collect().await?;
+    Ok(batches
+        .iter()
+        .flat_map(|batch| {
+            let clusters = batch.column(batch.num_columns() - 1).as_list::<i32>();
+            (0..clusters.len())
+                .map(|row| clusters.value(row).len())
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+/// Cluster numbers of the rows ordered by id.
+async fn cluster_numbers(ctx: &SessionContext, sql: &str) -> Result<Vec<Option<u32>>> {
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let ids = batches
+        .iter()
+        .map(|b| b.column(0).as_ref())
+        .collect::<Vec<_>>();
+    let numbers = batches
+        .iter()
+        .map(|b| b.column(1).as_ref())
+        .collect::<Vec<_>>();
+    let (ids, numbers) = (concat(&ids)?, concat(&numbers)?);
+
+    let mut rows = ids
+        .as_primitive::<Int64Type>()
+        .values()
+        .iter()
+        .zip(numbers.as_primitive::<UInt32Type>().iter())
+        .map(|(id, number)| (*id, number))
+        .collect::<Vec<_>>();
+    rows.sort();
+    Ok(rows.into_iter().map(|(_, number)| number).collect())
+}
+
+#[tokio::test]
+async fn aggregates() -> Result<()> {
+    let (ctx, dir) = context("cluster-aggregates").await?;
+
+    assert_eq!(
+        cluster_counts(
+            &ctx,
+            "SELECT block, ST_ClusterIntersecting(geometry) FROM lines \
+             GROUP BY block ORDER BY block"
+        )
+        .await?,
+        vec![2, 2]
+    );
+    assert_eq!(
+        cluster_counts(
+            &ctx,
+            "SELECT block, ST_ClusterWithin(geometry, 2) FROM lines \
+             GROUP BY block ORDER BY block"
+        )
+        .await?,
+        vec![2, 1]
+    );
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn window_functions() -> Result<()> {
+    let (ctx, dir) = context("cluster-windows").await?;
+
+    let numbers = cluster_numbers(
+        &ctx,
+        "SELECT id, ST_ClusterIntersecting(geometry) OVER (PARTITION BY block) FROM lines",
+    )
+    .await?;
+    assert!(numbers.iter().all(Option::is_some));
+    assert_eq!(numbers[0], numbers[1]);
+    assert_ne!(numbers[0], numbers[2]);
+    assert_ne!(numbers[3], numbers[4]);
+
+    let numbers = cluster_numbers(
+        &ctx,
+        "SELECT id, ST_ClusterWithin(geometry, 2) OVER (PARTITION BY block) FROM lines",
+    )
+    .await?;
+    assert_eq!(numbers[0], numbers[1]);
+    assert_ne!(numbers[0], numbers[2]);
+    assert_eq!(numbers[3], numbers[4]);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! `ST_Collect` in its aggregate and scalar forms, which share their name.
+
+use datafusion::{
+    arrow::{
+        array::{Array, AsArray},
+        datatypes::DataType,
+    },
+    error::Result,
+    logical_expr::{AggregateUDF, ScalarUDF},
+    prelude::{SessionConfig, SessionContext},
+};
+use datafusion_spatial::{udafs::CollectAgg, udfs::Collect};
+
+mod common;
+
+/// Session over a CSV table `c` of native points, one row per batch spread
+/// over several partitions.
+async fn context(name: &str) -> Result<(SessionContext, std::path::PathBuf)> {
+    let config = SessionConfig::new()
+        .with_target_partitions(4)
+        .with_batch_size(1);
+    let udfs = vec![
+        ScalarUDF::from(Collect::new()).into(),
+        AggregateUDF::from(CollectAgg::new()).into(),
+    ];
+    let points = "name,x,y\na,1,2\nb,3,4\na,5,6\nb,,\n";
+    common::context(name, config, udfs, "xy", &[("c", points)]).await
+}
+
+#[tokio::test]
+async fn aggregate() -> Result<()> {
+    let (ctx, dir) = context("collect-aggregate").await?;
+
+    let batches = ctx
+        .sql("SELECT name, ST_Collect(geometry) FROM c GROUP BY name ORDER BY name")
+        .await?
+        .collect()
+        .await?;
+    let counts = batches
+        .iter()
+        .flat_map(|batch| {
+            let multi_points = batch.column(1).as_list::<i32>();
+            assert!(matches!(multi_points.value_type(), DataType::Struct(_)));
+            (0..multi_points.len())
+                .map(|row| multi_points.value(row).len())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    // the points of each group, the NULL point of `b` is ignored
+    assert_eq!(counts, vec![2, 1]);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn scalar() -> Result<()> {
+    let (ctx, dir) = context("collect-scalar").await?;
+
+    let batches = ctx
+        .sql("SELECT ST_Collect(geometry, geometry) FROM c WHERE name = 'a'")
+        .await?
+        .collect()
+        .await?;
+    let lengths = batches
+        .iter()
+        .flat_map(|batch| {
+            let multi_points = batch.column(0).as_list::<i32>();
+            (0..multi_points.len())
+                .map(|row| multi_points.value(row).len())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lengths, vec![2, 2]);
+
+    // the scalar form is not an aggregate
+    let result = ctx
+        .sql("SELECT ST_Collect(geometry, geometry), count(*) FROM c")
+        .await?
+        .collect()
+        .await;
+    let error = result.err().map(|e| e.to_string()).unwrap_or_default();
+    assert!(error.contains("combined with aggregates"), "{error}");
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Session fixture shared by the integration tests.
+
+use std::{path::PathBuf, sync::Arc};
+
+use datafusion::{
+    error::Result,
+    logical_expr::{AggregateUDF, ScalarUDF, WindowUDF},
+    prelude::{SessionConfig, SessionContext},
+};
+use datafusion_spatial::{
+    io::{register_file_formats, SpatialTableFactory},
+    rules::SpatialAnalyzerRule,
+};
+
+/// A function registered with the session.
+pub enum Udf {
+    Scalar(ScalarUDF),
+    Aggregate(AggregateUDF),
+    Window(WindowUDF),
+}
+
+impl From<ScalarUDF> for Udf {
+    fn from(udf: ScalarUDF) -> Self {
+        Self::Scalar(udf)
+    }
+}
+
+impl From<AggregateUDF> for Udf {
+    fn from(udaf: AggregateUDF) -> Self {
+        Self::Aggregate(udaf)
+    }
+}
+
+impl From<WindowUDF> for Udf {
+    fn from(udwf: WindowUDF) -> Self {
+        Self::Window(udwf)
+    }
+}
+
+/// Session with the spatial analyzer, file formats and table factory, the
+/// functions `udfs` and a CSV table per `(name, contents)` of `tables`, their
+/// geometries given in `geometry_format` (`xy` or `wkt`).
+///
+/// The CSV files are written to a fresh temporary directory named after the
+/// test, which is returned to be removed by the test.
+pub async fn context(
+    name: &str,
+    config: SessionConfig,
+    udfs: Vec<Udf>,
+    geometry_format: &str,
+    tables: &[(&str, &str)],
+) -> Result<(SessionContext, PathBuf)> {
+    let ctx = SessionContext::new_with_config(config);
+    for udf in udfs {
+        match udf {
+            Udf::Scalar(udf) => ctx.register_udf(udf),
+            Udf::Aggregate(udaf) => ctx.register_udaf(udaf),
+            Udf::Window(udwf) => ctx.register_udwf(udwf),
+        }
+    }
+    ctx.add_analyzer_rule(Arc::new(SpatialAnalyzerRule {}));
+    register_file_formats(&mut ctx.state_ref().write())?;
+    SpatialTableFactory::register(&mut ctx.state_ref().write());
+
+    let dir = std::env::temp_dir().join(format!("datafusion-spatial-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    for (table, contents) in tables {
+        let path = dir.join(format!("{table}.csv"));
+        std::fs::write(&path, contents)?;
+        ctx.sql(&format!(
+            "CREATE EXTERNAL TABLE {table} STORED AS CSV LOCATION '{}' \
+             OPTIONS ('format.has_header' 'true', 'geometry_format' '{geometry_format}')",
+            path.display()
+        ))
+        .await?
+        .collect()
+        .await?;
+    }
+
+    Ok((ctx, dir))
+}
@@ -0,0 +1,162 @@
+//! CRS set with `ST_SetSRID` and `ST_Transform`, seen through subqueries and
+//! CTEs and written by the file writers.
+
+use datafusion::{
+    arrow::{array::AsArray, datatypes::Int32Type},
+    error::Result,
+    logical_expr::ScalarUDF,
+    parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
+    prelude::{SessionConfig, SessionContext},
+};
+use datafusion_spatial::{
+    io::SpatialDataFrameExt,
+    udfs::{SetSrid, Srid, Transform},
+};
+use serde_json::Value;
+
+mod common;
+
+/// Session with a CSV table `c` of native points without CRS.
+async fn context(name: &str) -> Result<(SessionContext, std::path::PathBuf)> {
+    let udfs = vec![
+        ScalarUDF::from(SetSrid::new()).into(),
+        ScalarUDF::from(Srid::new()).into(),
+        ScalarUDF::from(Transform::new()).into(),
+    ];
+    let points = "name,x,y\na,1,2\nb,3,4\n";
+    common::context(name, SessionConfig::new(), udfs, "xy", &[("c", points)]).await
+}
+
+async fn srids(ctx: &SessionContext, sql: &str) -> Result<Vec<Option<i32>>> {
+    let batches = ctx.sql(sql).await?.collect().await?;
+    Ok(batches
+        .iter()
+        .flat_map(|b| {
+            b.column(0)
+                .as_primitive::<Int32Type>()
+                .iter()
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn subqueries() -> Result<()> {
+    let (ctx, dir) = context("crs-subqueries").await?;
+
+    assert_eq!(
+        srids(&ctx, "SELECT ST_SRID(geometry) FROM c").await?,
+        vec![Some(0), Some(0)]
+    );
+    assert_eq!(
+        srids(
+            &ctx,
+            "SELECT ST_SRID(g) FROM (SELECT ST_SetSRID(geometry, 2056) AS g FROM c) s"
+        )
+        .await?,
+        vec![Some(2056), Some(2056)]
+    );
+    assert_eq!(
+        srids(
+            &ctx,
+            "WITH s AS (SELECT name, ST_SetSRID(geometry, 4326) AS geometry FROM c) \
+             SELECT ST_SRID(geometry) FROM s WHERE name = 'a'"
+        )
+        .await?,
+        vec![Some(4326)]
+    );
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn writers() -> Result<()> {
+    let (ctx, dir) = context("crs-writers").await?;
+    let sql = "SELECT name, ST_SetSRID(geometry, 2056) AS geometry FROM c";
+
+    // GeoArrow extension metadata of the written IPC file
+    let arrow = dir.join("points.arrow");
+    ctx.sql(&format!(
+        "COPY ({sql}) TO '{}' STORED AS GEOARROW",
+        arrow.display()
+    ))
+    .await?
+    .collect()
+    .await?;
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE a STORED AS GEOARROW LOCATION '{}'",
+        arrow.display()
+    ))
+    .await?
+    .collect()
+    .await?;
+    let table = ctx.table("a").await?;
+    let geometry = table.schema().field_with_unqualified_name("geometry")?;
+    let metadata = &geometry.metadata()["ARROW:extension:metadata"];
+    let metadata = serde_json::from_str::<Value>(metadata).unwrap();
+    assert_eq!(metadata["crs"], "EPSG:2056");
+    assert_eq!(
+        srids(&ctx, "SELECT ST_SRID(geometry) FROM a").await?,
+        vec![Some(2056), Some(2056)]
+    );
+
+    // `geo` metadata of the written GeoParquet file
+    let parquet = dir.join("points.parquet");
+    ctx.sql(sql)
+        .await?
+        .write_geoparquet(parquet.to_str().unwrap(), Default::default())
+        .await?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&parquet)?)?;
+    let geo = reader
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .unwrap()
+        .iter()
+        .find(|kv| kv.key == "geo")
+        .and_then(|kv| kv.value.clone())
+        .unwrap();
+    let geo = serde_json::from_str::<Value>(&geo).unwrap();
+    // without a PROJJSON definition the CRS is written as unknown
+    assert_eq!(geo["columns"]["geometry"]["crs"], Value::Null);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn transform() -> Result<()> {
+    let (ctx, dir) = context("crs-transform").await?;
+    let sql = "SELECT ST_Transform(ST_SetSRID(geometry, 4326), 2056) AS geometry FROM c";
+
+    assert_eq!(
+        srids(&ctx, &format!("SELECT ST_SRID(geometry) FROM ({sql}) t")).await?,
+        vec![Some(2056), Some(2056)]
+    );
+
+    // the target CRS is written with the reprojected geometries
+    let arrow = dir.join("points.arrow");
+    ctx.sql(&format!(
+        "COPY ({sql}) TO '{}' STORED AS GEOARROW",
+        arrow.display()
+    ))
+    .await?
+    .collect()
+    .await?;
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE a STORED AS GEOARROW LOCATION '{}'",
+        arrow.display()
+    ))
+    .await?
+    .collect()
+    .await?;
+    let table = ctx.table("a").await?;
+    let geometry = table.schema().field_with_unqualified_name("geometry")?;
+    let metadata = &geometry.metadata()["ARROW:extension:metadata"];
+    let metadata = serde_json::from_str::<Value>(metadata).unwrap();
+    assert_eq!(metadata["crs"], "EPSG:2056");
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! CSV tables with WKT geometry columns, their geometry type inferred from
+//! all rows of all files.
+
+use std::sync::Arc;
+
+use datafusion::{
+    arrow::{array::Array, datatypes::Schema},
+    error::Result,
+    prelude::{SessionConfig, SessionContext},
+};
+use datafusion_spatial::{io::SpatialTableFactory, rules::SpatialAnalyzerRule};
+
+/// Session reading each file of a table in its own partition.
+fn context() -> SessionContext {
+    let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(4));
+    ctx.add_analyzer_rule(Arc::new(SpatialAnalyzerRule {}));
+    SpatialTableFactory::register(&mut ctx.state_ref().write());
+    ctx
+}
+
+async fn create_table(ctx: &SessionContext, name: &str, location: &str) -> Result<()> {
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE {name} STORED AS CSV LOCATION '{location}' \
+         OPTIONS ('format.has_header' 'true', 'geometry_format' 'wkt')"
+    ))
+    .await?
+    .collect()
+    .await?;
+    Ok(())
+}
+
+/// GeoArrow extension name of the geometry column.
+fn extension(schema: &Schema) -> String {
+    let field = schema.field_with_name("geometry").unwrap();
+    field.metadata()["ARROW:extension:name"].clone()
+}
+
+/// Number of rows and of null geometries of a table.
+async fn counts(ctx: &SessionContext, table: &str) -> Result<(usize, usize)> {
+    let batches = ctx.table(table).await?.collect().await?;
+    let rows = batches.iter().map(|b| b.num_rows()).sum();
+    let nulls = batches
+        .iter()
+        .map(|b| b.column_by_name("geometry").unwrap().null_count())
+        .sum();
+    Ok((rows, nulls))
+}
+
+#[tokio::test]
+async fn data_files() -> Result<()> {
+    let ctx = context();
+
+    for (name, rows) in [
+        ("point", 4),
+        ("linestring", 3),
+        ("polygon", 4),
+        ("multipoint", 4),
+        ("multilinestring", 4),
+        ("multipolygon", 5),
+    ] {
+        create_table(&ctx, name, &format!("data/data-{name}-wkt.csv")).await?;
+
+        let schema = ctx.table(name).await?.schema().as_arrow().clone();
+        assert_eq!(extension(&schema), format!("geoarrow.{name}"), "{name}");
+        // the empty geometries are read, only the missing ones are null
+        assert_eq!(counts(&ctx, name).await?, (rows, 1), "{name}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn inference() -> Result<()> {
+    let ctx = context();
+    let dir = std::env::temp_dir().join("datafusion-spatial-csv-inference");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // the geometry type covers the rows of all files
+    let points = dir.join("points");
+    std::fs::create_dir_all(&points)?;
+    std::fs::write(points.join("a.csv"), "id,geometry\n1,POINT (1 2)\n")?;
+    std::fs::write(
+        points.join("b.csv"),
+        "id,geometry\n2,\"MULTIPOINT ((3 4), (5 6))\"\n",
+    )?;
+    create_table(&ctx, "points", &format!("{}/", points.display())).await?;
+    let schema = ctx.table("points").await?.schema().as_arrow().clone();
+    assert_eq!(extension(&schema), "geoarrow.multipoint");
+    assert_eq!(counts(&ctx, "points").await?, (2, 0));
+
+    // mixed geometry types and collections are read as WKB
+    let mixed = dir.join("mixed");
+    std::fs::create_dir_all(&mixed)?;
+    std::fs::write(
+        mixed.join("a.csv"),
+        "id,geometry\n1,POINT (1 2)\n2,\"LINESTRING (0 0, 1 1)\"\n\
+         3,\"GEOMETRYCOLLECTION (POINT (1 2))\"\n",
+    )?;
+    create_table(&ctx, "mixed", &format!("{}/", mixed.display())).await?;
+    let schema = ctx.table("mixed").await?.schema().as_arrow().clone();
+    assert_eq!(extension(&schema), "geoarrow.wkb");
+    assert_eq!(counts(&ctx, "mixed").await?, (3, 0));
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,206 @@
+//! `unnest` of `ST_Dump`, `ST_DumpPoints` and `ST_DumpRings` over native,
+//! native mixed and WKB geometry columns.
+
+use datafusion::{
+    arrow::{
+        array::{Array, AsArray},
+        datatypes::{Int32Type, Int64Type},
+    },
+    error::Result,
+    logical_expr::ScalarUDF,
+    prelude::{SessionConfig, SessionContext},
+};
+use datafusion_spatial::udfs::{Dump, DumpPoints, DumpRings};
+
+mod common;
+
+/// Native multi polygons, the second polygon of the first row with a hole.
+const POLYGONS: &str = r#"id,geometry
+1,"MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((5 5, 6 5, 6 6, 5 5), (5.2 5.1, 5.8 5.1, 5.8 5.7, 5.2 5.1)))"
+2,"MULTIPOLYGON (((0 0, 2 0, 2 2, 0 0)))"
+"#;
+
+/// Geometries of different kinds, read as a native mixed column.
+const MIXED: &str = r#"id,geometry
+1,POINT (1 2)
+2,"MULTILINESTRING ((0 0, 1 1), (2 2, 3 3))"
+"#;
+
+/// Geometries with measures, read as WKB.
+const MEASURES: &str = r#"id,geometry
+1,"LINESTRING M (1 2 3, 4 5 6)"
+2,"POLYGON M ((0 0 1, 1 0 2, 1 1 3, 0 0 1))"
+"#;
+
+/// Session with the CSV tables `polygons`, `mixed` and `measures`.
+async fn context(name: &str) -> Result<(SessionContext, std::path::PathBuf)> {
+    let config = SessionConfig::new().with_target_partitions(1);
+    let udfs = vec![
+        ScalarUDF::from(Dump::new()).into(),
+        ScalarUDF::from(DumpPoints::new()).into(),
+        ScalarUDF::from(DumpRings::new()).into(),
+    ];
+    let tables = [
+        ("polygons", POLYGONS),
+        ("mixed", MIXED),
+        ("measures", MEASURES),
+    ];
+    common::context(name, config, udfs, "wkt", &tables).await
+}
+
+/// Id and path of each dumped row of `SELECT id, unnest(...) AS part`.
+async fn paths(ctx: &SessionContext, sql: &str) -> Result<Vec<(i64, Vec<i32>)>> {
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let mut rows = vec![];
+    for batch in batches {
+        let ids = batch.column(0).as_primitive::<Int64Type>();
+        let paths = batch.column(1).as_struct().column(0).as_list::<i32>();
+        for row in 0..batch.num_rows() {
+            let path = paths.value(row);
+            let path = path.as_primitive::<Int32Type>().values().to_vec();
+            rows.push((ids.value(row), path));
+        }
+    }
+    // unnest keeps the order of the parts of each row
+    rows.sort_by_key(|(id, _)| *id);
+    Ok(rows)
+}
+
+/// WKB of the dumped geometries of `SELECT id, unnest(...) AS part`.
+async fn wkb(ctx: &SessionContext, sql: &str) -> Result<Vec<Vec<u8>>> {
+    let batches = ctx.sql(sql).await?.collect().await?;
+    Ok(batches
+        .iter()
+        .flat_map(|batch| {
+            let geoms = batch.column(1).as_struct().column(1).as_binary::<i32>();
+            geoms
+                .iter()
+                .map(|geom| geom.unwrap().to_vec())
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+/// Little endian ISO WKB header of a geometry with measures.
+fn header_m(kind: u32) -> Vec<u8> {
+    let mut wkb = vec![1];
+    wkb.extend((2000 + kind).to_le_bytes());
+    wkb
+}
+
+#[tokio::test]
+async fn native() -> Result<()> {
+    let (ctx, dir) = context("dump-native").await?;
+
+    assert_eq!(
+        paths(
+            &ctx,
+            "SELECT id, unnest(ST_Dump(geometry)) AS part FROM polygons"
+        )
+        .await?,
+        vec![(1, vec![1]), (1, vec![2]), (2, vec![1])]
+    );
+    assert_eq!(
+        paths(
+            &ctx,
+            "SELECT id, unnest(ST_DumpRings(geometry)) AS part FROM polygons"
+        )
+        .await?,
+        vec![
+            (1, vec![1, 0]),
+            (1, vec![2, 0]),
+            (1, vec![2, 1]),
+            (2, vec![1, 0])
+        ]
+    );
+
+    let points = paths(
+        &ctx,
+        "SELECT id, unnest(ST_DumpPoints(geometry)) AS part FROM polygons",
+    )
+    .await?;
+    assert_eq!(points.len(), 16);
+    assert_eq!(points[4], (1, vec![2, 1, 1]));
+    assert_eq!(points[15], (2, vec![1, 1, 4]));
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn mixed() -> Result<()> {
+    let (ctx, dir) = context("dump-mixed").await?;
+
+    assert_eq!(
+        paths(
+            &ctx,
+            "SELECT id, unnest(ST_Dump(geometry)) AS part FROM mixed"
+        )
+        .await?,
+        vec![(1, vec![]), (2, vec![1]), (2, vec![2])]
+    );
+    assert_eq!(
+        paths(
+            &ctx,
+            "SELECT id, unnest(ST_DumpPoints(geometry)) AS part FROM mixed"
+        )
+        .await?,
+        vec![
+            (1, vec![1]),
+            (2, vec![1, 1]),
+            (2, vec![1, 2]),
+            (2, vec![2, 1]),
+            (2, vec![2, 2]),
+        ]
+    );
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn measures() -> Result<()> {
+    let (ctx, dir) = context("dump-measures").await?;
+
+    // the single geometry itself, its WKB unchanged
+    let line = wkb(
+        &ctx,
+        "SELECT id, unnest(ST_Dump(geometry)) AS part FROM measures WHERE id = 1",
+    )
+    .await?;
+    let mut expected = header_m(2);
+    expected.extend(2_u32.to_le_bytes());
+    for ordinate in [1_f64, 2., 3., 4., 5., 6.] {
+        expected.extend(ordinate.to_le_bytes());
+    }
+    assert_eq!(line, vec![expected]);
+
+    // vertices keep their measures
+    let points = wkb(
+        &ctx,
+        "SELECT id, unnest(ST_DumpPoints(geometry)) AS part FROM measures WHERE id = 1",
+    )
+    .await?;
+    let mut expected = header_m(1);
+    for ordinate in [4_f64, 5., 6.] {
+        expected.extend(ordinate.to_le_bytes());
+    }
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1], expected);
+
+    let rings = wkb(
+        &ctx,
+        "SELECT id, unnest(ST_DumpRings(geometry)) AS part FROM measures WHERE id = 2",
+    )
+    .await?;
+    let mut expected = header_m(3);
+    expected.extend(1_u32.to_le_bytes());
+    expected.extend(4_u32.to_le_bytes());
+    for ordinate in [0_f64, 0., 1., 1., 0., 2., 1., 1., 3., 0., 0., 1.] {
+        expected.extend(ordinate.to_le_bytes());
+    }
+    assert_eq!(rings, vec![expected]);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,196 @@
+//! `ST_Extent` and `ST_3DExtent` regression tests, run with single and
+//! multi-partition plans.
+
+use std::sync::Arc;
+
+use datafusion::{
+    arrow::{array::AsArray, compute::concat, datatypes::Float64Type},
+    error::Result,
+    logical_expr::{AggregateUDF, ScalarUDF},
+    prelude::{ParquetReadOptions, SessionConfig, SessionContext},
+};
+use datafusion_spatial::{
+    rules::SpatialAnalyzerRule,
+    udafs::{Extent, Extent3D},
+    udfs::Envelope3D,
+};
+
+type Bounds = Option<[f64; 4]>;
+
+/// Session with one row per batch, spread round robin over `partitions`
+/// partitions so that partial states of several partitions get merged.
+async fn context(partitions: usize) -> Result<SessionContext> {
+    let mut config = SessionConfig::new()
+        .with_target_partitions(partitions)
+        .with_batch_size(1);
+    config.options_mut().execution.parquet.skip_metadata = false;
+    let ctx = SessionContext::new_with_config(config);
+
+    ctx.register_udaf(AggregateUDF::from(Extent::new()));
+    ctx.register_udaf(AggregateUDF::from(Extent3D::new()));
+    ctx.register_udf(ScalarUDF::from(Envelope3D::new()));
+    ctx.add_analyzer_rule(Arc::new(SpatialAnalyzerRule {}));
+
+    Ok(ctx)
+}
+
+/// Extents in the last column of the query result.
+async fn extents<const N: usize>(ctx: &SessionContext, sql: &str) -> Result<Vec<Option<[f64; N]>>> {
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let columns = batches
+        .iter()
+        .map(|b| b.column(b.num_columns() - 1).as_ref())
+        .collect::<Vec<_>>();
+    let extents = concat(&columns)?;
+    let extents = extents.as_struct();
+
+    Ok((0..extents.len())
+        .map(|row| {
+            extents.is_valid(row).then(|| {
+                std::array::from_fn(|i| extents.column(i).as_primitive::<Float64Type>().value(row))
+            })
+        })
+        .collect())
+}
+
+/// Check the total and per row extents of the `data/data-{name}-encoding_*`
+/// files for all encodings and partitionings.
+async fn check(name: &str, total: [f64; 4], rows: &[Bounds]) -> Result<()> {
+    for encoding in ["wkb", "native"] {
+        for partitions in [1, 4] {
+            let ctx = context(partitions).await?;
+            let path = format!("data/data-{name}-encoding_{encoding}.parquet");
+            ctx.register_parquet("t", &path, ParquetReadOptions::default())
+                .await?;
+
+            let message = format!("{name} {encoding} with {partitions} partitions");
+            assert_eq!(
+                extents(&ctx, "SELECT ST_Extent(geometry) FROM t").await?,
+                vec![Some(total)],
+                "{message}"
+            );
+            assert_eq!(
+                extents(
+                    &ctx,
+                    "SELECT col, ST_Extent(geometry) FROM t GROUP BY col ORDER BY col"
+                )
+                .await?,
+                rows,
+                "{message}"
+            );
+            assert_eq!(
+                extents::<4>(
+                    &ctx,
+                    "SELECT ST_Extent(geometry) FROM t WHERE geometry IS NULL"
+                )
+                .await?,
+                vec![None],
+                "{message}"
+            );
+
+            // nested spatial functions pass on the geometry info of their
+            // results, Box3D structs are native rects
+            assert_eq!(
+                extents(&ctx, "SELECT ST_Extent(ST_3DEnvelope(geometry)) FROM t").await?,
+                vec![Some(total)],
+                "{message}"
+            );
+
+            // 2D geometries have Z bounds 0
+            let [xmin, ymin, xmax, ymax] = total;
+            assert_eq!(
+                extents(&ctx, "SELECT ST_3DExtent(geometry) FROM t").await?,
+                vec![Some([xmin, ymin, 0., xmax, ymax, 0.])],
+                "{message}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn point() -> Result<()> {
+    check(
+        "point",
+        [30., 10., 40., 40.],
+        &[
+            Some([30., 10., 30., 10.]),
+            None,
+            None,
+            Some([40., 40., 40., 40.]),
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn linestring() -> Result<()> {
+    check(
+        "linestring",
+        [10., 10., 40., 40.],
+        &[Some([10., 10., 40., 40.]), None, None],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn polygon() -> Result<()> {
+    check(
+        "polygon",
+        [10., 10., 45., 45.],
+        &[
+            Some([10., 10., 40., 40.]),
+            Some([10., 10., 45., 45.]),
+            None,
+            None,
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn multipoint() -> Result<()> {
+    check(
+        "multipoint",
+        [10., 10., 40., 40.],
+        &[
+            Some([30., 10., 30., 10.]),
+            Some([10., 10., 40., 40.]),
+            None,
+            None,
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn multilinestring() -> Result<()> {
+    check(
+        "multilinestring",
+        [10., 10., 40., 40.],
+        &[
+            Some([10., 10., 40., 40.]),
+            Some([10., 10., 40., 40.]),
+            None,
+            None,
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn multipolygon() -> Result<()> {
+    check(
+        "multipolygon",
+        [5., 5., 45., 45.],
+        &[
+            Some([10., 10., 40., 40.]),
+            Some([5., 5., 45., 40.]),
+            Some([10., 5., 45., 45.]),
+            None,
+            None,
+        ],
+    )
+    .await
+}
@@ -0,0 +1,142 @@
+//! FlatGeobuf tables, written with `COPY ... STORED AS FLATGEOBUF` and read
+//! with their spatial index.
+
+use std::sync::Arc;
+
+use datafusion::{
+    arrow::array::AsArray,
+    error::Result,
+    logical_expr::ScalarUDF,
+    physical_plan::{collect, ExecutionPlan},
+    prelude::SessionContext,
+};
+use datafusion_spatial::{
+    io::{register_file_formats, SpatialTableFactory},
+    rules::SpatialAnalyzerRule,
+    udfs::Intersects,
+};
+
+const FEATURES: usize = 100;
+
+/// Session with a FlatGeobuf table `f` of points on the diagonal.
+async fn context(name: &str) -> Result<SessionContext> {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::from(Intersects::new()));
+    ctx.add_analyzer_rule(Arc::new(SpatialAnalyzerRule {}));
+    register_file_formats(&mut ctx.state_ref().write())?;
+    SpatialTableFactory::register(&mut ctx.state_ref().write());
+
+    let dir = std::env::temp_dir().join(format!("datafusion-spatial-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+
+    let csv = (0..FEATURES)
+        .map(|i| format!("p{i:03},{i},{i}\n"))
+        .collect::<String>();
+    std::fs::write(dir.join("points.csv"), format!("name,x,y\n{csv}"))?;
+
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE c STORED AS CSV LOCATION '{}' \
+         OPTIONS ('format.has_header' 'true', 'geometry_format' 'xy')",
+        dir.join("points.csv").display()
+    ))
+    .await?
+    .collect()
+    .await?;
+
+    let fgb = dir.join("points.fgb");
+    ctx.sql(&format!(
+        "COPY (SELECT name, geometry FROM c) TO '{}' STORED AS FLATGEOBUF",
+        fgb.display()
+    ))
+    .await?
+    .collect()
+    .await?;
+
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE f STORED AS FLATGEOBUF LOCATION '{}'",
+        fgb.display()
+    ))
+    .await?
+    .collect()
+    .await?;
+
+    Ok(ctx)
+}
+
+/// Number of features read by the FlatGeobuf scan of `sql`.
+async fn features_read(ctx: &SessionContext, sql: &str) -> Result<usize> {
+    fn scan(plan: &Arc<dyn ExecutionPlan>) -> Option<Arc<dyn ExecutionPlan>> {
+        match plan.name() {
+            "FlatGeobufExec" => Some(plan.clone()),
+            _ => plan.children().into_iter().find_map(scan),
+        }
+    }
+
+    let plan = ctx.sql(sql).await?.create_physical_plan().await?;
+    let scan = scan(&plan).expect("FlatGeobuf scan");
+    let batches = collect(scan, ctx.task_ctx()).await?;
+
+    Ok(batches.iter().map(|b| b.num_rows()).sum())
+}
+
+/// Little endian WKB of the polygon of a bounding box.
+fn envelope(xmin: f64, ymin: f64, xmax: f64, ymax: f64) -> Vec<u8> {
+    let mut wkb = vec![1];
+    wkb.extend(3_u32.to_le_bytes());
+    wkb.extend(1_u32.to_le_bytes());
+    wkb.extend(5_u32.to_le_bytes());
+    for (x, y) in [
+        (xmin, ymin),
+        (xmax, ymin),
+        (xmax, ymax),
+        (xmin, ymax),
+        (xmin, ymin),
+    ] {
+        wkb.extend(x.to_le_bytes());
+        wkb.extend(y.to_le_bytes());
+    }
+    wkb
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+#[tokio::test]
+async fn bbox_pushdown() -> Result<()> {
+    let ctx = context("flatgeobuf-bbox").await?;
+    let sql = format!(
+        "SELECT name FROM f WHERE ST_Intersects(geometry, X'{}') ORDER BY name",
+        hex(&envelope(-0.5, -0.5, 2.5, 2.5))
+    );
+    let sql = sql.as_str();
+
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let names = batches
+        .iter()
+        .flat_map(|b| {
+            b.column(0)
+                .as_string::<i32>()
+                .iter()
+                .map(|name| name.unwrap().to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["p000", "p001", "p002"]);
+
+    // only the features in the bounding box are read with the spatial index
+    assert_eq!(features_read(&ctx, sql).await?, 3);
+    assert_eq!(features_read(&ctx, "SELECT name FROM f").await?, FEATURES);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn limit() -> Result<()> {
+    let ctx = context("flatgeobuf-limit").await?;
+
+    assert_eq!(features_read(&ctx, "SELECT name FROM f LIMIT 5").await?, 5);
+
+    Ok(())
+}
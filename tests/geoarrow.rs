@@ -0,0 +1,143 @@
+//! Arrow IPC tables, written with `COPY ... STORED AS GEOARROW` and read back
+//! with their native GeoArrow geometries.
+
+use std::sync::Arc;
+
+use datafusion::{
+    arrow::array::AsArray, error::Result, logical_expr::ScalarUDF, prelude::SessionContext,
+};
+use datafusion_spatial::{
+    io::{register_file_formats, SpatialTableFactory},
+    rules::SpatialAnalyzerRule,
+    udfs::AsText,
+};
+
+/// Write the points of a CSV file as GeoArrow and read them back.
+async fn roundtrip(name: &str, ipc_format: &str, ext: &str) -> Result<()> {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::from(AsText::new()));
+    ctx.add_analyzer_rule(Arc::new(SpatialAnalyzerRule {}));
+    register_file_formats(&mut ctx.state_ref().write())?;
+    SpatialTableFactory::register(&mut ctx.state_ref().write());
+
+    let dir = std::env::temp_dir().join(format!("datafusion-spatial-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("points.csv"), "name,x,y\na,1,2\nb,3,4\n")?;
+
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE c STORED AS CSV LOCATION '{}' \
+         OPTIONS ('format.has_header' 'true', 'geometry_format' 'xy')",
+        dir.join("points.csv").display()
+    ))
+    .await?
+    .collect()
+    .await?;
+    let written = ctx.table("c").await?;
+    let written = written.schema().field_with_unqualified_name("geometry")?;
+
+    let path = dir.join(format!("points.{ext}"));
+    ctx.sql(&format!(
+        "COPY (SELECT name, geometry FROM c) TO '{}' STORED AS GEOARROW \
+         OPTIONS ('ipc_format' '{ipc_format}')",
+        path.display()
+    ))
+    .await?
+    .collect()
+    .await?;
+
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE g STORED AS GEOARROW LOCATION '{}' \
+         OPTIONS ('ipc_format' '{ipc_format}')",
+        path.display()
+    ))
+    .await?
+    .collect()
+    .await?;
+
+    // the native point column and its extension type are kept
+    let table = ctx.table("g").await?;
+    let geometry = table.schema().field_with_unqualified_name("geometry")?;
+    assert_eq!(geometry.data_type(), written.data_type());
+    assert_eq!(
+        geometry.metadata().get("ARROW:extension:name").unwrap(),
+        "geoarrow.point"
+    );
+
+    let batches = ctx
+        .sql("SELECT name, ST_AsText(geometry) FROM g ORDER BY name")
+        .await?
+        .collect()
+        .await?;
+    let rows = batches
+        .iter()
+        .flat_map(|batch| {
+            let names = batch.column(0).as_string::<i32>();
+            let wkt = batch.column(1).as_string::<i32>();
+            (0..batch.num_rows())
+                .map(|i| (names.value(i).to_string(), wkt.value(i).to_string()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![
+            ("a".to_string(), "POINT(1 2)".to_string()),
+            ("b".to_string(), "POINT(3 4)".to_string()),
+        ]
+    );
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn file() -> Result<()> {
+    roundtrip("geoarrow-file", "file", "arrow").await
+}
+
+#[tokio::test]
+async fn stream() -> Result<()> {
+    roundtrip("geoarrow-stream", "stream", "arrows").await
+}
+
+#[tokio::test]
+async fn invalid_files() -> Result<()> {
+    let ctx = SessionContext::new();
+    register_file_formats(&mut ctx.state_ref().write())?;
+
+    let dir = std::env::temp_dir().join("datafusion-spatial-geoarrow-invalid");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+
+    // file magic without a footer, a footer length beyond the start of the
+    // file and a stream message longer than the stream
+    let mut too_long = b"ARROW1\0\0".to_vec();
+    too_long.extend(1000_i32.to_le_bytes());
+    too_long.extend(b"ARROW1");
+    let mut message = vec![0xff; 4];
+    message.extend(1000_i32.to_le_bytes());
+    for (file, bytes) in [
+        ("truncated.arrow", b"ARROW1\0\0\0".to_vec()),
+        ("footer.arrow", too_long),
+        ("message.arrows", message),
+    ] {
+        let path = dir.join(file);
+        std::fs::write(&path, bytes)?;
+
+        let result = ctx
+            .sql(&format!(
+                "CREATE EXTERNAL TABLE t STORED AS GEOARROW LOCATION '{}'",
+                path.display()
+            ))
+            .await;
+        let error = result.err().map(|e| e.to_string()).unwrap_or_default();
+        assert!(
+            error.contains("Invalid Arrow IPC object"),
+            "{file}: {error}"
+        );
+    }
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
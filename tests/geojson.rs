@@ -0,0 +1,107 @@
+//! GeoJSON tables created with `CREATE EXTERNAL TABLE ... STORED AS GEOJSON`.
+
+use std::{path::PathBuf, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::AsArray,
+        datatypes::{DataType, Int64Type},
+    },
+    error::Result,
+    logical_expr::ScalarUDF,
+    prelude::SessionContext,
+};
+use datafusion_spatial::{io::SpatialTableFactory, rules::SpatialAnalyzerRule, udfs::AsText};
+
+fn context() -> SessionContext {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::from(AsText::new()));
+    ctx.add_analyzer_rule(Arc::new(SpatialAnalyzerRule {}));
+    SpatialTableFactory::register(&mut ctx.state_ref().write());
+    ctx
+}
+
+/// Directory with one feature collection per file.
+fn directory(name: &str, files: &[(&str, &str)]) -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("datafusion-spatial-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+
+    for (file, features) in files {
+        let collection = format!(r#"{{"type": "FeatureCollection", "features": [{features}]}}"#);
+        std::fs::write(dir.join(file), collection)?;
+    }
+
+    Ok(dir)
+}
+
+fn feature(name: &str, geometry: &str) -> String {
+    format!(r#"{{"type": "Feature", "properties": {{"name": "{name}"}}, "geometry": {geometry}}}"#)
+}
+
+#[tokio::test]
+async fn multiple_files() -> Result<()> {
+    let line = r#"{"type": "LineString", "coordinates": [[0, 0], [1, 1]]}"#;
+    let a = [
+        feature("a", r#"{"type": "Point", "coordinates": [1, 2]}"#),
+        feature("c", line),
+    ]
+    .join(",");
+    let b = [
+        feature("b", r#"{"type": "Point", "coordinates": [3, 4]}"#),
+        feature("d", line),
+    ]
+    .join(",");
+    let dir = directory(
+        "geojson",
+        &[("a.geojson", &a), ("b.geojson", &b), ("c.txt", "")],
+    )?;
+
+    let ctx = context();
+    let sql = format!(
+        "CREATE EXTERNAL TABLE t STORED AS GEOJSON LOCATION '{}'",
+        dir.display()
+    );
+    ctx.sql(&sql).await?.collect().await?;
+
+    // geometries are kept as a native GeoArrow column
+    let table = ctx.table("t").await?;
+    let geometry = table.schema().field_with_unqualified_name("geometry")?;
+    assert!(
+        matches!(geometry.data_type(), DataType::Union(_, _)),
+        "{}",
+        geometry.data_type()
+    );
+
+    let batches = ctx
+        .sql("SELECT name, ST_AsText(geometry) FROM t ORDER BY name")
+        .await?
+        .collect()
+        .await?;
+    let rows = batches
+        .iter()
+        .flat_map(|batch| {
+            let names = batch.column(0).as_string::<i32>();
+            let wkt = batch.column(1).as_string::<i32>();
+            (0..batch.num_rows())
+                .map(|i| (names.value(i).to_string(), wkt.value(i).to_string()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![
+            ("a".to_string(), "POINT(1 2)".to_string()),
+            ("b".to_string(), "POINT(3 4)".to_string()),
+            ("c".to_string(), "LINESTRING(0 0,1 1)".to_string()),
+            ("d".to_string(), "LINESTRING(0 0,1 1)".to_string()),
+        ]
+    );
+
+    // files are read when scanned, one partition per file
+    let batches = ctx.sql("SELECT count(*) FROM t").await?.collect().await?;
+    assert_eq!(batches[0].column(0).as_primitive::<Int64Type>().value(0), 4);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,165 @@
+//! GeoPackage feature tables written with `write_geopackage` and read back
+//! with `STORED AS GEOPACKAGE`.
+
+use datafusion::{
+    arrow::{
+        array::{Array, AsArray},
+        datatypes::{Int64Type, Schema},
+    },
+    error::Result,
+    prelude::{SessionConfig, SessionContext},
+};
+use datafusion_spatial::io::SpatialDataFrameExt;
+
+mod common;
+
+const POINTS: &str = r#"id,name,geometry
+1,a,POINT (1 2)
+2,b,POINT (3 4)
+3,c,
+4,d,POINT (7 8)
+5,e,POINT (9 10)
+"#;
+
+const MEASURES: &str = r#"id,geometry
+1,"LINESTRING M (1 2 3, 4 5 6)"
+2,"LINESTRING ZM (1 2 3 4, 5 6 7 8)"
+"#;
+
+/// Session reading the feature tables in several partitions, with the CSV
+/// tables `points` and `measures`.
+async fn context(name: &str) -> Result<(SessionContext, std::path::PathBuf)> {
+    let config = SessionConfig::new().with_target_partitions(3);
+    let tables = [("points", POINTS), ("measures", MEASURES)];
+    common::context(name, config, vec![], "wkt", &tables).await
+}
+
+/// Write `table` as a layer of the same name and register it as `{table}_gpkg`.
+async fn round_trip(ctx: &SessionContext, dir: &std::path::Path, table: &str) -> Result<()> {
+    let path = dir.join(format!("{table}.gpkg"));
+    let rows = ctx
+        .table(table)
+        .await?
+        .write_geopackage(path.to_str().unwrap(), table)
+        .await?;
+    assert!(rows > 0);
+
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE {table}_gpkg STORED AS GEOPACKAGE LOCATION '{}' \
+         OPTIONS ('layer' '{table}')",
+        path.display()
+    ))
+    .await?
+    .collect()
+    .await?;
+    Ok(())
+}
+
+/// WKB of the geometries in the first column of the query result.
+async fn wkb(ctx: &SessionContext, sql: &str) -> Result<Vec<Vec<u8>>> {
+    let batches = ctx.sql(sql).await?.collect().await?;
+    Ok(batches
+        .iter()
+        .flat_map(|b| {
+            b.column(0)
+                .as_binary::<i32>()
+                .iter()
+                .map(|wkb| wkb.unwrap().to_vec())
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+fn extension(schema: &Schema) -> String {
+    let field = schema.field_with_name("geometry").unwrap();
+    field.metadata()["ARROW:extension:name"].clone()
+}
+
+#[tokio::test]
+async fn points() -> Result<()> {
+    let (ctx, dir) = context("geopackage-points").await?;
+    round_trip(&ctx, &dir, "points").await?;
+
+    let schema = ctx.table("points_gpkg").await?.schema().as_arrow().clone();
+    assert_eq!(extension(&schema), "geoarrow.point");
+
+    // attributes and geometries of all partitions
+    let batches = ctx
+        .sql("SELECT id, name, geometry FROM points_gpkg ORDER BY id")
+        .await?
+        .collect()
+        .await?;
+    let ids = batches
+        .iter()
+        .flat_map(|b| b.column(0).as_primitive::<Int64Type>().values().to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+    let names = batches
+        .iter()
+        .flat_map(|b| {
+            b.column(1)
+                .as_string::<i32>()
+                .iter()
+                .map(|name| name.unwrap().to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+    let nulls = batches
+        .iter()
+        .map(|b| b.column(2).null_count())
+        .sum::<usize>();
+    assert_eq!(nulls, 1);
+
+    // projections without the geometry and limits
+    let batches = ctx
+        .sql("SELECT name FROM points_gpkg LIMIT 2")
+        .await?
+        .collect()
+        .await?;
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+    assert!(batches.iter().all(|b| b.num_columns() == 1));
+
+    let batches = ctx
+        .sql("SELECT count(*) FROM points_gpkg")
+        .await?
+        .collect()
+        .await?;
+    assert_eq!(batches[0].column(0).as_primitive::<Int64Type>().value(0), 5);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn measures() -> Result<()> {
+    let (ctx, dir) = context("geopackage-measures").await?;
+    round_trip(&ctx, &dir, "measures").await?;
+
+    // z and m flags derived from the geometries written
+    let connection = rusqlite::Connection::open(dir.join("measures.gpkg")).unwrap();
+    let flags = connection
+        .query_row(
+            "SELECT geometry_type_name, z, m FROM gpkg_geometry_columns",
+            [],
+            |row| Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!(flags, ("LINESTRING".to_string(), 2, 1));
+
+    // geometries with measures are read back as WKB, unchanged
+    let schema = ctx
+        .table("measures_gpkg")
+        .await?
+        .schema()
+        .as_arrow()
+        .clone();
+    assert_eq!(extension(&schema), "geoarrow.wkb");
+    assert_eq!(
+        wkb(&ctx, "SELECT geometry FROM measures_gpkg ORDER BY id").await?,
+        wkb(&ctx, "SELECT geometry FROM measures ORDER BY id").await?
+    );
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! `ST_MakeLine(point ORDER BY ts)` building tracks from one point per row,
+//! run with single and multi-partition plans.
+
+use datafusion::{
+    arrow::{
+        array::{Array, AsArray},
+        compute::concat,
+        datatypes::Float64Type,
+    },
+    error::Result,
+    logical_expr::{AggregateUDF, ScalarUDF},
+    prelude::{SessionConfig, SessionContext},
+};
+use datafusion_spatial::{udafs::MakeLineAgg, udfs::MakeLine};
+
+mod common;
+
+/// GPS fixes of two tracks in file order, not in time order, with a fix
+/// without position.
+const FIXES: &str = "track,ts,x,y
+a,3,30,3
+b,2,20,20
+a,1,10,1
+b,1,10,10
+a,4,,
+a,2,20,2
+b,3,30,30
+";
+
+/// Session with one row per batch spread over `partitions` partitions, with
+/// a CSV table `fixes` of native points.
+async fn context(name: &str, partitions: usize) -> Result<(SessionContext, std::path::PathBuf)> {
+    let config = SessionConfig::new()
+        .with_target_partitions(partitions)
+        .with_batch_size(1);
+    let udfs = vec![
+        ScalarUDF::from(MakeLine::new()).into(),
+        AggregateUDF::from(MakeLineAgg::new()).into(),
+    ];
+    let name = format!("{name}-{partitions}");
+    common::context(&name, config, udfs, "xy", &[("fixes", FIXES)]).await
+}
+
+/// X ordinates of the vertices of the native line strings in the last column.
+async fn lines(ctx: &SessionContext, sql: &str) -> Result<Vec<Option<Vec<f64>>>> {
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let columns = batches
+        .iter()
+        .map(|b| b.column(b.num_columns() - 1).as_ref())
+        .collect::<Vec<_>>();
+    let lines = concat(&columns)?;
+    let lines = lines.as_list::<i32>();
+
+    Ok((0..lines.len())
+        .map(|row| {
+            lines.is_valid(row).then(|| {
+                let vertices = lines.value(row);
+                let xs = vertices.as_struct().column(0).as_primitive::<Float64Type>();
+                xs.values().to_vec()
+            })
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn ordered_tracks() -> Result<()> {
+    for partitions in [1, 4] {
+        let (ctx, dir) = context("make-line", partitions).await?;
+
+        // the point without position is skipped
+        assert_eq!(
+            lines(
+                &ctx,
+                "SELECT track, ST_MakeLine(geometry ORDER BY ts) FROM fixes \
+                 GROUP BY track ORDER BY track"
+            )
+            .await?,
+            vec![Some(vec![10., 20., 30.]), Some(vec![10., 20., 30.])],
+            "{partitions} partitions"
+        );
+        assert_eq!(
+            lines(
+                &ctx,
+                "SELECT ST_MakeLine(geometry ORDER BY ts DESC) FROM fixes WHERE track = 'b'"
+            )
+            .await?,
+            vec![Some(vec![30., 20., 10.])],
+            "{partitions} partitions"
+        );
+        assert_eq!(
+            lines(
+                &ctx,
+                "SELECT ST_MakeLine(geometry ORDER BY ts) FROM fixes WHERE track = 'c'"
+            )
+            .await?,
+            vec![None],
+            "{partitions} partitions"
+        );
+
+        std::fs::remove_dir_all(dir)?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn scalar() -> Result<()> {
+    let (ctx, dir) = context("make-line-scalar", 1).await?;
+
+    assert_eq!(
+        lines(
+            &ctx,
+            "SELECT ST_MakeLine(geometry, geometry) FROM fixes WHERE ts = 1"
+        )
+        .await?,
+        vec![Some(vec![10., 10.]), Some(vec![10., 10.])]
+    );
+    assert_eq!(
+        lines(
+            &ctx,
+            "SELECT ST_MakeLine(make_array(geometry, geometry)) FROM fixes WHERE ts = 1"
+        )
+        .await?,
+        vec![Some(vec![10., 10.]), Some(vec![10., 10.])]
+    );
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
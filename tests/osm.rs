@@ -0,0 +1,151 @@
+//! OpenStreetMap extracts read with `STORED AS OSMPBF` and `OsmSchemaProvider`,
+//! from the fixture generated by `data/generate_osm_pbf.py`.
+
+use std::sync::Arc;
+
+use datafusion::{
+    arrow::{
+        array::{Array, AsArray},
+        datatypes::{DataType, Int64Type},
+    },
+    error::Result,
+    logical_expr::ScalarUDF,
+    prelude::SessionContext,
+};
+use datafusion_spatial::{
+    io::{osm::OsmSchemaProvider, SpatialTableFactory},
+    rules::SpatialAnalyzerRule,
+    udfs::AsText,
+};
+
+const EXTRACT: &str = "data/test.osm.pbf";
+
+fn context() -> SessionContext {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::from(AsText::new()));
+    ctx.add_analyzer_rule(Arc::new(SpatialAnalyzerRule {}));
+    SpatialTableFactory::register(&mut ctx.state_ref().write());
+    ctx
+}
+
+/// Ids and WKT geometries of the query result, ordered by id.
+async fn geometries(ctx: &SessionContext, table: &str) -> Result<Vec<(i64, Option<String>)>> {
+    let batches = ctx
+        .sql(&format!(
+            "SELECT id, ST_AsText(geometry) FROM {table} ORDER BY id"
+        ))
+        .await?
+        .collect()
+        .await?;
+    Ok(batches
+        .iter()
+        .flat_map(|batch| {
+            let ids = batch.column(0).as_primitive::<Int64Type>();
+            let wkt = batch.column(1).as_string::<i32>();
+            (0..batch.num_rows())
+                .map(|i| {
+                    (
+                        ids.value(i),
+                        wkt.is_valid(i).then(|| wkt.value(i).to_string()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn external_tables() -> Result<()> {
+    let ctx = context();
+    for table in ["nodes", "ways"] {
+        ctx.sql(&format!(
+            "CREATE EXTERNAL TABLE {table} STORED AS OSMPBF LOCATION '{EXTRACT}' \
+             OPTIONS ('table' '{table}', 'batch_size' '4')"
+        ))
+        .await?
+        .collect()
+        .await?;
+    }
+
+    // nodes in batches of at most `batch_size` rows
+    let batches = ctx.table("nodes").await?.collect().await?;
+    assert_eq!(
+        batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+        vec![4, 4, 2]
+    );
+    let batches = ctx
+        .sql("SELECT id FROM nodes WHERE tags['amenity'] = 'bench'")
+        .await?
+        .collect()
+        .await?;
+    assert_eq!(batches[0].column(0).as_primitive::<Int64Type>().value(0), 9);
+
+    // linestrings and areas in a native mixed column
+    let schema = ctx.table("ways").await?.schema().as_arrow().clone();
+    let geometry = schema.field_with_name("geometry")?;
+    assert!(
+        matches!(geometry.data_type(), DataType::Union(_, _)),
+        "{}",
+        geometry.data_type()
+    );
+    assert_eq!(
+        geometries(&ctx, "ways").await?,
+        vec![
+            (100, Some("LINESTRING(2 2,3 3)".to_string())),
+            (101, Some("POLYGON((0 0,1 0,1 1,0 1,0 0))".to_string())),
+            (
+                102,
+                Some("LINESTRING(0.25 0.25,0.75 0.25,0.75 0.75,0.25 0.75,0.25 0.25)".to_string())
+            ),
+            (103, Some("LINESTRING(0 0,1 0,1 1)".to_string())),
+            (104, Some("LINESTRING(1 1,0 1,0 0)".to_string())),
+            // node 999 is missing from the extract
+            (105, None),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn schema_provider() -> Result<()> {
+    let ctx = context();
+    ctx.catalog("datafusion")
+        .unwrap()
+        .register_schema("osm", Arc::new(OsmSchemaProvider::new(EXTRACT)))?;
+
+    let batches = ctx
+        .sql("SELECT count(*) FROM osm.nodes")
+        .await?
+        .collect()
+        .await?;
+    assert_eq!(
+        batches[0].column(0).as_primitive::<Int64Type>().value(0),
+        10
+    );
+
+    // multipolygons with their inner rings, other relations have no geometry
+    assert_eq!(
+        geometries(&ctx, "osm.relations").await?,
+        vec![
+            (
+                200,
+                Some(
+                    "MULTIPOLYGON(((1 1,0 1,0 0,1 0,1 1),\
+                     (0.25 0.25,0.75 0.25,0.75 0.75,0.25 0.75,0.25 0.25)))"
+                        .to_string()
+                )
+            ),
+            (201, None),
+        ]
+    );
+
+    let batches = ctx
+        .sql("SELECT members FROM osm.relations WHERE id = 200")
+        .await?
+        .collect()
+        .await?;
+    assert_eq!(batches[0].column(0).as_list::<i32>().value(0).len(), 3);
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Shapefile tables created with `CREATE EXTERNAL TABLE ... STORED AS SHAPEFILE`.
+
+use std::{path::Path, sync::Arc};
+
+use datafusion::{
+    arrow::{
+        array::AsArray,
+        datatypes::{DataType, Float64Type},
+    },
+    error::Result,
+    logical_expr::ScalarUDF,
+    prelude::SessionContext,
+};
+use datafusion_spatial::{io::SpatialTableFactory, rules::SpatialAnalyzerRule, udfs::AsText};
+use serde_json::Value;
+use shapefile::dbase::{FieldName, FieldValue, Record, TableWriterBuilder};
+
+const PRJ: &str = r#"GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]]"#;
+
+/// Write a point shapefile with a `.prj` file at `path`.
+fn write_points(path: &Path, points: &[(&str, f64, (f64, f64))]) {
+    let table = TableWriterBuilder::new()
+        .add_character_field(FieldName::try_from("name").unwrap(), 20)
+        .add_numeric_field(FieldName::try_from("value").unwrap(), 10, 2);
+    let mut writer = shapefile::Writer::from_path(path, table).unwrap();
+
+    for (name, value, (x, y)) in points {
+        let mut record = Record::default();
+        record.insert(
+            "name".to_string(),
+            FieldValue::Character(Some(name.to_string())),
+        );
+        record.insert("value".to_string(), FieldValue::Numeric(Some(*value)));
+        writer
+            .write_shape_and_record(&shapefile::Point::new(*x, *y), &record)
+            .unwrap();
+    }
+
+    std::fs::write(path.with_extension("prj"), PRJ).unwrap();
+}
+
+#[tokio::test]
+async fn directory() -> Result<()> {
+    let dir = std::env::temp_dir().join("datafusion-spatial-shapefile");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    write_points(
+        &dir.join("a.shp"),
+        &[("a", 1.5, (1., 2.)), ("b", 2.5, (3., 4.))],
+    );
+    write_points(&dir.join("b.shp"), &[("c", 3.5, (5., 6.))]);
+
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::from(AsText::new()));
+    ctx.add_analyzer_rule(Arc::new(SpatialAnalyzerRule {}));
+    SpatialTableFactory::register(&mut ctx.state_ref().write());
+
+    let sql = format!(
+        "CREATE EXTERNAL TABLE t STORED AS SHAPEFILE LOCATION '{}'",
+        dir.display()
+    );
+    ctx.sql(&sql).await?.collect().await?;
+
+    // the `.prj` CRS ends up in the `geo` metadata
+    let table = ctx.table("t").await?;
+    let geo = serde_json::from_str::<Value>(&table.schema().metadata()["geo"]).unwrap();
+    assert_eq!(geo["columns"]["geometry"]["crs"], PRJ);
+    assert_eq!(geo["columns"]["geometry"]["encoding"], "point");
+    let value = table.schema().field_with_unqualified_name("value")?;
+    assert_eq!(value.data_type(), &DataType::Float64);
+
+    let batches = ctx
+        .sql("SELECT name, value, ST_AsText(geometry) FROM t ORDER BY name")
+        .await?
+        .collect()
+        .await?;
+    let rows = batches
+        .iter()
+        .flat_map(|batch| {
+            let names = batch.column(0).as_string::<i32>();
+            let values = batch.column(1).as_primitive::<Float64Type>();
+            let wkt = batch.column(2).as_string::<i32>();
+            (0..batch.num_rows())
+                .map(|i| {
+                    (
+                        names.value(i).to_string(),
+                        values.value(i),
+                        wkt.value(i).to_string(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![
+            ("a".to_string(), 1.5, "POINT(1 2)".to_string()),
+            ("b".to_string(), 2.5, "POINT(3 4)".to_string()),
+            ("c".to_string(), 3.5, "POINT(5 6)".to_string()),
+        ]
+    );
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}